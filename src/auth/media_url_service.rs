@@ -0,0 +1,530 @@
+use std::net::IpAddr;
+
+use chrono::Utc;
+use hmac::{Hmac, Mac};
+use serde::Deserialize;
+use sha2::Sha256;
+use surrealdb::{
+    engine::any::Any,
+    sql::{Datetime, Thing},
+    Surreal,
+};
+
+use crate::{
+    auth::token_service::AuthConfig,
+    helpers::thing_helpers::{create_user_thing, parse_id_part},
+    middlewares::mw_auth::Ctx,
+    models::{
+        album::AlbumWithRelations,
+        artist::ArtistWithAlbumsAndTopSongs,
+        chart::{Chart, ChartEntry},
+        favorite::{FavoritesResponse, SongWithFavoriteMetadata},
+        pagination::PaginatedResponse,
+        playlist::PlaylistWithSongs,
+        recommendation::ScoredSong,
+        song::{Song, SongWithRelations},
+        wrapped::WrappedReport,
+    },
+    services::search_service::{SearchHit, SearchResult},
+    Error, Result,
+};
+
+type HmacSha256 = Hmac<Sha256>;
+
+/// Who a media URL is issued to.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum MediaSubject {
+    User(String),
+    /// Anonymous sessions are bound to the client IP.
+    Anonymous(IpAddr),
+}
+
+impl MediaSubject {
+    pub fn from_request(ctx: Option<&Ctx>, ip: IpAddr) -> Self {
+        match ctx {
+            Some(ctx) => Self::for_user(ctx),
+            None => Self::Anonymous(ip),
+        }
+    }
+
+    pub fn for_user(ctx: &Ctx) -> Self {
+        Self::User(parse_id_part(&ctx.user_id).to_string())
+    }
+
+    /// Value exposed in the URL, the IP of an anonymous session is left out.
+    fn url_value(&self) -> String {
+        match self {
+            Self::User(user_id) => format!("user:{}", user_id),
+            Self::Anonymous(_) => "anon".to_string(),
+        }
+    }
+
+    fn signed_value(&self) -> String {
+        match self {
+            Self::User(_) => self.url_value(),
+            Self::Anonymous(ip) => format!("anon:{}", ip),
+        }
+    }
+}
+
+/// Query string of a signed media URL.
+#[derive(Debug, Deserialize)]
+pub struct SignedMediaQuery {
+    pub sub: Option<String>,
+    pub iat: Option<i64>,
+    pub exp: Option<i64>,
+    pub sig: Option<String>,
+}
+
+/// Signs stream URLs for one subject, all sharing the same issue time and
+/// expiry.
+pub struct MediaUrlSigner<'a> {
+    config: &'a AuthConfig,
+    subject: MediaSubject,
+    issued_at: i64,
+    expires_at: i64,
+}
+
+impl<'a> MediaUrlSigner<'a> {
+    pub fn new(config: &'a AuthConfig, subject: MediaSubject) -> Self {
+        let issued_at = Utc::now().timestamp();
+        Self {
+            config,
+            subject,
+            issued_at,
+            expires_at: issued_at + config.media_url_ttl_secs,
+        }
+    }
+
+    pub fn sign_url(&self, song_id: &str) -> String {
+        let song_id = parse_id_part(song_id);
+        let signature = MediaUrlService::signature(
+            self.config,
+            song_id,
+            &self.subject.signed_value(),
+            self.issued_at,
+            self.expires_at,
+        );
+
+        format!(
+            "/api/song/{}/stream?sub={}&iat={}&exp={}&sig={}",
+            song_id,
+            self.subject.url_value(),
+            self.issued_at,
+            self.expires_at,
+            signature
+        )
+    }
+}
+
+pub struct MediaUrlService;
+
+impl MediaUrlService {
+    fn mac(
+        config: &AuthConfig,
+        song_id: &str,
+        subject: &str,
+        issued_at: i64,
+        expires_at: i64,
+    ) -> HmacSha256 {
+        let mut mac = HmacSha256::new_from_slice(config.media_url_secret.as_bytes())
+            .expect("HMAC accepts keys of any size");
+        mac.update(format!("{}\n{}\n{}\n{}", song_id, subject, issued_at, expires_at).as_bytes());
+        mac
+    }
+
+    fn signature(
+        config: &AuthConfig,
+        song_id: &str,
+        subject: &str,
+        issued_at: i64,
+        expires_at: i64,
+    ) -> String {
+        hex::encode(
+            Self::mac(config, song_id, subject, issued_at, expires_at)
+                .finalize()
+                .into_bytes(),
+        )
+    }
+
+    /// Checks a signed URL against the requested song and the client IP.
+    pub fn verify(
+        config: &AuthConfig,
+        song_id: &str,
+        query: &SignedMediaQuery,
+        ip: IpAddr,
+    ) -> Result<()> {
+        let (Some(sub), Some(iat), Some(exp), Some(sig)) =
+            (&query.sub, query.iat, query.exp, &query.sig)
+        else {
+            return Err(Error::InvalidMediaSignature {
+                reason: "Missing signature".to_string(),
+            });
+        };
+
+        if exp < Utc::now().timestamp() {
+            return Err(Error::InvalidMediaSignature {
+                reason: "Link expired".to_string(),
+            });
+        }
+
+        let subject = match sub.as_str() {
+            "anon" => MediaSubject::Anonymous(ip).signed_value(),
+            user if user.starts_with("user:") => user.to_string(),
+            _ => {
+                return Err(Error::InvalidMediaSignature {
+                    reason: "Unknown subject".to_string(),
+                })
+            }
+        };
+
+        let signature = hex::decode(sig).map_err(|_| Error::InvalidMediaSignature {
+            reason: "Malformed signature".to_string(),
+        })?;
+
+        Self::mac(config, parse_id_part(song_id), &subject, iat, exp)
+            .verify_slice(&signature)
+            .map_err(|_| Error::InvalidMediaSignature {
+                reason: "Signature mismatch".to_string(),
+            })
+    }
+
+    /// Checks that the user of a verified URL still exists and that the link
+    /// was issued after their last revocation. Anonymous URLs are left alone.
+    ///
+    /// The signed issue time is only known to the second: links issued during
+    /// the second of a revocation are rejected too.
+    pub async fn verify_subject(db: &Surreal<Any>, query: &SignedMediaQuery) -> Result<()> {
+        let (Some(user_id), Some(issued_at)) = (
+            query.sub.as_deref().and_then(|sub| sub.strip_prefix("user:")),
+            query.iat,
+        ) else {
+            return Ok(());
+        };
+
+        #[derive(Deserialize)]
+        struct Revocation {
+            media_revoked_before: Option<Datetime>,
+        }
+
+        let mut res = db
+            .query("SELECT media_revoked_before FROM $user;")
+            .bind(("user", create_user_thing(user_id)))
+            .await?;
+        let Some(user) = res.take::<Option<Revocation>>(0)? else {
+            return Err(Error::InvalidMediaSignature {
+                reason: "Unknown user".to_string(),
+            });
+        };
+
+        match user.media_revoked_before {
+            Some(cutoff) if issued_at <= cutoff.timestamp() => Err(Error::InvalidMediaSignature {
+                reason: "Link revoked".to_string(),
+            }),
+            _ => Ok(()),
+        }
+    }
+
+    /// Invalidates every media URL issued to the user so far, links signed
+    /// afterwards keep working.
+    pub async fn revoke_user(db: &Surreal<Any>, user_id: &str) -> Result<()> {
+        let mut res = db
+            .query("UPDATE $user SET media_revoked_before = time::now() RETURN id;")
+            .bind(("user", create_user_thing(user_id)))
+            .await?;
+        let updated: Vec<Thing> = res.take((0, "id"))?;
+        if updated.is_empty() {
+            return Err(Error::UserNotFound {
+                username: user_id.to_string(),
+            });
+        }
+        Ok(())
+    }
+}
+
+/// Replaces raw `file_url` values with signed stream URLs before a response
+/// leaves the API.
+pub trait SignMediaUrls {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner);
+}
+
+impl SignMediaUrls for Song {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        if let Some(id) = &self.id {
+            self.file_url = signer.sign_url(&id.id.to_raw());
+        }
+    }
+}
+
+impl SignMediaUrls for SongWithRelations {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        if let Some(id) = &self.id {
+            self.file_url = signer.sign_url(&id.id.to_raw());
+        }
+    }
+}
+
+impl SignMediaUrls for ScoredSong {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.song.sign_media_urls(signer);
+    }
+}
+
+impl<T: SignMediaUrls> SignMediaUrls for Vec<T> {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.iter_mut().for_each(|item| item.sign_media_urls(signer));
+    }
+}
+
+impl<T: SignMediaUrls> SignMediaUrls for Option<T> {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        if let Some(item) = self {
+            item.sign_media_urls(signer);
+        }
+    }
+}
+
+impl<T: SignMediaUrls> SignMediaUrls for PaginatedResponse<T> {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.data.sign_media_urls(signer);
+    }
+}
+
+impl<T: SignMediaUrls> SignMediaUrls for FavoritesResponse<T> {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.data.sign_media_urls(signer);
+    }
+}
+
+impl<T: SignMediaUrls> SignMediaUrls for ChartEntry<T> {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.item.sign_media_urls(signer);
+    }
+}
+
+impl<T: SignMediaUrls> SignMediaUrls for Chart<T> {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.entries.sign_media_urls(signer);
+    }
+}
+
+impl SignMediaUrls for WrappedReport {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.top_songs
+            .iter_mut()
+            .for_each(|entry| entry.song.sign_media_urls(signer));
+        if let Some(first_song) = &mut self.first_song {
+            first_song.song.sign_media_urls(signer);
+        }
+    }
+}
+
+impl SignMediaUrls for SongWithFavoriteMetadata {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.song.sign_media_urls(signer);
+    }
+}
+
+impl SignMediaUrls for AlbumWithRelations {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.songs.sign_media_urls(signer);
+    }
+}
+
+impl SignMediaUrls for ArtistWithAlbumsAndTopSongs {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.top_songs.sign_media_urls(signer);
+    }
+}
+
+impl SignMediaUrls for PlaylistWithSongs {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.songs.sign_media_urls(signer);
+    }
+}
+
+impl<T: SignMediaUrls> SignMediaUrls for SearchHit<T> {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.item.sign_media_urls(signer);
+    }
+}
+
+impl SignMediaUrls for SearchResult {
+    fn sign_media_urls(&mut self, signer: &MediaUrlSigner) {
+        self.songs.sign_media_urls(signer);
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use jsonwebtoken::Algorithm;
+    use std::net::Ipv4Addr;
+
+    fn test_config() -> AuthConfig {
+        AuthConfig {
+            jwt_secret: "test_secret".to_string(),
+            website_url: "http://localhost:3000".to_string(),
+            token_duration_min: 60,
+            jwt_algorithm: Algorithm::HS256,
+            media_url_secret: "test_media_secret".to_string(),
+            media_url_ttl_secs: 3600,
+        }
+    }
+
+    fn parse_query(url: &str) -> SignedMediaQuery {
+        let query = url.split_once('?').unwrap().1;
+        let mut parsed = SignedMediaQuery {
+            sub: None,
+            iat: None,
+            exp: None,
+            sig: None,
+        };
+        for pair in query.split('&') {
+            let (key, value) = pair.split_once('=').unwrap();
+            match key {
+                "sub" => parsed.sub = Some(value.to_string()),
+                "iat" => parsed.iat = value.parse().ok(),
+                "exp" => parsed.exp = value.parse().ok(),
+                "sig" => parsed.sig = Some(value.to_string()),
+                _ => {}
+            }
+        }
+        parsed
+    }
+
+    const IP: IpAddr = IpAddr::V4(Ipv4Addr::new(10, 0, 0, 1));
+
+    #[test]
+    fn test_signed_url_roundtrip() {
+        let config = test_config();
+
+        let signer = MediaUrlSigner::new(&config, MediaSubject::User("alice".to_string()));
+        let url = signer.sign_url("song:abc");
+        assert!(url.starts_with("/api/song/abc/stream?sub=user:alice&iat="));
+        // L'URL d'un utilisateur n'est pas liée à son IP
+        let other_ip = IpAddr::V4(Ipv4Addr::new(192, 168, 1, 1));
+        assert!(MediaUrlService::verify(&config, "abc", &parse_query(&url), other_ip).is_ok());
+
+        let signer = MediaUrlSigner::new(&config, MediaSubject::Anonymous(IP));
+        let url = signer.sign_url("abc");
+        assert!(!url.contains("10.0.0.1"));
+        assert!(MediaUrlService::verify(&config, "abc", &parse_query(&url), IP).is_ok());
+        assert!(MediaUrlService::verify(&config, "abc", &parse_query(&url), other_ip).is_err());
+    }
+
+    #[test]
+    fn test_rejects_tampered_or_expired_urls() {
+        let config = test_config();
+        let signer = MediaUrlSigner::new(&config, MediaSubject::User("alice".to_string()));
+        let query = parse_query(&signer.sign_url("abc"));
+
+        // Autre morceau
+        assert!(MediaUrlService::verify(&config, "xyz", &query, IP).is_err());
+
+        // Autre utilisateur
+        let tampered = SignedMediaQuery {
+            sub: Some("user:bob".to_string()),
+            ..parse_query(&signer.sign_url("abc"))
+        };
+        assert!(MediaUrlService::verify(&config, "abc", &tampered, IP).is_err());
+
+        // Expiration repoussée
+        let tampered = SignedMediaQuery {
+            exp: query.exp.map(|exp| exp + 3600),
+            ..parse_query(&signer.sign_url("abc"))
+        };
+        assert!(MediaUrlService::verify(&config, "abc", &tampered, IP).is_err());
+
+        // Émission rajeunie pour échapper à une révocation
+        let tampered = SignedMediaQuery {
+            iat: query.iat.map(|iat| iat + 3600),
+            ..parse_query(&signer.sign_url("abc"))
+        };
+        assert!(MediaUrlService::verify(&config, "abc", &tampered, IP).is_err());
+
+        // Clé changée : révocation
+        let rotated = AuthConfig {
+            media_url_secret: "rotated".to_string(),
+            ..test_config()
+        };
+        assert!(MediaUrlService::verify(&rotated, "abc", &query, IP).is_err());
+
+        let expired = AuthConfig {
+            media_url_ttl_secs: -10,
+            ..test_config()
+        };
+        let signer = MediaUrlSigner::new(&expired, MediaSubject::Anonymous(IP));
+        let result = MediaUrlService::verify(&expired, "abc", &parse_query(&signer.sign_url("abc")), IP);
+        assert!(matches!(result, Err(Error::InvalidMediaSignature { reason }) if reason == "Link expired"));
+
+        let missing = SignedMediaQuery {
+            sub: None,
+            iat: None,
+            exp: None,
+            sig: None,
+        };
+        assert!(MediaUrlService::verify(&config, "abc", &missing, IP).is_err());
+    }
+
+    #[tokio::test]
+    async fn test_rejects_links_of_revoked_or_deleted_users() {
+        let db = surrealdb::engine::any::connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(include_str!("../../database_media_revocation.surql"))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        db.query("CREATE user:alice SET username = 'alice';")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        let config = test_config();
+        let signer = MediaUrlSigner::new(&config, MediaSubject::User("alice".to_string()));
+        let query = parse_query(&signer.sign_url("abc"));
+        assert!(MediaUrlService::verify_subject(&db, &query).await.is_ok());
+
+        MediaUrlService::revoke_user(&db, "alice").await.unwrap();
+        let result = MediaUrlService::verify_subject(&db, &query).await;
+        assert!(matches!(result, Err(Error::InvalidMediaSignature { reason }) if reason == "Link revoked"));
+
+        // Les liens émis après la révocation restent valides
+        db.query("UPDATE user:alice SET media_revoked_before = time::now() - 1m;")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        let signer = MediaUrlSigner::new(&config, MediaSubject::User("alice".to_string()));
+        let query = parse_query(&signer.sign_url("abc"));
+        assert!(MediaUrlService::verify_subject(&db, &query).await.is_ok());
+
+        // L'émission fait foi, pas l'expiration : un lien émis une heure avant
+        // la révocation reste révoqué, quelle que soit sa durée de vie
+        let long_lived = AuthConfig {
+            media_url_ttl_secs: 7 * 24 * 3600,
+            ..test_config()
+        };
+        let signer = MediaUrlSigner::new(&long_lived, MediaSubject::User("alice".to_string()));
+        let mut query = parse_query(&signer.sign_url("abc"));
+        query.iat = query.iat.map(|iat| iat - 3600);
+        let result = MediaUrlService::verify_subject(&db, &query).await;
+        assert!(matches!(result, Err(Error::InvalidMediaSignature { reason }) if reason == "Link revoked"));
+
+        let signer = MediaUrlSigner::new(&config, MediaSubject::User("bob".to_string()));
+        let query = parse_query(&signer.sign_url("abc"));
+        let result = MediaUrlService::verify_subject(&db, &query).await;
+        assert!(matches!(result, Err(Error::InvalidMediaSignature { reason }) if reason == "Unknown user"));
+
+        let signer = MediaUrlSigner::new(&config, MediaSubject::Anonymous(IP));
+        let query = parse_query(&signer.sign_url("abc"));
+        assert!(MediaUrlService::verify_subject(&db, &query).await.is_ok());
+
+        assert!(matches!(
+            MediaUrlService::revoke_user(&db, "bob").await,
+            Err(Error::UserNotFound { .. })
+        ));
+    }
+}
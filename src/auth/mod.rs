@@ -1,3 +1,4 @@
 pub mod models;
 pub mod password_service;
 pub mod token_service;
+pub mod media_url_service;
@@ -4,7 +4,7 @@ use serde::{Deserialize, Serialize};
 use std::env;
 use uuid::Uuid;
 
-use crate::Result;
+use crate::{models::user::Role, Result};
 
 #[derive(Clone)]
 pub struct AuthConfig {
@@ -12,12 +12,22 @@ pub struct AuthConfig {
     pub website_url: String,
     pub token_duration_min: i64,
     pub jwt_algorithm: Algorithm,
+    pub media_url_secret: String,
+    pub media_url_ttl_secs: i64,
 }
 
 impl AuthConfig {
     pub fn from_env() -> Result<Self> {
+        let jwt_secret = env::var("JWT_SECRET")?;
+
         Ok(Self {
-            jwt_secret: env::var("JWT_SECRET")?,
+            // Changer ce secret révoque toutes les URLs média déjà émises
+            media_url_secret: env::var("MEDIA_URL_SECRET").unwrap_or_else(|_| jwt_secret.clone()),
+            media_url_ttl_secs: env::var("MEDIA_URL_TTL_SECS")
+                .unwrap_or_else(|_| "3600".to_string())
+                .parse::<i64>()
+                .unwrap_or(3600),
+            jwt_secret,
             website_url: env::var("WEBSITE_URL")?,
             token_duration_min: env::var("TOKEN_DURATION_MIN")
                 .unwrap_or_else(|_| "60".to_string())
@@ -37,10 +47,12 @@ pub struct Claims {
     pub iss: String, // issuer
     pub aud: String, // audience
     pub jti: String, // jtw id
+    #[serde(default)]
+    pub role: Role, // role at issue time, the database stays the source of truth
 }
 
 impl Claims {
-    pub fn new(sub: String, config: &AuthConfig) -> Self {
+    pub fn new(sub: String, role: Role, config: &AuthConfig) -> Self {
         let iat = Utc::now();
         let exp = iat + Duration::minutes(config.token_duration_min);
 
@@ -52,6 +64,7 @@ impl Claims {
             iss: config.website_url.to_string(),
             aud: config.website_url.to_string(),
             jti: Uuid::new_v4().to_string(),
+            role,
         }
     }
 }
@@ -59,8 +72,8 @@ impl Claims {
 pub struct TokenService;
 
 impl TokenService {
-    pub fn create_token(sub: String, config: &AuthConfig) -> Result<String> {
-        let claims = Claims::new(sub, config);
+    pub fn create_token(sub: String, role: Role, config: &AuthConfig) -> Result<String> {
+        let claims = Claims::new(sub, role, config);
         let token = encode(
             &Header::new(config.jwt_algorithm),
             &claims,
@@ -82,3 +95,39 @@ impl TokenService {
         Ok(decoded.claims)
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn test_config() -> AuthConfig {
+        AuthConfig {
+            jwt_secret: "test_secret".to_string(),
+            website_url: "http://localhost:3000".to_string(),
+            token_duration_min: 60,
+            jwt_algorithm: Algorithm::HS256,
+            media_url_secret: "test_media_secret".to_string(),
+            media_url_ttl_secs: 3600,
+        }
+    }
+
+    #[test]
+    fn test_token_carries_role() {
+        let config = test_config();
+
+        let token = TokenService::create_token("123".to_string(), Role::Moderator, &config).unwrap();
+        let claims = TokenService::validate_token(&token, &config).unwrap();
+
+        assert_eq!(claims.sub, "123");
+        assert_eq!(claims.role, Role::Moderator);
+    }
+
+    #[test]
+    fn test_role_ordering() {
+        assert!(Role::Admin.satisfies(Role::Moderator));
+        assert!(Role::Moderator.satisfies(Role::Moderator));
+        assert!(Role::User.satisfies(Role::User));
+        assert!(!Role::User.satisfies(Role::Moderator));
+        assert!(!Role::Moderator.satisfies(Role::Admin));
+    }
+}
@@ -0,0 +1,8 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{services::chart_service::ChartService, Result};
+
+/// `charts`, recomputes the song, album and artist charts of every period.
+pub async fn run(db: &Surreal<Any>) -> Result<()> {
+    super::print_report(&ChartService::rebuild(db).await?)
+}
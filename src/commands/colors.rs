@@ -0,0 +1,13 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    services::{color_service::ColorService, media_service::MediaConfig},
+    Result,
+};
+
+/// `colors [--force]`, extracts the dominant color of album and playlist covers.
+pub async fn run(db: &Surreal<Any>, args: &[String]) -> Result<()> {
+    let force = args.iter().any(|arg| arg == "--force");
+    let config = MediaConfig::from_env();
+    super::print_report(&ColorService::backfill(db, &config, force).await?)
+}
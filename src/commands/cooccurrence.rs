@@ -0,0 +1,9 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{services::recommendation_service::RecommendationService, Result};
+
+/// `cooccurrence`, rebuilds the song co-occurrence table read by
+/// `GET /api/recommendations/songs` and `/albums`.
+pub async fn run(db: &Surreal<Any>) -> Result<()> {
+    super::print_report(&RecommendationService::rebuild_cooccurrence(db).await?)
+}
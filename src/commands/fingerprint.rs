@@ -0,0 +1,13 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    services::{fingerprint_service::FingerprintService, media_service::MediaConfig},
+    Result,
+};
+
+/// `fingerprint`, fingerprints every song without one; duplicates are then
+/// listed by `GET /api/admin/duplicates`.
+pub async fn run(db: &Surreal<Any>) -> Result<()> {
+    let config = MediaConfig::from_env();
+    super::print_report(&FingerprintService::analyze_pending(db, &config).await?)
+}
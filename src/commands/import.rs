@@ -0,0 +1,31 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    services::{
+        color_service::ColorService,
+        import_service::{ImportFormat, ImportService},
+        media_service::MediaConfig,
+    },
+    Error, Result,
+};
+
+/// `import <manifest.json|manifest.csv> [--dry-run]`
+pub async fn run(db: &Surreal<Any>, args: &[String]) -> Result<()> {
+    let dry_run = args.iter().any(|arg| arg == "--dry-run");
+    let path = args
+        .iter()
+        .find(|arg| !arg.starts_with("--"))
+        .ok_or(Error::InvalidInput {
+            reason: "Usage: import <manifest.json|manifest.csv> [--dry-run]".to_string(),
+        })?;
+
+    let input = tokio::fs::read_to_string(path).await?;
+    let format = ImportFormat::from_path(path);
+
+    let report = ImportService::import(db, &input, format, dry_run).await?;
+    if report.applied {
+        ColorService::backfill(db, &MediaConfig::from_env(), false).await?;
+    }
+
+    super::print_report(&report)
+}
@@ -0,0 +1,12 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    services::{loudness_service::LoudnessService, media_service::MediaConfig},
+    Result,
+};
+
+/// `loudness`, measures the albums and songs without a loudness yet.
+pub async fn run(db: &Surreal<Any>) -> Result<()> {
+    let config = MediaConfig::from_env();
+    super::print_report(&LoudnessService::analyze_pending(db, &config).await?)
+}
@@ -0,0 +1,9 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{services::mix_service::MixService, Result};
+
+/// `mixes`, regenerates the daily mixes of every user with a listening
+/// history.
+pub async fn run(db: &Surreal<Any>) -> Result<()> {
+    super::print_report(&MixService::refresh_all(db).await?)
+}
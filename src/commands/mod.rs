@@ -0,0 +1,44 @@
+use serde::Serialize;
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{Error, Result};
+
+pub mod charts;
+pub mod colors;
+pub mod cooccurrence;
+pub mod fingerprint;
+pub mod import;
+pub mod loudness;
+pub mod mixes;
+pub mod scan;
+pub mod tempo;
+pub mod waveform;
+
+/// Dispatches a one-shot maintenance command, e.g. `import catalog.csv --dry-run`.
+pub async fn run(db: &Surreal<Any>, args: &[String]) -> Result<()> {
+    let (command, rest) = args.split_first().ok_or(Error::InvalidInput {
+        reason: "Missing command".to_string(),
+    })?;
+
+    match command.as_str() {
+        "charts" => charts::run(db).await,
+        "colors" => colors::run(db, rest).await,
+        "cooccurrence" => cooccurrence::run(db).await,
+        "fingerprint" => fingerprint::run(db).await,
+        "import" => import::run(db, rest).await,
+        "loudness" => loudness::run(db).await,
+        "mixes" => mixes::run(db).await,
+        "scan" => scan::run(db).await,
+        "tempo" => tempo::run(db).await,
+        "waveform" => waveform::run(db).await,
+        other => Err(Error::InvalidInput {
+            reason: format!("Unknown command '{}'", other),
+        }),
+    }
+}
+
+/// Prints the report of a command as pretty JSON.
+fn print_report<T: Serialize>(report: &T) -> Result<()> {
+    println!("{}", serde_json::to_string_pretty(report)?);
+    Ok(())
+}
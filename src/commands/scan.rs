@@ -0,0 +1,12 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    services::{media_service::MediaConfig, scan_service::ScanService},
+    Result,
+};
+
+/// `scan`, walks `MEDIA_ROOT` and syncs songs from the audio tags.
+pub async fn run(db: &Surreal<Any>) -> Result<()> {
+    let config = MediaConfig::from_env();
+    super::print_report(&ScanService::scan(db, &config).await?)
+}
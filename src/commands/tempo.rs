@@ -0,0 +1,12 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    services::{media_service::MediaConfig, tempo_service::TempoService},
+    Result,
+};
+
+/// `tempo`, estimates the BPM of every song still at `tempo = 0`.
+pub async fn run(db: &Surreal<Any>) -> Result<()> {
+    let config = MediaConfig::from_env();
+    super::print_report(&TempoService::analyze_pending(db, &config).await?)
+}
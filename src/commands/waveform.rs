@@ -0,0 +1,12 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    services::{media_service::MediaConfig, waveform_service::WaveformService},
+    Result,
+};
+
+/// `waveform`, computes the peaks of every song without a stored waveform.
+pub async fn run(db: &Surreal<Any>) -> Result<()> {
+    let config = MediaConfig::from_env();
+    super::print_report(&WaveformService::analyze_pending(db, &config).await?)
+}
@@ -0,0 +1,376 @@
+use axum::{
+    extract::{Path, Query, State},
+    http::{header, HeaderMap, StatusCode},
+    Json,
+};
+
+use crate::{
+    auth::media_url_service::MediaUrlService,
+    controllers::playlist_controller::SuccessResponse,
+    models::{
+        album::{Album, AlbumWithRelations, CreateAlbumRequest, UpdateAlbumRequest},
+        analysis::{AnalysisFailure, AnalysisFailureQuery, AnalysisReport},
+        cover::{ColorBackfillQuery, ColorBackfillReport},
+        artist::{Artist, CreateArtistRequest, UpdateArtistRequest},
+        duplicate::{DuplicateCluster, DuplicateQuery, MergeSongsRequest},
+        import::{ImportQuery, ImportReport},
+        scan::ScanStatus,
+        search_analytics::{
+            ClickThroughReport, SearchReportQuery, TopSearchQuery, ZeroResultQuery,
+            DEFAULT_REPORT_DAYS, DEFAULT_REPORT_LIMIT,
+        },
+        song::{CreateSongRequest, Song, UpdateSongRequest},
+        waveform::Waveform,
+    },
+    services::{
+        album_service::AlbumService,
+        artist_service::ArtistService,
+        audio_analysis_service::AudioAnalysisService,
+        color_service::ColorService,
+        fingerprint_service::{FingerprintService, DEFAULT_MIN_SIMILARITY},
+        import_service::{ImportFormat, ImportService},
+        loudness_service::LoudnessService,
+        search_analytics_service::SearchAnalyticsService,
+        song_service::SongService,
+        tempo_service::TempoService,
+        waveform_service::WaveformService,
+    },
+    AppState, Error,
+};
+
+pub struct AdminController;
+
+impl AdminController {
+    // -- Albums
+
+    pub async fn create_album(
+        State(state): State<AppState>,
+        Json(payload): Json<CreateAlbumRequest>,
+    ) -> Result<(StatusCode, Json<Album>), Error> {
+        // Une couleur fournie explicitement l'emporte sur l'extraction
+        let extract_color = payload.cover_url.is_some() && payload.dominant_color.is_none();
+
+        let mut album = AlbumService::create_album(&state.db, payload).await?;
+        state.suggest_index.invalidate();
+        if let (true, Some(album_thing)) = (extract_color, &album.id) {
+            album.dominant_color =
+                ColorService::refresh(&state.db, &state.media_config, album_thing).await?;
+        }
+        Ok((StatusCode::CREATED, Json(album)))
+    }
+
+    pub async fn update_album(
+        State(state): State<AppState>,
+        Path(album_id): Path<String>,
+        Json(payload): Json<UpdateAlbumRequest>,
+    ) -> Result<Json<Album>, Error> {
+        let extract_color = payload.cover_url.is_some() && payload.dominant_color.is_none();
+
+        let mut album = AlbumService::update_album(&state.db, &album_id, payload).await?;
+        state.suggest_index.invalidate();
+        if let (true, Some(album_thing)) = (extract_color, &album.id) {
+            album.dominant_color =
+                ColorService::refresh(&state.db, &state.media_config, album_thing).await?;
+        }
+        Ok(Json(album))
+    }
+
+    pub async fn delete_album(
+        State(state): State<AppState>,
+        Path(album_id): Path<String>,
+    ) -> Result<Json<SuccessResponse>, Error> {
+        AlbumService::delete_album(&state.db, &album_id).await?;
+        state.suggest_index.invalidate();
+        Ok(Json(SuccessResponse { success: true }))
+    }
+
+    pub async fn link_album_artist(
+        State(state): State<AppState>,
+        Path((album_id, artist_id)): Path<(String, String)>,
+    ) -> Result<Json<SuccessResponse>, Error> {
+        AlbumService::link_artist(&state.db, &album_id, &artist_id).await?;
+        Ok(Json(SuccessResponse { success: true }))
+    }
+
+    pub async fn unlink_album_artist(
+        State(state): State<AppState>,
+        Path((album_id, artist_id)): Path<(String, String)>,
+    ) -> Result<Json<SuccessResponse>, Error> {
+        AlbumService::unlink_artist(&state.db, &album_id, &artist_id).await?;
+        Ok(Json(SuccessResponse { success: true }))
+    }
+
+    pub async fn add_album_song(
+        State(state): State<AppState>,
+        Path((album_id, song_id)): Path<(String, String)>,
+    ) -> Result<Json<SuccessResponse>, Error> {
+        AlbumService::add_song(&state.db, &album_id, &song_id).await?;
+        Ok(Json(SuccessResponse { success: true }))
+    }
+
+    pub async fn remove_album_song(
+        State(state): State<AppState>,
+        Path((album_id, song_id)): Path<(String, String)>,
+    ) -> Result<Json<SuccessResponse>, Error> {
+        AlbumService::remove_song(&state.db, &album_id, &song_id).await?;
+        Ok(Json(SuccessResponse { success: true }))
+    }
+
+    // -- Artists
+
+    pub async fn create_artist(
+        State(state): State<AppState>,
+        Json(payload): Json<CreateArtistRequest>,
+    ) -> Result<(StatusCode, Json<Artist>), Error> {
+        let artist = ArtistService::create_artist(&state.db, payload).await?;
+        state.suggest_index.invalidate();
+        Ok((StatusCode::CREATED, Json(artist)))
+    }
+
+    pub async fn update_artist(
+        State(state): State<AppState>,
+        Path(artist_id): Path<String>,
+        Json(payload): Json<UpdateArtistRequest>,
+    ) -> Result<Json<Artist>, Error> {
+        let artist = ArtistService::update_artist(&state.db, &artist_id, payload).await?;
+        state.suggest_index.invalidate();
+        Ok(Json(artist))
+    }
+
+    pub async fn delete_artist(
+        State(state): State<AppState>,
+        Path(artist_id): Path<String>,
+    ) -> Result<Json<SuccessResponse>, Error> {
+        ArtistService::delete_artist(&state.db, &artist_id).await?;
+        state.suggest_index.invalidate();
+        Ok(Json(SuccessResponse { success: true }))
+    }
+
+    // -- Songs
+
+    pub async fn create_song(
+        State(state): State<AppState>,
+        Json(payload): Json<CreateSongRequest>,
+    ) -> Result<(StatusCode, Json<Song>), Error> {
+        let song = SongService::create_song(&state.db, payload).await?;
+        state.suggest_index.invalidate();
+        Ok((StatusCode::CREATED, Json(song)))
+    }
+
+    pub async fn update_song(
+        State(state): State<AppState>,
+        Path(song_id): Path<String>,
+        Json(payload): Json<UpdateSongRequest>,
+    ) -> Result<Json<Song>, Error> {
+        let song = SongService::update_song(&state.db, &song_id, payload).await?;
+        state.suggest_index.invalidate();
+        Ok(Json(song))
+    }
+
+    pub async fn delete_song(
+        State(state): State<AppState>,
+        Path(song_id): Path<String>,
+    ) -> Result<Json<SuccessResponse>, Error> {
+        SongService::delete_song(&state.db, &song_id).await?;
+        state.suggest_index.invalidate();
+        Ok(Json(SuccessResponse { success: true }))
+    }
+
+    pub async fn link_song_artist(
+        State(state): State<AppState>,
+        Path((song_id, artist_id)): Path<(String, String)>,
+    ) -> Result<Json<SuccessResponse>, Error> {
+        SongService::link_artist(&state.db, &song_id, &artist_id).await?;
+        Ok(Json(SuccessResponse { success: true }))
+    }
+
+    pub async fn unlink_song_artist(
+        State(state): State<AppState>,
+        Path((song_id, artist_id)): Path<(String, String)>,
+    ) -> Result<Json<SuccessResponse>, Error> {
+        SongService::unlink_artist(&state.db, &song_id, &artist_id).await?;
+        Ok(Json(SuccessResponse { success: true }))
+    }
+
+    // -- Import
+
+    /// Body is a JSON manifest, or a CSV one when sent as `text/csv`.
+    pub async fn import_catalog(
+        State(state): State<AppState>,
+        Query(params): Query<ImportQuery>,
+        headers: HeaderMap,
+        body: String,
+    ) -> Result<Json<ImportReport>, Error> {
+        let content_type = headers
+            .get(header::CONTENT_TYPE)
+            .and_then(|v| v.to_str().ok());
+        let format = ImportFormat::from_content_type(content_type);
+        let dry_run = params.dry_run.unwrap_or(false);
+
+        let report = ImportService::import(&state.db, &body, format, dry_run).await?;
+        if report.applied {
+            state.suggest_index.invalidate();
+            ColorService::backfill(&state.db, &state.media_config, false).await?;
+        }
+        Ok(Json(report))
+    }
+
+    // -- Media scan
+
+    /// Starts a scan in the background; its report is read from
+    /// [`Self::scan_status`].
+    pub async fn scan_media(State(state): State<AppState>) -> (StatusCode, Json<ScanStatus>) {
+        let status = state.scan_task.start(
+            state.db.clone(),
+            state.media_config.clone(),
+            state.suggest_index.clone(),
+        );
+        (StatusCode::ACCEPTED, Json(status))
+    }
+
+    pub async fn scan_status(State(state): State<AppState>) -> Json<ScanStatus> {
+        Json(state.scan_task.status())
+    }
+
+    // -- Covers
+
+    pub async fn backfill_cover_colors(
+        State(state): State<AppState>,
+        Query(params): Query<ColorBackfillQuery>,
+    ) -> Result<Json<ColorBackfillReport>, Error> {
+        let force = params.force.unwrap_or(false);
+        let report = ColorService::backfill(&state.db, &state.media_config, force).await?;
+        Ok(Json(report))
+    }
+
+    // -- Audio analysis
+
+    /// Re-estimates the BPM of one song, even if it already has a tempo.
+    pub async fn analyze_song_tempo(
+        State(state): State<AppState>,
+        Path(song_id): Path<String>,
+    ) -> Result<Json<Song>, Error> {
+        let song = TempoService::analyze_song(&state.db, &state.media_config, &song_id).await?;
+        Ok(Json(song))
+    }
+
+    /// Recomputes the waveform peaks of one song from its current file.
+    pub async fn analyze_song_waveform(
+        State(state): State<AppState>,
+        Path(song_id): Path<String>,
+    ) -> Result<Json<Waveform>, Error> {
+        let waveform = WaveformService::analyze_song(&state.db, &state.media_config, &song_id).await?;
+        Ok(Json(waveform))
+    }
+
+    /// Measures the loudness and track gain of one song.
+    pub async fn analyze_song_loudness(
+        State(state): State<AppState>,
+        Path(song_id): Path<String>,
+    ) -> Result<Json<Song>, Error> {
+        let song = LoudnessService::analyze_song(&state.db, &state.media_config, &song_id).await?;
+        Ok(Json(song))
+    }
+
+    /// Measures every song of the album, then the album gain.
+    pub async fn analyze_album_loudness(
+        State(state): State<AppState>,
+        Path(album_id): Path<String>,
+    ) -> Result<Json<AlbumWithRelations>, Error> {
+        let album =
+            LoudnessService::analyze_album_by_id(&state.db, &state.media_config, &album_id).await?;
+        Ok(Json(album))
+    }
+
+    /// Recomputes the acoustic fingerprint of one song.
+    pub async fn analyze_song_fingerprint(
+        State(state): State<AppState>,
+        Path(song_id): Path<String>,
+    ) -> Result<Json<AnalysisReport>, Error> {
+        let report =
+            FingerprintService::analyze_song(&state.db, &state.media_config, &song_id).await?;
+        Ok(Json(report))
+    }
+
+    pub async fn list_analysis_failures(
+        State(state): State<AppState>,
+        Query(params): Query<AnalysisFailureQuery>,
+    ) -> Result<Json<Vec<AnalysisFailure>>, Error> {
+        let failures = AudioAnalysisService::list_failures(&state.db, params.kind).await?;
+        Ok(Json(failures))
+    }
+
+    // -- Duplicates
+
+    /// Clusters of probable duplicates among the fingerprinted songs.
+    pub async fn list_duplicates(
+        State(state): State<AppState>,
+        Query(params): Query<DuplicateQuery>,
+    ) -> Result<Json<Vec<DuplicateCluster>>, Error> {
+        let min_similarity = params.min_similarity.unwrap_or(DEFAULT_MIN_SIMILARITY);
+        let clusters = FingerprintService::find_duplicates(&state.db, min_similarity).await?;
+        Ok(Json(clusters))
+    }
+
+    /// Merges the given duplicates into the song of the path.
+    pub async fn merge_songs(
+        State(state): State<AppState>,
+        Path(song_id): Path<String>,
+        Json(payload): Json<MergeSongsRequest>,
+    ) -> Result<Json<Song>, Error> {
+        let song = SongService::merge_songs(&state.db, &song_id, &payload.duplicate_ids).await?;
+        state.suggest_index.invalidate();
+        Ok(Json(song))
+    }
+
+    // -- Users
+
+    /// Invalidates the media URLs already handed out to the user.
+    pub async fn revoke_user_media(
+        State(state): State<AppState>,
+        Path(user_id): Path<String>,
+    ) -> Result<Json<SuccessResponse>, Error> {
+        MediaUrlService::revoke_user(&state.db, &user_id).await?;
+        Ok(Json(SuccessResponse { success: true }))
+    }
+
+    // -- Search analytics
+
+    pub async fn search_top_queries(
+        State(state): State<AppState>,
+        Query(params): Query<SearchReportQuery>,
+    ) -> Result<Json<Vec<TopSearchQuery>>, Error> {
+        let queries = SearchAnalyticsService::top_queries(
+            &state.db,
+            params.days.unwrap_or(DEFAULT_REPORT_DAYS),
+            params.limit.unwrap_or(DEFAULT_REPORT_LIMIT),
+        )
+        .await?;
+        Ok(Json(queries))
+    }
+
+    pub async fn search_zero_result_queries(
+        State(state): State<AppState>,
+        Query(params): Query<SearchReportQuery>,
+    ) -> Result<Json<Vec<ZeroResultQuery>>, Error> {
+        let queries = SearchAnalyticsService::zero_result_queries(
+            &state.db,
+            params.days.unwrap_or(DEFAULT_REPORT_DAYS),
+            params.limit.unwrap_or(DEFAULT_REPORT_LIMIT),
+        )
+        .await?;
+        Ok(Json(queries))
+    }
+
+    pub async fn search_click_through(
+        State(state): State<AppState>,
+        Query(params): Query<SearchReportQuery>,
+    ) -> Result<Json<ClickThroughReport>, Error> {
+        let report = SearchAnalyticsService::click_through(
+            &state.db,
+            params.days.unwrap_or(DEFAULT_REPORT_DAYS),
+        )
+        .await?;
+        Ok(Json(report))
+    }
+}
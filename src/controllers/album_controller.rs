@@ -1,8 +1,9 @@
 use crate::{
+    auth::media_url_service::{MediaSubject, MediaUrlSigner, SignMediaUrls},
     error::Error,
     models::album::{AlbumWithArtists, AlbumWithRelations, AlbumsMetaResponse},
     models::database_helpers::CountResult,
-    services::album_service::AlbumService,
+    services::{album_service::AlbumService, image_service::AttachImageVariants},
     validators::listen_validator::{ListenValidator, ValidationResult},
     middlewares::mw_auth::Ctx,
     AppState,
@@ -53,18 +54,25 @@ impl AlbumController {
     pub async fn get_albums(
         State(state): State<AppState>,
     ) -> Result<Json<Vec<AlbumWithArtists>>> {
-        let albums = AlbumService::get_albums(&state.db).await?;
+        let mut albums = AlbumService::get_albums(&state.db).await?;
+        albums.attach_image_variants();
         Ok(Json(albums))
     }
 
     pub async fn get_album(
         State(state): State<AppState>,
         Path(album_id): Path<String>,
+        ConnectInfo(addr): ConnectInfo<SocketAddr>,
+        ctx: Option<Extension<Ctx>>,
     ) -> Result<Json<AlbumWithRelations>> {
-        let album = AlbumService::get_album(&state.db, &album_id)
+        let mut album = AlbumService::get_album(&state.db, &album_id)
             .await?
             .ok_or(Error::AlbumNotFound { id: album_id })?;
 
+        let subject = MediaSubject::from_request(ctx.as_deref(), addr.ip());
+        album.sign_media_urls(&MediaUrlSigner::new(&state.auth_config, subject));
+        album.attach_image_variants();
+
         Ok(Json(album))
     }
 
@@ -72,8 +80,9 @@ impl AlbumController {
         State(state): State<AppState>,
         Query(params): Query<InitialAlbumsQuery>,
     ) -> Result<Json<AlbumsMetaResponse>> {
-        let response =
+        let mut response =
             AlbumService::get_initial_albums_with_meta(&state.db, params.limit).await?;
+        response.attach_image_variants();
         Ok(Json(response))
     }
 
@@ -81,8 +90,9 @@ impl AlbumController {
         State(state): State<AppState>,
         Query(params): Query<AlbumsBatchQuery>,
     ) -> Result<Json<Vec<AlbumWithArtists>>> {
-        let albums =
+        let mut albums =
             AlbumService::get_albums_batch(&state.db, params.offset, params.limit).await?;
+        albums.attach_image_variants();
         Ok(Json(albums))
     }
 
@@ -98,7 +108,7 @@ impl AlbumController {
                 .collect::<Vec<String>>()
         });
 
-        let response = AlbumService::get_albums_filtered(
+        let mut response = AlbumService::get_albums_filtered(
             &state.db,
             params.offset,
             params.limit,
@@ -107,6 +117,7 @@ impl AlbumController {
             params.sort_by,
         )
         .await?;
+        response.attach_image_variants();
 
         Ok(Json(response))
     }
@@ -1,11 +1,15 @@
+use std::net::SocketAddr;
+
 use axum::{
-    extract::{Path, State},
-    Json,
+    extract::{ConnectInfo, Path, State},
+    Extension, Json,
 };
 
 use crate::{
+    auth::media_url_service::{MediaSubject, MediaUrlSigner, SignMediaUrls},
+    middlewares::mw_auth::Ctx,
     models::artist::{Artist, ArtistWithAlbumsAndTopSongs},
-    services::artist_service::ArtistService,
+    services::{artist_service::ArtistService, image_service::AttachImageVariants},
     AppState, Error,
 };
 
@@ -13,18 +17,25 @@ pub struct ArtistController;
 
 impl ArtistController {
     pub async fn get_artists(State(state): State<AppState>) -> Result<Json<Vec<Artist>>, Error> {
-        let albums = ArtistService::get_artists(&state.db).await?;
-        Ok(Json(albums))
+        let mut artists = ArtistService::get_artists(&state.db).await?;
+        artists.attach_image_variants();
+        Ok(Json(artists))
     }
 
     pub async fn get_artist(
         State(state): State<AppState>,
         Path(artist_id): Path<String>,
+        ConnectInfo(addr): ConnectInfo<SocketAddr>,
+        ctx: Option<Extension<Ctx>>,
     ) -> Result<Json<ArtistWithAlbumsAndTopSongs>, Error> {
-        let album = ArtistService::get_artist(&state.db, &artist_id)
+        let mut artist = ArtistService::get_artist(&state.db, &artist_id)
             .await?
             .ok_or(Error::ArtistNotFound { id: artist_id })?;
 
-        Ok(Json(album))
+        let subject = MediaSubject::from_request(ctx.as_deref(), addr.ip());
+        artist.sign_media_urls(&MediaUrlSigner::new(&state.auth_config, subject));
+        artist.attach_image_variants();
+
+        Ok(Json(artist))
     }
 }
@@ -29,7 +29,7 @@ impl AuthController {
             Some(id) => {
                 let thing_str = thing_to_string(id);
                 let id_part = parse_id_part(&thing_str);
-                TokenService::create_token(id_part.to_string(), &state.auth_config)?
+                TokenService::create_token(id_part.to_string(), user.role, &state.auth_config)?
             }
             None => return Err(Error::LoginFail),
         };
@@ -0,0 +1,75 @@
+use std::net::SocketAddr;
+
+use axum::{
+    extract::{ConnectInfo, Query, State},
+    Extension, Json,
+};
+
+use crate::{
+    auth::media_url_service::{MediaSubject, MediaUrlSigner, SignMediaUrls},
+    middlewares::mw_auth::Ctx,
+    models::{
+        album::AlbumWithArtists,
+        artist::Artist,
+        chart::{Chart, ChartQuery, DEFAULT_CHART_LIMIT},
+        song::SongWithRelations,
+    },
+    services::{chart_service::ChartService, image_service::AttachImageVariants},
+    AppState, Result,
+};
+
+pub struct ChartController;
+
+impl ChartController {
+    pub async fn get_song_chart(
+        State(state): State<AppState>,
+        Query(query): Query<ChartQuery>,
+        ConnectInfo(addr): ConnectInfo<SocketAddr>,
+        ctx: Option<Extension<Ctx>>,
+    ) -> Result<Json<Chart<SongWithRelations>>> {
+        let mut chart = ChartService::get_song_chart(
+            &state.db,
+            query.period.unwrap_or_default(),
+            query.limit.unwrap_or(DEFAULT_CHART_LIMIT),
+        )
+        .await?;
+
+        let subject = MediaSubject::from_request(ctx.as_deref(), addr.ip());
+        chart.sign_media_urls(&MediaUrlSigner::new(&state.auth_config, subject));
+        chart.attach_image_variants();
+
+        Ok(Json(chart))
+    }
+
+    pub async fn get_album_chart(
+        State(state): State<AppState>,
+        Query(query): Query<ChartQuery>,
+    ) -> Result<Json<Chart<AlbumWithArtists>>> {
+        let mut chart = ChartService::get_album_chart(
+            &state.db,
+            query.period.unwrap_or_default(),
+            query.limit.unwrap_or(DEFAULT_CHART_LIMIT),
+        )
+        .await?;
+
+        chart.attach_image_variants();
+
+        Ok(Json(chart))
+    }
+
+    pub async fn get_artist_chart(
+        State(state): State<AppState>,
+        Query(query): Query<ChartQuery>,
+    ) -> Result<Json<Chart<Artist>>> {
+        let mut chart = ChartService::get_artist_chart(
+            &state.db,
+            query.period.unwrap_or_default(),
+            query.limit.unwrap_or(DEFAULT_CHART_LIMIT),
+        )
+        .await?;
+
+        chart.attach_image_variants();
+
+        Ok(Json(chart))
+    }
+}
@@ -1,11 +1,19 @@
-use crate::{error::Error, middlewares::mw_auth::Ctx, AppState};
+use crate::{
+    auth::media_url_service::{MediaSubject, MediaUrlSigner, SignMediaUrls},
+    error::Error,
+    middlewares::mw_auth::Ctx,
+    AppState,
+};
 use axum::{
     extract::{Path, Query, State},
     Extension, Json,
 };
 use reqwest::StatusCode;
 
-use crate::{models::favorite::*, services::favorite_service::FavoriteService};
+use crate::{
+    models::favorite::*,
+    services::{favorite_service::FavoriteService, image_service::AttachImageVariants},
+};
 
 pub struct FavoriteController;
 
@@ -66,7 +74,9 @@ impl FavoriteController {
         Extension(ctx): Extension<Ctx>,
         Query(query): Query<FavoritesQuery>,
     ) -> Result<Json<FavoritesResponse<AlbumWithFavoriteMetadata>>, Error> {
-        let albums = FavoriteService::get_favorite_albums(&state.db, &ctx.user_id, &query).await?;
+        let mut albums =
+            FavoriteService::get_favorite_albums(&state.db, &ctx.user_id, &query).await?;
+        albums.attach_image_variants();
 
         Ok(Json(albums))
     }
@@ -85,8 +95,9 @@ impl FavoriteController {
         Extension(ctx): Extension<Ctx>,
         Query(query): Query<FavoritesQuery>,
     ) -> Result<Json<FavoritesResponse<ArtistWithFavoriteMetadata>>, Error> {
-        let artists =
+        let mut artists =
             FavoriteService::get_favorite_artists(&state.db, &ctx.user_id, &query).await?;
+        artists.attach_image_variants();
 
         Ok(Json(artists))
     }
@@ -105,7 +116,11 @@ impl FavoriteController {
         Extension(ctx): Extension<Ctx>,
         Query(query): Query<FavoritesQuery>,
     ) -> Result<Json<FavoritesResponse<SongWithFavoriteMetadata>>, Error> {
-        let songs = FavoriteService::get_favorite_songs(&state.db, &ctx.user_id, &query).await?;
+        let mut songs = FavoriteService::get_favorite_songs(&state.db, &ctx.user_id, &query).await?;
+
+        let signer = MediaUrlSigner::new(&state.auth_config, MediaSubject::for_user(&ctx));
+        songs.sign_media_urls(&signer);
+        songs.attach_image_variants();
 
         Ok(Json(songs))
     }
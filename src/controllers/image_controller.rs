@@ -0,0 +1,20 @@
+use axum::{
+    extract::{Path, State},
+    http::HeaderMap,
+    response::Response,
+};
+
+use crate::{error::Result, services::image_service::ImageService, AppState};
+
+pub struct ImageController;
+
+impl ImageController {
+    /// Serves a cover or artist image from the media root, resized to `size`.
+    pub async fn get_image(
+        State(state): State<AppState>,
+        Path((size, format, path)): Path<(String, String, String)>,
+        headers: HeaderMap,
+    ) -> Result<Response> {
+        ImageService::serve_variant(&state.media_config, &size, &format, &path, &headers).await
+    }
+}
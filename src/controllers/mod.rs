@@ -1,9 +1,14 @@
+pub mod admin_controller;
 pub mod album_controller;
 pub mod artist_controller;
 pub mod auth_controller;
+pub mod chart_controller;
 pub mod favorite_controller;
+pub mod image_controller;
 pub mod user_controller;
 
 pub mod playlist_controller;
+pub mod radio_controller;
+pub mod recommendation_controller;
 pub mod search_controller;
 pub mod song_controller;
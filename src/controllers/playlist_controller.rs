@@ -7,8 +7,12 @@ use serde::Serialize;
 use surrealdb::sql::Thing;
 
 use crate::{
-    models::playlist::{CreatePlaylistRequest, Playlist, PlaylistWithSongs},
-    services::playlist_service::PlaylistService,
+    auth::media_url_service::{MediaSubject, MediaUrlSigner, SignMediaUrls},
+    models::playlist::{CreatePlaylistRequest, Playlist, PlaylistWithSongs, SaveMixRequest},
+    services::{
+        color_service::ColorService, image_service::AttachImageVariants,
+        mix_service::MixService, playlist_service::PlaylistService,
+    },
     middlewares::mw_auth::Ctx,
     AppState, Error,
 };
@@ -26,9 +30,14 @@ impl PlaylistController {
         Extension(ctx): Extension<Ctx>,
         Json(payload): Json<CreatePlaylistRequest>,
     ) -> Result<Json<Thing>, Error> {
+        let has_cover = payload.cover_url.is_some();
         let created_playlist_thing =
             PlaylistService::create_playlist(&state.db, &ctx.user_id, payload).await?;
 
+        if has_cover {
+            ColorService::refresh(&state.db, &state.media_config, &created_playlist_thing).await?;
+        }
+
         Ok(Json(created_playlist_thing))
     }
 
@@ -65,10 +74,33 @@ impl PlaylistController {
         State(state): State<AppState>,
         Extension(ctx): Extension<Ctx>,
     ) -> Result<Json<Vec<Playlist>>, Error> {
-        let result = PlaylistService::get_user_playlists(&state.db, &ctx.user_id).await?;
+        let mut result = PlaylistService::get_user_playlists(&state.db, &ctx.user_id).await?;
+        result.attach_image_variants();
         Ok(Json(result))
     }
 
+    /// Daily mixes of the signed-in user, generated on the first call
+    pub async fn get_my_mixes(
+        State(state): State<AppState>,
+        Extension(ctx): Extension<Ctx>,
+    ) -> Result<Json<Vec<Playlist>>, Error> {
+        let mut result = MixService::get_user_mixes(&state.db, &ctx.user_id).await?;
+        result.attach_image_variants();
+        Ok(Json(result))
+    }
+
+    /// Saves a copy of a daily mix as a normal playlist
+    pub async fn save_mix(
+        State(state): State<AppState>,
+        Extension(ctx): Extension<Ctx>,
+        Path(playlist_id): Path<String>,
+        Json(payload): Json<SaveMixRequest>,
+    ) -> Result<Json<Thing>, Error> {
+        let playlist = MixService::save_mix(&state.db, &ctx.user_id, &playlist_id, payload).await?;
+
+        Ok(Json(playlist))
+    }
+
     /// Récupère les playlists d'un utilisateur spécifique
     pub async fn get_user_playlists(
         State(state): State<AppState>,
@@ -81,16 +113,22 @@ impl PlaylistController {
             });
         }
 
-        let result = PlaylistService::get_user_playlists(&state.db, &user_id).await?;
+        let mut result = PlaylistService::get_user_playlists(&state.db, &user_id).await?;
+        result.attach_image_variants();
 
         Ok(Json(result))
     }
 
     pub async fn get_playlist_with_songs(
         State(state): State<AppState>,
+        Extension(ctx): Extension<Ctx>,
         Path(playlist_id): Path<String>,
     ) -> Result<Json<PlaylistWithSongs>, Error> {
-        let result = PlaylistService::get_playlist_with_songs(&state.db, &playlist_id).await?;
+        let mut result = PlaylistService::get_playlist_with_songs(&state.db, &playlist_id).await?;
+
+        let signer = MediaUrlSigner::new(&state.auth_config, MediaSubject::for_user(&ctx));
+        result.sign_media_urls(&signer);
+        result.attach_image_variants();
 
         Ok(Json(result))
     }
@@ -109,7 +147,8 @@ impl PlaylistController {
     pub async fn get_public_playlists(
         State(state): State<AppState>,
     ) -> Result<Json<Vec<Playlist>>, Error> {
-        let result = PlaylistService::get_public_playlists(&state.db).await?;
+        let mut result = PlaylistService::get_public_playlists(&state.db).await?;
+        result.attach_image_variants();
 
         Ok(Json(result))
     }
@@ -0,0 +1,42 @@
+use axum::{
+    extract::{Query, State},
+    Extension, Json,
+};
+
+use crate::{
+    auth::media_url_service::{MediaSubject, MediaUrlSigner, SignMediaUrls},
+    middlewares::mw_auth::Ctx,
+    models::radio::{RadioBatch, RadioFeedbackRequest, RadioQuery},
+    services::{image_service::AttachImageVariants, radio_service::RadioService},
+    AppState, Result,
+};
+
+pub struct RadioController;
+
+impl RadioController {
+    /// Next songs of a radio, started from `seed` or continued from `token`.
+    pub async fn get_next_batch(
+        State(state): State<AppState>,
+        Extension(ctx): Extension<Ctx>,
+        Query(query): Query<RadioQuery>,
+    ) -> Result<Json<RadioBatch>> {
+        let mut batch = RadioService::get_next_batch(&state.db, &ctx.user_id, &query).await?;
+
+        let signer = MediaUrlSigner::new(&state.auth_config, MediaSubject::for_user(&ctx));
+        batch.songs.sign_media_urls(&signer);
+        batch.songs.attach_image_variants();
+
+        Ok(Json(batch))
+    }
+
+    /// Skip or like of a played song, taken into account from the next batch.
+    pub async fn give_feedback(
+        State(state): State<AppState>,
+        Extension(ctx): Extension<Ctx>,
+        Json(payload): Json<RadioFeedbackRequest>,
+    ) -> Result<Json<bool>> {
+        RadioService::give_feedback(&state.db, &ctx.user_id, payload).await?;
+
+        Ok(Json(true))
+    }
+}
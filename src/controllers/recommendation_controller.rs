@@ -0,0 +1,58 @@
+use axum::{
+    extract::{Query, State},
+    Extension, Json,
+};
+
+use crate::{
+    auth::media_url_service::{MediaSubject, MediaUrlSigner, SignMediaUrls},
+    middlewares::mw_auth::Ctx,
+    models::recommendation::{
+        RecommendationQuery, ScoredAlbum, ScoredSong, DEFAULT_RECOMMENDATION_LIMIT,
+    },
+    services::{image_service::AttachImageVariants, recommendation_service::RecommendationService},
+    AppState, Result,
+};
+
+pub struct RecommendationController;
+
+impl RecommendationController {
+    /// Songs the user has not listened to or liked, from the co-occurrence
+    /// model, then the most popular ones.
+    pub async fn recommend_songs(
+        State(state): State<AppState>,
+        Extension(ctx): Extension<Ctx>,
+        Query(query): Query<RecommendationQuery>,
+    ) -> Result<Json<Vec<ScoredSong>>> {
+        let mut songs = RecommendationService::recommend_songs(
+            &state.db,
+            &ctx.user_id,
+            query.limit.unwrap_or(DEFAULT_RECOMMENDATION_LIMIT),
+        )
+        .await?;
+
+        let signer = MediaUrlSigner::new(&state.auth_config, MediaSubject::for_user(&ctx));
+        songs.sign_media_urls(&signer);
+        songs.attach_image_variants();
+
+        Ok(Json(songs))
+    }
+
+    /// Albums the user knows none of the songs of, from the co-occurrence
+    /// model, then the most popular ones.
+    pub async fn recommend_albums(
+        State(state): State<AppState>,
+        Extension(ctx): Extension<Ctx>,
+        Query(query): Query<RecommendationQuery>,
+    ) -> Result<Json<Vec<ScoredAlbum>>> {
+        let mut albums = RecommendationService::recommend_albums(
+            &state.db,
+            &ctx.user_id,
+            query.limit.unwrap_or(DEFAULT_RECOMMENDATION_LIMIT),
+        )
+        .await?;
+
+        albums.attach_image_variants();
+
+        Ok(Json(albums))
+    }
+}
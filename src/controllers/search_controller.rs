@@ -1,14 +1,16 @@
+use crate::auth::media_url_service::{MediaSubject, MediaUrlSigner, SignMediaUrls};
+use crate::controllers::playlist_controller::SuccessResponse;
 use crate::error::Result;
+use crate::middlewares::mw_auth::Ctx;
+use crate::models::search::{SearchOptions, SearchQuery, SuggestQuery, SuggestResult};
+use crate::models::search_analytics::SearchClickRequest;
+use crate::services::image_service::AttachImageVariants;
+use crate::services::search_analytics_service::SearchAnalyticsService;
 use crate::services::search_service::SearchService;
 use crate::{services::search_service::SearchResult, AppState};
-use axum::extract::{Query, State};
-use axum::Json;
-use serde::Deserialize;
-
-#[derive(Deserialize)]
-pub struct SearchQuery {
-    pub term: String,
-}
+use axum::extract::{ConnectInfo, Path, Query, State};
+use axum::{Extension, Json};
+use std::net::SocketAddr;
 
 pub struct SearchController;
 
@@ -16,6 +18,8 @@ impl SearchController {
     pub async fn search_albums_songs_artists(
         State(state): State<AppState>,
         Query(params): Query<SearchQuery>,
+        ConnectInfo(addr): ConnectInfo<SocketAddr>,
+        ctx: Option<Extension<Ctx>>,
     ) -> Result<Json<SearchResult>> {
         let term = params.term.trim();
 
@@ -25,15 +29,61 @@ impl SearchController {
             });
         }
 
-        if term.len() > 50 {
+        // Les requêtes structurées (`artist:"…" year:…`) dépassent vite 50 caractères
+        if term.len() > 200 {
             return Err(crate::error::Error::InvalidInput {
-                reason: "Search term cannot be longer than 50 characters".to_string(),
+                reason: "Search term cannot be longer than 200 characters".to_string(),
             });
         }
 
-        let result: SearchResult =
-            SearchService::search_albums_songs_artists(&state.db, term).await?;
+        let options = SearchOptions::from_query(&params)?;
+        let mut result: SearchResult = SearchService::search_albums_songs_artists(
+            &state.db,
+            &state.suggest_index,
+            term,
+            &options,
+        )
+        .await?;
+
+        // Les pages suivantes ne sont pas de nouvelles recherches ; un échec
+        // d'enregistrement ne doit pas faire échouer la recherche
+        if options.page == 1 {
+            match SearchAnalyticsService::record(&state.db, term, &result, ctx.is_some()).await {
+                Ok(search_id) => result.search_id = Some(search_id),
+                Err(e) => tracing::warn!("Failed to record search event: {:?}", e),
+            }
+        }
+
+        let subject = MediaSubject::from_request(ctx.as_deref(), addr.ip());
+        result.sign_media_urls(&MediaUrlSigner::new(&state.auth_config, subject));
+        result.attach_image_variants();
+
+        Ok(Json(result))
+    }
+
+    /// Autocomplete, answered from the in-memory index without querying the
+    /// database.
+    pub async fn suggest(
+        State(state): State<AppState>,
+        Query(params): Query<SuggestQuery>,
+    ) -> Result<Json<SuggestResult>> {
+        if params.q.len() > 50 {
+            return Err(crate::error::Error::InvalidInput {
+                reason: "Search term cannot be longer than 50 characters".to_string(),
+            });
+        }
 
+        let result = state.suggest_index.suggest(&state.db, &params.q).await?;
         Ok(Json(result))
     }
+
+    /// Reports that a result of the search `search_id` was opened.
+    pub async fn record_click(
+        State(state): State<AppState>,
+        Path(search_id): Path<String>,
+        Json(payload): Json<SearchClickRequest>,
+    ) -> Result<Json<SuccessResponse>> {
+        SearchAnalyticsService::record_click(&state.db, &search_id, &payload.result_id).await?;
+        Ok(Json(SuccessResponse { success: true }))
+    }
 }
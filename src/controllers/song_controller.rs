@@ -1,12 +1,17 @@
 use crate::{
+    auth::media_url_service::{MediaSubject, MediaUrlSigner, SignMediaUrls},
     error::Result, middlewares::mw_auth::Ctx, models::{
         album::AlbumWithRelations,
         pagination::{PaginatedResponse, PaginationQuery},
+        recommendation::{ScoredSong, RecommendationQuery, DEFAULT_RECOMMENDATION_LIMIT},
         song::{SongWithRelations},
-    }, services::song_service::{ListenResult, SongService}, validators::listen_validator::{ListenValidator, ValidationResult}, AppState, Error
+        waveform::{WaveformFormat, WaveformQuery},
+    }, services::{image_service::AttachImageVariants, media_service::MediaService, recommendation_service::RecommendationService, song_service::{ListenResult, SongService}, waveform_service::WaveformService}, validators::listen_validator::{ListenValidator, ValidationResult}, AppState, Error
 };
 use axum::{
     extract::{ConnectInfo, Path, Query, State},
+    http::{header, HeaderMap, HeaderValue},
+    response::{IntoResponse, Response},
     Extension, Json,
 };
 use std::net::SocketAddr;
@@ -56,7 +61,11 @@ impl SongController {
         Extension(ctx): Extension<Ctx>,
         Query(query): Query<PaginationQuery>,
     ) -> Result<Json<PaginatedResponse<SongWithRelations>>> {
-        let result = SongService::get_user_recent_listens(&state.db, &ctx.user_id, &query).await?;
+        let mut result = SongService::get_user_recent_listens(&state.db, &ctx.user_id, &query).await?;
+
+        let signer = MediaUrlSigner::new(&state.auth_config, MediaSubject::for_user(&ctx));
+        result.sign_media_urls(&signer);
+        result.attach_image_variants();
 
         Ok(Json(result))
     }
@@ -64,11 +73,115 @@ impl SongController {
     pub async fn get_album_from_song(
         State(state): State<AppState>,
         Path(song_id): Path<String>,
+        ConnectInfo(addr): ConnectInfo<SocketAddr>,
+        ctx: Option<Extension<Ctx>>,
     ) -> Result<Json<AlbumWithRelations>> {
-        let album = SongService::get_album_from_song(&state.db, &song_id)
+        let mut album = SongService::get_album_from_song(&state.db, &song_id)
             .await?
             .ok_or(Error::AlbumNotFound { id: song_id })?;
 
+        let subject = MediaSubject::from_request(ctx.as_deref(), addr.ip());
+        album.sign_media_urls(&MediaUrlSigner::new(&state.auth_config, subject));
+        album.attach_image_variants();
+
         Ok(Json(album))
     }
+
+    /// Other songs ranked by shared artists, album genres and languages, and
+    /// tempo proximity.
+    pub async fn get_similar_songs(
+        State(state): State<AppState>,
+        Path(song_id): Path<String>,
+        Query(query): Query<RecommendationQuery>,
+        ConnectInfo(addr): ConnectInfo<SocketAddr>,
+        ctx: Option<Extension<Ctx>>,
+    ) -> Result<Json<Vec<ScoredSong>>> {
+        let mut songs = RecommendationService::get_similar_songs(
+            &state.db,
+            &song_id,
+            query.limit.unwrap_or(DEFAULT_RECOMMENDATION_LIMIT),
+        )
+        .await?;
+
+        let subject = MediaSubject::from_request(ctx.as_deref(), addr.ip());
+        songs.sign_media_urls(&MediaUrlSigner::new(&state.auth_config, subject));
+        songs.attach_image_variants();
+
+        Ok(Json(songs))
+    }
+
+    /// Streams the song's file from the media root, with `Range` support.
+    /// The signed URL has already been checked by `verify_media_signature`.
+    pub async fn stream_song(
+        State(state): State<AppState>,
+        Path(song_id): Path<String>,
+        headers: HeaderMap,
+    ) -> Result<Response> {
+        let song = SongService::get_song_by_id(&state.db, &song_id)
+            .await?
+            .ok_or_else(|| Error::SongNotFound {
+                id: song_id.clone(),
+            })?;
+
+        let path = state
+            .media_config
+            .resolve(&song.file_url)
+            .filter(|path| path.is_file())
+            .ok_or(Error::MediaNotFound { song_id })?;
+
+        MediaService::serve_file(&path, &headers).await
+    }
+
+    /// Min/max peaks of the song, as JSON or as interleaved signed bytes
+    /// (`format=binary`) for a single resolution.
+    pub async fn get_song_waveform(
+        State(state): State<AppState>,
+        Path(song_id): Path<String>,
+        Query(query): Query<WaveformQuery>,
+    ) -> Result<Response> {
+        let mut waveform = WaveformService::get_waveform(&state.db, &song_id).await?;
+
+        let mut response = match query.format.unwrap_or_default() {
+            WaveformFormat::Json => {
+                if query.points.is_some() {
+                    let peaks = waveform.resolution(query.points).cloned();
+                    waveform.resolutions = peaks.into_iter().collect();
+                }
+                Json(waveform).into_response()
+            }
+            WaveformFormat::Binary => {
+                let peaks = waveform
+                    .resolution(query.points)
+                    .ok_or(Error::WaveformNotFound { song_id })?;
+                let body: Vec<u8> = peaks
+                    .min
+                    .iter()
+                    .zip(&peaks.max)
+                    .flat_map(|(&min, &max)| [min as u8, max as u8])
+                    .collect();
+
+                let mut response = body.into_response();
+                let headers = response.headers_mut();
+                headers.insert(
+                    header::CONTENT_TYPE,
+                    HeaderValue::from_static("application/octet-stream"),
+                );
+                headers.insert("x-waveform-points", HeaderValue::from(peaks.points));
+                headers.insert(
+                    "x-waveform-duration",
+                    HeaderValue::from_str(&waveform.duration_secs.to_string())
+                        .expect("a float is a valid header value"),
+                );
+                response
+            }
+        };
+
+        // Les pics ne changent qu'avec le fichier
+        response.headers_mut().insert(
+            header::CACHE_CONTROL,
+            HeaderValue::from_static("public, max-age=86400"),
+        );
+
+        Ok(response)
+    }
 }
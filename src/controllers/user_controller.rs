@@ -3,9 +3,20 @@ use axum::{
     Extension, Json,
 };
 
+use chrono::{Datelike, Utc};
+
 use crate::{
-    models::user::UserProfile, services::user_service::UserService, middlewares::mw_auth::Ctx, AppState,
-    Error,
+    auth::media_url_service::{MediaSubject, MediaUrlSigner, SignMediaUrls},
+    models::{
+        user::UserProfile,
+        wrapped::{WrappedQuery, WrappedReport},
+    },
+    services::{
+        image_service::AttachImageVariants, user_service::UserService,
+        wrapped_service::WrappedService,
+    },
+    middlewares::mw_auth::Ctx,
+    AppState, Error,
 };
 
 pub struct UserController;
@@ -19,6 +30,22 @@ impl UserController {
         Ok(Json(result))
     }
 
+    /// Year in review of the signed-in user, the current year by default
+    pub async fn get_my_wrapped(
+        State(state): State<AppState>,
+        Extension(ctx): Extension<Ctx>,
+        Query(query): Query<WrappedQuery>,
+    ) -> Result<Json<WrappedReport>, Error> {
+        let year = query.year.unwrap_or_else(|| Utc::now().year());
+        let mut report = WrappedService::get_wrapped(&state.db, &ctx.user_id, year).await?;
+
+        let signer = MediaUrlSigner::new(&state.auth_config, MediaSubject::for_user(&ctx));
+        report.sign_media_urls(&signer);
+        report.attach_image_variants();
+
+        Ok(Json(report))
+    }
+
     pub async fn get_user_profile(
         State(state): State<AppState>,
         // todo: forcer l'auth
@@ -14,12 +14,19 @@ pub enum Error {
     AuthFailNoAuthTokenCookie,
     AuthFailTokenWrongFormat,
     AuthFailCtxNotInRequestExt,
+    Forbidden {
+        required_role: String,
+    },
+    InvalidMediaSignature {
+        reason: String,
+    },
     TokenCreationError(String),
     InvalidToken,
     InvalidUsername,
 
     EnvVarError(String),
     DbError(String),
+    SerializationError(String),
     AlbumNotFound {
         id: String,
     },
@@ -32,6 +39,25 @@ pub enum Error {
     PlaylistNotFound {
         id: String,
     },
+    MediaNotFound {
+        song_id: String,
+    },
+    ImageNotFound {
+        path: String,
+    },
+    WaveformNotFound {
+        song_id: String,
+    },
+    SearchEventNotFound {
+        id: String,
+    },
+    RadioSessionNotFound {
+        token: String,
+    },
+    AnalysisFailed {
+        song_id: String,
+        reason: String,
+    },
     UserAlreadyExists {
         username: String,
     },
@@ -119,6 +145,10 @@ impl Error {
             | Self::AuthFailTokenWrongFormat
             | Self::AuthFailCtxNotInRequestExt => (StatusCode::FORBIDDEN, ClientError::NO_AUTH),
 
+            Self::Forbidden { .. } | Self::InvalidMediaSignature { .. } => {
+                (StatusCode::FORBIDDEN, ClientError::ACCESS_DENIED)
+            }
+
             Self::TokenCreationError { .. } | Self::InvalidToken => {
                 (StatusCode::INTERNAL_SERVER_ERROR, ClientError::TOKEN_ERROR)
             }
@@ -139,6 +169,16 @@ impl Error {
             Error::PlaylistNotFound { id: _ } => {
                 (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND)
             }
+            Error::MediaNotFound { .. }
+            | Error::ImageNotFound { .. }
+            | Error::WaveformNotFound { .. }
+            | Error::SearchEventNotFound { .. }
+            | Error::RadioSessionNotFound { .. } => {
+                (StatusCode::NOT_FOUND, ClientError::RESOURCE_NOT_FOUND)
+            }
+            Error::AnalysisFailed { .. } => {
+                (StatusCode::UNPROCESSABLE_ENTITY, ClientError::ANALYSIS_FAILED)
+            }
 
             Error::UserAlreadyExists { username: _ } => {
                 (StatusCode::CONFLICT, ClientError::USER_ALREADY_EXISTS)
@@ -172,6 +212,7 @@ impl Error {
 pub enum ClientError {
     LOGIN_FAIL,
     NO_AUTH,
+    ACCESS_DENIED,
     INVALID_PARAMS,
     SERVICE_ERROR,
     RESOURCE_NOT_FOUND,
@@ -180,6 +221,7 @@ pub enum ClientError {
     USER_ALREADY_EXISTS,
     INVALID_CREDENTIALS,
     INVALID_CAPTCHA,
+    ANALYSIS_FAILED,
 }
 
 impl From<surrealdb::Error> for Error {
@@ -206,6 +248,12 @@ impl From<jsonwebtoken::errors::Error> for Error {
     }
 }
 
+impl From<serde_json::Error> for Error {
+    fn from(err: serde_json::Error) -> Self {
+        Error::SerializationError(err.to_string())
+    }
+}
+
 impl From<std::io::Error> for Error {
     fn from(err: std::io::Error) -> Self {
         Error::DbError(format!("IO error: {}", err))
@@ -27,10 +27,12 @@ mod tests {
             id: None,
             title: "AlbumTitle".to_string(),
             cover_url: Some("https://example.com/cover.jpg".to_string()),
+            cover_variants: None,
             release_year: Some(2001),
             genres: vec!["RAP".to_string(), "ROCK_PSY".to_string()],
             langs: vec!["en".to_string()],
             dominant_color: Some("#D4AF37".to_string()),
+            loudness: None,
             total_tracks: 14,
             total_duration: Duration::from_secs(100),
             total_listens: 999999,
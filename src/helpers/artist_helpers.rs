@@ -33,6 +33,7 @@ mod tests {
             genres: vec![MusicGenre::Rap, MusicGenre::PsychedelicRock],
             country_code: "fr".to_string(),
             artist_image: Some("https://example.com/artist.jpg".to_string()),
+            image_variants: None,
             albums_count: 3,
             songs_count: 10,
             total_likes: 0,
@@ -1,4 +1,5 @@
 pub mod album_helpers;
 pub mod artist_helpers;
+pub mod search_helpers;
 pub mod song_helpers;
 pub mod thing_helpers;
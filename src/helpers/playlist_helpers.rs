@@ -103,6 +103,7 @@ mod tests {
             id: None,
             name: "Test Playlist".to_string(),
             cover_url: Some("https://example.com/cover.jpg".to_string()),
+            cover_variants: None,
             is_public: true,
             dominant_color: Some("#FF0000".to_string()),
             songs_count: 0,
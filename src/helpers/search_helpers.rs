@@ -0,0 +1,280 @@
+use std::{ops::Bound, str::FromStr};
+
+use crate::{
+    error::{Error, Result},
+    models::search::{normalize_genre, parse_types, split_list, SearchOptions},
+};
+
+const FIELDS: &str = "artist, genre, lang, year, tempo, country, type";
+
+/// Parses a structured search term such as
+/// `artist:"The Clash" genre:OI year:1977..1980 tempo:>140 lang:en london`.
+///
+/// Each `field:value` pair is moved into `options`, the values being bound as
+/// query parameters later on, never spliced into SurrealQL. Values containing
+/// spaces are double-quoted. The remaining words are returned as the
+/// full-text part of the query; a term without any field is returned as is,
+/// and so is a word ending with `:` such as `Star Wars: Episode IV`.
+///
+/// | Field | Value |
+/// |---|---|
+/// | `artist` | part of an artist name |
+/// | `genre`, `lang` | comma-separated list |
+/// | `year`, `tempo` | `140`, `>140`, `>=140`, `<140`, `<=140`, `120..140`, `120..`, `..140` |
+/// | `country` | country code |
+/// | `type` | `album`, `song`, `artist`, `playlist` and/or `user` |
+///
+/// An unknown field or a malformed value is an [`Error::InvalidInput`].
+pub fn parse_search_term(term: &str, options: &mut SearchOptions) -> Result<String> {
+    let mut text = Vec::new();
+
+    for (field, value) in tokenize(term) {
+        let Some(field) = field else {
+            text.push(value);
+            continue;
+        };
+
+        let value = value.trim();
+        match field.to_lowercase().as_str() {
+            "artist" => options.artist = Some(value.to_lowercase()),
+            "genre" | "genres" => options
+                .genres
+                .extend(split_list(Some(value)).map(|genre| normalize_genre(&genre))),
+            "lang" | "langs" => options
+                .langs
+                .extend(split_list(Some(value)).map(|lang| lang.to_lowercase())),
+            "year" => {
+                let (from, to) = parse_range::<u16>("year", value)?;
+                options.year_from = match from {
+                    Bound::Included(year) => Some(year),
+                    Bound::Excluded(year) => Some(year.saturating_add(1)),
+                    Bound::Unbounded => None,
+                };
+                options.year_to = match to {
+                    Bound::Included(year) => Some(year),
+                    Bound::Excluded(year) => Some(year.saturating_sub(1)),
+                    Bound::Unbounded => None,
+                };
+            }
+            "tempo" | "bpm" => {
+                let (min, max) = parse_range::<f64>("tempo", value)?;
+                options.tempo_min = match min {
+                    Bound::Included(bpm) => Some(bpm),
+                    Bound::Excluded(bpm) => Some(bpm.next_up()),
+                    Bound::Unbounded => None,
+                };
+                options.tempo_max = match max {
+                    Bound::Included(bpm) => Some(bpm),
+                    Bound::Excluded(bpm) => Some(bpm.next_down()),
+                    Bound::Unbounded => None,
+                };
+            }
+            "country" => options.country_code = Some(value.to_uppercase()),
+            "type" | "types" => options.types = parse_types(Some(value))?,
+            other => {
+                return Err(Error::InvalidInput {
+                    reason: format!(
+                        "Unknown search field '{}', expected one of: {}",
+                        other, FIELDS
+                    ),
+                })
+            }
+        }
+    }
+
+    if let (Some(from), Some(to)) = (options.year_from, options.year_to) {
+        if from > to {
+            return Err(Error::InvalidInput {
+                reason: format!("Empty year range {}..{}", from, to),
+            });
+        }
+    }
+    if let (Some(min), Some(max)) = (options.tempo_min, options.tempo_max) {
+        if min > max {
+            return Err(Error::InvalidInput {
+                reason: format!("Empty tempo range {}..{}", min, max),
+            });
+        }
+    }
+
+    Ok(text.join(" "))
+}
+
+/// Splits on whitespace outside double quotes. A word whose first `:`, before
+/// any quote, follows letters only and precedes a value is a `field:value`
+/// pair.
+fn tokenize(term: &str) -> Vec<(Option<String>, String)> {
+    let mut tokens = Vec::new();
+    let mut field: Option<String> = None;
+    let mut current = String::new();
+    let mut quoted = false;
+    let mut seen_quote = false;
+
+    let mut flush = |field: &mut Option<String>, current: &mut String| {
+        match field.take() {
+            // Sans valeur, « Wars: » reste du texte
+            Some(name) if current.trim().is_empty() => {
+                tokens.push((None, format!("{}:{}", name, std::mem::take(current))))
+            }
+            Some(name) => tokens.push((Some(name), std::mem::take(current))),
+            None if !current.is_empty() => tokens.push((None, std::mem::take(current))),
+            None => {}
+        }
+    };
+
+    for c in term.chars() {
+        match c {
+            '"' => {
+                quoted = !quoted;
+                seen_quote = true;
+            }
+            c if c.is_whitespace() && !quoted => {
+                flush(&mut field, &mut current);
+                seen_quote = false;
+            }
+            ':' if field.is_none()
+                && !seen_quote
+                && !current.is_empty()
+                && current.chars().all(|c| c.is_ascii_alphabetic()) =>
+            {
+                field = Some(std::mem::take(&mut current));
+            }
+            c => current.push(c),
+        }
+    }
+    flush(&mut field, &mut current);
+
+    tokens
+}
+
+/// Parses `n`, `>n`, `>=n`, `<n`, `<=n`, `a..b`, `a..` or `..b`.
+fn parse_range<T: FromStr + Copy>(field: &str, value: &str) -> Result<(Bound<T>, Bound<T>)> {
+    let invalid = || Error::InvalidInput {
+        reason: format!("Invalid {} '{}'", field, value),
+    };
+    let number = |text: &str| text.trim().parse::<T>().map_err(|_| invalid());
+
+    let range = if let Some(rest) = value.strip_prefix(">=") {
+        (Bound::Included(number(rest)?), Bound::Unbounded)
+    } else if let Some(rest) = value.strip_prefix('>') {
+        (Bound::Excluded(number(rest)?), Bound::Unbounded)
+    } else if let Some(rest) = value.strip_prefix("<=") {
+        (Bound::Unbounded, Bound::Included(number(rest)?))
+    } else if let Some(rest) = value.strip_prefix('<') {
+        (Bound::Unbounded, Bound::Excluded(number(rest)?))
+    } else if let Some((from, to)) = value.split_once("..") {
+        let bound = |text: &str| -> Result<Bound<T>> {
+            if text.trim().is_empty() {
+                Ok(Bound::Unbounded)
+            } else {
+                Ok(Bound::Included(number(text)?))
+            }
+        };
+        match (bound(from)?, bound(to)?) {
+            (Bound::Unbounded, Bound::Unbounded) => return Err(invalid()),
+            range => range,
+        }
+    } else {
+        let exact = number(value)?;
+        (Bound::Included(exact), Bound::Included(exact))
+    };
+
+    Ok(range)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::search::SearchType;
+
+    fn parse(term: &str) -> Result<(String, SearchOptions)> {
+        let mut options = SearchOptions::default();
+        let text = parse_search_term(term, &mut options)?;
+        Ok((text, options))
+    }
+
+    #[test]
+    fn test_plain_text_is_kept() {
+        let (text, options) = parse("London Calling").unwrap();
+        assert_eq!(text, "London Calling");
+        assert_eq!(options, SearchOptions::default());
+
+        // Un « : » après autre chose que des lettres reste du texte
+        let (text, _) = parse("12:30 \"Live: Paris\"").unwrap();
+        assert_eq!(text, "12:30 Live: Paris");
+    }
+
+    #[test]
+    fn test_word_ending_with_colon_is_text() {
+        let (text, options) = parse("Star Wars: Episode IV").unwrap();
+        assert_eq!(text, "Star Wars: Episode IV");
+        assert_eq!(options, SearchOptions::default());
+
+        let (text, options) = parse("Live: Paris genre:rock artist:").unwrap();
+        assert_eq!(text, "Live: Paris artist:");
+        assert_eq!(options.genres, vec!["rock"]);
+    }
+
+    #[test]
+    fn test_fields_are_parsed() {
+        let (text, options) = parse(
+            r#"artist:"The Clash" genre:OI,Punk year:1990..1995 tempo:>140 lang:fr country:gb type:songs london"#,
+        )
+        .unwrap();
+
+        assert_eq!(text, "london");
+        assert_eq!(options.artist.as_deref(), Some("the clash"));
+        assert_eq!(options.genres, vec!["oi", "punk"]);
+        assert_eq!(options.langs, vec!["fr"]);
+        assert_eq!(
+            (options.year_from, options.year_to),
+            (Some(1990), Some(1995))
+        );
+        assert!(options.tempo_min.unwrap() > 140.0 && options.tempo_min.unwrap() < 140.001);
+        assert_eq!(options.tempo_max, None);
+        assert_eq!(options.country_code.as_deref(), Some("GB"));
+        assert_eq!(options.types, vec![SearchType::Song]);
+    }
+
+    #[test]
+    fn test_ranges() {
+        let years = |term: &str| {
+            let (_, options) = parse(term).unwrap();
+            (options.year_from, options.year_to)
+        };
+        assert_eq!(years("year:1977"), (Some(1977), Some(1977)));
+        assert_eq!(years("year:>1977"), (Some(1978), None));
+        assert_eq!(years("year:<=1980"), (None, Some(1980)));
+        assert_eq!(years("year:<1980"), (None, Some(1979)));
+        assert_eq!(years("year:1977.."), (Some(1977), None));
+
+        let (_, options) = parse("tempo:120..128.5").unwrap();
+        assert_eq!(
+            (options.tempo_min, options.tempo_max),
+            (Some(120.0), Some(128.5))
+        );
+    }
+
+    #[test]
+    fn test_invalid_fields_are_rejected() {
+        for term in [
+            "label:Stiff",
+            "year:soon",
+            "year:..",
+            "year:1995..1990",
+            "tempo:>fast",
+            "type:podcast",
+        ] {
+            assert!(
+                matches!(parse(term), Err(Error::InvalidInput { .. })),
+                "{} should be rejected",
+                term
+            );
+        }
+
+        let Err(Error::InvalidInput { reason }) = parse("label:Stiff") else {
+            panic!("label should be rejected");
+        };
+        assert!(reason.contains("'label'") && reason.contains("artist, genre"));
+    }
+}
@@ -26,6 +26,7 @@ mod tests {
             duration: Duration::from_secs(100),
             song_index: 1,
             tempo: 150.0,
+            loudness: None,
             total_listens: 100,
             total_user_listens: 100,
             total_likes: 100,
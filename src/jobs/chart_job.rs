@@ -0,0 +1,18 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::services::chart_service::ChartService;
+
+const DEFAULT_INTERVAL_SECS: u64 = 900;
+
+/// Recomputes the song, album and artist charts of every period.
+pub fn spawn(db: Surreal<Any>) {
+    super::spawn_periodic(
+        "charts",
+        "CHART_JOB_INTERVAL_SECS",
+        DEFAULT_INTERVAL_SECS,
+        move || {
+            let db = db.clone();
+            async move { ChartService::rebuild(&db).await }
+        },
+    );
+}
@@ -0,0 +1,18 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::services::recommendation_service::RecommendationService;
+
+const DEFAULT_INTERVAL_SECS: u64 = 3600;
+
+/// Rebuilds the song co-occurrence table behind the personal recommendations.
+pub fn spawn(db: Surreal<Any>) {
+    super::spawn_periodic(
+        "cooccurrence",
+        "COOCCURRENCE_JOB_INTERVAL_SECS",
+        DEFAULT_INTERVAL_SECS,
+        move || {
+            let db = db.clone();
+            async move { RecommendationService::rebuild_cooccurrence(&db).await }
+        },
+    );
+}
@@ -0,0 +1,19 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::services::{fingerprint_service::FingerprintService, media_service::MediaConfig};
+
+const DEFAULT_INTERVAL_SECS: u64 = 3600;
+
+/// Fingerprints the songs that have no fingerprint yet.
+pub fn spawn(db: Surreal<Any>, config: MediaConfig) {
+    super::spawn_periodic(
+        "fingerprint",
+        "FINGERPRINT_JOB_INTERVAL_SECS",
+        DEFAULT_INTERVAL_SECS,
+        move || {
+            let db = db.clone();
+            let config = config.clone();
+            async move { FingerprintService::analyze_pending(&db, &config).await }
+        },
+    );
+}
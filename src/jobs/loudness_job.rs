@@ -0,0 +1,19 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::services::{loudness_service::LoudnessService, media_service::MediaConfig};
+
+const DEFAULT_INTERVAL_SECS: u64 = 3600;
+
+/// Measures the loudness of albums and songs that don't have one yet.
+pub fn spawn(db: Surreal<Any>, config: MediaConfig) {
+    super::spawn_periodic(
+        "loudness",
+        "LOUDNESS_JOB_INTERVAL_SECS",
+        DEFAULT_INTERVAL_SECS,
+        move || {
+            let db = db.clone();
+            let config = config.clone();
+            async move { LoudnessService::analyze_pending(&db, &config).await }
+        },
+    );
+}
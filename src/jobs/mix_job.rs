@@ -0,0 +1,18 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::services::mix_service::MixService;
+
+const DEFAULT_INTERVAL_SECS: u64 = 86400;
+
+/// Regenerates the daily mixes of every user with a listening history.
+pub fn spawn(db: Surreal<Any>) {
+    super::spawn_periodic(
+        "mixes",
+        "DAILY_MIX_JOB_INTERVAL_SECS",
+        DEFAULT_INTERVAL_SECS,
+        move || {
+            let db = db.clone();
+            async move { MixService::refresh_all(&db).await }
+        },
+    );
+}
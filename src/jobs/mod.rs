@@ -0,0 +1,70 @@
+use std::{env, future::Future, time::Duration};
+
+use serde::Serialize;
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    services::{media_service::MediaConfig, suggest_service::SuggestIndex},
+    Result,
+};
+
+pub mod chart_job;
+pub mod cooccurrence_job;
+pub mod fingerprint_job;
+pub mod loudness_job;
+pub mod mix_job;
+pub mod suggest_job;
+pub mod tempo_job;
+pub mod waveform_job;
+
+/// Starts the background jobs. Each one runs on its own interval, read from
+/// the environment; an interval of `0` disables the job.
+pub fn spawn_all(db: &Surreal<Any>, media_config: &MediaConfig, suggest_index: &SuggestIndex) {
+    tempo_job::spawn(db.clone(), media_config.clone());
+    waveform_job::spawn(db.clone(), media_config.clone());
+    loudness_job::spawn(db.clone(), media_config.clone());
+    fingerprint_job::spawn(db.clone(), media_config.clone());
+    suggest_job::spawn(db.clone(), suggest_index.clone());
+    cooccurrence_job::spawn(db.clone());
+    mix_job::spawn(db.clone());
+    chart_job::spawn(db.clone());
+}
+
+/// Runs `task` now, then every `interval_var` seconds (`default_secs` when unset),
+/// logging the report of each pass or its error.
+pub(crate) fn spawn_periodic<F, Fut, R>(name: &'static str, interval_var: &str, default_secs: u64, task: F)
+where
+    F: Fn() -> Fut + Send + 'static,
+    Fut: Future<Output = Result<R>> + Send,
+    R: Serialize,
+{
+    let interval_secs = env::var(interval_var)
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(default_secs);
+
+    if interval_secs == 0 {
+        tracing::info!("Job {} disabled ({}=0)", name, interval_var);
+        return;
+    }
+
+    tracing::info!("Job {} scheduled every {}s", name, interval_secs);
+
+    tokio::spawn(async move {
+        let mut interval = tokio::time::interval(Duration::from_secs(interval_secs));
+        // Un passage trop long ne doit pas déclencher une rafale de rattrapage
+        interval.set_missed_tick_behavior(tokio::time::MissedTickBehavior::Skip);
+
+        loop {
+            interval.tick().await;
+            match task().await {
+                Ok(report) => tracing::info!(
+                    "Job {}: {}",
+                    name,
+                    serde_json::to_string(&report).unwrap_or_else(|e| e.to_string())
+                ),
+                Err(e) => tracing::error!("Job {} failed: {:?}", name, e),
+            }
+        }
+    });
+}
@@ -0,0 +1,20 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::services::suggest_service::SuggestIndex;
+
+const DEFAULT_INTERVAL_SECS: u64 = 600;
+
+/// Rebuilds the autocomplete index, to pick up the catalog changes made
+/// outside of the API (command line scans and imports, direct edits).
+pub fn spawn(db: Surreal<Any>, index: SuggestIndex) {
+    super::spawn_periodic(
+        "suggest",
+        "SUGGEST_INDEX_REFRESH_SECS",
+        DEFAULT_INTERVAL_SECS,
+        move || {
+            let db = db.clone();
+            let index = index.clone();
+            async move { index.rebuild(&db).await }
+        },
+    );
+}
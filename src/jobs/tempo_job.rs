@@ -0,0 +1,14 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::services::{media_service::MediaConfig, tempo_service::TempoService};
+
+const DEFAULT_INTERVAL_SECS: u64 = 3600;
+
+/// Estimates the BPM of songs still at `tempo = 0`.
+pub fn spawn(db: Surreal<Any>, config: MediaConfig) {
+    super::spawn_periodic("tempo", "TEMPO_JOB_INTERVAL_SECS", DEFAULT_INTERVAL_SECS, move || {
+        let db = db.clone();
+        let config = config.clone();
+        async move { TempoService::analyze_pending(&db, &config).await }
+    });
+}
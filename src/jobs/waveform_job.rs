@@ -0,0 +1,14 @@
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::services::{media_service::MediaConfig, waveform_service::WaveformService};
+
+const DEFAULT_INTERVAL_SECS: u64 = 3600;
+
+/// Computes the peaks of songs without a stored waveform.
+pub fn spawn(db: Surreal<Any>, config: MediaConfig) {
+    super::spawn_periodic("waveform", "WAVEFORM_JOB_INTERVAL_SECS", DEFAULT_INTERVAL_SECS, move || {
+        let db = db.clone();
+        let config = config.clone();
+        async move { WaveformService::analyze_pending(&db, &config).await }
+    });
+}
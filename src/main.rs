@@ -20,9 +20,13 @@ use uuid::Uuid;
 
 use crate::{
     auth::token_service::AuthConfig,
+    models::user::Role,
+    services::{media_service::MediaConfig, scan_service::ScanTask, suggest_service::SuggestIndex},
     routes::{
-        album_routes::AlbumRoutes, artist_routes::ArtistRoutes, auth_routes::AuthRoutes,
-        favorite_routes::FavoriteRoutes, playlist_routes::PlaylistRoutes,
+        admin_routes::AdminRoutes, album_routes::AlbumRoutes, artist_routes::ArtistRoutes, auth_routes::AuthRoutes,
+        chart_routes::ChartRoutes,
+        favorite_routes::FavoriteRoutes, image_routes::ImageRoutes, playlist_routes::PlaylistRoutes,
+        radio_routes::RadioRoutes, recommendation_routes::RecommendationRoutes,
         search_routes::SearchRoutes, song_routes::SongRoutes, user_routes::UserRoutes,
     },
 };
@@ -30,9 +34,11 @@ use crate::{
 pub use self::error::{Error, Result};
 
 mod auth;
+mod commands;
 mod controllers;
 mod error;
 mod helpers;
+mod jobs;
 mod models;
 mod routes;
 mod services;
@@ -45,6 +51,9 @@ struct AppState {
     #[allow(dead_code)]
     rate_limit_cache: moka::future::Cache<String, ()>,
     auth_config: AuthConfig,
+    media_config: MediaConfig,
+    suggest_index: SuggestIndex,
+    scan_task: ScanTask,
 }
 
 #[tokio::main]
@@ -74,30 +83,105 @@ async fn main() -> Result<()> {
 
     tracing::info!("Database connected successfully!");
 
+    // `cargo run -- <command>` exécute une commande ponctuelle au lieu du serveur
+    let args: Vec<String> = env::args().skip(1).collect();
+    if !args.is_empty() {
+        return commands::run(&db, &args).await;
+    }
+
     let auth_config = AuthConfig::from_env()?;
     tracing::info!("Auth configuration loaded");
 
+    let media_config = MediaConfig::from_env();
+    tracing::info!("Media root: {}", media_config.media_root.display());
+
+    let suggest_index = SuggestIndex::default();
+
+    jobs::spawn_all(&db, &media_config, &suggest_index);
+
     let app_state = AppState {
         db,
         rate_limit_cache: moka::future::Cache::new(1000),
         auth_config: auth_config.clone(),
+        media_config,
+        suggest_index,
+        scan_task: ScanTask::default(),
     };
 
+    let routes_all = app(app_state);
+
+    let host = env::var("BIND_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
+    let port: u16 = env::var("PORT")
+        .ok()
+        .and_then(|v| v.parse().ok())
+        .unwrap_or(8080);
+
+    let addr: SocketAddr = format!("{}:{}", host, port)
+        .parse()
+        .expect("Invalid bind address");
+    
+    let listener = tokio::net::TcpListener::bind(addr).await?;
+    
+    tracing::info!("Listening on http://{}", addr);
+
+    axum::serve(
+        listener,
+        routes_all.into_make_service_with_connect_info::<SocketAddr>(),
+    )
+    .await?;
+
+    Ok(())
+}
+
+/// Every route of the API, with its middlewares.
+fn app(app_state: AppState) -> Router {
     let routes_api = Router::new()
         .nest("/auth", AuthRoutes::routes())
         .nest("/albums", AlbumRoutes::routes())
         .nest("/artists", ArtistRoutes::routes())
         .nest("/song", SongRoutes::routes())
         .nest("/search", SearchRoutes::routes())
+        .nest("/charts", ChartRoutes::routes())
         .route_layer(middleware::from_fn_with_state(
             app_state.clone(),
             middlewares::mw_rate_limit::rate_limit_middleware,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            middlewares::mw_auth::mw_auth_optional,
         ));
 
+    // Une requête par frappe : un budget à part, plus large
+    let suggest_routes = Router::new()
+        .nest("/search", SearchRoutes::suggest_routes())
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            middlewares::mw_rate_limit::suggest_rate_limit_middleware,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            middlewares::mw_auth::mw_auth_optional,
+        ));
+
+    // Pas de limite de débit : chaque saut dans un titre et chaque préchargement
+    // est une requête Range, et l'URL signée restreint déjà l'accès
+    let media_routes = Router::new()
+        .nest("/song", SongRoutes::media_routes())
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            middlewares::mw_media_signature::verify_media_signature,
+        ));
+
+    // Pas de limite de débit : une grille charge des dizaines de vignettes d'un coup,
+    // et les variantes sont servies depuis le cache disque
+    let image_routes = Router::new().nest("/images", ImageRoutes::routes());
+
     let protected_routes = Router::new()
         .nest("/user", UserRoutes::routes())
         .nest("/favorites", FavoriteRoutes::routes())
         .nest("/playlist", PlaylistRoutes::routes())
+        .nest("/recommendations", RecommendationRoutes::routes())
+        .nest("/radio", RadioRoutes::routes())
         .route_layer(middleware::from_fn_with_state(
             app_state.clone(),
             middlewares::mw_auth::mw_auth,
@@ -107,9 +191,28 @@ async fn main() -> Result<()> {
             middlewares::mw_rate_limit::rate_limit_middleware,
         ));
 
-    let routes_all = Router::new()
+    let admin_routes = Router::new()
+        .nest("/admin", AdminRoutes::routes())
+        .route_layer(middleware::from_fn_with_state(
+            Role::Admin,
+            middlewares::mw_role::require_role,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            middlewares::mw_auth::mw_auth,
+        ))
+        .route_layer(middleware::from_fn_with_state(
+            app_state.clone(),
+            middlewares::mw_rate_limit::rate_limit_middleware,
+        ));
+
+    Router::new()
         .nest("/api", routes_api)
+        .nest("/api", suggest_routes)
         .nest("/api", protected_routes)
+        .nest("/api", admin_routes)
+        .nest("/api", media_routes)
+        .nest("/api", image_routes)
         .with_state(app_state)
         .layer(
             TraceLayer::new_for_http()
@@ -141,29 +244,7 @@ async fn main() -> Result<()> {
                     }
                 })
         )
-        .layer(CorsLayer::very_permissive());
-
-    let host = env::var("BIND_HOST").unwrap_or_else(|_| "0.0.0.0".to_string());
-    let port: u16 = env::var("PORT")
-        .ok()
-        .and_then(|v| v.parse().ok())
-        .unwrap_or(8080);
-
-    let addr: SocketAddr = format!("{}:{}", host, port)
-        .parse()
-        .expect("Invalid bind address");
-    
-    let listener = tokio::net::TcpListener::bind(addr).await?;
-    
-    tracing::info!("Listening on http://{}", addr);
-
-    axum::serve(
-        listener,
-        routes_all.into_make_service_with_connect_info::<SocketAddr>(),
-    )
-    .await?;
-
-    Ok(())
+        .layer(CorsLayer::very_permissive())
 }
 
 fn init_tracing() {
@@ -184,4 +265,94 @@ fn init_tracing() {
                 .compact()
         )
         .init();
-}
\ No newline at end of file
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::http::StatusCode;
+    use jsonwebtoken::Algorithm;
+
+    /// Serves the API over a fresh in-memory database, returns its base URL.
+    async fn serve() -> String {
+        let db = any::connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        let app_state = AppState {
+            db,
+            rate_limit_cache: moka::future::Cache::new(1000),
+            auth_config: AuthConfig {
+                jwt_secret: "test_secret".to_string(),
+                website_url: "http://localhost:3000".to_string(),
+                token_duration_min: 60,
+                jwt_algorithm: Algorithm::HS256,
+                media_url_secret: "test_media_secret".to_string(),
+                media_url_ttl_secs: 3600,
+            },
+            media_config: MediaConfig {
+                media_root: "./media".into(),
+                image_cache_dir: "./cache/images".into(),
+            },
+            suggest_index: SuggestIndex::default(),
+            scan_task: ScanTask::default(),
+        };
+
+        let listener = tokio::net::TcpListener::bind("127.0.0.1:0").await.unwrap();
+        let addr = listener.local_addr().unwrap();
+        tokio::spawn(async move {
+            axum::serve(
+                listener,
+                app(app_state).into_make_service_with_connect_info::<SocketAddr>(),
+            )
+            .await
+            .unwrap();
+        });
+        format!("http://{}/api", addr)
+    }
+
+    #[tokio::test]
+    async fn test_suggest_has_its_own_rate_limit() {
+        let base = serve().await;
+        let client = reqwest::Client::new();
+
+        // Une requête par frappe
+        let term = "london calling the clash";
+        for end in 1..=term.len() {
+            let response = client
+                .get(format!("{}/search/suggest", base))
+                .query(&[("q", &term[..end])])
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::OK, "keystroke {}", end);
+        }
+
+        // Le budget des autres routes est intact, puis épuisé par une rafale
+        let mut statuses = Vec::new();
+        for _ in 0..11 {
+            let response = client
+                .get(format!("{}/charts/songs", base))
+                .send()
+                .await
+                .unwrap();
+            statuses.push(response.status());
+        }
+        assert_ne!(statuses[0], StatusCode::TOO_MANY_REQUESTS);
+        assert_eq!(statuses[10], StatusCode::TOO_MANY_REQUESTS);
+    }
+
+    #[tokio::test]
+    async fn test_stream_is_not_rate_limited() {
+        let base = serve().await;
+        let client = reqwest::Client::new();
+
+        // Sondage initial, sauts et préchargement : autant de requêtes Range
+        for _ in 0..20 {
+            let response = client
+                .get(format!("{}/song/abc/stream", base))
+                .header("Range", "bytes=0-")
+                .send()
+                .await
+                .unwrap();
+            assert_eq!(response.status(), StatusCode::FORBIDDEN);
+        }
+    }
+}
@@ -1,2 +1,4 @@
 pub mod mw_auth;
+pub mod mw_media_signature;
 pub mod mw_rate_limit;
+pub mod mw_role;
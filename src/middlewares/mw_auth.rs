@@ -1,7 +1,8 @@
 use crate::auth::token_service::{Claims, TokenService};
 use crate::error::{Error, Result};
 use crate::helpers::thing_helpers::{create_user_thing, parse_id_part};
-use crate::{models::user::UserRecord, AppState};
+use crate::models::user::{Role, UserRecord};
+use crate::AppState;
 use axum::body::Body;
 use axum::extract::{Request, State};
 use axum::http::header;
@@ -13,12 +14,20 @@ use serde::{Deserialize, Serialize};
 pub struct Ctx {
     pub user_id: String,
     pub exp: usize,
+    pub role: Role,
     pub user: UserRecord,
 }
 
 impl Ctx {
+    /// The role is read from the stored user rather than the token claims,
+    /// so demoting a user takes effect without waiting for the token to expire.
     pub fn new(user_id: String, exp: usize, user: UserRecord) -> Self {
-        Self { user_id, exp, user }
+        Self {
+            user_id,
+            exp,
+            role: user.role,
+            user,
+        }
     }
 }
 
@@ -27,13 +36,41 @@ pub async fn mw_auth(
     mut req: Request<Body>,
     next: Next,
 ) -> Result<Response> {
-    let token = req
-        .headers()
+    let token = bearer_token(&req).ok_or(Error::AuthFailNoAuthTokenCookie)?;
+
+    let ctx = resolve_ctx(&app_state, token).await?;
+    req.extensions_mut().insert(ctx);
+
+    Ok(next.run(req).await)
+}
+
+/// Same as `mw_auth` for public routes: a valid token sets the `Ctx`, a
+/// missing or invalid one lets the request through anonymously.
+pub async fn mw_auth_optional(
+    State(app_state): State<AppState>,
+    mut req: Request<Body>,
+    next: Next,
+) -> Response {
+    if let Some(token) = bearer_token(&req) {
+        match resolve_ctx(&app_state, token).await {
+            Ok(ctx) => {
+                req.extensions_mut().insert(ctx);
+            }
+            Err(e) => tracing::debug!("Ignoring invalid token on public route: {:?}", e),
+        }
+    }
+
+    next.run(req).await
+}
+
+fn bearer_token(req: &Request<Body>) -> Option<&str> {
+    req.headers()
         .get(header::AUTHORIZATION)
         .and_then(|value| value.to_str().ok())
         .and_then(|str| str.strip_prefix("Bearer "))
-        .ok_or(Error::AuthFailNoAuthTokenCookie)?;
+}
 
+async fn resolve_ctx(app_state: &AppState, token: &str) -> Result<Ctx> {
     let claims: Claims = TokenService::validate_token(token, &app_state.auth_config)?;
 
     let sub_str = claims.sub.clone();
@@ -51,8 +88,5 @@ pub async fn mw_auth(
         username: claims.sub.clone(),
     })?;
 
-    let ctx = Ctx::new(claims.sub, claims.exp as usize, user);
-    req.extensions_mut().insert(ctx);
-
-    Ok(next.run(req).await)
+    Ok(Ctx::new(claims.sub, claims.exp as usize, user))
 }
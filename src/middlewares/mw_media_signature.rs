@@ -0,0 +1,30 @@
+use std::net::SocketAddr;
+
+use axum::{
+    body::Body,
+    extract::{ConnectInfo, Path, Query, Request, State},
+    middleware::Next,
+    response::Response,
+};
+
+use crate::{
+    auth::media_url_service::{MediaUrlService, SignedMediaQuery},
+    error::Result,
+    AppState,
+};
+
+/// Rejects media requests whose signed URL is missing, expired, tampered or
+/// revoked.
+pub async fn verify_media_signature(
+    State(app_state): State<AppState>,
+    ConnectInfo(addr): ConnectInfo<SocketAddr>,
+    Path(song_id): Path<String>,
+    Query(query): Query<SignedMediaQuery>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response> {
+    MediaUrlService::verify(&app_state.auth_config, &song_id, &query, addr.ip())?;
+    MediaUrlService::verify_subject(&app_state.db, &query).await?;
+
+    Ok(next.run(req).await)
+}
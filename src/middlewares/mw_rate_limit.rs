@@ -10,6 +10,26 @@ use axum::{
 
 use crate::{middlewares::mw_auth::Ctx, AppState};
 
+/// Request budget of a group of routes, counted apart from the other groups.
+struct RateLimit {
+    scope: &'static str,
+    per_minute: usize,
+    per_10_sec: usize,
+}
+
+const API_LIMIT: RateLimit = RateLimit {
+    scope: "api",
+    per_minute: 150,
+    per_10_sec: 10,
+};
+
+/// Search-as-you-type sends a request per keystroke.
+const SUGGEST_LIMIT: RateLimit = RateLimit {
+    scope: "suggest",
+    per_minute: 300,
+    per_10_sec: 50,
+};
+
 /// Global rate limiting middleware that only blocks heavy spammers
 ///
 /// Limits:
@@ -26,17 +46,41 @@ pub async fn rate_limit_middleware(
     req: Request<Body>,
     next: Next,
 ) -> Result<Response, StatusCode> {
-    // Identify the requester (user or IP)
-    let identifier = req
-        .extensions()
+    check_rate_limit(&app_state, &API_LIMIT, requester(ip, &req)).await?;
+    Ok(next.run(req).await)
+}
+
+/// Rate limiting of `/search/suggest`, with a larger budget of its own so that
+/// typing neither hits `429` nor uses up the budget of the other calls.
+pub async fn suggest_rate_limit_middleware(
+    State(app_state): State<AppState>,
+    ConnectInfo(ip): ConnectInfo<SocketAddr>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response, StatusCode> {
+    check_rate_limit(&app_state, &SUGGEST_LIMIT, requester(ip, &req)).await?;
+    Ok(next.run(req).await)
+}
+
+/// Identify the requester (user or IP)
+fn requester(ip: SocketAddr, req: &Request<Body>) -> String {
+    req.extensions()
         .get::<Ctx>()
         .map(|ctx| format!("user:{}", ctx.user_id))
-        .unwrap_or_else(|| format!("ip:{}", ip.ip()));
+        .unwrap_or_else(|| format!("ip:{}", ip.ip()))
+}
+
+async fn check_rate_limit(
+    app_state: &AppState,
+    limit: &RateLimit,
+    requester: String,
+) -> Result<(), StatusCode> {
+    let identifier = format!("{}:{}", limit.scope, requester);
 
     // Generate unique request ID for this request
     let request_id = uuid::Uuid::new_v4();
     
-    // Minute-based rate limit (150 requests per minute)
+    // Minute-based rate limit
     let minute_key = format!("rl:min:{}:{}", identifier, request_id);
     app_state.rate_limit_cache.insert(minute_key.clone(), ()).await;
     
@@ -47,12 +91,11 @@ pub async fn rate_limit_middleware(
         .filter(|(k, _)| k.starts_with(&minute_prefix))
         .count();
     
-    const MAX_REQUESTS_PER_MINUTE: usize = 150;
-    if minute_reqs > MAX_REQUESTS_PER_MINUTE {
+    if minute_reqs > limit.per_minute {
         return Err(StatusCode::TOO_MANY_REQUESTS);
     }
     
-    // 10-second burst limit (10 requests per 10 seconds)
+    // 10-second burst limit
     // Only check burst if we're under minute limit
     let burst_key = format!("rl:burst:{}:{}", identifier, request_id);
     app_state.rate_limit_cache.insert(burst_key, ()).await;
@@ -63,10 +106,9 @@ pub async fn rate_limit_middleware(
         .filter(|(k, _)| k.starts_with(&burst_prefix))
         .count();
     
-    const MAX_REQUESTS_PER_10SEC: usize = 10;
-    if burst_reqs > MAX_REQUESTS_PER_10SEC {
+    if burst_reqs > limit.per_10_sec {
         return Err(StatusCode::TOO_MANY_REQUESTS);
     }
 
-    Ok(next.run(req).await)
+    Ok(())
 }
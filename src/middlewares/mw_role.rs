@@ -0,0 +1,34 @@
+use crate::error::{Error, Result};
+use crate::middlewares::mw_auth::Ctx;
+use crate::models::user::Role;
+use axum::body::Body;
+use axum::extract::{Request, State};
+use axum::middleware::Next;
+use axum::response::Response;
+
+/// Rejects the request unless the authenticated user has at least the `required` role.
+///
+/// Must run after `mw_auth`, which puts the `Ctx` in the request extensions.
+/// The required role is given as the middleware state:
+///
+/// ```ignore
+/// .route_layer(middleware::from_fn_with_state(Role::Admin, mw_role::require_role))
+/// ```
+pub async fn require_role(
+    State(required): State<Role>,
+    req: Request<Body>,
+    next: Next,
+) -> Result<Response> {
+    let ctx = req
+        .extensions()
+        .get::<Ctx>()
+        .ok_or(Error::AuthFailCtxNotInRequestExt)?;
+
+    if !ctx.role.satisfies(required) {
+        return Err(Error::Forbidden {
+            required_role: required.to_string(),
+        });
+    }
+
+    Ok(next.run(req).await)
+}
@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::{Duration, Thing};
 
-use crate::models::{artist::Artist, song::Song};
+use crate::models::{artist::Artist, cover::ImageVariants, loudness::Loudness, song::Song};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Album {
@@ -10,10 +10,14 @@ pub struct Album {
 
     pub title: String,
     pub cover_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_variants: Option<ImageVariants>,
     pub release_year: Option<u16>,
     pub genres: Vec<String>,
     pub langs: Vec<String>,
     pub dominant_color: Option<String>,
+    /// EBU R128 measurement over all songs, with the album gain.
+    pub loudness: Option<Loudness>,
     pub total_tracks: u32,
     pub total_duration: Duration,
 
@@ -32,6 +36,8 @@ pub struct AlbumWithArtists {
     pub id: Option<Thing>,
     pub title: String,
     pub cover_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_variants: Option<ImageVariants>,
     pub release_year: Option<u16>,
     pub genres: Vec<String>,
     pub langs: Vec<String>,
@@ -53,10 +59,14 @@ pub struct AlbumWithRelations {
     pub id: Option<Thing>,
     pub title: String,
     pub cover_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_variants: Option<ImageVariants>,
     pub release_year: Option<u16>,
     pub genres: Vec<String>,
     pub langs: Vec<String>,
     pub dominant_color: Option<String>,
+    /// EBU R128 measurement over all songs, with the album gain.
+    pub loudness: Option<Loudness>,
     pub total_tracks: u32,
     pub total_duration: Duration,
     #[serde(default)]
@@ -81,3 +91,34 @@ pub struct AlbumsBatchRequest {
     pub offset: Option<u32>,
     pub limit: Option<u32>,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateAlbumRequest {
+    pub title: String,
+    pub cover_url: Option<String>,
+    pub release_year: Option<u16>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub langs: Vec<String>,
+    pub dominant_color: Option<String>,
+    #[serde(default)]
+    pub artist_ids: Vec<String>,
+}
+
+/// Partial update, only the provided fields are merged into the record.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UpdateAlbumRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub title: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub cover_url: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub release_year: Option<u16>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genres: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub langs: Option<Vec<String>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub dominant_color: Option<String>,
+}
@@ -0,0 +1,45 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::{Datetime, Thing};
+
+/// Audio analyses run on local song files.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum AnalysisKind {
+    Tempo,
+    Waveform,
+    Loudness,
+    Fingerprint,
+}
+
+impl AnalysisKind {
+    pub fn as_str(&self) -> &'static str {
+        match self {
+            Self::Tempo => "tempo",
+            Self::Waveform => "waveform",
+            Self::Loudness => "loudness",
+            Self::Fingerprint => "fingerprint",
+        }
+    }
+}
+
+/// Last failure of one analysis on one song, kept until the analysis succeeds.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct AnalysisFailure {
+    pub song: Thing,
+    pub kind: AnalysisKind,
+    pub reason: String,
+    pub attempts: u32,
+    pub failed_at: Datetime,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct AnalysisFailureQuery {
+    pub kind: Option<AnalysisKind>,
+}
+
+/// Result of a batch run over the songs still waiting for an analysis.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct AnalysisReport {
+    pub analyzed: u32,
+    pub failed: u32,
+}
@@ -3,6 +3,7 @@ use surrealdb::sql::Thing;
 
 use crate::models::{
     album::{Album, AlbumWithArtists},
+    cover::ImageVariants,
     music_genre::MusicGenre,
     song::Song,
 };
@@ -16,6 +17,8 @@ pub struct Artist {
     pub genres: Vec<MusicGenre>,
     pub country_code: String,
     pub artist_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_variants: Option<ImageVariants>,
     pub albums_count: u16,
     pub songs_count: u16,
     
@@ -31,6 +34,8 @@ pub struct ArtistWithAlbums {
     pub genres: Vec<MusicGenre>,
     pub country_code: String,
     pub artist_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_variants: Option<ImageVariants>,
     pub albums_count: u16,
     pub songs_count: u16,
     #[serde(default)]
@@ -46,6 +51,8 @@ pub struct ArtistWithAlbumsAndTopSongs {
     pub genres: Vec<MusicGenre>,
     pub country_code: String,
     pub artist_image: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub image_variants: Option<ImageVariants>,
     pub albums_count: u16,
     pub songs_count: u16,
     #[serde(default)]
@@ -53,3 +60,25 @@ pub struct ArtistWithAlbumsAndTopSongs {
     pub albums: Vec<AlbumWithArtists>,
     pub top_songs: Vec<Song>,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateArtistRequest {
+    pub name: String,
+    #[serde(default)]
+    pub genres: Vec<MusicGenre>,
+    pub country_code: String,
+    pub artist_image: Option<String>,
+}
+
+/// Partial update, only the provided fields are merged into the record.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UpdateArtistRequest {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub name: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub genres: Option<Vec<MusicGenre>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub country_code: Option<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artist_image: Option<String>,
+}
@@ -0,0 +1,73 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::Datetime;
+
+pub const DEFAULT_CHART_LIMIT: u32 = 50;
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "snake_case")]
+pub enum ChartPeriod {
+    /// Last 24 hours.
+    Day,
+    /// Last 7 days.
+    #[default]
+    Week,
+    /// Last 30 days.
+    Month,
+    /// Every listen, including those before the listen log.
+    AllTime,
+}
+
+impl ChartPeriod {
+    pub const ALL: [ChartPeriod; 4] = [Self::Day, Self::Week, Self::Month, Self::AllTime];
+
+    /// Window of the period as a SurrealQL duration, `None` for all time.
+    pub fn window(self) -> Option<&'static str> {
+        match self {
+            Self::Day => Some("1d"),
+            Self::Week => Some("1w"),
+            Self::Month => Some("30d"),
+            Self::AllTime => None,
+        }
+    }
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum ChartKind {
+    Song,
+    Album,
+    Artist,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ChartQuery {
+    pub period: Option<ChartPeriod>,
+    pub limit: Option<u32>,
+}
+
+/// A song, album or artist of a chart with its rank, from 1, and listens over
+/// the period.
+#[derive(Debug, Clone, Serialize)]
+pub struct ChartEntry<T> {
+    pub rank: u32,
+    pub listens: u64,
+    #[serde(flatten)]
+    pub item: T,
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Chart<T> {
+    pub period: ChartPeriod,
+    /// Last run of the `charts` job, `None` while the chart is empty.
+    pub computed_at: Option<Datetime>,
+    pub entries: Vec<ChartEntry<T>>,
+}
+
+/// Outcome of a rebuild of the charts: the entries stored per kind, all
+/// periods together.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct ChartReport {
+    pub songs: usize,
+    pub albums: usize,
+    pub artists: usize,
+}
@@ -0,0 +1,38 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct ColorBackfillQuery {
+    /// Recompute colors that are already set.
+    pub force: Option<bool>,
+}
+
+/// Result of a dominant color backfill over album and playlist covers.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ColorBackfillReport {
+    pub albums: u32,
+    pub playlists: u32,
+    pub errors: Vec<CoverError>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct CoverError {
+    pub id: String,
+    pub cover_url: String,
+    pub reason: String,
+}
+
+/// Resized copies of a cover or artist image, served by `GET /api/images/...`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImageVariants {
+    pub small: ImageVariant,
+    pub medium: ImageVariant,
+    pub large: ImageVariant,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImageVariant {
+    /// Longest side in pixels.
+    pub size: u32,
+    pub webp: String,
+    pub jpeg: String,
+}
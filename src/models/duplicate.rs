@@ -0,0 +1,41 @@
+use serde::{Deserialize, Serialize};
+
+#[derive(Debug, Deserialize)]
+pub struct DuplicateQuery {
+    /// Lowest similarity (`0.0..=1.0`) for two songs to be reported.
+    pub min_similarity: Option<f32>,
+}
+
+/// Songs that probably hold the same recording, linked by their pairs.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateCluster {
+    /// Most listened first, the natural survivor of a merge.
+    pub songs: Vec<DuplicateSong>,
+    pub pairs: Vec<DuplicatePair>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct DuplicateSong {
+    pub id: String,
+    pub title: String,
+    pub file_url: String,
+    pub duration_secs: u64,
+    pub total_listens: u32,
+    pub artists: Vec<String>,
+    pub albums: Vec<String>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct DuplicatePair {
+    pub a: String,
+    pub b: String,
+    /// Share of matching fingerprint bits at the best alignment; unrelated
+    /// songs score around 0.5.
+    pub similarity: f32,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct MergeSongsRequest {
+    /// Songs merged into the one of the path, then deleted.
+    pub duplicate_ids: Vec<String>,
+}
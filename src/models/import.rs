@@ -0,0 +1,82 @@
+use serde::{Deserialize, Serialize};
+
+/// Catalog manifest: artists -> albums -> songs.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ImportManifest {
+    #[serde(default)]
+    pub artists: Vec<ImportArtist>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ImportArtist {
+    pub name: String,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub country_code: String,
+    pub artist_image: Option<String>,
+    #[serde(default)]
+    pub albums: Vec<ImportAlbum>,
+
+    // Ligne du CSV d'origine, utilisée dans les erreurs
+    #[serde(skip)]
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ImportAlbum {
+    pub title: String,
+    pub cover_url: Option<String>,
+    pub release_year: Option<u16>,
+    #[serde(default)]
+    pub genres: Vec<String>,
+    #[serde(default)]
+    pub langs: Vec<String>,
+    pub dominant_color: Option<String>,
+    #[serde(default)]
+    pub songs: Vec<ImportSong>,
+
+    #[serde(skip)]
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ImportSong {
+    pub title: String,
+    pub file_url: String,
+    pub duration_secs: u64,
+    pub song_index: u16,
+    /// Absent keeps the stored tempo on re-import, defaults to 0 on creation.
+    pub tempo: Option<f32>,
+
+    #[serde(skip)]
+    pub line: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct ImportQuery {
+    pub dry_run: Option<bool>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default, PartialEq)]
+pub struct ImportCounts {
+    pub created: u32,
+    pub updated: u32,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ImportRowError {
+    /// `line 12` for CSV, `artists[0].albums[1].songs[2]` for JSON.
+    pub row: String,
+    pub reason: String,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ImportReport {
+    pub dry_run: bool,
+    pub applied: bool,
+    pub artists: ImportCounts,
+    pub albums: ImportCounts,
+    pub songs: ImportCounts,
+    pub errors: Vec<ImportRowError>,
+}
@@ -0,0 +1,21 @@
+use serde::{Deserialize, Serialize};
+
+/// EBU R128 measurement of a song or of a whole album.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq)]
+pub struct Loudness {
+    /// Integrated loudness, in LUFS.
+    pub integrated_lufs: f32,
+    /// Highest inter-sample peak, in dBTP.
+    pub true_peak_dbtp: f32,
+    /// ReplayGain 2.0 gain (dB) bringing playback to -18 LUFS: the track gain
+    /// on a song, the album gain on an album.
+    pub gain_db: f32,
+}
+
+/// Result of a loudness backfill.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct LoudnessReport {
+    pub analyzed: u32,
+    pub failed: u32,
+    pub albums: u32,
+}
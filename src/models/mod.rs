@@ -1,9 +1,22 @@
 pub mod album;
+pub mod analysis;
 pub mod artist;
+pub mod chart;
+pub mod cover;
+pub mod duplicate;
 pub mod favorite;
+pub mod import;
+pub mod loudness;
 pub mod playlist;
+pub mod radio;
+pub mod recommendation;
+pub mod scan;
+pub mod search;
+pub mod search_analytics;
 pub mod song;
 pub mod user;
+pub mod waveform;
+pub mod wrapped;
 
 pub mod database_helpers;
 pub mod pagination;
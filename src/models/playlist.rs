@@ -1,7 +1,11 @@
 use serde::{Deserialize, Serialize};
 use surrealdb::{sql::Duration, sql::Thing, Datetime};
 
-use crate::models::{song::SongWithRelations, user::UserRecord};
+use crate::models::{
+    cover::ImageVariants,
+    song::SongWithRelations,
+    user::{UserProfile, UserRecord},
+};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Playlist {
@@ -10,7 +14,15 @@ pub struct Playlist {
 
     pub name: String,
     pub cover_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_variants: Option<ImageVariants>,
     pub is_public: bool,
+    /// Daily mix generated by the server, which the user can only save a
+    /// copy of.
+    #[serde(default)]
+    pub is_system: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     pub dominant_color: Option<String>,
     pub created_by: Thing,
 
@@ -31,7 +43,13 @@ pub struct PlaylistWithSongs {
     pub id: Option<Thing>,
     pub name: String,
     pub cover_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_variants: Option<ImageVariants>,
     pub is_public: bool,
+    #[serde(default)]
+    pub is_system: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
     pub dominant_color: Option<String>,
     pub created_at: Datetime,
     pub updated_at: Datetime,
@@ -43,6 +61,30 @@ pub struct PlaylistWithSongs {
     pub songs: Option<Vec<SongWithRelations>>,
 }
 
+/// A playlist with its owner's profile, without its songs.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct PlaylistWithOwner {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub id: Option<Thing>,
+    pub name: String,
+    pub cover_url: Option<String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub cover_variants: Option<ImageVariants>,
+    pub is_public: bool,
+    #[serde(default)]
+    pub is_system: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    pub description: Option<String>,
+    pub dominant_color: Option<String>,
+    pub created_at: Datetime,
+    pub updated_at: Datetime,
+    pub songs_count: u32,
+    pub total_duration: Duration,
+    pub total_listens: u32,
+    pub total_likes: u32,
+    pub created_by: UserProfile,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct PlaylistSong {
     #[serde(skip_serializing_if = "Option::is_none")]
@@ -65,6 +107,13 @@ pub struct CreatePlaylistRequest {
     pub is_public: bool,
 }
 
+/// Copy of a daily mix as a normal playlist, named after the mix by default.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct SaveMixRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+}
+
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct UpdatePlaylistRequest {
     pub name: Option<String>,
@@ -0,0 +1,51 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+use crate::models::song::SongWithRelations;
+
+pub const DEFAULT_RADIO_BATCH_SIZE: u32 = 10;
+
+/// Either `seed`, which starts a new radio, or the `token` of a running one.
+#[derive(Debug, Deserialize)]
+pub struct RadioQuery {
+    /// `song:<id>`, `album:<id>` or `artist:<id>`.
+    pub seed: Option<String>,
+    pub token: Option<String>,
+    pub limit: Option<u32>,
+}
+
+/// Next songs of a radio, with the token to pass to get the following ones.
+#[derive(Debug, Clone, Serialize)]
+pub struct RadioBatch {
+    pub token: String,
+    pub seed: Thing,
+    pub songs: Vec<SongWithRelations>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "lowercase")]
+pub enum RadioFeedbackKind {
+    /// Steers away from songs like this one.
+    Skip,
+    /// Steers toward songs like this one.
+    Like,
+}
+
+/// Feedback on a song already played by the radio of `token`.
+#[derive(Debug, Deserialize)]
+pub struct RadioFeedbackRequest {
+    pub token: String,
+    pub song_id: String,
+    pub feedback: RadioFeedbackKind,
+}
+
+/// Stored state of a radio, see `database_radio.surql`.
+#[derive(Debug, Deserialize)]
+pub struct RadioSession {
+    pub id: Thing,
+    pub seed: Thing,
+    pub seed_songs: Vec<Thing>,
+    pub played: Vec<Thing>,
+    pub skipped: Vec<Thing>,
+    pub liked: Vec<Thing>,
+}
@@ -0,0 +1,37 @@
+use serde::{Deserialize, Serialize};
+
+use crate::models::{album::AlbumWithArtists, song::SongWithRelations};
+
+pub const DEFAULT_RECOMMENDATION_LIMIT: u32 = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct RecommendationQuery {
+    pub limit: Option<u32>,
+}
+
+/// A recommended song with its score, higher is better.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoredSong {
+    #[serde(flatten)]
+    pub song: SongWithRelations,
+    pub score: f32,
+}
+
+/// A recommended album with its score, higher is better.
+#[derive(Debug, Clone, Serialize)]
+pub struct ScoredAlbum {
+    #[serde(flatten)]
+    pub album: AlbumWithArtists,
+    pub score: f32,
+}
+
+/// Outcome of a rebuild of the song co-occurrence table.
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct CooccurrenceReport {
+    /// Users with at least two known songs.
+    pub users: usize,
+    /// Songs with at least one neighbour.
+    pub songs: usize,
+    /// Stored neighbour links, one per direction of a pair.
+    pub neighbours: usize,
+}
@@ -0,0 +1,31 @@
+use chrono::{DateTime, Utc};
+use serde::{Deserialize, Serialize};
+
+/// Result of a media directory scan. Paths are relative to the media root.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct ScanReport {
+    pub scanned: u32,
+    pub unchanged: u32,
+    pub new_files: Vec<String>,
+    pub changed_files: Vec<String>,
+    pub missing_files: Vec<String>,
+    pub errors: Vec<ScanFileError>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ScanFileError {
+    pub file: String,
+    pub reason: String,
+}
+
+/// Progress of the media scan started from the admin API.
+#[derive(Debug, Serialize, Clone, Default)]
+pub struct ScanStatus {
+    pub running: bool,
+    pub started_at: Option<DateTime<Utc>>,
+    pub finished_at: Option<DateTime<Utc>>,
+    /// Report of the last finished scan.
+    pub report: Option<ScanReport>,
+    /// Why the last scan failed.
+    pub error: Option<String>,
+}
@@ -0,0 +1,216 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::sql::Thing;
+
+use crate::error::{Error, Result};
+
+pub const DEFAULT_SEARCH_PAGE_SIZE: u32 = 20;
+pub const MAX_SEARCH_PAGE_SIZE: u32 = 50;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum SearchType {
+    Album,
+    Song,
+    Artist,
+    Playlist,
+    User,
+}
+
+impl SearchType {
+    pub const ALL: [SearchType; 5] = [
+        SearchType::Album,
+        SearchType::Song,
+        SearchType::Artist,
+        SearchType::Playlist,
+        SearchType::User,
+    ];
+
+    pub(crate) fn parse(value: &str) -> Result<Self> {
+        match value.to_lowercase().as_str() {
+            "album" | "albums" => Ok(SearchType::Album),
+            "song" | "songs" => Ok(SearchType::Song),
+            "artist" | "artists" => Ok(SearchType::Artist),
+            "playlist" | "playlists" => Ok(SearchType::Playlist),
+            "user" | "users" => Ok(SearchType::User),
+            other => Err(Error::InvalidInput {
+                reason: format!("Unknown search type '{}'", other),
+            }),
+        }
+    }
+}
+
+/// Query string of `GET /api/search`. List parameters are comma-separated.
+#[derive(Debug, Default, Deserialize)]
+pub struct SearchQuery {
+    pub term: String,
+    /// `album`, `song`, `artist`, `playlist` and/or `user`; every type when
+    /// absent.
+    pub types: Option<String>,
+    pub page: Option<u32>,
+    pub page_size: Option<u32>,
+    pub genres: Option<String>,
+    pub langs: Option<String>,
+    pub year_from: Option<u16>,
+    pub year_to: Option<u16>,
+    pub country_code: Option<String>,
+}
+
+/// Types, page and filters of a search. The same page is returned for every
+/// requested type.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SearchOptions {
+    pub types: Vec<SearchType>,
+    pub page: u32,
+    pub page_size: u32,
+    /// Lowercased without separators, so that `CHANT_MILITAIRE` matches the
+    /// `ChantMilitaire` artist genre; matched against album genres, and
+    /// artist genres for artists.
+    pub genres: Vec<String>,
+    /// Lowercased; matched against album languages.
+    pub langs: Vec<String>,
+    pub year_from: Option<u16>,
+    pub year_to: Option<u16>,
+    /// Uppercased; matched against the artist's country.
+    pub country_code: Option<String>,
+    /// Lowercased; part of the name of an artist of the album or song, or of
+    /// the artist.
+    pub artist: Option<String>,
+    /// Inclusive BPM bounds; songs without a measured tempo never match.
+    pub tempo_min: Option<f64>,
+    pub tempo_max: Option<f64>,
+}
+
+impl Default for SearchOptions {
+    fn default() -> Self {
+        Self {
+            types: SearchType::ALL.to_vec(),
+            page: 1,
+            page_size: DEFAULT_SEARCH_PAGE_SIZE,
+            genres: Vec::new(),
+            langs: Vec::new(),
+            year_from: None,
+            year_to: None,
+            country_code: None,
+            artist: None,
+            tempo_min: None,
+            tempo_max: None,
+        }
+    }
+}
+
+impl SearchOptions {
+    pub fn from_query(query: &SearchQuery) -> Result<Self> {
+        let mut types = parse_types(query.types.as_deref())?;
+        if types.is_empty() {
+            types = SearchType::ALL.to_vec();
+        }
+
+        if let (Some(from), Some(to)) = (query.year_from, query.year_to) {
+            if from > to {
+                return Err(Error::InvalidInput {
+                    reason: "year_from must not be after year_to".to_string(),
+                });
+            }
+        }
+
+        Ok(Self {
+            types,
+            page: query.page.unwrap_or(1).max(1),
+            page_size: query
+                .page_size
+                .unwrap_or(DEFAULT_SEARCH_PAGE_SIZE)
+                .clamp(1, MAX_SEARCH_PAGE_SIZE),
+            genres: split_list(query.genres.as_deref())
+                .map(|genre| normalize_genre(&genre))
+                .collect(),
+            langs: split_list(query.langs.as_deref())
+                .map(|lang| lang.to_lowercase())
+                .collect(),
+            year_from: query.year_from,
+            year_to: query.year_to,
+            country_code: query
+                .country_code
+                .as_deref()
+                .map(str::trim)
+                .filter(|code| !code.is_empty())
+                .map(str::to_uppercase),
+            ..Default::default()
+        })
+    }
+
+    pub fn includes(&self, search_type: SearchType) -> bool {
+        self.types.contains(&search_type)
+    }
+
+    /// Whether songs must belong to an album matching the album filters.
+    pub fn filters_albums(&self) -> bool {
+        !self.genres.is_empty() || self.filters_releases()
+    }
+
+    /// Whether artists must have an album matching the language and year filters.
+    pub fn filters_releases(&self) -> bool {
+        !self.langs.is_empty() || self.year_from.is_some() || self.year_to.is_some()
+    }
+
+    /// Whether anything but the full-text terms restricts the results.
+    pub fn has_filters(&self) -> bool {
+        self.filters_albums()
+            || self.filters_tempo()
+            || self.artist.is_some()
+            || self.country_code.is_some()
+    }
+
+    /// Whether albums and artists must have a song matching the tempo range.
+    pub fn filters_tempo(&self) -> bool {
+        self.tempo_min.is_some() || self.tempo_max.is_some()
+    }
+}
+
+/// Query string of `GET /api/search/suggest`.
+#[derive(Debug, Deserialize)]
+pub struct SuggestQuery {
+    pub q: String,
+}
+
+/// An autocomplete entry: the title of an album or song, or an artist name.
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Suggestion {
+    pub id: Thing,
+    pub title: String,
+}
+
+#[derive(Debug, Clone, Default, Serialize)]
+pub struct SuggestResult {
+    pub albums: Vec<Suggestion>,
+    pub artists: Vec<Suggestion>,
+    pub songs: Vec<Suggestion>,
+}
+
+/// Types of a comma-separated list, without duplicates.
+pub(crate) fn parse_types(value: Option<&str>) -> Result<Vec<SearchType>> {
+    let mut types = Vec::new();
+    for value in split_list(value) {
+        let search_type = SearchType::parse(&value)?;
+        if !types.contains(&search_type) {
+            types.push(search_type);
+        }
+    }
+    Ok(types)
+}
+
+/// Lowercased, without `_`, `-` and spaces.
+pub(crate) fn normalize_genre(genre: &str) -> String {
+    genre
+        .chars()
+        .filter(|c| !matches!(c, '_' | '-' | ' '))
+        .flat_map(char::to_lowercase)
+        .collect()
+}
+
+pub(crate) fn split_list(value: Option<&str>) -> impl Iterator<Item = String> + '_ {
+    value
+        .unwrap_or_default()
+        .split(',')
+        .map(str::trim)
+        .filter(|item| !item.is_empty())
+        .map(str::to_string)
+}
@@ -0,0 +1,55 @@
+use serde::{Deserialize, Serialize};
+use surrealdb::Datetime;
+
+pub const DEFAULT_REPORT_DAYS: u32 = 30;
+pub const DEFAULT_REPORT_LIMIT: u32 = 20;
+
+#[derive(Debug, Deserialize)]
+pub struct SearchClickRequest {
+    /// Opened result, e.g. `song:abc`.
+    pub result_id: String,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct SearchReportQuery {
+    /// Reported period, counted back from now.
+    pub days: Option<u32>,
+    pub limit: Option<u32>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct TopSearchQuery {
+    pub query: String,
+    pub searches: u64,
+    /// Searches after which a result was opened.
+    pub clicked_searches: u64,
+    pub click_through_rate: f64,
+    pub avg_results: f64,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct ZeroResultQuery {
+    pub query: String,
+    pub searches: u64,
+    pub last_searched_at: Datetime,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClickThroughReport {
+    pub days: u32,
+    pub searches: u64,
+    pub clicked_searches: u64,
+    pub click_through_rate: f64,
+    pub zero_result_searches: u64,
+    pub authenticated_searches: u64,
+    /// Searches whose results come from a corrected query.
+    pub corrected_searches: u64,
+    pub clicks_by_type: Vec<ClicksByType>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct ClicksByType {
+    /// `album`, `song`, `artist`, `playlist` or `user`.
+    pub result_type: String,
+    pub clicks: u64,
+}
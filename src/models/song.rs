@@ -1,7 +1,7 @@
 use serde::{Deserialize, Serialize};
 use surrealdb::sql::{Duration, Thing};
 
-use crate::models::{album::Album, artist::Artist};
+use crate::models::{album::Album, artist::Artist, loudness::Loudness};
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
 pub struct Song {
@@ -15,6 +15,8 @@ pub struct Song {
 
     // Audio metadata for recommendations
     pub tempo: f32, // BPM
+    /// EBU R128 measurement with the track gain, once analyzed.
+    pub loudness: Option<Loudness>,
 
     // Stats
     #[serde(default)]
@@ -37,6 +39,8 @@ pub struct SongWithRelations {
 
     // Audio metadata for recommendations
     pub tempo: f32, // BPM
+    /// EBU R128 measurement with the track gain, once analyzed.
+    pub loudness: Option<Loudness>,
 
     // Stats
     #[serde(default)]
@@ -54,3 +58,26 @@ pub struct SongWithRelations {
     #[serde(skip_serializing_if = "Option::is_none")]
     pub added_at: Option<surrealdb::sql::Datetime>,
 }
+
+#[derive(Debug, Serialize, Deserialize, Clone)]
+pub struct CreateSongRequest {
+    pub title: String,
+    pub file_url: String,
+    pub duration_secs: u64,
+    pub song_index: u16,
+    #[serde(default)]
+    pub tempo: f32,
+    pub album_id: Option<String>,
+    #[serde(default)]
+    pub artist_ids: Vec<String>,
+}
+
+/// Partial update, only the provided fields are changed.
+#[derive(Debug, Serialize, Deserialize, Clone, Default)]
+pub struct UpdateSongRequest {
+    pub title: Option<String>,
+    pub file_url: Option<String>,
+    pub duration_secs: Option<u64>,
+    pub song_index: Option<u16>,
+    pub tempo: Option<f32>,
+}
@@ -18,6 +18,9 @@ pub struct UserRecord {
     //NOTE: idk if I will implement the following
     pub level: u16,
     pub experience_points: u32,
+
+    #[serde(default)]
+    pub role: Role,
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -37,6 +40,39 @@ pub struct UserProfile {
     //NOTE: idk if I will implement the following
     pub level: u16,
     pub experience_points: u32,
+
+    #[serde(default)]
+    pub role: Role,
+}
+
+/// Authorization level of a user, ordered from least to most privileged.
+#[derive(
+    Debug, Serialize, Deserialize, Clone, Copy, Default, PartialEq, Eq, PartialOrd, Ord, Hash,
+)]
+#[serde(rename_all = "lowercase")]
+pub enum Role {
+    #[default]
+    User,
+    Moderator,
+    Admin,
+}
+
+impl Role {
+    /// Whether this role grants at least the privileges of `required`.
+    pub fn satisfies(self, required: Role) -> bool {
+        self >= required
+    }
+}
+
+impl std::fmt::Display for Role {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        let s = match self {
+            Role::User => "user",
+            Role::Moderator => "moderator",
+            Role::Admin => "admin",
+        };
+        write!(f, "{}", s)
+    }
 }
 
 #[derive(Debug, Serialize, Deserialize, Clone, PartialEq, Eq, Hash)]
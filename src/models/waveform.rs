@@ -0,0 +1,49 @@
+use serde::{Deserialize, Serialize};
+
+/// Min/max peaks of a song, computed once per file at a few resolutions.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct Waveform {
+    pub duration_secs: f64,
+    pub sample_rate: u32,
+    /// Ordered by increasing number of points.
+    pub resolutions: Vec<WaveformPeaks>,
+}
+
+/// `points` buckets spanning the whole song. Amplitudes are scaled to
+/// `-127..=127`, `min[i]` and `max[i]` bound the signal in bucket `i`.
+#[derive(Debug, Serialize, Deserialize, Clone, PartialEq)]
+pub struct WaveformPeaks {
+    pub points: u32,
+    pub min: Vec<i8>,
+    pub max: Vec<i8>,
+}
+
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq, Default)]
+#[serde(rename_all = "lowercase")]
+pub enum WaveformFormat {
+    #[default]
+    Json,
+    /// Interleaved `min, max` signed bytes of a single resolution.
+    Binary,
+}
+
+#[derive(Debug, Deserialize)]
+pub struct WaveformQuery {
+    /// Smallest stored resolution with at least this many points, the
+    /// largest one otherwise. JSON returns every resolution when unset.
+    pub points: Option<u32>,
+    pub format: Option<WaveformFormat>,
+}
+
+impl Waveform {
+    pub fn resolution(&self, points: Option<u32>) -> Option<&WaveformPeaks> {
+        match points {
+            Some(points) => self
+                .resolutions
+                .iter()
+                .find(|peaks| peaks.points >= points)
+                .or_else(|| self.resolutions.last()),
+            None => self.resolutions.last(),
+        }
+    }
+}
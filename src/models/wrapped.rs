@@ -0,0 +1,87 @@
+use chrono::NaiveDate;
+use serde::{Deserialize, Serialize};
+use surrealdb::Datetime;
+
+use crate::models::{
+    album::AlbumWithArtists, artist::Artist, song::SongWithRelations, user::BadgeEnum,
+};
+
+#[derive(Debug, Deserialize)]
+pub struct WrappedQuery {
+    /// Current year when unset.
+    pub year: Option<i32>,
+}
+
+/// Year in review of a user, from their song listens of that year.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedReport {
+    pub year: i32,
+    /// `false` while the year is running; a complete report is cached.
+    pub complete: bool,
+    pub listens: u64,
+    pub minutes: u64,
+    pub top_songs: Vec<WrappedSong>,
+    pub top_artists: Vec<WrappedArtist>,
+    pub top_albums: Vec<WrappedAlbum>,
+    pub top_genres: Vec<WrappedGenre>,
+    pub longest_streak: Option<WrappedStreak>,
+    /// Badges earned during the year, in order.
+    pub badges: Vec<BadgeEnum>,
+    pub first_song: Option<WrappedFirstSong>,
+    /// January to December.
+    pub months: Vec<WrappedMonth>,
+    /// All-time totals of the user, for comparison.
+    pub lifetime_listens: u64,
+    pub lifetime_minutes: u64,
+    pub computed_at: Datetime,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedSong {
+    pub song: SongWithRelations,
+    pub listens: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedArtist {
+    pub artist: Artist,
+    pub listens: u64,
+    pub minutes: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedAlbum {
+    pub album: AlbumWithArtists,
+    pub listens: u64,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WrappedGenre {
+    /// Most listened spelling of the genre.
+    pub genre: String,
+    pub listens: u64,
+}
+
+/// Consecutive days with at least one listen, UTC.
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WrappedStreak {
+    pub days: u32,
+    pub from: NaiveDate,
+    pub to: NaiveDate,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct WrappedFirstSong {
+    pub listened_at: Datetime,
+    pub song: SongWithRelations,
+}
+
+#[derive(Debug, Clone, Serialize, Deserialize, PartialEq)]
+pub struct WrappedMonth {
+    /// `1` for January.
+    pub month: u32,
+    pub listens: u64,
+    pub minutes: u64,
+    pub top_song: Option<String>,
+    pub top_artist: Option<String>,
+}
@@ -0,0 +1,104 @@
+use axum::{
+    extract::DefaultBodyLimit,
+    routing::{delete, get, patch, post},
+    Router,
+};
+
+use crate::{controllers::admin_controller::AdminController, AppState};
+
+// Un manifeste de discographie complète dépasse vite la limite par défaut (2 Mo)
+const IMPORT_BODY_LIMIT: usize = 32 * 1024 * 1024;
+
+pub struct AdminRoutes;
+
+impl AdminRoutes {
+    pub fn routes() -> Router<AppState> {
+        Router::new()
+            .route("/albums", post(AdminController::create_album))
+            .route("/albums/{album_id}", patch(AdminController::update_album))
+            .route("/albums/{album_id}", delete(AdminController::delete_album))
+            .route(
+                "/albums/{album_id}/artists/{artist_id}",
+                post(AdminController::link_album_artist),
+            )
+            .route(
+                "/albums/{album_id}/artists/{artist_id}",
+                delete(AdminController::unlink_album_artist),
+            )
+            .route(
+                "/albums/{album_id}/songs/{song_id}",
+                post(AdminController::add_album_song),
+            )
+            .route(
+                "/albums/{album_id}/songs/{song_id}",
+                delete(AdminController::remove_album_song),
+            )
+            .route("/artists", post(AdminController::create_artist))
+            .route("/artists/{artist_id}", patch(AdminController::update_artist))
+            .route("/artists/{artist_id}", delete(AdminController::delete_artist))
+            .route("/songs", post(AdminController::create_song))
+            .route("/songs/{song_id}", patch(AdminController::update_song))
+            .route("/songs/{song_id}", delete(AdminController::delete_song))
+            .route(
+                "/songs/{song_id}/artists/{artist_id}",
+                post(AdminController::link_song_artist),
+            )
+            .route(
+                "/songs/{song_id}/artists/{artist_id}",
+                delete(AdminController::unlink_song_artist),
+            )
+            .route(
+                "/import",
+                post(AdminController::import_catalog)
+                    .layer(DefaultBodyLimit::max(IMPORT_BODY_LIMIT)),
+            )
+            .route("/scan", post(AdminController::scan_media))
+            .route("/scan", get(AdminController::scan_status))
+            .route(
+                "/covers/colors",
+                post(AdminController::backfill_cover_colors),
+            )
+            .route(
+                "/songs/{song_id}/analyze/tempo",
+                post(AdminController::analyze_song_tempo),
+            )
+            .route(
+                "/songs/{song_id}/analyze/waveform",
+                post(AdminController::analyze_song_waveform),
+            )
+            .route(
+                "/songs/{song_id}/analyze/loudness",
+                post(AdminController::analyze_song_loudness),
+            )
+            .route(
+                "/albums/{album_id}/analyze/loudness",
+                post(AdminController::analyze_album_loudness),
+            )
+            .route(
+                "/songs/{song_id}/analyze/fingerprint",
+                post(AdminController::analyze_song_fingerprint),
+            )
+            .route(
+                "/analysis/failures",
+                get(AdminController::list_analysis_failures),
+            )
+            .route("/duplicates", get(AdminController::list_duplicates))
+            .route("/songs/{song_id}/merge", post(AdminController::merge_songs))
+            .route(
+                "/users/{user_id}/media/revoke",
+                post(AdminController::revoke_user_media),
+            )
+            .route(
+                "/search/top-queries",
+                get(AdminController::search_top_queries),
+            )
+            .route(
+                "/search/zero-results",
+                get(AdminController::search_zero_result_queries),
+            )
+            .route(
+                "/search/click-through",
+                get(AdminController::search_click_through),
+            )
+    }
+}
@@ -0,0 +1,13 @@
+use crate::{controllers::chart_controller::ChartController, AppState};
+use axum::{routing::get, Router};
+
+pub struct ChartRoutes;
+
+impl ChartRoutes {
+    pub fn routes() -> Router<AppState> {
+        Router::new()
+            .route("/songs", get(ChartController::get_song_chart))
+            .route("/albums", get(ChartController::get_album_chart))
+            .route("/artists", get(ChartController::get_artist_chart))
+    }
+}
@@ -0,0 +1,11 @@
+use axum::{routing::get, Router};
+
+use crate::{controllers::image_controller::ImageController, AppState};
+
+pub struct ImageRoutes;
+
+impl ImageRoutes {
+    pub fn routes() -> Router<AppState> {
+        Router::new().route("/{size}/{format}/{*path}", get(ImageController::get_image))
+    }
+}
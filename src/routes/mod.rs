@@ -1,9 +1,14 @@
+pub mod admin_routes;
 pub mod album_routes;
 pub mod artist_routes;
 pub mod auth_routes;
+pub mod chart_routes;
 pub mod favorite_routes;
+pub mod image_routes;
 
 pub mod playlist_routes;
+pub mod radio_routes;
+pub mod recommendation_routes;
 pub mod search_routes;
 pub mod song_routes;
 pub mod user_routes;
@@ -24,6 +24,8 @@ impl PlaylistRoutes {
                 get(PlaylistController::get_user_playlists),
             )
             .route("/user/me", get(PlaylistController::get_my_playlists))
+            .route("/mixes", get(PlaylistController::get_my_mixes))
+            .route("/{playlist_id}/save", post(PlaylistController::save_mix))
             .route(
                 "/{playlist_id}",
                 get(PlaylistController::get_playlist_with_songs),
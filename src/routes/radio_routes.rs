@@ -0,0 +1,15 @@
+use crate::{controllers::radio_controller::RadioController, AppState};
+use axum::{
+    routing::{get, post},
+    Router,
+};
+
+pub struct RadioRoutes;
+
+impl RadioRoutes {
+    pub fn routes() -> Router<AppState> {
+        Router::new()
+            .route("/", get(RadioController::get_next_batch))
+            .route("/feedback", post(RadioController::give_feedback))
+    }
+}
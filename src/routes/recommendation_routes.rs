@@ -0,0 +1,12 @@
+use crate::{controllers::recommendation_controller::RecommendationController, AppState};
+use axum::{routing::get, Router};
+
+pub struct RecommendationRoutes;
+
+impl RecommendationRoutes {
+    pub fn routes() -> Router<AppState> {
+        Router::new()
+            .route("/songs", get(RecommendationController::recommend_songs))
+            .route("/albums", get(RecommendationController::recommend_albums))
+    }
+}
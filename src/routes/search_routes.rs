@@ -1,10 +1,20 @@
 use crate::{controllers::search_controller::SearchController, AppState};
-use axum::{routing::get, Router};
+use axum::{
+    routing::{get, post},
+    Router,
+};
 
 pub struct SearchRoutes;
 
 impl SearchRoutes {
     pub fn routes() -> Router<AppState> {
-        Router::new().route("/", get(SearchController::search_albums_songs_artists))
+        Router::new()
+            .route("/", get(SearchController::search_albums_songs_artists))
+            .route("/{search_id}/click", post(SearchController::record_click))
+    }
+
+    /// Search-as-you-type, rate limited apart from the other routes.
+    pub fn suggest_routes() -> Router<AppState> {
+        Router::new().route("/suggest", get(SearchController::suggest))
     }
 }
@@ -14,6 +14,13 @@ impl SongRoutes {
                 post(SongController::listen_to_song),
             )
             .route("/{song_id}/album", get(SongController::get_album_from_song))
+            .route("/{song_id}/similar", get(SongController::get_similar_songs))
+            .route("/{song_id}/waveform", get(SongController::get_song_waveform))
             .route("/recents", get(SongController::get_user_recent_listens))
     }
+
+    /// Routes serving audio, only reachable through signed URLs.
+    pub fn media_routes() -> Router<AppState> {
+        Router::new().route("/{song_id}/stream", get(SongController::stream_song))
+    }
 }
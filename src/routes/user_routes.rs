@@ -8,6 +8,7 @@ impl UserRoutes {
     pub fn routes() -> Router<AppState> {
         Router::new()
             .route("/me", get(UserController::get_my_profile))
+            .route("/me/wrapped", get(UserController::get_my_wrapped))
             .route("/{user_id}", get(UserController::get_user_profile))
             .route("/check-username", get(UserController::check_username))
 
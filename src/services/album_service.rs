@@ -3,14 +3,23 @@ use crate::models::album::AlbumsMetaResponse;
 use crate::{
     helpers::{
         album_helpers::album_exists,
-        thing_helpers::{create_album_thing, create_user_thing},
+        artist_helpers::artist_exists,
+        song_helpers::song_exists,
+        thing_helpers::{
+            create_album_thing, create_artist_thing, create_song_thing, create_user_thing,
+        },
     },
     models::{
-        album::{AlbumWithArtists, AlbumWithRelations},
+        album::{Album, AlbumWithArtists, AlbumWithRelations, CreateAlbumRequest, UpdateAlbumRequest},
         database_helpers::CountResult,
     },
+    services::artist_service::ArtistService,
+};
+use surrealdb::{
+    engine::any::Any,
+    sql::{Id, Thing},
+    Surreal,
 };
-use surrealdb::{engine::any::Any, Surreal};
 
 pub struct AlbumService;
 
@@ -292,13 +301,290 @@ impl AlbumService {
             }
         }
 
-        // Update global counter
-        db.query("UPDATE $album_id SET total_listens = (total_listens OR 0) + 1")
-            .bind(("album_id", album_thing))
-            .await?;
+        // Update global counter and append to the listen log
+        db.query(
+            "
+            UPDATE $album_id SET total_listens = (total_listens OR 0) + 1;
+            CREATE listen_event SET album = $album_id, user = $user_id, listened_at = time::now();
+            ",
+        )
+        .bind(("album_id", album_thing))
+        .bind(("user_id", user_id.map(create_user_thing)))
+        .await?
+        .check()?;
 
         Ok(true)
     }
+
+    pub async fn create_album(
+        db: &Surreal<Any>,
+        payload: CreateAlbumRequest,
+    ) -> Result<Album, Error> {
+        let title = payload.title.trim().to_string();
+        if title.is_empty() {
+            return Err(Error::InvalidInput {
+                reason: "Album title cannot be empty".to_string(),
+            });
+        }
+
+        let mut artist_things = Vec::with_capacity(payload.artist_ids.len());
+        for artist_id in &payload.artist_ids {
+            if !artist_exists(db, artist_id).await? {
+                return Err(Error::ArtistNotFound {
+                    id: artist_id.to_string(),
+                });
+            }
+            artist_things.push(create_artist_thing(artist_id));
+        }
+
+        let album_thing = Thing::from(("album", Id::rand()));
+
+        let create_query = r#"
+            BEGIN TRANSACTION;
+
+            CREATE $album SET
+                title = $title,
+                cover_url = $cover_url,
+                release_year = $release_year,
+                genres = $genres,
+                langs = $langs,
+                dominant_color = $dominant_color,
+                total_tracks = 0,
+                total_duration = 0s,
+                total_listens = 0,
+                total_user_listens = 0,
+                total_likes = 0;
+
+            FOR $artist IN $artists {
+                RELATE $artist->artist_creates_album->$album;
+            };
+
+            COMMIT TRANSACTION;
+        "#;
+
+        db.query(create_query)
+            .bind(("album", album_thing.clone()))
+            .bind(("title", title))
+            .bind(("cover_url", payload.cover_url))
+            .bind(("release_year", payload.release_year))
+            .bind(("genres", payload.genres))
+            .bind(("langs", payload.langs))
+            .bind(("dominant_color", payload.dominant_color))
+            .bind(("artists", artist_things.clone()))
+            .await?
+            .check()?;
+
+        for artist_thing in &artist_things {
+            ArtistService::refresh_counts(db, artist_thing).await?;
+        }
+
+        Self::get_album_record(db, &album_thing)
+            .await?
+            .ok_or(Error::DbError("Could not create album".into()))
+    }
+
+    pub async fn update_album(
+        db: &Surreal<Any>,
+        album_id: &str,
+        payload: UpdateAlbumRequest,
+    ) -> Result<Album, Error> {
+        if !album_exists(db, album_id).await? {
+            return Err(Error::AlbumNotFound {
+                id: album_id.to_string(),
+            });
+        }
+
+        if payload.title.as_deref().is_some_and(|t| t.trim().is_empty()) {
+            return Err(Error::InvalidInput {
+                reason: "Album title cannot be empty".to_string(),
+            });
+        }
+
+        let album_thing = create_album_thing(album_id);
+
+        db.query("UPDATE $album MERGE $patch")
+            .bind(("album", album_thing.clone()))
+            .bind(("patch", payload))
+            .await?
+            .check()?;
+
+        Self::get_album_record(db, &album_thing)
+            .await?
+            .ok_or(Error::AlbumNotFound {
+                id: album_id.to_string(),
+            })
+    }
+
+    /// Deletes an album with its artist, like and listen edges. Songs are kept, only unlinked.
+    pub async fn delete_album(db: &Surreal<Any>, album_id: &str) -> Result<(), Error> {
+        if !album_exists(db, album_id).await? {
+            return Err(Error::AlbumNotFound {
+                id: album_id.to_string(),
+            });
+        }
+
+        let album_thing = create_album_thing(album_id);
+
+        let delete_query = r#"
+            LET $artists = (SELECT VALUE in FROM artist_creates_album WHERE out = $album);
+
+            BEGIN TRANSACTION;
+            DELETE artist_creates_album WHERE out = $album;
+            DELETE album_contains_song WHERE in = $album;
+            DELETE user_likes_album WHERE out = $album;
+            DELETE user_listens_album WHERE out = $album;
+            DELETE $album;
+            COMMIT TRANSACTION;
+
+            RETURN $artists;
+        "#;
+
+        let mut response = db
+            .query(delete_query)
+            .bind(("album", album_thing))
+            .await?;
+
+        let artists: Vec<Thing> = response.take(response.num_statements() - 1)?;
+        for artist_thing in &artists {
+            ArtistService::refresh_counts(db, artist_thing).await?;
+        }
+
+        Ok(())
+    }
+
+    pub async fn link_artist(
+        db: &Surreal<Any>,
+        album_id: &str,
+        artist_id: &str,
+    ) -> Result<(), Error> {
+        if !album_exists(db, album_id).await? {
+            return Err(Error::AlbumNotFound {
+                id: album_id.to_string(),
+            });
+        }
+        if !artist_exists(db, artist_id).await? {
+            return Err(Error::ArtistNotFound {
+                id: artist_id.to_string(),
+            });
+        }
+
+        let artist_thing = create_artist_thing(artist_id);
+
+        // Linking twice is a no-op
+        let link_query = r#"
+            IF array::len(SELECT id FROM artist_creates_album WHERE in = $artist AND out = $album) = 0 THEN
+                RELATE $artist->artist_creates_album->$album
+            END;
+        "#;
+
+        db.query(link_query)
+            .bind(("artist", artist_thing.clone()))
+            .bind(("album", create_album_thing(album_id)))
+            .await?
+            .check()?;
+
+        ArtistService::refresh_counts(db, &artist_thing).await
+    }
+
+    pub async fn unlink_artist(
+        db: &Surreal<Any>,
+        album_id: &str,
+        artist_id: &str,
+    ) -> Result<(), Error> {
+        let artist_thing = create_artist_thing(artist_id);
+
+        db.query("DELETE artist_creates_album WHERE in = $artist AND out = $album")
+            .bind(("artist", artist_thing.clone()))
+            .bind(("album", create_album_thing(album_id)))
+            .await?
+            .check()?;
+
+        ArtistService::refresh_counts(db, &artist_thing).await
+    }
+
+    pub async fn add_song(db: &Surreal<Any>, album_id: &str, song_id: &str) -> Result<(), Error> {
+        if !album_exists(db, album_id).await? {
+            return Err(Error::AlbumNotFound {
+                id: album_id.to_string(),
+            });
+        }
+        if !song_exists(db, song_id).await? {
+            return Err(Error::SongNotFound {
+                id: song_id.to_string(),
+            });
+        }
+
+        let album_thing = create_album_thing(album_id);
+
+        // Linking twice is a no-op
+        let link_query = r#"
+            IF array::len(SELECT id FROM album_contains_song WHERE in = $album AND out = $song) = 0 THEN
+                RELATE $album->album_contains_song->$song
+            END;
+        "#;
+
+        db.query(link_query)
+            .bind(("album", album_thing.clone()))
+            .bind(("song", create_song_thing(song_id)))
+            .await?
+            .check()?;
+
+        Self::refresh_aggregates(db, &album_thing).await
+    }
+
+    pub async fn remove_song(
+        db: &Surreal<Any>,
+        album_id: &str,
+        song_id: &str,
+    ) -> Result<(), Error> {
+        let album_thing = create_album_thing(album_id);
+
+        db.query("DELETE album_contains_song WHERE in = $album AND out = $song")
+            .bind(("album", album_thing.clone()))
+            .bind(("song", create_song_thing(song_id)))
+            .await?
+            .check()?;
+
+        Self::refresh_aggregates(db, &album_thing).await
+    }
+
+    /// Recomputes `total_tracks` and `total_duration` from the album's songs.
+    /// A different track count invalidates the album loudness.
+    pub async fn refresh_aggregates(db: &Surreal<Any>, album_thing: &Thing) -> Result<(), Error> {
+        let refresh_query = r#"
+            LET $total_tracks = (
+                SELECT count() AS cnt FROM album_contains_song WHERE in = $album GROUP ALL
+            )[0].cnt OR 0;
+            LET $total_ns = (
+                SELECT math::sum(duration::nanos(out.duration)) AS ns
+                FROM album_contains_song
+                WHERE in = $album AND out.duration IS NOT NONE
+                GROUP ALL
+            )[0].ns OR 0;
+
+            UPDATE $album SET
+                loudness = IF total_tracks = $total_tracks THEN loudness ELSE NONE END,
+                total_tracks = $total_tracks,
+                total_duration = duration::from::nanos($total_ns);
+        "#;
+
+        db.query(refresh_query)
+            .bind(("album", album_thing.clone()))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    async fn get_album_record(db: &Surreal<Any>, album_thing: &Thing) -> Result<Option<Album>, Error> {
+        let album: Option<Album> = db
+            .query("SELECT * FROM $album")
+            .bind(("album", album_thing.clone()))
+            .await?
+            .take(0)?;
+
+        Ok(album)
+    }
 }
 
 #[cfg(test)]
@@ -323,10 +609,12 @@ mod tests {
             id: Some(create_album_thing(id)),
             title: title.to_string(),
             cover_url: Some(format!("/covers/{}.jpg", id)),
+            cover_variants: None,
             release_year: Some(2024),
             genres: vec!["Rock".to_string()],
             langs: vec!["en".to_string()],
             dominant_color: Some("#FF0000".to_string()),
+            loudness: None,
             total_tracks: 10,
             total_duration: Duration::new(2400, 0),
             total_listens: 0,
@@ -351,6 +639,7 @@ mod tests {
             genres: vec![MusicGenre::Rac],
             country_code: "US".to_string(),
             artist_image: Some(format!("/artists/{}.jpg", id)),
+            image_variants: None,
             albums_count: 1,
             songs_count: 10,
             total_likes: 0,
@@ -374,6 +663,7 @@ mod tests {
             duration: Duration::new(240, 0),
             song_index: index,
             tempo: 120.0,
+            loudness: None,
             total_listens: 0,
             total_user_listens: 0,
             total_likes: 0,
@@ -390,7 +680,7 @@ mod tests {
     }
 
     async fn create_test_user(db: &Surreal<Any>, id: &str) -> String {
-        use crate::models::user::UserRecord;
+        use crate::models::user::{Role, UserRecord};
 
         let user_content = UserRecord {
             id: Some(create_user_thing(id)),
@@ -404,6 +694,7 @@ mod tests {
             badges: Vec::new(),
             level: 0,
             experience_points: 0,
+            role: Role::User,
         };
 
         let created: UserRecord = db
@@ -555,10 +846,12 @@ mod tests {
             id: Some(create_album_thing("rock1")),
             title: "Rock Album".to_string(),
             cover_url: None,
+            cover_variants: None,
             release_year: Some(2024),
             genres: vec!["Rock".to_string()],
             langs: vec!["en".to_string()],
             dominant_color: None,
+            loudness: None,
             total_tracks: 10,
             total_duration: Duration::new(2400, 0),
             total_listens: 0,
@@ -570,10 +863,12 @@ mod tests {
             id: Some(create_album_thing("jazz1")),
             title: "Jazz Album".to_string(),
             cover_url: None,
+            cover_variants: None,
             release_year: Some(2024),
             genres: vec!["Jazz".to_string()],
             langs: vec!["en".to_string()],
             dominant_color: None,
+            loudness: None,
             total_tracks: 10,
             total_duration: Duration::new(2400, 0),
             total_listens: 0,
@@ -634,10 +929,12 @@ mod tests {
             id: Some(create_album_thing("popular")),
             title: "Popular Album".to_string(),
             cover_url: None,
+            cover_variants: None,
             release_year: Some(2020),
             genres: vec!["Pop".to_string()],
             langs: vec!["en".to_string()],
             dominant_color: None,
+            loudness: None,
             total_tracks: 10,
             total_duration: Duration::new(2400, 0),
             total_listens: 1000,
@@ -649,10 +946,12 @@ mod tests {
             id: Some(create_album_thing("recent")),
             title: "Recent Album".to_string(),
             cover_url: None,
+            cover_variants: None,
             release_year: Some(2024),
             genres: vec!["Pop".to_string()],
             langs: vec!["en".to_string()],
             dominant_color: None,
+            loudness: None,
             total_tracks: 10,
             total_duration: Duration::new(2400, 0),
             total_listens: 10,
@@ -889,10 +1188,12 @@ mod tests {
                 id: Some(create_album_thing(&format!("rock{}", i))),
                 title: format!("Rock Album {}", i),
                 cover_url: None,
+                cover_variants: None,
                 release_year: Some(2020 + i as u16),
                 genres: vec!["Rock".to_string(), "Alternative".to_string()],
                 langs: vec!["en".to_string()],
                 dominant_color: None,
+                loudness: None,
                 total_tracks: 10,
                 total_duration: Duration::new(2400, 0),
                 total_listens: i * 100,
@@ -950,5 +1251,170 @@ mod tests {
         assert!(artist_names.contains(&"Artist Two".to_string()));
     }
 
+    #[tokio::test]
+    async fn test_create_album_links_artists() {
+        let db = setup_db().await;
+        let artist_id = create_test_artist(&db, "artist1", "Test Artist").await;
+
+        let album = AlbumService::create_album(
+            &db,
+            CreateAlbumRequest {
+                title: "  New Album ".to_string(),
+                cover_url: None,
+                release_year: Some(1995),
+                genres: vec!["OI".to_string()],
+                langs: vec!["fr".to_string()],
+                dominant_color: None,
+                artist_ids: vec![artist_id.clone()],
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(album.title, "New Album");
+        assert_eq!(album.total_tracks, 0);
+
+        let album_id = album.id.unwrap().id.to_raw();
+        let with_relations = AlbumService::get_album(&db, &album_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(with_relations.artists.len(), 1);
+
+        let artist: Option<Artist> = db
+            .query("SELECT * FROM $artist")
+            .bind(("artist", create_artist_thing(&artist_id)))
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(artist.unwrap().albums_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_create_album_validation() {
+        let db = setup_db().await;
+
+        let empty_title = AlbumService::create_album(
+            &db,
+            CreateAlbumRequest {
+                title: " ".to_string(),
+                cover_url: None,
+                release_year: None,
+                genres: vec![],
+                langs: vec![],
+                dominant_color: None,
+                artist_ids: vec![],
+            },
+        )
+        .await;
+        assert!(matches!(empty_title, Err(Error::InvalidInput { .. })));
+
+        let unknown_artist = AlbumService::create_album(
+            &db,
+            CreateAlbumRequest {
+                title: "Album".to_string(),
+                cover_url: None,
+                release_year: None,
+                genres: vec![],
+                langs: vec![],
+                dominant_color: None,
+                artist_ids: vec!["missing".to_string()],
+            },
+        )
+        .await;
+        assert!(matches!(unknown_artist, Err(Error::ArtistNotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_update_album() {
+        let db = setup_db().await;
+        let album_id = create_test_album(&db, "album1", "Old Title").await;
+
+        let album = AlbumService::update_album(
+            &db,
+            &album_id,
+            UpdateAlbumRequest {
+                title: Some("New Title".to_string()),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(album.title, "New Title");
+        assert_eq!(album.release_year, Some(2024));
+
+        let missing = AlbumService::update_album(&db, "missing", UpdateAlbumRequest::default()).await;
+        assert!(matches!(missing, Err(Error::AlbumNotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_add_and_remove_song_refreshes_aggregates() {
+        let db = setup_db().await;
+        let album_id = create_test_album(&db, "album1", "Test Album").await;
+        let song1_id = create_test_song(&db, "song1", "Song 1", 1).await;
+        let song2_id = create_test_song(&db, "song2", "Song 2", 2).await;
+
+        AlbumService::add_song(&db, &album_id, &song1_id).await.unwrap();
+        AlbumService::add_song(&db, &album_id, &song2_id).await.unwrap();
+        db.query("UPDATE type::thing('album', $id) SET loudness = { integrated_lufs: -14.0, true_peak_dbtp: -1.0, gain_db: -4.0 }")
+            .bind(("id", album_id.clone()))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        // Adding the same song twice doesn't duplicate the edge
+        AlbumService::add_song(&db, &album_id, &song2_id).await.unwrap();
+
+        let album = AlbumService::get_album(&db, &album_id).await.unwrap().unwrap();
+        assert_eq!(album.songs.len(), 2);
+        assert_eq!(album.total_tracks, 2);
+        assert_eq!(album.total_duration, Duration::new(480, 0));
+        // Same tracks, the album gain still holds
+        assert!(album.loudness.is_some());
+
+        AlbumService::remove_song(&db, &album_id, &song1_id).await.unwrap();
+
+        let album = AlbumService::get_album(&db, &album_id).await.unwrap().unwrap();
+        assert_eq!(album.total_tracks, 1);
+        assert_eq!(album.total_duration, Duration::new(240, 0));
+        assert!(album.loudness.is_none());
+    }
+
+    #[tokio::test]
+    async fn test_delete_album_refreshes_artist_counts() {
+        let db = setup_db().await;
+        let album_id = create_test_album(&db, "album1", "Test Album").await;
+        let artist_id = create_test_artist(&db, "artist1", "Test Artist").await;
+        let song_id = create_test_song(&db, "song1", "Song 1", 1).await;
+
+        AlbumService::link_artist(&db, &album_id, &artist_id).await.unwrap();
+        AlbumService::add_song(&db, &album_id, &song_id).await.unwrap();
+
+        AlbumService::delete_album(&db, &album_id).await.unwrap();
+
+        assert!(AlbumService::get_album(&db, &album_id).await.unwrap().is_none());
+
+        let artist: Option<Artist> = db
+            .query("SELECT * FROM $artist")
+            .bind(("artist", create_artist_thing(&artist_id)))
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(artist.unwrap().albums_count, 0);
+
+        // The song itself is kept
+        let song: Option<Song> = db
+            .query("SELECT * FROM $song")
+            .bind(("song", create_song_thing(&song_id)))
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert!(song.is_some());
+    }
+
     use crate::helpers::thing_helpers::{create_artist_thing, create_song_thing};
 }
\ No newline at end of file
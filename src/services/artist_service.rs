@@ -1,8 +1,13 @@
-use surrealdb::{engine::any::Any, Error, Surreal};
+use surrealdb::{
+    engine::any::Any,
+    sql::{Id, Thing},
+    Surreal,
+};
 
 use crate::{
-    helpers::thing_helpers::create_artist_thing,
-    models::artist::{Artist, ArtistWithAlbumsAndTopSongs},
+    helpers::{artist_helpers::artist_exists, thing_helpers::create_artist_thing},
+    models::artist::{Artist, ArtistWithAlbumsAndTopSongs, CreateArtistRequest, UpdateArtistRequest},
+    Error,
 };
 
 pub struct ArtistService;
@@ -38,4 +43,133 @@ impl ArtistService {
 
         Ok(artist)
     }
+
+    pub async fn create_artist(
+        db: &Surreal<Any>,
+        payload: CreateArtistRequest,
+    ) -> Result<Artist, Error> {
+        let name = payload.name.trim().to_string();
+        if name.is_empty() {
+            return Err(Error::InvalidInput {
+                reason: "Artist name cannot be empty".to_string(),
+            });
+        }
+
+        let artist_thing = Thing::from(("artist", Id::rand()));
+
+        let create_query = r#"
+            CREATE $artist SET
+                name = $name,
+                genres = $genres,
+                country_code = $country_code,
+                artist_image = $artist_image,
+                albums_count = 0,
+                songs_count = 0,
+                total_likes = 0;
+        "#;
+
+        db.query(create_query)
+            .bind(("artist", artist_thing.clone()))
+            .bind(("name", name))
+            .bind(("genres", payload.genres))
+            .bind(("country_code", payload.country_code))
+            .bind(("artist_image", payload.artist_image))
+            .await?
+            .check()?;
+
+        Self::get_artist_record(db, &artist_thing)
+            .await?
+            .ok_or(Error::DbError("Could not create artist".into()))
+    }
+
+    pub async fn update_artist(
+        db: &Surreal<Any>,
+        artist_id: &str,
+        payload: UpdateArtistRequest,
+    ) -> Result<Artist, Error> {
+        if !artist_exists(db, artist_id).await? {
+            return Err(Error::ArtistNotFound {
+                id: artist_id.to_string(),
+            });
+        }
+
+        if payload.name.as_deref().is_some_and(|n| n.trim().is_empty()) {
+            return Err(Error::InvalidInput {
+                reason: "Artist name cannot be empty".to_string(),
+            });
+        }
+
+        let artist_thing = create_artist_thing(artist_id);
+
+        db.query("UPDATE $artist MERGE $patch")
+            .bind(("artist", artist_thing.clone()))
+            .bind(("patch", payload))
+            .await?
+            .check()?;
+
+        Self::get_artist_record(db, &artist_thing)
+            .await?
+            .ok_or(Error::ArtistNotFound {
+                id: artist_id.to_string(),
+            })
+    }
+
+    /// Deletes an artist and its edges. Albums and songs are kept.
+    pub async fn delete_artist(db: &Surreal<Any>, artist_id: &str) -> Result<(), Error> {
+        if !artist_exists(db, artist_id).await? {
+            return Err(Error::ArtistNotFound {
+                id: artist_id.to_string(),
+            });
+        }
+
+        let delete_query = r#"
+            BEGIN TRANSACTION;
+            DELETE artist_creates_album WHERE in = $artist;
+            DELETE artist_performs_song WHERE in = $artist;
+            DELETE user_likes_artist WHERE out = $artist;
+            DELETE $artist;
+            COMMIT TRANSACTION;
+        "#;
+
+        db.query(delete_query)
+            .bind(("artist", create_artist_thing(artist_id)))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    /// Recomputes `albums_count` and `songs_count` from the artist's edges.
+    pub async fn refresh_counts(db: &Surreal<Any>, artist_thing: &Thing) -> Result<(), Error> {
+        let refresh_query = r#"
+            LET $albums_count = (
+                SELECT count() AS cnt FROM artist_creates_album WHERE in = $artist GROUP ALL
+            )[0].cnt OR 0;
+            LET $songs_count = (
+                SELECT count() AS cnt FROM artist_performs_song WHERE in = $artist GROUP ALL
+            )[0].cnt OR 0;
+
+            UPDATE $artist SET albums_count = $albums_count, songs_count = $songs_count;
+        "#;
+
+        db.query(refresh_query)
+            .bind(("artist", artist_thing.clone()))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    async fn get_artist_record(
+        db: &Surreal<Any>,
+        artist_thing: &Thing,
+    ) -> Result<Option<Artist>, Error> {
+        let artist: Option<Artist> = db
+            .query("SELECT * FROM $artist")
+            .bind(("artist", artist_thing.clone()))
+            .await?
+            .take(0)?;
+
+        Ok(artist)
+    }
 }
@@ -0,0 +1,408 @@
+use std::{fs::File, io, path::Path};
+
+use serde::Deserialize;
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
+use symphonia::core::{
+    audio::SampleBuffer,
+    codecs::DecoderOptions,
+    errors::Error as SymphoniaError,
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::MetadataOptions,
+    probe::Hint,
+};
+
+use crate::{
+    error::{Error, Result},
+    helpers::thing_helpers::create_song_thing,
+    models::analysis::{AnalysisFailure, AnalysisKind},
+    services::media_service::MediaConfig,
+};
+
+/// Mono PCM, channels are averaged.
+#[derive(Debug, Clone)]
+pub struct DecodedAudio {
+    pub samples: Vec<f32>,
+    pub sample_rate: u32,
+}
+
+impl DecodedAudio {
+    pub fn duration_secs(&self) -> f64 {
+        self.samples.len() as f64 / f64::from(self.sample_rate)
+    }
+}
+
+/// A song waiting for an analysis.
+#[derive(Debug, Clone, Deserialize)]
+pub struct SongFile {
+    pub id: Thing,
+    pub file_url: String,
+}
+
+pub struct AudioAnalysisService;
+
+impl AudioAnalysisService {
+    /// Decodes the default track of `path`, stopping after `max_secs` when set.
+    pub fn decode_mono(path: &Path, max_secs: Option<f64>) -> std::result::Result<DecodedAudio, String> {
+        let mut samples = Vec::new();
+        let sample_rate = Self::decode_each(path, max_secs, |chunk, _| samples.extend_from_slice(chunk))?;
+
+        Ok(DecodedAudio {
+            samples,
+            sample_rate,
+        })
+    }
+
+    /// Streams the default track of `path` as mono chunks, for analyses that
+    /// don't need the whole signal in memory. Returns the sample rate.
+    pub fn decode_each<F>(
+        path: &Path,
+        max_secs: Option<f64>,
+        mut on_chunk: F,
+    ) -> std::result::Result<u32, String>
+    where
+        F: FnMut(&[f32], u32),
+    {
+        let mut mono = Vec::new();
+        Self::decode_interleaved(path, max_secs, |samples, channels, sample_rate| {
+            mono.clear();
+            mono.extend(
+                samples
+                    .chunks(channels)
+                    .map(|frame| frame.iter().sum::<f32>() / channels as f32),
+            );
+            on_chunk(&mono, sample_rate);
+        })
+    }
+
+    /// Same as `decode_each`, keeping the channels: `on_chunk` receives
+    /// interleaved samples with the channel count.
+    pub fn decode_interleaved<F>(
+        path: &Path,
+        max_secs: Option<f64>,
+        mut on_chunk: F,
+    ) -> std::result::Result<u32, String>
+    where
+        F: FnMut(&[f32], usize, u32),
+    {
+        let file = File::open(path).map_err(|e| e.to_string())?;
+        let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+        let mut hint = Hint::new();
+        if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+            hint.with_extension(ext);
+        }
+
+        let mut format = symphonia::default::get_probe()
+            .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+            .map_err(|e| format!("Unsupported audio file: {}", e))?
+            .format;
+
+        let track = format
+            .default_track()
+            .ok_or_else(|| "No audio track".to_string())?;
+        let track_id = track.id;
+
+        let mut decoder = symphonia::default::get_codecs()
+            .make(&track.codec_params, &DecoderOptions::default())
+            .map_err(|e| format!("Unsupported codec: {}", e))?;
+
+        let mut sample_rate = track.codec_params.sample_rate.unwrap_or(0);
+        let mut decoded_frames: u64 = 0;
+        let mut buffer: Option<SampleBuffer<f32>> = None;
+
+        loop {
+            let packet = match format.next_packet() {
+                Ok(packet) => packet,
+                Err(SymphoniaError::IoError(e)) if e.kind() == io::ErrorKind::UnexpectedEof => break,
+                Err(SymphoniaError::ResetRequired) => break,
+                Err(e) => return Err(format!("Read error: {}", e)),
+            };
+            if packet.track_id() != track_id {
+                continue;
+            }
+
+            let decoded = match decoder.decode(&packet) {
+                Ok(decoded) => decoded,
+                // Trame corrompue : on passe à la suivante
+                Err(SymphoniaError::DecodeError(_)) => continue,
+                Err(e) => return Err(format!("Decode error: {}", e)),
+            };
+
+            let spec = *decoded.spec();
+            sample_rate = spec.rate;
+            let channels = spec.channels.count().max(1);
+
+            let buffer = match &mut buffer {
+                Some(buffer) if buffer.capacity() >= decoded.capacity() * channels => buffer,
+                _ => buffer.insert(SampleBuffer::new(decoded.capacity() as u64, spec)),
+            };
+            buffer.copy_interleaved_ref(decoded);
+
+            let samples = buffer.samples();
+            decoded_frames += (samples.len() / channels) as u64;
+            on_chunk(samples, channels, sample_rate);
+
+            if let Some(max_secs) = max_secs {
+                if decoded_frames as f64 >= max_secs * f64::from(sample_rate) {
+                    break;
+                }
+            }
+        }
+
+        if decoded_frames == 0 || sample_rate == 0 {
+            return Err("No audio decoded".to_string());
+        }
+
+        Ok(sample_rate)
+    }
+
+    /// Songs without a result for `kind` that have not failed it yet.
+    pub async fn pending_songs(
+        db: &Surreal<Any>,
+        kind: AnalysisKind,
+        limit: u32,
+    ) -> Result<Vec<SongFile>> {
+        // Ensembles liés une seule fois, pas réévalués pour chaque morceau
+        let (condition, done) = match kind {
+            AnalysisKind::Tempo => ("tempo = 0", "[]"),
+            AnalysisKind::Waveform => ("id NOTINSIDE $done", "SELECT VALUE song FROM song_waveform"),
+            AnalysisKind::Loudness => ("loudness = NONE", "[]"),
+            AnalysisKind::Fingerprint => (
+                "id NOTINSIDE $done",
+                "SELECT VALUE song FROM song_fingerprint",
+            ),
+        };
+
+        let query = format!(
+            r#"
+            LET $failed = (SELECT VALUE song FROM analysis_failure WHERE kind = $kind);
+            LET $done = ({});
+            SELECT id, file_url FROM song
+            WHERE {} AND id NOTINSIDE $failed
+            LIMIT $limit;
+            "#,
+            done, condition
+        );
+
+        let songs: Vec<SongFile> = db
+            .query(query)
+            .bind(("kind", kind))
+            .bind(("limit", limit))
+            .await?
+            .take(2)?;
+        Ok(songs)
+    }
+
+    pub async fn get_song_file(db: &Surreal<Any>, song_id: &str) -> Result<SongFile> {
+        let song: Option<SongFile> = db
+            .query("SELECT id, file_url FROM $song")
+            .bind(("song", create_song_thing(song_id)))
+            .await?
+            .take(0)?;
+
+        song.ok_or(Error::SongNotFound {
+            id: song_id.to_string(),
+        })
+    }
+
+    /// Runs `analyze` on the song's local file off the async runtime. A failure
+    /// is recorded on the song and a success clears the previous one.
+    pub async fn run<T, F>(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        song: &SongFile,
+        kind: AnalysisKind,
+        analyze: F,
+    ) -> Result<std::result::Result<T, String>>
+    where
+        T: Send + 'static,
+        F: FnOnce(&Path) -> std::result::Result<T, String> + Send + 'static,
+    {
+        let outcome = match config.resolve(&song.file_url).filter(|path| path.is_file()) {
+            Some(path) => tokio::task::spawn_blocking(move || analyze(&path))
+                .await
+                .unwrap_or_else(|e| Err(format!("Analysis panicked: {}", e))),
+            None => Err("File not found in the media root".to_string()),
+        };
+
+        match &outcome {
+            Ok(_) => Self::clear_failure(db, &song.id, kind).await?,
+            Err(reason) => {
+                tracing::warn!("{} analysis failed for {}: {}", kind.as_str(), song.id, reason);
+                Self::record_failure(db, &song.id, kind, reason).await?
+            }
+        }
+
+        Ok(outcome)
+    }
+
+    pub async fn record_failure(
+        db: &Surreal<Any>,
+        song: &Thing,
+        kind: AnalysisKind,
+        reason: &str,
+    ) -> Result<()> {
+        let query = r#"
+            UPSERT type::thing('analysis_failure', [$song, $kind]) SET
+                song = $song,
+                kind = $kind,
+                reason = $reason,
+                attempts = (attempts OR 0) + 1,
+                failed_at = time::now();
+        "#;
+
+        db.query(query)
+            .bind(("song", song.clone()))
+            .bind(("kind", kind))
+            .bind(("reason", reason.to_string()))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    pub async fn clear_failure(db: &Surreal<Any>, song: &Thing, kind: AnalysisKind) -> Result<()> {
+        db.query("DELETE type::thing('analysis_failure', [$song, $kind])")
+            .bind(("song", song.clone()))
+            .bind(("kind", kind))
+            .await?
+            .check()?;
+        Ok(())
+    }
+
+    pub async fn list_failures(
+        db: &Surreal<Any>,
+        kind: Option<AnalysisKind>,
+    ) -> Result<Vec<AnalysisFailure>> {
+        let failures: Vec<AnalysisFailure> = db
+            .query(
+                r#"
+                SELECT song, kind, reason, attempts, failed_at FROM analysis_failure
+                WHERE $kind = NONE OR kind = $kind
+                ORDER BY failed_at DESC;
+                "#,
+            )
+            .bind(("kind", kind))
+            .await?
+            .take(0)?;
+        Ok(failures)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::engine::any::connect;
+
+    async fn setup_db() -> Surreal<Any> {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db
+    }
+
+    /// Silent MPEG-1 Layer III frames (128 kbps, 44.1 kHz, 1152 samples each).
+    fn write_silent_mp3(path: &Path, frames: usize) {
+        let mut data = Vec::new();
+        for _ in 0..frames {
+            let mut frame = vec![0u8; 417];
+            frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+            data.extend(frame);
+        }
+        std::fs::write(path, data).unwrap();
+    }
+
+    #[test]
+    fn test_decode_mono() {
+        let path = std::env::temp_dir().join(format!("decode-{}.mp3", uuid::Uuid::new_v4()));
+        write_silent_mp3(&path, 100);
+
+        let audio = AudioAnalysisService::decode_mono(&path, None).unwrap();
+        assert_eq!(audio.sample_rate, 44_100);
+        assert!(audio.duration_secs() > 2.0 && audio.duration_secs() <= 100.0 * 1152.0 / 44_100.0);
+        assert!(audio.samples.iter().all(|s| s.abs() < 1e-3));
+
+        // Arrêt anticipé
+        let audio = AudioAnalysisService::decode_mono(&path, Some(1.0)).unwrap();
+        assert!(audio.duration_secs() < 1.1);
+
+        std::fs::write(&path, b"not audio").unwrap();
+        assert!(AudioAnalysisService::decode_mono(&path, None).is_err());
+
+        std::fs::remove_file(&path).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_recorded_per_song_and_kind() {
+        let db = setup_db().await;
+        let song = Thing::from(("song", "a"));
+        let other = Thing::from(("song", "b"));
+
+        AudioAnalysisService::record_failure(&db, &song, AnalysisKind::Tempo, "first")
+            .await
+            .unwrap();
+        AudioAnalysisService::record_failure(&db, &song, AnalysisKind::Tempo, "second")
+            .await
+            .unwrap();
+        AudioAnalysisService::record_failure(&db, &other, AnalysisKind::Tempo, "other")
+            .await
+            .unwrap();
+
+        let failures = AudioAnalysisService::list_failures(&db, Some(AnalysisKind::Tempo))
+            .await
+            .unwrap();
+        assert_eq!(failures.len(), 2);
+        let failure = failures.iter().find(|f| f.song == song).unwrap();
+        assert_eq!(failure.reason, "second");
+        assert_eq!(failure.attempts, 2);
+
+        AudioAnalysisService::clear_failure(&db, &song, AnalysisKind::Tempo)
+            .await
+            .unwrap();
+        let failures = AudioAnalysisService::list_failures(&db, None).await.unwrap();
+        assert_eq!(failures.len(), 1);
+        assert_eq!(failures[0].song, other);
+    }
+
+    #[tokio::test]
+    async fn test_pending_songs_skip_done_and_failed_songs() {
+        let db = setup_db().await;
+        db.query(
+            r#"
+            CREATE song:a SET file_url = 'a.mp3', tempo = 0;
+            CREATE song:b SET file_url = 'b.mp3', tempo = 0;
+            CREATE song:c SET file_url = 'c.mp3', tempo = 120;
+            CREATE song_waveform:c SET song = song:c;
+            CREATE song_fingerprint:a SET song = song:a;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+        let a = Thing::from(("song", "a"));
+        let b = Thing::from(("song", "b"));
+        AudioAnalysisService::record_failure(&db, &b, AnalysisKind::Tempo, "failed")
+            .await
+            .unwrap();
+        AudioAnalysisService::record_failure(&db, &a, AnalysisKind::Waveform, "failed")
+            .await
+            .unwrap();
+
+        let pending = |kind| {
+            let db = db.clone();
+            async move {
+                let mut ids: Vec<String> = AudioAnalysisService::pending_songs(&db, kind, 10)
+                    .await
+                    .unwrap()
+                    .into_iter()
+                    .map(|song| song.id.id.to_raw())
+                    .collect();
+                ids.sort();
+                ids
+            }
+        };
+        assert_eq!(pending(AnalysisKind::Tempo).await, vec!["a"]);
+        assert_eq!(pending(AnalysisKind::Waveform).await, vec!["b"]);
+        assert_eq!(pending(AnalysisKind::Fingerprint).await, vec!["b", "c"]);
+    }
+}
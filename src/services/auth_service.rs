@@ -5,7 +5,7 @@ use crate::{
     },
     error::{Error, Result},
     helpers::thing_helpers::{parse_id_part, thing_to_string},
-    models::user::UserRecord,
+    models::user::{Role, UserRecord},
 };
 use chrono::Utc;
 use surrealdb::{engine::any::Any, Surreal};
@@ -39,6 +39,7 @@ impl AuthService {
                 badges: vec![],
                 level: 1,
                 experience_points: 0,
+                role: Role::User,
             };
             db.create("user")
                 .content(new_user)
@@ -74,7 +75,7 @@ impl AuthService {
             Some(id) => {
                 let thing_str = thing_to_string(id);
                 let id_part = parse_id_part(&thing_str);
-                TokenService::create_token(id_part.to_string(), config)?
+                TokenService::create_token(id_part.to_string(), user.role, config)?
             }
             None => return Err(Error::LoginFail),
         };
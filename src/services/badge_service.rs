@@ -205,7 +205,13 @@ impl BadgeService {
         user_id: Thing,
         badges: &[BadgeEnum],
     ) -> Result<()> {
-        let query = "UPDATE $user_id SET badges = array::union(badges, $new_badges)";
+        // Chaque badge obtenu est daté pour le récapitulatif annuel
+        let query = "
+            UPDATE $user_id SET badges = array::union(badges, $new_badges);
+            FOR $badge IN $new_badges {
+                CREATE badge_award SET user = $user_id, badge = $badge, awarded_at = time::now();
+            };
+        ";
 
         let badges_owned: Vec<BadgeEnum> = badges.to_vec();
 
@@ -0,0 +1,425 @@
+use std::collections::HashMap;
+
+use chrono::Utc;
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use surrealdb::{engine::any::Any, sql::Thing, Datetime, Surreal};
+
+use crate::{
+    error::Result,
+    models::{
+        album::AlbumWithArtists,
+        artist::Artist,
+        chart::{Chart, ChartEntry, ChartKind, ChartPeriod, ChartReport},
+        song::SongWithRelations,
+    },
+    services::recommendation_service::{ALBUM_RELATIONS, SONG_RELATIONS},
+};
+
+/// Entries stored per chart, the most a chart request returns.
+pub const CHART_SIZE: usize = 100;
+
+#[derive(Debug, Deserialize)]
+struct Count {
+    item: Thing,
+    listens: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct SongArtists {
+    id: Thing,
+    artists: Vec<Thing>,
+}
+
+#[derive(Debug, Serialize)]
+struct StoredEntry {
+    kind: ChartKind,
+    period: ChartPeriod,
+    rank: u32,
+    item: Thing,
+    listens: u64,
+    computed_at: Datetime,
+}
+
+#[derive(Debug, Deserialize)]
+struct ChartRow<T> {
+    rank: u32,
+    listens: u64,
+    computed_at: Datetime,
+    /// `None` once the song, album or artist is deleted.
+    item: Option<T>,
+}
+
+/// The [`CHART_SIZE`] most listened items, equal counts ordered by id.
+fn top(mut counts: Vec<Count>) -> Vec<Count> {
+    counts.sort_by(|a, b| {
+        b.listens
+            .cmp(&a.listens)
+            .then_with(|| a.item.to_string().cmp(&b.item.to_string()))
+    });
+    counts.truncate(CHART_SIZE);
+    counts
+}
+
+pub struct ChartService;
+
+impl ChartService {
+    /// Replaces every chart. Day, week and month count the events of the
+    /// listen log over the last 24 hours, 7 days and 30 days; all time reads
+    /// the `total_listens` counters, which predate the log. An artist counts
+    /// the listens of the songs they perform, album listens count for the
+    /// album charts only.
+    pub async fn rebuild(db: &Surreal<Any>) -> Result<ChartReport> {
+        let computed_at = Datetime::from(Utc::now());
+        let mut report = ChartReport {
+            songs: 0,
+            albums: 0,
+            artists: 0,
+        };
+        let mut entries: Vec<StoredEntry> = Vec::new();
+
+        for period in ChartPeriod::ALL {
+            let (songs, albums) = Self::counts(db, period).await?;
+            let artists = Self::artist_counts(db, &songs).await?;
+
+            for (kind, counts) in [
+                (ChartKind::Song, songs),
+                (ChartKind::Album, albums),
+                (ChartKind::Artist, artists),
+            ] {
+                let counts = top(counts);
+                match kind {
+                    ChartKind::Song => report.songs += counts.len(),
+                    ChartKind::Album => report.albums += counts.len(),
+                    ChartKind::Artist => report.artists += counts.len(),
+                }
+                entries.extend(
+                    counts
+                        .into_iter()
+                        .enumerate()
+                        .map(|(index, count)| StoredEntry {
+                            kind,
+                            period,
+                            rank: index as u32 + 1,
+                            item: count.item,
+                            listens: count.listens,
+                            computed_at: computed_at.clone(),
+                        }),
+                );
+            }
+        }
+
+        let sql = "
+            BEGIN TRANSACTION;
+            DELETE chart_entry;
+            INSERT INTO chart_entry $entries;
+            COMMIT TRANSACTION;
+        ";
+        db.query(sql).bind(("entries", entries)).await?.check()?;
+
+        Ok(report)
+    }
+
+    /// Listens of every song and album over the period.
+    async fn counts(db: &Surreal<Any>, period: ChartPeriod) -> Result<(Vec<Count>, Vec<Count>)> {
+        let sql = match period.window() {
+            Some(window) => format!(
+                "
+                SELECT song AS item, listens FROM (
+                    SELECT song, count() AS listens FROM listen_event
+                    WHERE song != NONE AND listened_at > time::now() - {window}
+                    GROUP BY song
+                );
+                SELECT album AS item, listens FROM (
+                    SELECT album, count() AS listens FROM listen_event
+                    WHERE album != NONE AND listened_at > time::now() - {window}
+                    GROUP BY album
+                );
+                "
+            ),
+            None => "
+                SELECT id AS item, total_listens AS listens FROM song WHERE total_listens > 0;
+                SELECT id AS item, total_listens AS listens FROM album WHERE total_listens > 0;
+            "
+            .to_string(),
+        };
+        let mut res = db.query(sql).await?;
+
+        Ok((res.take(0)?, res.take(1)?))
+    }
+
+    /// Listens of the artists of the counted songs.
+    async fn artist_counts(db: &Surreal<Any>, songs: &[Count]) -> Result<Vec<Count>> {
+        let ids: Vec<Thing> = songs.iter().map(|count| count.item.clone()).collect();
+        let mut res = db
+            .query("SELECT id, <-artist_performs_song.in AS artists FROM $ids;")
+            .bind(("ids", ids))
+            .await?;
+        let performers: HashMap<String, Vec<Thing>> = res
+            .take::<Vec<SongArtists>>(0)?
+            .into_iter()
+            .map(|song| (song.id.to_string(), song.artists))
+            .collect();
+
+        let mut artists: HashMap<String, Count> = HashMap::new();
+        for song in songs {
+            for artist in performers.get(&song.item.to_string()).into_iter().flatten() {
+                artists
+                    .entry(artist.to_string())
+                    .or_insert_with(|| Count {
+                        item: artist.clone(),
+                        listens: 0,
+                    })
+                    .listens += song.listens;
+            }
+        }
+
+        Ok(artists.into_values().collect())
+    }
+
+    pub async fn get_song_chart(
+        db: &Surreal<Any>,
+        period: ChartPeriod,
+        limit: u32,
+    ) -> Result<Chart<SongWithRelations>> {
+        Self::chart(
+            db,
+            ChartKind::Song,
+            period,
+            limit,
+            &format!("*, {SONG_RELATIONS}"),
+        )
+        .await
+    }
+
+    pub async fn get_album_chart(
+        db: &Surreal<Any>,
+        period: ChartPeriod,
+        limit: u32,
+    ) -> Result<Chart<AlbumWithArtists>> {
+        Self::chart(
+            db,
+            ChartKind::Album,
+            period,
+            limit,
+            &format!("*, {ALBUM_RELATIONS}"),
+        )
+        .await
+    }
+
+    pub async fn get_artist_chart(
+        db: &Surreal<Any>,
+        period: ChartPeriod,
+        limit: u32,
+    ) -> Result<Chart<Artist>> {
+        Self::chart(db, ChartKind::Artist, period, limit, "*").await
+    }
+
+    /// Stored chart, items selected with `projection`. Deleted items are left
+    /// out without renumbering the others.
+    async fn chart<T: DeserializeOwned>(
+        db: &Surreal<Any>,
+        kind: ChartKind,
+        period: ChartPeriod,
+        limit: u32,
+        projection: &str,
+    ) -> Result<Chart<T>> {
+        let sql = format!(
+            "
+            SELECT rank, listens, computed_at, (SELECT {projection} FROM $parent.item)[0] AS item
+            FROM chart_entry WHERE kind = $kind AND period = $period
+            ORDER BY rank ASC LIMIT $limit;
+            "
+        );
+        let mut res = db
+            .query(sql)
+            .bind(("kind", kind))
+            .bind(("period", period))
+            .bind(("limit", limit.clamp(1, CHART_SIZE as u32)))
+            .await?;
+        let rows: Vec<ChartRow<T>> = res.take(0)?;
+
+        Ok(Chart {
+            period,
+            computed_at: rows.first().map(|row| row.computed_at.clone()),
+            entries: rows
+                .into_iter()
+                .filter_map(|row| {
+                    Some(ChartEntry {
+                        rank: row.rank,
+                        listens: row.listens,
+                        item: row.item?,
+                    })
+                })
+                .collect(),
+        })
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::services::{album_service::AlbumService, song_service::SongService};
+    use surrealdb::{engine::any::connect, sql::Duration};
+
+    fn ranking<T>(chart: &Chart<T>, id: impl Fn(&T) -> String) -> Vec<(u32, String, u64)> {
+        chart
+            .entries
+            .iter()
+            .map(|entry| (entry.rank, id(&entry.item), entry.listens))
+            .collect()
+    }
+
+    fn song_id(song: &SongWithRelations) -> String {
+        song.id.as_ref().unwrap().id.to_raw()
+    }
+
+    #[tokio::test]
+    async fn test_charts_count_listens_per_period() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(include_str!("../../database_charts.surql"))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        db.query(
+            r#"
+            CREATE user:u1 SET username = 'u1', password = 'x', created_at = time::now(), listen_count = 0,
+                total_listening_time = 0, favorite_count = 0, listening_streak = 0, badges = [], level = 0,
+                experience_points = 0, role = 'user';
+            CREATE artist:clash SET name = 'The Clash', genres = [], country_code = 'GB', albums_count = 1, songs_count = 2;
+            CREATE artist:jam SET name = 'The Jam', genres = [], country_code = 'GB', albums_count = 1, songs_count = 1;
+            CREATE album:london SET title = 'London Calling', genres = [], langs = [], total_tracks = 2, total_duration = 6m;
+            CREATE album:setting SET title = 'Setting Sons', genres = [], langs = [], total_tracks = 1, total_duration = 3m;
+            RELATE artist:clash->artist_creates_album->album:london;
+            RELATE artist:jam->artist_creates_album->album:setting;
+            FOR $song IN [['c1', 'clash', 'london', 0], ['c2', 'clash', 'london', 10], ['j1', 'jam', 'setting', 0]] {
+                CREATE type::thing('song', $song[0]) SET title = $song[0], file_url = 'f.mp3', duration = 3m,
+                    song_index = 1, tempo = 0.0, total_listens = $song[3];
+                RELATE (type::thing('artist', $song[1]))->artist_performs_song->(type::thing('song', $song[0]));
+                RELATE (type::thing('album', $song[2]))->album_contains_song->(type::thing('song', $song[0]));
+            };
+            -- Écoutes d'avant la semaine, hors compteurs
+            FOR $i IN 1..=3 {
+                CREATE listen_event SET song = song:c2, listened_at = time::now() - 10d;
+            };
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let duration = Duration::from_secs(180);
+        for (song, user) in [
+            ("c1", None),
+            ("c1", None),
+            ("c1", None),
+            ("c2", Some("u1")),
+            ("j1", None),
+            ("j1", Some("u1")),
+        ] {
+            SongService::listen_to_song(&db, song, user, duration)
+                .await
+                .unwrap();
+        }
+        AlbumService::listen_to_album(&db, "setting", Some("u1"))
+            .await
+            .unwrap();
+
+        let mut res = db
+            .query("SELECT count() FROM listen_event WHERE user = user:u1 GROUP ALL;")
+            .await
+            .unwrap();
+        let by_user: Option<u64> = res.take((0, "count")).unwrap();
+        assert_eq!(by_user, Some(3));
+
+        assert!(ChartService::get_song_chart(&db, ChartPeriod::Week, 10)
+            .await
+            .unwrap()
+            .computed_at
+            .is_none());
+
+        let report = ChartService::rebuild(&db).await.unwrap();
+        assert_eq!(
+            report,
+            ChartReport {
+                songs: 12,
+                albums: 4,
+                artists: 8,
+            }
+        );
+        // Un second calcul remplace le premier
+        assert_eq!(ChartService::rebuild(&db).await.unwrap(), report);
+        let mut res = db
+            .query("SELECT count() FROM chart_entry GROUP ALL;")
+            .await
+            .unwrap();
+        let stored: Option<u64> = res.take((0, "count")).unwrap();
+        assert_eq!(stored, Some(24));
+
+        let week = ChartService::get_song_chart(&db, ChartPeriod::Week, 10)
+            .await
+            .unwrap();
+        assert!(week.computed_at.is_some());
+        assert_eq!(
+            ranking(&week, song_id),
+            vec![
+                (1, "c1".to_string(), 3),
+                (2, "j1".to_string(), 2),
+                (3, "c2".to_string(), 1),
+            ]
+        );
+        assert_eq!(week.entries[0].item.artists.as_ref().map(Vec::len), Some(1));
+
+        let month = ChartService::get_song_chart(&db, ChartPeriod::Month, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            ranking(&month, song_id),
+            vec![
+                (1, "c2".to_string(), 4),
+                (2, "c1".to_string(), 3),
+                (3, "j1".to_string(), 2),
+            ]
+        );
+
+        let all_time = ChartService::get_song_chart(&db, ChartPeriod::AllTime, 1)
+            .await
+            .unwrap();
+        assert_eq!(ranking(&all_time, song_id), vec![(1, "c2".to_string(), 11)]);
+
+        let artists = ChartService::get_artist_chart(&db, ChartPeriod::Day, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            ranking(&artists, |artist| artist.name.clone()),
+            vec![
+                (1, "The Clash".to_string(), 4),
+                (2, "The Jam".to_string(), 2),
+            ]
+        );
+
+        let albums = ChartService::get_album_chart(&db, ChartPeriod::Week, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            ranking(&albums, |album| album.title.clone()),
+            vec![(1, "Setting Sons".to_string(), 1)]
+        );
+        assert_eq!(albums.entries[0].item.artists[0].name, "The Jam");
+
+        // Un titre supprimé disparaît sans renuméroter les autres
+        db.query("DELETE song:c1;").await.unwrap().check().unwrap();
+        let week = ChartService::get_song_chart(&db, ChartPeriod::Week, 10)
+            .await
+            .unwrap();
+        assert_eq!(
+            week.entries
+                .iter()
+                .map(|entry| entry.rank)
+                .collect::<Vec<_>>(),
+            vec![2, 3]
+        );
+    }
+}
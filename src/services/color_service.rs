@@ -0,0 +1,309 @@
+use std::path::Path;
+
+use image::DynamicImage;
+use serde::Deserialize;
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
+
+use crate::{
+    error::Result,
+    helpers::thing_helpers::thing_to_string,
+    models::cover::{ColorBackfillReport, CoverError},
+    services::media_service::MediaConfig,
+};
+
+// Taille de la vignette analysée : suffisant pour la couleur, rapide à découper
+const SAMPLE_SIZE: u32 = 64;
+const PALETTE_SIZE: usize = 8;
+
+#[derive(Debug, Deserialize)]
+struct CoverRecord {
+    id: Thing,
+    cover_url: String,
+}
+
+pub struct ColorService;
+
+impl ColorService {
+    /// Dominant color as `#rrggbb`, from a median cut palette of the image.
+    /// The most populated box wins: uniform areas are never split, so a large
+    /// flat background keeps a bigger share than detailed regions.
+    pub fn dominant_color(image: &DynamicImage) -> Option<String> {
+        let sample = image.thumbnail(SAMPLE_SIZE, SAMPLE_SIZE).to_rgba8();
+        let pixels: Vec<[u8; 3]> = sample
+            .pixels()
+            .filter(|pixel| pixel[3] >= 128)
+            .map(|pixel| [pixel[0], pixel[1], pixel[2]])
+            .collect();
+
+        let palette = median_cut(pixels, PALETTE_SIZE);
+        let dominant = palette.iter().max_by_key(|bucket| bucket.len())?;
+
+        let [r, g, b] = average(dominant);
+        Some(format!("#{:02x}{:02x}{:02x}", r, g, b))
+    }
+
+    pub fn color_of_file(path: &Path) -> std::result::Result<String, String> {
+        let image = image::open(path).map_err(|e| format!("Unreadable image: {}", e))?;
+        Self::dominant_color(&image).ok_or_else(|| "Image has no opaque pixel".to_string())
+    }
+
+    /// Computes the color of a cover stored under the media root.
+    pub async fn cover_color(
+        config: &MediaConfig,
+        cover_url: &str,
+    ) -> std::result::Result<String, String> {
+        let path = config
+            .resolve(cover_url)
+            .filter(|path| path.is_file())
+            .ok_or_else(|| "Cover not found in the media root".to_string())?;
+
+        tokio::task::spawn_blocking(move || Self::color_of_file(&path))
+            .await
+            .unwrap_or_else(|e| Err(format!("Color extraction panicked: {}", e)))
+    }
+
+    /// Recomputes the color of an album or playlist cover. A cover that cannot
+    /// be read clears the color, which belonged to the previous cover.
+    pub async fn refresh(db: &Surreal<Any>, config: &MediaConfig, record: &Thing) -> Result<Option<String>> {
+        let cover_url: Option<String> = db
+            .query("SELECT VALUE cover_url FROM ONLY $record")
+            .bind(("record", record.clone()))
+            .await?
+            .take(0)?;
+
+        let color = match cover_url {
+            Some(cover_url) => match Self::cover_color(config, &cover_url).await {
+                Ok(color) => Some(color),
+                Err(reason) => {
+                    tracing::warn!("No dominant color for {} ({}): {}", record, cover_url, reason);
+                    None
+                }
+            },
+            None => None,
+        };
+
+        db.query("UPDATE $record SET dominant_color = $color")
+            .bind(("record", record.clone()))
+            .bind(("color", color.clone()))
+            .await?
+            .check()?;
+
+        Ok(color)
+    }
+
+    /// Fills the dominant color of album and playlist covers, every cover when
+    /// `force` is set, otherwise only those without a color.
+    pub async fn backfill(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        force: bool,
+    ) -> Result<ColorBackfillReport> {
+        let mut report = ColorBackfillReport::default();
+
+        for table in ["album", "playlist"] {
+            let records: Vec<CoverRecord> = db
+                .query(
+                    "SELECT id, cover_url FROM type::table($table)
+                     WHERE cover_url != NONE AND ($force OR dominant_color = NONE)",
+                )
+                .bind(("table", table))
+                .bind(("force", force))
+                .await?
+                .take(0)?;
+
+            for record in records {
+                match Self::cover_color(config, &record.cover_url).await {
+                    Ok(color) => {
+                        db.query("UPDATE $record SET dominant_color = $color")
+                            .bind(("record", record.id))
+                            .bind(("color", color))
+                            .await?
+                            .check()?;
+
+                        match table {
+                            "album" => report.albums += 1,
+                            _ => report.playlists += 1,
+                        }
+                    }
+                    Err(reason) => report.errors.push(CoverError {
+                        id: thing_to_string(&record.id),
+                        cover_url: record.cover_url,
+                        reason,
+                    }),
+                }
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Splits the pixels into at most `size` boxes, always cutting the box with
+/// the widest channel range at its median.
+fn median_cut(pixels: Vec<[u8; 3]>, size: usize) -> Vec<Vec<[u8; 3]>> {
+    if pixels.is_empty() {
+        return Vec::new();
+    }
+
+    let mut boxes = vec![pixels];
+
+    while boxes.len() < size {
+        let widest = boxes
+            .iter()
+            .enumerate()
+            .filter(|(_, bucket)| bucket.len() > 1)
+            .map(|(index, bucket)| {
+                let (channel, range) = widest_channel(bucket);
+                (index, channel, range)
+            })
+            .max_by_key(|(_, _, range)| *range);
+
+        let Some((index, channel, range)) = widest else { break };
+        if range == 0 {
+            break;
+        }
+
+        let mut bucket = boxes.swap_remove(index);
+        bucket.sort_unstable_by_key(|pixel| pixel[channel]);
+        let upper = bucket.split_off(bucket.len() / 2);
+        boxes.push(bucket);
+        boxes.push(upper);
+    }
+
+    boxes
+}
+
+fn widest_channel(pixels: &[[u8; 3]]) -> (usize, u8) {
+    (0..3)
+        .map(|channel| {
+            let (min, max) = pixels.iter().fold((u8::MAX, u8::MIN), |(min, max), pixel| {
+                (min.min(pixel[channel]), max.max(pixel[channel]))
+            });
+            (channel, max - min)
+        })
+        .max_by_key(|(_, range)| *range)
+        .unwrap_or((0, 0))
+}
+
+fn average(pixels: &[[u8; 3]]) -> [u8; 3] {
+    let mut sums = [0u64; 3];
+    for pixel in pixels {
+        for (sum, value) in sums.iter_mut().zip(pixel) {
+            *sum += u64::from(*value);
+        }
+    }
+    let len = pixels.len().max(1) as u64;
+    sums.map(|sum| ((sum + len / 2) / len) as u8)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use image::{Rgba, RgbaImage};
+    use std::fs;
+    use surrealdb::engine::any::connect;
+
+    /// Left 70% red, right 30% blue.
+    fn two_tone_image() -> DynamicImage {
+        DynamicImage::ImageRgba8(RgbaImage::from_fn(100, 100, |x, _| {
+            if x < 70 {
+                Rgba([220, 20, 30, 255])
+            } else {
+                Rgba([10, 40, 200, 255])
+            }
+        }))
+    }
+
+    #[test]
+    fn test_dominant_color() {
+        assert_eq!(
+            ColorService::dominant_color(&two_tone_image()),
+            Some("#dc141e".to_string())
+        );
+
+        // Les pixels transparents sont ignorés
+        let transparent_background = DynamicImage::ImageRgba8(RgbaImage::from_fn(50, 50, |x, y| {
+            if x > 20 && x < 30 && y > 20 && y < 30 {
+                Rgba([0, 128, 0, 255])
+            } else {
+                Rgba([255, 255, 255, 0])
+            }
+        }));
+        assert_eq!(
+            ColorService::dominant_color(&transparent_background),
+            Some("#008000".to_string())
+        );
+
+        let empty = DynamicImage::ImageRgba8(RgbaImage::new(10, 10));
+        assert_eq!(ColorService::dominant_color(&empty), None);
+    }
+
+    #[tokio::test]
+    async fn test_refresh_and_backfill() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+
+        let media_root = std::env::temp_dir().join(format!("color-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(media_root.join("covers")).unwrap();
+        two_tone_image().save(media_root.join("covers/a.png")).unwrap();
+        let config = MediaConfig {
+            media_root: media_root.clone(),
+            image_cache_dir: media_root.join("cache"),
+        };
+
+        db.query(
+            r#"
+            CREATE album:a SET title = 'A', cover_url = '/covers/a.png';
+            CREATE album:b SET title = 'B', cover_url = 'https://cdn.example.com/b.jpg';
+            CREATE album:c SET title = 'C', cover_url = '/covers/a.png', dominant_color = '#000000';
+            CREATE playlist:p SET name = 'P', cover_url = 'covers/a.png';
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let report = ColorService::backfill(&db, &config, false).await.unwrap();
+        assert_eq!(report.albums, 1);
+        assert_eq!(report.playlists, 1);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].id, "album:b");
+
+        let colors: Vec<Option<String>> = db
+            .query("SELECT VALUE dominant_color FROM [album:a, album:c, playlist:p]")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(
+            colors,
+            vec![
+                Some("#dc141e".to_string()),
+                Some("#000000".to_string()),
+                Some("#dc141e".to_string())
+            ]
+        );
+
+        let report = ColorService::backfill(&db, &config, true).await.unwrap();
+        assert_eq!(report.albums, 2);
+
+        // Nouvelle couverture illisible : l'ancienne couleur est retirée
+        db.query("UPDATE album:a SET cover_url = '/covers/missing.png'")
+            .await
+            .unwrap();
+        let color = ColorService::refresh(&db, &config, &Thing::from(("album", "a")))
+            .await
+            .unwrap();
+        assert_eq!(color, None);
+        let stored: Option<String> = db
+            .query("SELECT VALUE dominant_color FROM ONLY album:a")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(stored, None);
+
+        fs::remove_dir_all(&media_root).unwrap();
+    }
+}
@@ -286,6 +286,7 @@ impl FavoriteService {
                         duration: out.duration OR 0s,
                         song_index: out.song_index,
                         tempo: out.tempo,
+                        loudness: out.loudness,
                         total_listens: out.total_listens,
                         total_user_listens: out.total_user_listens,
                         total_likes: out.total_likes,
@@ -0,0 +1,520 @@
+use std::{collections::HashMap, f32::consts::PI, path::Path};
+
+use rustfft::{num_complex::Complex, FftPlanner};
+use serde::Deserialize;
+use surrealdb::{
+    engine::any::Any,
+    sql::{Duration, Thing},
+    Surreal,
+};
+
+use crate::{
+    error::{Error, Result},
+    helpers::thing_helpers::thing_to_string,
+    models::{
+        analysis::{AnalysisKind, AnalysisReport},
+        duplicate::{DuplicateCluster, DuplicatePair, DuplicateSong},
+    },
+    services::{
+        audio_analysis_service::{AudioAnalysisService, SongFile},
+        media_service::MediaConfig,
+    },
+};
+
+// Même fréquence d'analyse que le tempo : l'empreinte se lit entre 300 Hz et 2 kHz
+const ANALYSIS_RATE: u32 = 11_025;
+const FRAME_SIZE: usize = 2048;
+const HOP_SIZE: usize = 512;
+const MIN_FREQ: f32 = 300.0;
+const MAX_FREQ: f32 = 2000.0;
+// 33 bandes donnent les 32 bits de chaque trame
+const BANDS: usize = 33;
+
+const MIN_ANALYSIS_SECS: f64 = 5.0;
+const MAX_ANALYSIS_SECS: f64 = 90.0;
+
+// Décalage toléré entre deux encodages (silence initial, délai d'encodeur) : ~3 s
+const MAX_OFFSET_FRAMES: usize = 64;
+const MIN_OVERLAP_FRAMES: usize = 100;
+// Seules les paires de durées proches sont comparées
+const DURATION_TOLERANCE_SECS: u64 = 10;
+
+pub const DEFAULT_MIN_SIMILARITY: f32 = 0.8;
+
+const BATCH_SIZE: u32 = 50;
+
+#[derive(Debug, Deserialize)]
+struct StoredFingerprint {
+    song: Thing,
+    duration: Duration,
+    fingerprint: Vec<u32>,
+}
+
+#[derive(Debug, Deserialize)]
+struct SongSummary {
+    id: Thing,
+    title: String,
+    file_url: String,
+    duration: Duration,
+    #[serde(default)]
+    total_listens: u32,
+    artists: Vec<String>,
+    albums: Vec<String>,
+}
+
+pub struct FingerprintService;
+
+impl FingerprintService {
+    /// Acoustic fingerprint of a mono signal, one 32-bit word per frame: each
+    /// bit tells whether the energy difference between two adjacent bands grew
+    /// since the previous frame. Robust to gain changes and lossy encoding.
+    /// `None` for silence or signals shorter than a few seconds.
+    pub fn compute(samples: &[f32], sample_rate: u32) -> Option<Vec<u32>> {
+        let factor = (sample_rate / ANALYSIS_RATE).max(1) as usize;
+        let signal: Vec<f32> = samples
+            .chunks(factor)
+            .map(|chunk| chunk.iter().sum::<f32>() / chunk.len() as f32)
+            .collect();
+        let rate = sample_rate as f32 / factor as f32;
+
+        if (signal.len() as f64) < MIN_ANALYSIS_SECS * f64::from(rate) {
+            return None;
+        }
+        let rms = (signal.iter().map(|s| s * s).sum::<f32>() / signal.len() as f32).sqrt();
+        if rms < 1e-4 {
+            return None;
+        }
+
+        let edges: Vec<usize> = (0..=BANDS)
+            .map(|k| {
+                let frequency = MIN_FREQ * (MAX_FREQ / MIN_FREQ).powf(k as f32 / BANDS as f32);
+                ((frequency * FRAME_SIZE as f32 / rate).round() as usize).clamp(1, FRAME_SIZE / 2)
+            })
+            .collect();
+
+        let fft = FftPlanner::<f32>::new().plan_fft_forward(FRAME_SIZE);
+        let window: Vec<f32> = (0..FRAME_SIZE)
+            .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FRAME_SIZE as f32).cos())
+            .collect();
+        let mut buffer = vec![Complex::new(0.0, 0.0); FRAME_SIZE];
+
+        let mut previous: Option<[f32; BANDS]> = None;
+        let mut fingerprint = Vec::with_capacity((signal.len() - FRAME_SIZE) / HOP_SIZE + 1);
+
+        for start in (0..=signal.len() - FRAME_SIZE).step_by(HOP_SIZE) {
+            for (i, value) in buffer.iter_mut().enumerate() {
+                *value = Complex::new(signal[start + i] * window[i], 0.0);
+            }
+            fft.process(&mut buffer);
+
+            let mut energies = [0.0f32; BANDS];
+            for (band, energy) in energies.iter_mut().enumerate() {
+                let from = edges[band];
+                let to = edges[band + 1].max(from + 1);
+                *energy = buffer[from..to].iter().map(|bin| bin.norm_sqr()).sum();
+            }
+
+            if let Some(previous) = previous {
+                let word = (0..BANDS - 1).fold(0u32, |word, band| {
+                    let now = energies[band] - energies[band + 1];
+                    let before = previous[band] - previous[band + 1];
+                    (word << 1) | u32::from(now - before > 0.0)
+                });
+                fingerprint.push(word);
+            }
+            previous = Some(energies);
+        }
+
+        Some(fingerprint)
+    }
+
+    /// Share of matching bits at the best alignment of the two fingerprints,
+    /// from about 0.5 for unrelated songs to 1.0 for the same encoding.
+    pub fn similarity(a: &[u32], b: &[u32]) -> f32 {
+        let max_offset = MAX_OFFSET_FRAMES as isize;
+
+        (-max_offset..=max_offset)
+            .filter_map(|offset| {
+                let a_start = (-offset).max(0) as usize;
+                let b_start = offset.max(0) as usize;
+                let overlap = a
+                    .len()
+                    .saturating_sub(a_start)
+                    .min(b.len().saturating_sub(b_start));
+                if overlap < MIN_OVERLAP_FRAMES {
+                    return None;
+                }
+
+                let errors: u32 = a[a_start..a_start + overlap]
+                    .iter()
+                    .zip(&b[b_start..b_start + overlap])
+                    .map(|(x, y)| (x ^ y).count_ones())
+                    .sum();
+                Some(1.0 - errors as f32 / (32 * overlap) as f32)
+            })
+            .fold(0.0, f32::max)
+    }
+
+    fn analyze_file(path: &Path) -> std::result::Result<Vec<u32>, String> {
+        let audio = AudioAnalysisService::decode_mono(path, Some(MAX_ANALYSIS_SECS))?;
+        if audio.duration_secs() < MIN_ANALYSIS_SECS {
+            return Err("Audio too short to fingerprint".to_string());
+        }
+        Self::compute(&audio.samples, audio.sample_rate)
+            .ok_or_else(|| "No audible content".to_string())
+    }
+
+    async fn analyze(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        song: &SongFile,
+    ) -> Result<std::result::Result<Vec<u32>, String>> {
+        let outcome = AudioAnalysisService::run(
+            db,
+            config,
+            song,
+            AnalysisKind::Fingerprint,
+            Self::analyze_file,
+        )
+        .await?;
+
+        if let Ok(fingerprint) = &outcome {
+            let query = r#"
+                UPSERT type::thing('song_fingerprint', record::id($song)) SET
+                    song = $song,
+                    fingerprint = $fingerprint,
+                    computed_at = time::now();
+            "#;
+
+            db.query(query)
+                .bind(("song", song.id.clone()))
+                .bind(("fingerprint", fingerprint.clone()))
+                .await?
+                .check()?;
+        }
+
+        Ok(outcome)
+    }
+
+    /// On-demand fingerprint of one song, replacing the stored one.
+    pub async fn analyze_song(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        song_id: &str,
+    ) -> Result<AnalysisReport> {
+        let song = AudioAnalysisService::get_song_file(db, song_id).await?;
+
+        match Self::analyze(db, config, &song).await? {
+            Ok(_) => Ok(AnalysisReport {
+                analyzed: 1,
+                failed: 0,
+            }),
+            Err(reason) => Err(Error::AnalysisFailed {
+                song_id: thing_to_string(&song.id),
+                reason,
+            }),
+        }
+    }
+
+    /// Fingerprints every song without one. Songs that already failed are
+    /// skipped until they are analyzed on demand.
+    pub async fn analyze_pending(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+    ) -> Result<AnalysisReport> {
+        let mut report = AnalysisReport::default();
+
+        loop {
+            let songs =
+                AudioAnalysisService::pending_songs(db, AnalysisKind::Fingerprint, BATCH_SIZE)
+                    .await?;
+
+            for song in &songs {
+                match Self::analyze(db, config, song).await? {
+                    Ok(_) => report.analyzed += 1,
+                    Err(_) => report.failed += 1,
+                }
+            }
+
+            if (songs.len() as u32) < BATCH_SIZE {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Groups fingerprinted songs whose similarity reaches `min_similarity`.
+    /// Clusters are ordered by their best pair.
+    pub async fn find_duplicates(
+        db: &Surreal<Any>,
+        min_similarity: f32,
+    ) -> Result<Vec<DuplicateCluster>> {
+        if !(0.0..=1.0).contains(&min_similarity) {
+            return Err(Error::InvalidInput {
+                reason: "min_similarity must be between 0 and 1".to_string(),
+            });
+        }
+
+        let stored: Vec<StoredFingerprint> = db
+            .query(
+                "SELECT song, song.duration AS duration, fingerprint FROM song_fingerprint
+                 WHERE song.duration != NONE",
+            )
+            .await?
+            .take(0)?;
+
+        // Comparaison de toutes les paires proches en durée : hors du runtime async
+        let pairs = tokio::task::spawn_blocking(move || similar_pairs(stored, min_similarity))
+            .await
+            .map_err(|e| Error::DbError(format!("Duplicate search panicked: {}", e)))?;
+
+        let clusters = cluster_pairs(&pairs);
+        if clusters.is_empty() {
+            return Ok(Vec::new());
+        }
+
+        let songs: Vec<Thing> = clusters.iter().flatten().cloned().collect();
+        let summaries: Vec<SongSummary> = db
+            .query(
+                r#"
+                SELECT id, title, file_url, duration, total_listens,
+                    (<-artist_performs_song<-artist.name) AS artists,
+                    (<-album_contains_song<-album.title) AS albums
+                FROM $songs;
+                "#,
+            )
+            .bind(("songs", songs))
+            .await?
+            .take(0)?;
+        let mut summaries: HashMap<String, SongSummary> = summaries
+            .into_iter()
+            .map(|summary| (thing_to_string(&summary.id), summary))
+            .collect();
+
+        let mut report: Vec<(f32, DuplicateCluster)> = clusters
+            .into_iter()
+            .map(|members| {
+                let ids: Vec<String> = members.iter().map(thing_to_string).collect();
+
+                let mut songs: Vec<DuplicateSong> = ids
+                    .iter()
+                    .filter_map(|id| summaries.remove(id))
+                    .map(|summary| DuplicateSong {
+                        id: thing_to_string(&summary.id),
+                        title: summary.title,
+                        file_url: summary.file_url,
+                        duration_secs: summary.duration.as_secs(),
+                        total_listens: summary.total_listens,
+                        artists: summary.artists,
+                        albums: summary.albums,
+                    })
+                    .collect();
+                songs.sort_by_key(|song| std::cmp::Reverse(song.total_listens));
+
+                let mut cluster_pairs: Vec<DuplicatePair> = pairs
+                    .iter()
+                    .filter(|pair| ids.contains(&pair.a))
+                    .cloned()
+                    .collect();
+                cluster_pairs.sort_by(|a, b| b.similarity.total_cmp(&a.similarity));
+
+                let best = cluster_pairs.first().map_or(0.0, |pair| pair.similarity);
+                (
+                    best,
+                    DuplicateCluster {
+                        songs,
+                        pairs: cluster_pairs,
+                    },
+                )
+            })
+            .collect();
+        report.sort_by(|a, b| b.0.total_cmp(&a.0));
+
+        Ok(report.into_iter().map(|(_, cluster)| cluster).collect())
+    }
+}
+
+/// Compares every pair of songs whose durations are within the tolerance.
+fn similar_pairs(mut stored: Vec<StoredFingerprint>, min_similarity: f32) -> Vec<DuplicatePair> {
+    stored.sort_by_key(|entry| entry.duration.as_secs());
+
+    let mut pairs = Vec::new();
+    for (i, a) in stored.iter().enumerate() {
+        for b in &stored[i + 1..] {
+            if b.duration.as_secs() - a.duration.as_secs() > DURATION_TOLERANCE_SECS {
+                break;
+            }
+
+            let similarity = FingerprintService::similarity(&a.fingerprint, &b.fingerprint);
+            if similarity >= min_similarity {
+                pairs.push(DuplicatePair {
+                    a: thing_to_string(&a.song),
+                    b: thing_to_string(&b.song),
+                    similarity: (similarity * 1000.0).round() / 1000.0,
+                });
+            }
+        }
+    }
+    pairs
+}
+
+/// Connected components of the pair graph (union-find).
+fn cluster_pairs(pairs: &[DuplicatePair]) -> Vec<Vec<Thing>> {
+    let mut index: HashMap<&str, usize> = HashMap::new();
+    let mut ids: Vec<&str> = Vec::new();
+    for pair in pairs {
+        for id in [pair.a.as_str(), pair.b.as_str()] {
+            index.entry(id).or_insert_with(|| {
+                ids.push(id);
+                ids.len() - 1
+            });
+        }
+    }
+
+    let mut parent: Vec<usize> = (0..ids.len()).collect();
+    fn find(parent: &mut [usize], mut node: usize) -> usize {
+        while parent[node] != node {
+            parent[node] = parent[parent[node]];
+            node = parent[node];
+        }
+        node
+    }
+
+    for pair in pairs {
+        let a = find(&mut parent, index[pair.a.as_str()]);
+        let b = find(&mut parent, index[pair.b.as_str()]);
+        if a != b {
+            parent[b] = a;
+        }
+    }
+
+    let mut clusters: HashMap<usize, Vec<Thing>> = HashMap::new();
+    for (node, id) in ids.iter().enumerate() {
+        let root = find(&mut parent, node);
+        if let Some((table, key)) = id.split_once(':') {
+            clusters
+                .entry(root)
+                .or_default()
+                .push(Thing::from((table, key)));
+        }
+    }
+    clusters.into_values().collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::engine::any::connect;
+
+    const SAMPLE_RATE: u32 = 22_050;
+
+    /// A melody of random notes (with harmonics), reproducible from `seed`.
+    fn melody(seed: u64, secs: f32) -> Vec<f32> {
+        let mut state = seed;
+        let mut next = move || {
+            state = state
+                .wrapping_mul(6364136223846793005)
+                .wrapping_add(1442695040888963407);
+            (state >> 33) as u32
+        };
+
+        let note_len = (0.25 * SAMPLE_RATE as f32) as usize;
+        let len = (secs * SAMPLE_RATE as f32) as usize;
+        let mut samples = Vec::with_capacity(len);
+        while samples.len() < len {
+            let frequency = 220.0 * 2f32.powf((next() % 24) as f32 / 12.0);
+            for i in 0..note_len {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                let envelope = (-t * 4.0).exp();
+                let value = (1..=4)
+                    .map(|h| (2.0 * PI * frequency * h as f32 * t).sin() / h as f32)
+                    .sum::<f32>();
+                samples.push(0.3 * envelope * value);
+            }
+        }
+        samples.truncate(len);
+        samples
+    }
+
+    #[test]
+    fn test_similarity_of_altered_copies() {
+        let original = melody(1, 30.0);
+        let fingerprint = FingerprintService::compute(&original, SAMPLE_RATE).unwrap();
+        assert!((FingerprintService::similarity(&fingerprint, &fingerprint) - 1.0).abs() < 1e-6);
+
+        // Gain réduit, bruit léger et 0.7 s de silence en tête
+        let mut noise_state = 7u32;
+        let mut altered = vec![0.0f32; (0.7 * SAMPLE_RATE as f32) as usize];
+        altered.extend(original.iter().map(|s| {
+            noise_state = noise_state.wrapping_mul(1_103_515_245).wrapping_add(12_345);
+            let noise = (noise_state >> 16) as f32 / 65_536.0 - 0.5;
+            0.6 * s + 0.005 * noise
+        }));
+        let altered = FingerprintService::compute(&altered, SAMPLE_RATE).unwrap();
+        let same = FingerprintService::similarity(&fingerprint, &altered);
+        assert!(same > 0.85, "altered copy scored {}", same);
+
+        let other = FingerprintService::compute(&melody(2, 30.0), SAMPLE_RATE).unwrap();
+        let different = FingerprintService::similarity(&fingerprint, &other);
+        assert!(different < 0.7, "unrelated song scored {}", different);
+
+        let silence = vec![0.0f32; SAMPLE_RATE as usize * 10];
+        assert_eq!(FingerprintService::compute(&silence, SAMPLE_RATE), None);
+        assert_eq!(
+            FingerprintService::compute(&melody(3, 2.0), SAMPLE_RATE),
+            None
+        );
+    }
+
+    #[tokio::test]
+    async fn test_find_duplicates_clusters_similar_songs() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+
+        let a = FingerprintService::compute(&melody(1, 30.0), SAMPLE_RATE).unwrap();
+        let b = FingerprintService::compute(&melody(2, 30.0), SAMPLE_RATE).unwrap();
+
+        db.query(
+            r#"
+            CREATE artist:x SET name = 'X';
+            CREATE album:original SET title = 'Original';
+            CREATE album:best_of SET title = 'Best Of';
+            CREATE song:a1 SET title = 'Song A', file_url = 'a1.mp3', duration = 3m, total_listens = 5;
+            CREATE song:a2 SET title = 'Song A (Remaster)', file_url = 'a2.mp3', duration = 3m2s, total_listens = 50;
+            CREATE song:a3 SET title = 'Song A', file_url = 'a3.mp3', duration = 7m, total_listens = 1;
+            CREATE song:b SET title = 'Song B', file_url = 'b.mp3', duration = 3m1s, total_listens = 3;
+            RELATE artist:x->artist_performs_song->song:a1;
+            RELATE album:original->album_contains_song->song:a1;
+            RELATE album:best_of->album_contains_song->song:a2;
+            UPSERT song_fingerprint:a1 SET song = song:a1, fingerprint = $a;
+            UPSERT song_fingerprint:a2 SET song = song:a2, fingerprint = $a;
+            UPSERT song_fingerprint:a3 SET song = song:a3, fingerprint = $a;
+            UPSERT song_fingerprint:b SET song = song:b, fingerprint = $b;
+            "#,
+        )
+        .bind(("a", a))
+        .bind(("b", b))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let clusters = FingerprintService::find_duplicates(&db, DEFAULT_MIN_SIMILARITY)
+            .await
+            .unwrap();
+
+        // a3 a la même empreinte mais une durée trop différente
+        assert_eq!(clusters.len(), 1);
+        let cluster = &clusters[0];
+        let ids: Vec<&str> = cluster.songs.iter().map(|song| song.id.as_str()).collect();
+        assert_eq!(ids, vec!["song:a2", "song:a1"]);
+        assert_eq!(cluster.songs[1].artists, vec!["X".to_string()]);
+        assert_eq!(cluster.songs[1].albums, vec!["Original".to_string()]);
+        assert_eq!(cluster.pairs.len(), 1);
+        assert_eq!(cluster.pairs[0].similarity, 1.0);
+
+        let result = FingerprintService::find_duplicates(&db, 1.5).await;
+        assert!(matches!(result, Err(Error::InvalidInput { .. })));
+    }
+}
@@ -0,0 +1,492 @@
+use std::{
+    io::Cursor,
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use axum::{
+    http::{header, HeaderMap, HeaderValue},
+    response::Response,
+};
+use image::{
+    codecs::{jpeg::JpegEncoder, webp::WebPEncoder},
+    imageops::FilterType,
+    DynamicImage,
+};
+use sha2::{Digest, Sha256};
+
+use crate::{
+    models::{
+        album::{Album, AlbumWithArtists, AlbumWithRelations, AlbumsMetaResponse},
+        artist::{Artist, ArtistWithAlbums, ArtistWithAlbumsAndTopSongs},
+        chart::{Chart, ChartEntry},
+        cover::{ImageVariant, ImageVariants},
+        favorite::{
+            AlbumWithFavoriteMetadata, ArtistWithFavoriteMetadata, FavoritesResponse,
+            SongWithFavoriteMetadata,
+        },
+        pagination::PaginatedResponse,
+        playlist::{Playlist, PlaylistWithOwner, PlaylistWithSongs},
+        recommendation::{ScoredAlbum, ScoredSong},
+        song::SongWithRelations,
+        wrapped::WrappedReport,
+    },
+    services::{media_service::{MediaConfig, MediaService}, search_service::{SearchHit, SearchResult}},
+    Error, Result,
+};
+
+/// Longest side of the small, medium and large variants.
+pub const IMAGE_SIZES: [u32; 3] = [64, 256, 640];
+
+// L'URL ne change pas avec le fichier : l'ETag permet la revalidation au-delà d'une semaine
+const CACHE_CONTROL: &str = "public, max-age=604800, stale-while-revalidate=86400";
+const JPEG_QUALITY: u8 = 85;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImageFormat {
+    Webp,
+    Jpeg,
+}
+
+impl ImageFormat {
+    pub fn from_name(name: &str) -> Option<Self> {
+        match name.to_ascii_lowercase().as_str() {
+            "webp" => Some(Self::Webp),
+            "jpeg" | "jpg" => Some(Self::Jpeg),
+            _ => None,
+        }
+    }
+
+    pub fn name(&self) -> &'static str {
+        match self {
+            Self::Webp => "webp",
+            Self::Jpeg => "jpeg",
+        }
+    }
+
+    pub fn content_type(&self) -> &'static str {
+        match self {
+            Self::Webp => "image/webp",
+            Self::Jpeg => "image/jpeg",
+        }
+    }
+}
+
+pub struct ImageService;
+
+impl ImageService {
+    pub fn variant_url(image_url: &str, size: u32, format: ImageFormat) -> String {
+        format!(
+            "/api/images/{}/{}/{}",
+            size,
+            format.name(),
+            encode_path(image_url.trim_start_matches('/'))
+        )
+    }
+
+    /// Variant URLs of a local image, `None` for remote URLs.
+    pub fn variants(image_url: Option<&str>) -> Option<ImageVariants> {
+        let image_url = image_url.filter(|url| !url.is_empty() && !url.contains("://"))?;
+        let variant = |size| ImageVariant {
+            size,
+            webp: Self::variant_url(image_url, size, ImageFormat::Webp),
+            jpeg: Self::variant_url(image_url, size, ImageFormat::Jpeg),
+        };
+
+        Some(ImageVariants {
+            small: variant(IMAGE_SIZES[0]),
+            medium: variant(IMAGE_SIZES[1]),
+            large: variant(IMAGE_SIZES[2]),
+        })
+    }
+
+    /// Serves `image_path` resized to `size`, rendering it into the disk cache
+    /// on first request. Cache entries are keyed by the source mtime and size,
+    /// so replacing an image invalidates its variants.
+    pub async fn serve_variant(
+        config: &MediaConfig,
+        size: &str,
+        format: &str,
+        image_path: &str,
+        headers: &HeaderMap,
+    ) -> Result<Response> {
+        let size: u32 = size
+            .parse()
+            .ok()
+            .filter(|size| IMAGE_SIZES.contains(size))
+            .ok_or_else(|| Error::InvalidInput {
+                reason: format!("Image size must be one of {:?}", IMAGE_SIZES),
+            })?;
+        let format = ImageFormat::from_name(format).ok_or_else(|| Error::InvalidInput {
+            reason: "Image format must be webp or jpeg".to_string(),
+        })?;
+
+        let not_found = || Error::ImageNotFound {
+            path: image_path.to_string(),
+        };
+        let source = config
+            .resolve(image_path)
+            .filter(|path| path.is_file())
+            .ok_or_else(not_found)?;
+
+        let cached = Self::cache_path(config, &source, size, format).await?;
+        if !tokio::fs::try_exists(&cached).await? {
+            let target = cached.clone();
+            tokio::task::spawn_blocking(move || render_variant(&source, &target, size, format))
+                .await
+                .map_err(|e| Error::DbError(format!("Image task failed: {}", e)))?
+                .map_err(|reason| {
+                    tracing::warn!("Could not resize {}: {}", image_path, reason);
+                    not_found()
+                })?;
+        }
+
+        let mut response = MediaService::serve_file(&cached, headers).await?;
+        let response_headers = response.headers_mut();
+        response_headers.insert(header::CONTENT_TYPE, HeaderValue::from_static(format.content_type()));
+        response_headers.insert(header::CACHE_CONTROL, HeaderValue::from_static(CACHE_CONTROL));
+        Ok(response)
+    }
+
+    async fn cache_path(
+        config: &MediaConfig,
+        source: &Path,
+        size: u32,
+        format: ImageFormat,
+    ) -> Result<PathBuf> {
+        let metadata = tokio::fs::metadata(source).await?;
+        let modified = metadata
+            .modified()
+            .ok()
+            .and_then(|time| time.duration_since(UNIX_EPOCH).ok())
+            .map(|duration| duration.as_nanos())
+            .unwrap_or_default();
+
+        let mut hasher = Sha256::new();
+        hasher.update(
+            format!("{}\n{}\n{}\n{}\n{}", source.display(), metadata.len(), modified, size, format.name())
+                .as_bytes(),
+        );
+        let key = hex::encode(hasher.finalize());
+
+        Ok(config
+            .image_cache_dir
+            .join(&key[..2])
+            .join(format!("{}.{}", key, format.name())))
+    }
+}
+
+fn render_variant(
+    source: &Path,
+    target: &Path,
+    size: u32,
+    format: ImageFormat,
+) -> std::result::Result<(), String> {
+    let image = image::open(source).map_err(|e| format!("Unreadable image: {}", e))?;
+
+    // Jamais d'agrandissement
+    let image = if image.width() > size || image.height() > size {
+        image.resize(size, size, FilterType::Lanczos3)
+    } else {
+        image
+    };
+
+    let mut encoded = Cursor::new(Vec::new());
+    match format {
+        ImageFormat::Jpeg => DynamicImage::ImageRgb8(image.to_rgb8())
+            .write_with_encoder(JpegEncoder::new_with_quality(&mut encoded, JPEG_QUALITY)),
+        ImageFormat::Webp => DynamicImage::ImageRgba8(image.to_rgba8())
+            .write_with_encoder(WebPEncoder::new_lossless(&mut encoded)),
+    }
+    .map_err(|e| format!("Encoding failed: {}", e))?;
+
+    let parent = target.parent().ok_or("Invalid cache path")?;
+    std::fs::create_dir_all(parent).map_err(|e| e.to_string())?;
+
+    // Écriture dans un fichier temporaire puis renommage : une requête
+    // concurrente ne lit jamais un fichier à moitié écrit
+    let partial = target.with_extension(format!("{}.part", uuid::Uuid::new_v4()));
+    std::fs::write(&partial, encoded.into_inner()).map_err(|e| e.to_string())?;
+    std::fs::rename(&partial, target).map_err(|e| e.to_string())
+}
+
+/// Percent-encodes a relative path, keeping `/` separators.
+fn encode_path(path: &str) -> String {
+    let mut encoded = String::with_capacity(path.len());
+    for byte in path.bytes() {
+        match byte {
+            b'A'..=b'Z' | b'a'..=b'z' | b'0'..=b'9' | b'-' | b'_' | b'.' | b'~' | b'/' => {
+                encoded.push(byte as char)
+            }
+            _ => encoded.push_str(&format!("%{:02X}", byte)),
+        }
+    }
+    encoded
+}
+
+/// Fills the `cover_variants` / `image_variants` fields before a response
+/// leaves the API.
+pub trait AttachImageVariants {
+    fn attach_image_variants(&mut self);
+}
+
+impl AttachImageVariants for Album {
+    fn attach_image_variants(&mut self) {
+        self.cover_variants = ImageService::variants(self.cover_url.as_deref());
+    }
+}
+
+impl AttachImageVariants for AlbumWithArtists {
+    fn attach_image_variants(&mut self) {
+        self.cover_variants = ImageService::variants(self.cover_url.as_deref());
+        self.artists.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for AlbumWithRelations {
+    fn attach_image_variants(&mut self) {
+        self.cover_variants = ImageService::variants(self.cover_url.as_deref());
+        self.artists.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for AlbumsMetaResponse {
+    fn attach_image_variants(&mut self) {
+        self.albums.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for Artist {
+    fn attach_image_variants(&mut self) {
+        self.image_variants = ImageService::variants(self.artist_image.as_deref());
+    }
+}
+
+impl AttachImageVariants for ArtistWithAlbums {
+    fn attach_image_variants(&mut self) {
+        self.image_variants = ImageService::variants(self.artist_image.as_deref());
+        self.albums.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for ArtistWithAlbumsAndTopSongs {
+    fn attach_image_variants(&mut self) {
+        self.image_variants = ImageService::variants(self.artist_image.as_deref());
+        self.albums.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for Playlist {
+    fn attach_image_variants(&mut self) {
+        self.cover_variants = ImageService::variants(self.cover_url.as_deref());
+    }
+}
+
+impl AttachImageVariants for PlaylistWithOwner {
+    fn attach_image_variants(&mut self) {
+        self.cover_variants = ImageService::variants(self.cover_url.as_deref());
+    }
+}
+
+impl AttachImageVariants for PlaylistWithSongs {
+    fn attach_image_variants(&mut self) {
+        self.cover_variants = ImageService::variants(self.cover_url.as_deref());
+        self.songs.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for SongWithRelations {
+    fn attach_image_variants(&mut self) {
+        self.album.attach_image_variants();
+        self.artists.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for AlbumWithFavoriteMetadata {
+    fn attach_image_variants(&mut self) {
+        self.album.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for ArtistWithFavoriteMetadata {
+    fn attach_image_variants(&mut self) {
+        self.artist.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for SongWithFavoriteMetadata {
+    fn attach_image_variants(&mut self) {
+        self.song.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for SearchResult {
+    fn attach_image_variants(&mut self) {
+        self.albums.attach_image_variants();
+        self.artists.attach_image_variants();
+        self.songs.attach_image_variants();
+        self.playlists.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for ScoredSong {
+    fn attach_image_variants(&mut self) {
+        self.song.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for ScoredAlbum {
+    fn attach_image_variants(&mut self) {
+        self.album.attach_image_variants();
+    }
+}
+
+impl<T: AttachImageVariants> AttachImageVariants for SearchHit<T> {
+    fn attach_image_variants(&mut self) {
+        self.item.attach_image_variants();
+    }
+}
+
+impl<T: AttachImageVariants> AttachImageVariants for Vec<T> {
+    fn attach_image_variants(&mut self) {
+        self.iter_mut().for_each(T::attach_image_variants);
+    }
+}
+
+impl<T: AttachImageVariants> AttachImageVariants for Option<T> {
+    fn attach_image_variants(&mut self) {
+        if let Some(item) = self {
+            item.attach_image_variants();
+        }
+    }
+}
+
+impl<T: AttachImageVariants> AttachImageVariants for PaginatedResponse<T> {
+    fn attach_image_variants(&mut self) {
+        self.data.attach_image_variants();
+    }
+}
+
+impl<T: AttachImageVariants> AttachImageVariants for FavoritesResponse<T> {
+    fn attach_image_variants(&mut self) {
+        self.data.attach_image_variants();
+    }
+}
+
+impl<T: AttachImageVariants> AttachImageVariants for ChartEntry<T> {
+    fn attach_image_variants(&mut self) {
+        self.item.attach_image_variants();
+    }
+}
+
+impl<T: AttachImageVariants> AttachImageVariants for Chart<T> {
+    fn attach_image_variants(&mut self) {
+        self.entries.attach_image_variants();
+    }
+}
+
+impl AttachImageVariants for WrappedReport {
+    fn attach_image_variants(&mut self) {
+        self.top_songs
+            .iter_mut()
+            .for_each(|entry| entry.song.attach_image_variants());
+        self.top_artists
+            .iter_mut()
+            .for_each(|entry| entry.artist.attach_image_variants());
+        self.top_albums
+            .iter_mut()
+            .for_each(|entry| entry.album.attach_image_variants());
+        if let Some(first_song) = &mut self.first_song {
+            first_song.song.attach_image_variants();
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use axum::{body::to_bytes, http::StatusCode};
+    use image::{Rgb, RgbImage};
+    use std::fs;
+
+    fn setup_media_root() -> MediaConfig {
+        let media_root = std::env::temp_dir().join(format!("image-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(media_root.join("covers")).unwrap();
+        DynamicImage::ImageRgb8(RgbImage::from_pixel(1000, 500, Rgb([200, 30, 30])))
+            .save(media_root.join("covers/Big Cover.png"))
+            .unwrap();
+        MediaConfig {
+            image_cache_dir: media_root.join("cache"),
+            media_root,
+        }
+    }
+
+    #[test]
+    fn test_variant_urls() {
+        let variants = ImageService::variants(Some("/covers/Big Cover.png")).unwrap();
+        assert_eq!(variants.small.size, 64);
+        assert_eq!(variants.small.webp, "/api/images/64/webp/covers/Big%20Cover.png");
+        assert_eq!(variants.large.jpeg, "/api/images/640/jpeg/covers/Big%20Cover.png");
+
+        assert_eq!(ImageService::variants(Some("https://cdn.example.com/a.jpg")), None);
+        assert_eq!(ImageService::variants(None), None);
+    }
+
+    #[tokio::test]
+    async fn test_serve_variant_resizes_and_caches() {
+        let config = setup_media_root();
+
+        let response = ImageService::serve_variant(&config, "256", "jpeg", "covers/Big Cover.png", &HeaderMap::new())
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/jpeg");
+        assert!(response.headers()[header::CACHE_CONTROL]
+            .to_str()
+            .unwrap()
+            .contains("max-age"));
+        let etag = response.headers()[header::ETAG].clone();
+
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resized = image::load_from_memory(&body).unwrap();
+        assert_eq!((resized.width(), resized.height()), (256, 128));
+
+        // Servi depuis le cache, revalidation par ETag
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag);
+        let response = ImageService::serve_variant(&config, "256", "jpeg", "covers/Big Cover.png", &headers)
+            .await
+            .unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let response = ImageService::serve_variant(&config, "64", "webp", "covers/Big Cover.png", &HeaderMap::new())
+            .await
+            .unwrap();
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "image/webp");
+        let body = to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        let resized = image::load_from_memory(&body).unwrap();
+        assert_eq!((resized.width(), resized.height()), (64, 32));
+
+        let cached = walkdir::WalkDir::new(&config.image_cache_dir)
+            .into_iter()
+            .filter_map(|entry| entry.ok())
+            .filter(|entry| entry.file_type().is_file())
+            .count();
+        assert_eq!(cached, 2);
+
+        assert!(matches!(
+            ImageService::serve_variant(&config, "100", "jpeg", "covers/Big Cover.png", &HeaderMap::new()).await,
+            Err(Error::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            ImageService::serve_variant(&config, "64", "gif", "covers/Big Cover.png", &HeaderMap::new()).await,
+            Err(Error::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            ImageService::serve_variant(&config, "64", "jpeg", "../etc/passwd", &HeaderMap::new()).await,
+            Err(Error::ImageNotFound { .. })
+        ));
+
+        fs::remove_dir_all(&config.media_root).unwrap();
+    }
+}
@@ -0,0 +1,896 @@
+use std::collections::{HashMap, HashSet};
+use std::str::FromStr;
+
+use chrono::Datelike;
+use serde::{Deserialize, Serialize};
+use surrealdb::{
+    engine::any::Any,
+    sql::{Duration, Id, Thing},
+    Surreal,
+};
+
+use crate::{
+    error::{Error, Result},
+    models::{
+        import::{
+            ImportAlbum, ImportArtist, ImportCounts, ImportManifest, ImportReport, ImportRowError,
+            ImportSong,
+        },
+        music_genre::MusicGenre,
+    },
+    services::{album_service::AlbumService, artist_service::ArtistService},
+};
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ImportFormat {
+    Json,
+    Csv,
+}
+
+impl ImportFormat {
+    /// Picks the format from a `Content-Type` header, JSON by default.
+    pub fn from_content_type(content_type: Option<&str>) -> Self {
+        match content_type {
+            Some(ct) if ct.starts_with("text/csv") => Self::Csv,
+            _ => Self::Json,
+        }
+    }
+
+    /// Picks the format from a file extension, JSON by default.
+    pub fn from_path(path: &str) -> Self {
+        if path.to_lowercase().ends_with(".csv") {
+            Self::Csv
+        } else {
+            Self::Json
+        }
+    }
+}
+
+/// Une ligne du CSV : un morceau, avec son album et son artiste.
+#[derive(Debug, Deserialize)]
+struct CsvRow {
+    artist: String,
+    #[serde(default)]
+    artist_country_code: String,
+    #[serde(default)]
+    artist_genres: String,
+    artist_image: Option<String>,
+    album: String,
+    album_release_year: Option<u16>,
+    #[serde(default)]
+    album_genres: String,
+    #[serde(default)]
+    album_langs: String,
+    album_cover_url: Option<String>,
+    album_dominant_color: Option<String>,
+    song_index: u16,
+    song_title: String,
+    file_url: String,
+    duration_secs: u64,
+    tempo: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+struct ArtistWrite {
+    id: Thing,
+    is_new: bool,
+    name: String,
+    genres: Vec<MusicGenre>,
+    country_code: String,
+    artist_image: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct AlbumWrite {
+    id: Thing,
+    is_new: bool,
+    title: String,
+    cover_url: Option<String>,
+    release_year: Option<u16>,
+    genres: Vec<MusicGenre>,
+    langs: Vec<String>,
+    dominant_color: Option<String>,
+}
+
+#[derive(Debug, Serialize)]
+struct SongWrite {
+    id: Thing,
+    is_new: bool,
+    title: String,
+    file_url: String,
+    duration: Duration,
+    song_index: u16,
+    tempo: Option<f32>,
+}
+
+#[derive(Debug, Serialize)]
+struct EdgeWrite {
+    from: Thing,
+    to: Thing,
+}
+
+#[derive(Debug, Default)]
+struct ImportPlan {
+    artists: Vec<ArtistWrite>,
+    albums: Vec<AlbumWrite>,
+    songs: Vec<SongWrite>,
+    album_artists: Vec<EdgeWrite>,
+    album_songs: Vec<EdgeWrite>,
+    song_artists: Vec<EdgeWrite>,
+}
+
+pub struct ImportService;
+
+impl ImportService {
+    /// Validates a manifest, matches it against the catalog and, unless `dry_run`
+    /// is set, writes every record and edge in a single transaction.
+    ///
+    /// Existing artists are matched by name, albums by title within the artist
+    /// and songs by title within the album (case-insensitive), so re-importing
+    /// a manifest updates records instead of duplicating them.
+    pub async fn import(
+        db: &Surreal<Any>,
+        input: &str,
+        format: ImportFormat,
+        dry_run: bool,
+    ) -> Result<ImportReport> {
+        let (manifest, mut errors) = match format {
+            ImportFormat::Json => (Self::parse_json(input)?, Vec::new()),
+            ImportFormat::Csv => Self::parse_csv(input)?,
+        };
+
+        let mut report = ImportReport {
+            dry_run,
+            ..Default::default()
+        };
+        let plan = Self::build_plan(db, &manifest, &mut report, &mut errors).await?;
+        report.errors = errors;
+
+        if !report.errors.is_empty() {
+            if dry_run {
+                return Ok(report);
+            }
+            let details: Vec<String> = report
+                .errors
+                .iter()
+                .map(|e| format!("{}: {}", e.row, e.reason))
+                .collect();
+            return Err(Error::InvalidInput {
+                reason: format!(
+                    "Import manifest has {} invalid row(s): {}",
+                    report.errors.len(),
+                    details.join("; ")
+                ),
+            });
+        }
+
+        if dry_run {
+            return Ok(report);
+        }
+
+        let touched_artists: Vec<Thing> = plan.artists.iter().map(|a| a.id.clone()).collect();
+        let touched_albums: Vec<Thing> = plan.albums.iter().map(|a| a.id.clone()).collect();
+
+        Self::apply_plan(db, plan).await?;
+        report.applied = true;
+
+        for album_thing in &touched_albums {
+            AlbumService::refresh_aggregates(db, album_thing).await?;
+        }
+        for artist_thing in &touched_artists {
+            ArtistService::refresh_counts(db, artist_thing).await?;
+        }
+
+        tracing::info!(
+            "Catalog import applied: artists {:?}, albums {:?}, songs {:?}",
+            report.artists,
+            report.albums,
+            report.songs
+        );
+
+        Ok(report)
+    }
+
+    pub fn parse_json(input: &str) -> Result<ImportManifest> {
+        serde_json::from_str(input).map_err(|e| Error::InvalidInput {
+            reason: format!("Invalid JSON manifest: {}", e),
+        })
+    }
+
+    /// Parses a flat CSV manifest (one song per row) and groups rows by artist
+    /// name and album title. Rows that cannot be read are reported, not fatal.
+    pub fn parse_csv(input: &str) -> Result<(ImportManifest, Vec<ImportRowError>)> {
+        let mut reader = csv::ReaderBuilder::new()
+            .trim(csv::Trim::All)
+            .from_reader(input.as_bytes());
+
+        let headers = reader
+            .headers()
+            .map_err(|e| Error::InvalidInput {
+                reason: format!("Invalid CSV header: {}", e),
+            })?
+            .clone();
+
+        let mut manifest = ImportManifest::default();
+        let mut errors = Vec::new();
+        let mut artist_positions: HashMap<String, usize> = HashMap::new();
+        let mut album_positions: HashMap<(usize, String), usize> = HashMap::new();
+
+        for record in reader.records() {
+            let record = match record {
+                Ok(record) => record,
+                Err(e) => {
+                    let line = e.position().map(|p| p.line()).unwrap_or_default();
+                    errors.push(ImportRowError {
+                        row: format!("line {}", line),
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+            let line = record.position().map(|p| p.line() as usize);
+            let row_label = format!("line {}", line.unwrap_or_default());
+
+            let row: CsvRow = match record.deserialize(Some(&headers)) {
+                Ok(row) => row,
+                Err(e) => {
+                    errors.push(ImportRowError {
+                        row: row_label,
+                        reason: e.to_string(),
+                    });
+                    continue;
+                }
+            };
+
+            let artist_pos = *artist_positions
+                .entry(row.artist.to_lowercase())
+                .or_insert_with(|| {
+                    manifest.artists.push(ImportArtist {
+                        name: row.artist.clone(),
+                        genres: split_list(&row.artist_genres),
+                        country_code: row.artist_country_code.clone(),
+                        artist_image: row.artist_image.clone(),
+                        albums: Vec::new(),
+                        line,
+                    });
+                    manifest.artists.len() - 1
+                });
+            let artist = &mut manifest.artists[artist_pos];
+
+            let album_pos = *album_positions
+                .entry((artist_pos, row.album.to_lowercase()))
+                .or_insert_with(|| {
+                    artist.albums.push(ImportAlbum {
+                        title: row.album.clone(),
+                        cover_url: row.album_cover_url.clone(),
+                        release_year: row.album_release_year,
+                        genres: split_list(&row.album_genres),
+                        langs: split_list(&row.album_langs),
+                        dominant_color: row.album_dominant_color.clone(),
+                        songs: Vec::new(),
+                        line,
+                    });
+                    artist.albums.len() - 1
+                });
+
+            artist.albums[album_pos].songs.push(ImportSong {
+                title: row.song_title,
+                file_url: row.file_url,
+                duration_secs: row.duration_secs,
+                song_index: row.song_index,
+                tempo: row.tempo,
+                line,
+            });
+        }
+
+        Ok((manifest, errors))
+    }
+
+    /// Validates every row and resolves which records already exist.
+    /// Invalid artists or albums are skipped along with their children.
+    async fn build_plan(
+        db: &Surreal<Any>,
+        manifest: &ImportManifest,
+        report: &mut ImportReport,
+        errors: &mut Vec<ImportRowError>,
+    ) -> Result<ImportPlan> {
+        let mut plan = ImportPlan::default();
+        let mut seen_artists = HashSet::new();
+        let max_year = chrono::Utc::now().year() as u16 + 1;
+
+        for (artist_idx, artist) in manifest.artists.iter().enumerate() {
+            let artist_row = row_label(artist.line, || format!("artists[{}]", artist_idx));
+            let name = artist.name.trim().to_string();
+            let mut row_errors = Vec::new();
+
+            if name.is_empty() {
+                row_errors.push("Artist name cannot be empty".to_string());
+            } else if !seen_artists.insert(name.to_lowercase()) {
+                row_errors.push(format!("Duplicate artist '{}' in manifest", name));
+            }
+            if artist.country_code.trim().is_empty() {
+                row_errors.push("Artist country_code cannot be empty".to_string());
+            }
+            let genres = parse_genres(&artist.genres, &mut row_errors);
+
+            if !row_errors.is_empty() {
+                push_errors(errors, &artist_row, row_errors);
+                continue;
+            }
+
+            let existing_artist = Self::find_artist(db, &name).await?;
+            let artist_thing = existing_artist
+                .clone()
+                .unwrap_or_else(|| Thing::from(("artist", Id::rand())));
+            count(&mut report.artists, existing_artist.is_none());
+
+            plan.artists.push(ArtistWrite {
+                id: artist_thing.clone(),
+                is_new: existing_artist.is_none(),
+                name,
+                genres,
+                country_code: artist.country_code.trim().to_string(),
+                artist_image: artist.artist_image.clone(),
+            });
+
+            let mut seen_albums = HashSet::new();
+
+            for (album_idx, album) in artist.albums.iter().enumerate() {
+                let album_row = row_label(album.line, || {
+                    format!("artists[{}].albums[{}]", artist_idx, album_idx)
+                });
+                let title = album.title.trim().to_string();
+                let mut row_errors = Vec::new();
+
+                if title.is_empty() {
+                    row_errors.push("Album title cannot be empty".to_string());
+                } else if !seen_albums.insert(title.to_lowercase()) {
+                    row_errors.push(format!("Duplicate album '{}' for this artist", title));
+                }
+                if album.release_year.is_some_and(|year| year > max_year) {
+                    row_errors.push(format!(
+                        "release_year {} is in the future",
+                        album.release_year.unwrap_or_default()
+                    ));
+                }
+                if album.langs.iter().any(|lang| lang.trim().is_empty()) {
+                    row_errors.push("Album langs cannot contain empty values".to_string());
+                }
+                let album_genres = parse_genres(&album.genres, &mut row_errors);
+
+                if !row_errors.is_empty() {
+                    push_errors(errors, &album_row, row_errors);
+                    continue;
+                }
+
+                let existing_album = match &existing_artist {
+                    Some(artist_thing) => Self::find_album(db, artist_thing, &title).await?,
+                    None => None,
+                };
+                let album_thing = existing_album
+                    .clone()
+                    .unwrap_or_else(|| Thing::from(("album", Id::rand())));
+                count(&mut report.albums, existing_album.is_none());
+
+                plan.albums.push(AlbumWrite {
+                    id: album_thing.clone(),
+                    is_new: existing_album.is_none(),
+                    title,
+                    cover_url: album.cover_url.clone(),
+                    release_year: album.release_year,
+                    genres: album_genres,
+                    langs: album.langs.iter().map(|l| l.trim().to_string()).collect(),
+                    dominant_color: album.dominant_color.clone(),
+                });
+                plan.album_artists.push(EdgeWrite {
+                    from: artist_thing.clone(),
+                    to: album_thing.clone(),
+                });
+
+                let mut seen_titles = HashSet::new();
+                let mut seen_indexes = HashSet::new();
+
+                for (song_idx, song) in album.songs.iter().enumerate() {
+                    let song_row = row_label(song.line, || {
+                        format!(
+                            "artists[{}].albums[{}].songs[{}]",
+                            artist_idx, album_idx, song_idx
+                        )
+                    });
+                    let title = song.title.trim().to_string();
+                    let mut row_errors = Vec::new();
+
+                    if title.is_empty() {
+                        row_errors.push("Song title cannot be empty".to_string());
+                    } else if !seen_titles.insert(title.to_lowercase()) {
+                        row_errors.push(format!("Duplicate song '{}' in this album", title));
+                    }
+                    if !seen_indexes.insert(song.song_index) {
+                        row_errors.push(format!(
+                            "Duplicate song_index {} in this album",
+                            song.song_index
+                        ));
+                    }
+                    if song.file_url.trim().is_empty() {
+                        row_errors.push("Song file_url cannot be empty".to_string());
+                    }
+                    if song.duration_secs == 0 {
+                        row_errors.push("Song duration_secs must be greater than 0".to_string());
+                    }
+                    if song.tempo.is_some_and(|tempo| !tempo.is_finite() || tempo < 0.0) {
+                        row_errors.push("Song tempo must be a positive number".to_string());
+                    }
+
+                    if !row_errors.is_empty() {
+                        push_errors(errors, &song_row, row_errors);
+                        continue;
+                    }
+
+                    let existing_song = match &existing_album {
+                        Some(album_thing) => Self::find_song(db, album_thing, &title).await?,
+                        None => None,
+                    };
+                    let song_thing = existing_song
+                        .clone()
+                        .unwrap_or_else(|| Thing::from(("song", Id::rand())));
+                    count(&mut report.songs, existing_song.is_none());
+
+                    plan.songs.push(SongWrite {
+                        id: song_thing.clone(),
+                        is_new: existing_song.is_none(),
+                        title,
+                        file_url: song.file_url.trim().to_string(),
+                        duration: Duration::from_secs(song.duration_secs),
+                        song_index: song.song_index,
+                        tempo: song.tempo,
+                    });
+                    plan.album_songs.push(EdgeWrite {
+                        from: album_thing.clone(),
+                        to: song_thing.clone(),
+                    });
+                    plan.song_artists.push(EdgeWrite {
+                        from: artist_thing.clone(),
+                        to: song_thing,
+                    });
+                }
+            }
+        }
+
+        Ok(plan)
+    }
+
+    async fn apply_plan(db: &Surreal<Any>, plan: ImportPlan) -> Result<()> {
+        let import_query = r#"
+            BEGIN TRANSACTION;
+
+            FOR $artist IN $artists {
+                LET $id = $artist.id;
+                IF $artist.is_new THEN
+                    CREATE $id SET
+                        name = $artist.name,
+                        genres = $artist.genres,
+                        country_code = $artist.country_code,
+                        artist_image = $artist.artist_image,
+                        albums_count = 0,
+                        songs_count = 0,
+                        total_likes = 0
+                ELSE
+                    UPDATE $id SET
+                        name = $artist.name,
+                        genres = $artist.genres,
+                        country_code = $artist.country_code,
+                        artist_image = $artist.artist_image OR artist_image
+                END;
+            };
+
+            FOR $album IN $albums {
+                LET $id = $album.id;
+                IF $album.is_new THEN
+                    CREATE $id SET
+                        title = $album.title,
+                        cover_url = $album.cover_url,
+                        release_year = $album.release_year,
+                        genres = $album.genres,
+                        langs = $album.langs,
+                        dominant_color = $album.dominant_color,
+                        total_tracks = 0,
+                        total_duration = 0s,
+                        total_listens = 0,
+                        total_user_listens = 0,
+                        total_likes = 0
+                ELSE
+                    UPDATE $id SET
+                        title = $album.title,
+                        -- Nouvelle couverture sans couleur fournie : la couleur sera recalculée
+                        dominant_color = $album.dominant_color OR (
+                            IF $album.cover_url != NONE AND $album.cover_url != cover_url THEN NONE ELSE dominant_color END
+                        ),
+                        cover_url = $album.cover_url OR cover_url,
+                        release_year = $album.release_year OR release_year,
+                        genres = $album.genres,
+                        langs = $album.langs
+                END;
+            };
+
+            FOR $song IN $songs {
+                LET $id = $song.id;
+                IF $song.is_new THEN
+                    CREATE $id SET
+                        title = $song.title,
+                        file_url = $song.file_url,
+                        duration = $song.duration,
+                        song_index = $song.song_index,
+                        tempo = $song.tempo OR 0,
+                        total_listens = 0,
+                        total_user_listens = 0,
+                        total_likes = 0
+                ELSE
+                    UPDATE $id SET
+                        title = $song.title,
+                        file_url = $song.file_url,
+                        duration = $song.duration,
+                        song_index = $song.song_index,
+                        tempo = $song.tempo OR tempo
+                END;
+            };
+
+            FOR $edge IN $album_artists {
+                LET $from = $edge.from;
+                LET $to = $edge.to;
+                IF array::len(SELECT id FROM artist_creates_album WHERE in = $from AND out = $to) = 0 THEN
+                    RELATE $from->artist_creates_album->$to
+                END;
+            };
+
+            FOR $edge IN $album_songs {
+                LET $from = $edge.from;
+                LET $to = $edge.to;
+                IF array::len(SELECT id FROM album_contains_song WHERE in = $from AND out = $to) = 0 THEN
+                    RELATE $from->album_contains_song->$to
+                END;
+            };
+
+            FOR $edge IN $song_artists {
+                LET $from = $edge.from;
+                LET $to = $edge.to;
+                IF array::len(SELECT id FROM artist_performs_song WHERE in = $from AND out = $to) = 0 THEN
+                    RELATE $from->artist_performs_song->$to
+                END;
+            };
+
+            COMMIT TRANSACTION;
+        "#;
+
+        db.query(import_query)
+            .bind(("artists", plan.artists))
+            .bind(("albums", plan.albums))
+            .bind(("songs", plan.songs))
+            .bind(("album_artists", plan.album_artists))
+            .bind(("album_songs", plan.album_songs))
+            .bind(("song_artists", plan.song_artists))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    pub(crate) async fn find_artist(db: &Surreal<Any>, name: &str) -> Result<Option<Thing>> {
+        let ids: Vec<Thing> = db
+            .query("SELECT VALUE id FROM artist WHERE string::lowercase(name) = $name LIMIT 1")
+            .bind(("name", name.to_lowercase()))
+            .await?
+            .take(0)?;
+
+        Ok(ids.into_iter().next())
+    }
+
+    pub(crate) async fn find_album(db: &Surreal<Any>, artist: &Thing, title: &str) -> Result<Option<Thing>> {
+        let ids: Vec<Thing> = db
+            .query(
+                "SELECT VALUE out FROM artist_creates_album
+                 WHERE in = $artist AND string::lowercase(out.title) = $title LIMIT 1",
+            )
+            .bind(("artist", artist.clone()))
+            .bind(("title", title.to_lowercase()))
+            .await?
+            .take(0)?;
+
+        Ok(ids.into_iter().next())
+    }
+
+    pub(crate) async fn find_song(db: &Surreal<Any>, album: &Thing, title: &str) -> Result<Option<Thing>> {
+        let ids: Vec<Thing> = db
+            .query(
+                "SELECT VALUE out FROM album_contains_song
+                 WHERE in = $album AND string::lowercase(out.title) = $title LIMIT 1",
+            )
+            .bind(("album", album.clone()))
+            .bind(("title", title.to_lowercase()))
+            .await?
+            .take(0)?;
+
+        Ok(ids.into_iter().next())
+    }
+}
+
+fn row_label(line: Option<usize>, path: impl FnOnce() -> String) -> String {
+    match line {
+        Some(line) => format!("line {}", line),
+        None => path(),
+    }
+}
+
+fn push_errors(errors: &mut Vec<ImportRowError>, row: &str, reasons: Vec<String>) {
+    errors.extend(reasons.into_iter().map(|reason| ImportRowError {
+        row: row.to_string(),
+        reason,
+    }));
+}
+
+fn count(counts: &mut ImportCounts, is_new: bool) {
+    if is_new {
+        counts.created += 1;
+    } else {
+        counts.updated += 1;
+    }
+}
+
+fn split_list(value: &str) -> Vec<String> {
+    value
+        .split(';')
+        .map(str::trim)
+        .filter(|v| !v.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+/// Accepte `RAC` comme `Rac`.
+fn parse_genres(values: &[String], row_errors: &mut Vec<String>) -> Vec<MusicGenre> {
+    let mut genres = Vec::with_capacity(values.len());
+    for value in values {
+        let value = value.trim();
+        let genre = MusicGenre::from_str(value)
+            .ok()
+            .or_else(|| serde_json::from_value(serde_json::Value::String(value.to_string())).ok());
+        match genre {
+            Some(genre) => genres.push(genre),
+            None => row_errors.push(format!("Unknown genre '{}'", value)),
+        }
+    }
+    genres
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{album::Album, artist::Artist};
+    use surrealdb::engine::any::connect;
+
+    async fn setup_db() -> Surreal<Any> {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db
+    }
+
+    fn sample_manifest() -> String {
+        serde_json::json!({
+            "artists": [{
+                "name": "Test Artist",
+                "genres": ["RAC", "Rap"],
+                "country_code": "FR",
+                "albums": [{
+                    "title": "First Album",
+                    "release_year": 2020,
+                    "genres": ["RAC"],
+                    "langs": ["fr"],
+                    "songs": [
+                        { "title": "Intro", "file_url": "/a/1.mp3", "duration_secs": 60, "song_index": 1 },
+                        { "title": "Outro", "file_url": "/a/2.mp3", "duration_secs": 120, "song_index": 2, "tempo": 128.0 }
+                    ]
+                }]
+            }]
+        })
+        .to_string()
+    }
+
+    #[tokio::test]
+    async fn test_import_json_creates_records_and_edges() {
+        let db = setup_db().await;
+
+        let report = ImportService::import(&db, &sample_manifest(), ImportFormat::Json, false)
+            .await
+            .unwrap();
+
+        assert!(report.applied);
+        assert_eq!(report.artists, ImportCounts { created: 1, updated: 0 });
+        assert_eq!(report.albums, ImportCounts { created: 1, updated: 0 });
+        assert_eq!(report.songs, ImportCounts { created: 2, updated: 0 });
+
+        let artists: Vec<Artist> = db.select("artist").await.unwrap();
+        assert_eq!(artists.len(), 1);
+        assert_eq!(artists[0].genres, vec![MusicGenre::Rac, MusicGenre::Rap]);
+        assert_eq!(artists[0].albums_count, 1);
+        assert_eq!(artists[0].songs_count, 2);
+
+        let albums: Vec<Album> = db.select("album").await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].total_tracks, 2);
+        assert_eq!(albums[0].total_duration, Duration::from_secs(180));
+
+        let edges: Vec<Thing> = db
+            .query("SELECT VALUE id FROM album_contains_song")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(edges.len(), 2);
+    }
+
+    #[tokio::test]
+    async fn test_reimport_updates_instead_of_duplicating() {
+        let db = setup_db().await;
+
+        ImportService::import(&db, &sample_manifest(), ImportFormat::Json, false)
+            .await
+            .unwrap();
+
+        let updated = sample_manifest()
+            .replace("\"duration_secs\":60", "\"duration_secs\":90")
+            .replace("Test Artist", "TEST ARTIST");
+        let report = ImportService::import(&db, &updated, ImportFormat::Json, false)
+            .await
+            .unwrap();
+
+        assert_eq!(report.artists, ImportCounts { created: 0, updated: 1 });
+        assert_eq!(report.albums, ImportCounts { created: 0, updated: 1 });
+        assert_eq!(report.songs, ImportCounts { created: 0, updated: 2 });
+
+        let artists: Vec<Artist> = db.select("artist").await.unwrap();
+        assert_eq!(artists.len(), 1);
+        assert_eq!(artists[0].songs_count, 2);
+
+        let albums: Vec<Album> = db.select("album").await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].total_duration, Duration::from_secs(210));
+
+        let edges: Vec<Thing> = db
+            .query("SELECT VALUE id FROM artist_performs_song")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(edges.len(), 2);
+
+        // Le tempo absent du manifeste ne doit pas écraser la valeur existante
+        let tempos: Vec<f32> = db
+            .query("SELECT VALUE tempo FROM song WHERE title = 'Outro'")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(tempos, vec![128.0]);
+
+        // Une nouvelle couverture sans couleur fournie efface l'ancienne couleur
+        db.query("UPDATE album SET cover_url = '/covers/old.jpg', dominant_color = '#112233'")
+            .await
+            .unwrap();
+        ImportService::import(&db, &updated, ImportFormat::Json, false)
+            .await
+            .unwrap();
+        let albums: Vec<Album> = db.select("album").await.unwrap();
+        assert_eq!(albums[0].dominant_color.as_deref(), Some("#112233"));
+
+        let new_cover = updated.replace(
+            "\"title\":\"First Album\"",
+            "\"cover_url\":\"/covers/new.jpg\",\"title\":\"First Album\"",
+        );
+        ImportService::import(&db, &new_cover, ImportFormat::Json, false)
+            .await
+            .unwrap();
+        let albums: Vec<Album> = db.select("album").await.unwrap();
+        assert_eq!(albums[0].cover_url.as_deref(), Some("/covers/new.jpg"));
+        assert_eq!(albums[0].dominant_color, None);
+    }
+
+    #[tokio::test]
+    async fn test_dry_run_reports_row_errors_without_writing() {
+        let db = setup_db().await;
+
+        let manifest = serde_json::json!({
+            "artists": [{
+                "name": "Artist",
+                "genres": ["JAZZ"],
+                "country_code": "FR",
+                "albums": [{
+                    "title": "Album",
+                    "songs": [
+                        { "title": "One", "file_url": "/1.mp3", "duration_secs": 0, "song_index": 1 },
+                        { "title": "Two", "file_url": "/2.mp3", "duration_secs": 10, "song_index": 1 }
+                    ]
+                }]
+            }, {
+                "name": "Valid",
+                "country_code": "DE",
+                "albums": [{
+                    "title": "Album",
+                    "songs": [
+                        { "title": "One", "file_url": "/1.mp3", "duration_secs": 0, "song_index": 1 },
+                        { "title": "Two", "file_url": "/2.mp3", "duration_secs": 10, "song_index": 1 }
+                    ]
+                }]
+            }]
+        })
+        .to_string();
+
+        let report = ImportService::import(&db, &manifest, ImportFormat::Json, true)
+            .await
+            .unwrap();
+
+        assert!(report.dry_run);
+        assert!(!report.applied);
+        assert_eq!(report.artists.created, 1);
+        assert_eq!(report.songs.created, 0);
+        assert_eq!(
+            report.errors,
+            vec![
+                ImportRowError {
+                    row: "artists[0]".to_string(),
+                    reason: "Unknown genre 'JAZZ'".to_string(),
+                },
+                ImportRowError {
+                    row: "artists[1].albums[0].songs[0]".to_string(),
+                    reason: "Song duration_secs must be greater than 0".to_string(),
+                },
+                ImportRowError {
+                    row: "artists[1].albums[0].songs[1]".to_string(),
+                    reason: "Duplicate song_index 1 in this album".to_string(),
+                },
+            ]
+        );
+
+        let artists: Vec<Artist> = db.select("artist").await.unwrap();
+        assert!(artists.is_empty());
+
+        let result = ImportService::import(&db, &manifest, ImportFormat::Json, false).await;
+        assert!(matches!(result, Err(Error::InvalidInput { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_import_csv_groups_rows() {
+        let db = setup_db().await;
+
+        let csv = "\
+artist,artist_country_code,artist_genres,album,album_release_year,album_langs,song_index,song_title,file_url,duration_secs,tempo
+Band,FR,RAC;OI,Album A,2001,fr;en,1,Song 1,/1.mp3,100,
+Band,FR,RAC;OI,Album A,2001,fr;en,2,Song 2,/2.mp3,200,120
+Band,FR,RAC;OI,Album B,2003,fr,1,Song 3,/3.mp3,not-a-number,
+";
+
+        let report = ImportService::import(&db, csv, ImportFormat::Csv, true)
+            .await
+            .unwrap();
+
+        assert_eq!(report.artists.created, 1);
+        assert_eq!(report.albums.created, 1);
+        assert_eq!(report.songs.created, 2);
+        assert_eq!(report.errors.len(), 1);
+        assert_eq!(report.errors[0].row, "line 4");
+
+        let fixed = csv.replace("not-a-number", "300");
+        let report = ImportService::import(&db, &fixed, ImportFormat::Csv, false)
+            .await
+            .unwrap();
+        assert!(report.applied);
+
+        let albums: Vec<Album> = db
+            .query("SELECT * FROM album ORDER BY title")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(albums.len(), 2);
+        assert_eq!(albums[0].langs, vec!["fr".to_string(), "en".to_string()]);
+        assert_eq!(albums[0].total_tracks, 2);
+        assert_eq!(albums[1].total_tracks, 1);
+    }
+}
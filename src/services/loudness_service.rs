@@ -0,0 +1,624 @@
+use std::{f64::consts::PI, path::Path};
+
+use serde::Deserialize;
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
+
+use crate::{
+    error::{Error, Result},
+    helpers::thing_helpers::{create_album_thing, thing_to_string},
+    models::{
+        album::AlbumWithRelations,
+        analysis::AnalysisKind,
+        loudness::{Loudness, LoudnessReport},
+        song::Song,
+    },
+    services::{
+        album_service::AlbumService,
+        audio_analysis_service::{AudioAnalysisService, SongFile},
+        media_service::MediaConfig,
+        song_service::SongService,
+    },
+};
+
+/// ReplayGain 2.0 reference level.
+pub const REFERENCE_LUFS: f64 = -18.0;
+
+// BS.1770 : blocs de 400 ms avec 75 % de recouvrement, soit un pas de 100 ms
+const SEGMENTS_PER_BLOCK: usize = 4;
+const SEGMENTS_PER_SEC: u32 = 10;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+// Suréchantillonnage du true peak : 12 coefficients par phase
+const TRUE_PEAK_TAPS_PER_PHASE: usize = 12;
+
+const BATCH_SIZE: u32 = 50;
+
+/// Loudness of one file, with the block energies needed to gate a whole album.
+#[derive(Debug, Clone)]
+pub struct TrackMeasurement {
+    pub loudness: Loudness,
+    /// Channel-weighted mean square of every 400 ms block.
+    pub blocks: Vec<f64>,
+    /// Linear true peak.
+    pub peak: f64,
+}
+
+/// Second-order IIR section, direct form II transposed.
+#[derive(Debug, Clone, Copy)]
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    z: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x: f64) -> f64 {
+        let y = self.b[0] * x + self.z[0];
+        self.z[0] = self.b[1] * x - self.a[0] * y + self.z[1];
+        self.z[1] = self.b[2] * x - self.a[1] * y;
+        y
+    }
+}
+
+/// BS.1770 K-weighting (high shelf then high pass), derived for any sample
+/// rate from the analog prototypes of the 48 kHz reference filters.
+fn k_weighting(sample_rate: u32) -> [Biquad; 2] {
+    let rate = f64::from(sample_rate);
+
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+    let k = (PI * f0 / rate).tan();
+    let vh = 10f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    let shelf = Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+    let k = (PI * f0 / rate).tan();
+    let a0 = 1.0 + k / q + k * k;
+    let high_pass = Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        z: [0.0; 2],
+    };
+
+    [shelf, high_pass]
+}
+
+/// BS.1770 channel weights, surround channels count +1.5 dB and the LFE not
+/// at all. Layouts follow the WAV/FLAC order (L, R, C, LFE, Ls, Rs).
+fn channel_weights(channels: usize) -> Vec<f64> {
+    match channels {
+        5 => vec![1.0, 1.0, 1.0, 1.41, 1.41],
+        6 => vec![1.0, 1.0, 1.0, 0.0, 1.41, 1.41],
+        _ => vec![1.0; channels],
+    }
+}
+
+/// Polyphase windowed-sinc interpolator, one row of taps per phase.
+fn oversampling_taps(factor: usize) -> Vec<Vec<f64>> {
+    let len = TRUE_PEAK_TAPS_PER_PHASE * factor;
+    let center = (len - 1) as f64 / 2.0;
+    let taps: Vec<f64> = (0..len)
+        .map(|j| {
+            let t = (j as f64 - center) / factor as f64;
+            let sinc = if t.abs() < 1e-12 {
+                1.0
+            } else {
+                (PI * t).sin() / (PI * t)
+            };
+            let window = 0.5 - 0.5 * (2.0 * PI * (j as f64 + 0.5) / len as f64).cos();
+            sinc * window
+        })
+        .collect();
+
+    (0..factor)
+        .map(|phase| {
+            let row: Vec<f64> = (0..TRUE_PEAK_TAPS_PER_PHASE)
+                .map(|i| taps[i * factor + phase])
+                .collect();
+            // Gain unitaire pour chaque phase
+            let sum: f64 = row.iter().sum();
+            row.into_iter().map(|tap| tap / sum).collect()
+        })
+        .collect()
+}
+
+/// Streaming BS.1770-4 meter: gated integrated loudness and true peak.
+struct LoudnessMeter {
+    channels: usize,
+    weights: Vec<f64>,
+    filters: Vec<[Biquad; 2]>,
+    segment_len: usize,
+    segment_energy: f64,
+    segment_filled: usize,
+    segments: Vec<f64>,
+    taps: Vec<Vec<f64>>,
+    history: Vec<Vec<f64>>,
+    peak: f64,
+}
+
+impl LoudnessMeter {
+    fn new(channels: usize, sample_rate: u32) -> Self {
+        // 4x sous 96 kHz, 2x au-delà, comme le recommande BS.1770
+        let factor = if sample_rate < 96_000 { 4 } else { 2 };
+
+        Self {
+            channels,
+            weights: channel_weights(channels),
+            filters: vec![k_weighting(sample_rate); channels],
+            segment_len: (sample_rate / SEGMENTS_PER_SEC).max(1) as usize,
+            segment_energy: 0.0,
+            segment_filled: 0,
+            segments: Vec::new(),
+            taps: oversampling_taps(factor),
+            history: vec![vec![0.0; TRUE_PEAK_TAPS_PER_PHASE]; channels],
+            peak: 0.0,
+        }
+    }
+
+    fn push(&mut self, interleaved: &[f32]) {
+        for frame in interleaved.chunks_exact(self.channels) {
+            for (channel, &sample) in frame.iter().enumerate() {
+                let x = f64::from(sample);
+
+                let [shelf, high_pass] = &mut self.filters[channel];
+                let y = high_pass.process(shelf.process(x));
+                self.segment_energy += self.weights[channel] * y * y;
+
+                let history = &mut self.history[channel];
+                history.rotate_right(1);
+                history[0] = x;
+                self.peak = self.peak.max(x.abs());
+                for phase in &self.taps {
+                    let value: f64 = phase
+                        .iter()
+                        .zip(history.iter())
+                        .map(|(tap, x)| tap * x)
+                        .sum();
+                    self.peak = self.peak.max(value.abs());
+                }
+            }
+
+            self.segment_filled += 1;
+            if self.segment_filled == self.segment_len {
+                self.segments
+                    .push(self.segment_energy / self.segment_len as f64);
+                self.segment_energy = 0.0;
+                self.segment_filled = 0;
+            }
+        }
+    }
+
+    /// Mean square of every 400 ms block, one block every 100 ms.
+    fn blocks(&self) -> Vec<f64> {
+        self.segments
+            .windows(SEGMENTS_PER_BLOCK)
+            .map(|window| window.iter().sum::<f64>() / SEGMENTS_PER_BLOCK as f64)
+            .collect()
+    }
+}
+
+fn block_loudness(energy: f64) -> f64 {
+    -0.691 + 10.0 * energy.log10()
+}
+
+/// Gated integrated loudness of a set of blocks, `None` when no block is
+/// above the absolute gate (silence).
+pub fn integrated_loudness(blocks: &[f64]) -> Option<f64> {
+    let mean = |blocks: &mut dyn Iterator<Item = f64>| {
+        let (sum, count) = blocks.fold((0.0, 0usize), |(sum, count), energy| {
+            (sum + energy, count + 1)
+        });
+        (count > 0).then(|| sum / count as f64)
+    };
+
+    let absolute = mean(
+        &mut blocks
+            .iter()
+            .copied()
+            .filter(|&energy| block_loudness(energy) > ABSOLUTE_GATE_LUFS),
+    )?;
+    let relative_gate = block_loudness(absolute) + RELATIVE_GATE_LU;
+
+    let gated = mean(&mut blocks.iter().copied().filter(|&energy| {
+        let loudness = block_loudness(energy);
+        loudness > ABSOLUTE_GATE_LUFS && loudness > relative_gate
+    }))?;
+
+    Some(block_loudness(gated))
+}
+
+fn to_loudness(integrated: f64, peak: f64) -> Loudness {
+    let round = |value: f64| ((value * 100.0).round() / 100.0) as f32;
+
+    Loudness {
+        integrated_lufs: round(integrated),
+        true_peak_dbtp: round(20.0 * peak.max(1e-10).log10()),
+        gain_db: round(REFERENCE_LUFS - integrated),
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct PendingAlbum {
+    id: Thing,
+    songs: Vec<SongFile>,
+}
+
+pub struct LoudnessService;
+
+impl LoudnessService {
+    /// `None` for silence.
+    fn finish(meter: &LoudnessMeter) -> Option<TrackMeasurement> {
+        let blocks = meter.blocks();
+        let integrated = integrated_loudness(&blocks)?;
+
+        Some(TrackMeasurement {
+            loudness: to_loudness(integrated, meter.peak),
+            blocks,
+            peak: meter.peak,
+        })
+    }
+
+    fn analyze_file(path: &Path) -> std::result::Result<TrackMeasurement, String> {
+        let mut meter: Option<LoudnessMeter> = None;
+
+        AudioAnalysisService::decode_interleaved(path, None, |samples, channels, sample_rate| {
+            let meter = meter.get_or_insert_with(|| LoudnessMeter::new(channels, sample_rate));
+            // Un changement de disposition en cours de fichier est ignoré
+            if meter.channels == channels {
+                meter.push(samples);
+            }
+        })?;
+
+        meter
+            .as_ref()
+            .and_then(Self::finish)
+            .ok_or_else(|| "No audible content".to_string())
+    }
+
+    async fn analyze(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        song: &SongFile,
+    ) -> Result<std::result::Result<TrackMeasurement, String>> {
+        let outcome =
+            AudioAnalysisService::run(db, config, song, AnalysisKind::Loudness, Self::analyze_file)
+                .await?;
+
+        if let Ok(measurement) = &outcome {
+            db.query("UPDATE $song SET loudness = $loudness")
+                .bind(("song", song.id.clone()))
+                .bind(("loudness", measurement.loudness))
+                .await?
+                .check()?;
+        }
+
+        Ok(outcome)
+    }
+
+    /// Measures every song of the album, then the album itself by gating the
+    /// blocks of all its songs together. Songs that fail are left out of the
+    /// album gain.
+    async fn analyze_album(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        album: &PendingAlbum,
+        report: &mut LoudnessReport,
+    ) -> Result<Option<Loudness>> {
+        let mut blocks = Vec::new();
+        let mut peak: f64 = 0.0;
+
+        for song in &album.songs {
+            match Self::analyze(db, config, song).await? {
+                Ok(measurement) => {
+                    report.analyzed += 1;
+                    blocks.extend(measurement.blocks);
+                    peak = peak.max(measurement.peak);
+                }
+                Err(_) => report.failed += 1,
+            }
+        }
+
+        let loudness = integrated_loudness(&blocks).map(|integrated| to_loudness(integrated, peak));
+        if loudness.is_some() {
+            report.albums += 1;
+        }
+
+        db.query("UPDATE $album SET loudness = $loudness")
+            .bind(("album", album.id.clone()))
+            .bind(("loudness", loudness))
+            .await?
+            .check()?;
+
+        Ok(loudness)
+    }
+
+    /// On-demand measurement of one song (track gain only).
+    pub async fn analyze_song(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        song_id: &str,
+    ) -> Result<Song> {
+        let song = AudioAnalysisService::get_song_file(db, song_id).await?;
+
+        if let Err(reason) = Self::analyze(db, config, &song).await? {
+            return Err(Error::AnalysisFailed {
+                song_id: thing_to_string(&song.id),
+                reason,
+            });
+        }
+
+        SongService::get_song_by_id(db, song_id)
+            .await?
+            .ok_or(Error::SongNotFound {
+                id: song_id.to_string(),
+            })
+    }
+
+    /// On-demand measurement of an album and all its songs, previous
+    /// failures included.
+    pub async fn analyze_album_by_id(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        album_id: &str,
+    ) -> Result<AlbumWithRelations> {
+        let album: Option<PendingAlbum> = db
+            .query(
+                "SELECT id, (SELECT id, file_url FROM ->album_contains_song->song) AS songs
+                 FROM $album",
+            )
+            .bind(("album", create_album_thing(album_id)))
+            .await?
+            .take(0)?;
+        let album = album.ok_or(Error::AlbumNotFound {
+            id: album_id.to_string(),
+        })?;
+
+        Self::analyze_album(db, config, &album, &mut LoudnessReport::default()).await?;
+
+        AlbumService::get_album(db, album_id)
+            .await?
+            .ok_or(Error::AlbumNotFound {
+                id: album_id.to_string(),
+            })
+    }
+
+    /// Measures albums without a loudness, then the remaining songs without
+    /// one. Songs that already failed are skipped until analyzed on demand.
+    pub async fn analyze_pending(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+    ) -> Result<LoudnessReport> {
+        let mut report = LoudnessReport::default();
+
+        let query = r#"
+            LET $failed = (SELECT VALUE song FROM analysis_failure WHERE kind = $kind);
+            SELECT id, (SELECT id, file_url FROM ->album_contains_song->song WHERE id NOTINSIDE $failed) AS songs
+            FROM album
+            WHERE loudness = NONE;
+        "#;
+        let albums: Vec<PendingAlbum> = db
+            .query(query)
+            .bind(("kind", AnalysisKind::Loudness))
+            .await?
+            .take(1)?;
+
+        for album in albums.iter().filter(|album| !album.songs.is_empty()) {
+            Self::analyze_album(db, config, album, &mut report).await?;
+        }
+
+        loop {
+            let songs =
+                AudioAnalysisService::pending_songs(db, AnalysisKind::Loudness, BATCH_SIZE).await?;
+
+            for song in &songs {
+                match Self::analyze(db, config, song).await? {
+                    Ok(_) => report.analyzed += 1,
+                    Err(_) => report.failed += 1,
+                }
+            }
+
+            if (songs.len() as u32) < BATCH_SIZE {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use surrealdb::engine::any::connect;
+
+    fn measure(samples: &[f32], channels: usize, sample_rate: u32) -> Option<TrackMeasurement> {
+        let mut meter = LoudnessMeter::new(channels, sample_rate);
+        meter.push(samples);
+        LoudnessService::finish(&meter)
+    }
+
+    fn db_to_amplitude(db: f64) -> f32 {
+        10f64.powf(db / 20.0) as f32
+    }
+
+    /// Interleaved stereo sine, same signal on both channels.
+    fn stereo_sine(
+        frequency: f64,
+        amplitude: f32,
+        secs: f64,
+        sample_rate: u32,
+        phase: f64,
+    ) -> Vec<f32> {
+        let len = (secs * f64::from(sample_rate)) as usize;
+        (0..len)
+            .flat_map(|i| {
+                let t = i as f64 / f64::from(sample_rate);
+                let value = amplitude * (2.0 * PI * frequency * t + phase).sin() as f32;
+                [value, value]
+            })
+            .collect()
+    }
+
+    #[test]
+    fn test_integrated_loudness_of_reference_sine() {
+        // EBU Tech 3341, cas 1 : sinus stéréo 1 kHz à -23 dBFS => -23 LUFS
+        for sample_rate in [44_100, 48_000] {
+            let samples = stereo_sine(1000.0, db_to_amplitude(-23.0), 20.0, sample_rate, 0.0);
+            let measurement = measure(&samples, 2, sample_rate).unwrap();
+
+            assert!(
+                (measurement.loudness.integrated_lufs + 23.0).abs() <= 0.1,
+                "{} Hz: {:?}",
+                sample_rate,
+                measurement.loudness
+            );
+            assert!((measurement.loudness.gain_db - 5.0).abs() <= 0.1);
+            assert!((measurement.loudness.true_peak_dbtp + 23.0).abs() <= 0.2);
+        }
+    }
+
+    #[test]
+    fn test_relative_gate_ignores_quiet_passages() {
+        // EBU Tech 3341, cas 3 : -36 / -23 / -36 dBFS pendant 10 / 20 / 10 s
+        let sample_rate = 48_000;
+        let mut samples = stereo_sine(1000.0, db_to_amplitude(-36.0), 10.0, sample_rate, 0.0);
+        samples.extend(stereo_sine(
+            1000.0,
+            db_to_amplitude(-23.0),
+            20.0,
+            sample_rate,
+            0.0,
+        ));
+        samples.extend(stereo_sine(
+            1000.0,
+            db_to_amplitude(-36.0),
+            10.0,
+            sample_rate,
+            0.0,
+        ));
+
+        let measurement = measure(&samples, 2, sample_rate).unwrap();
+        assert!((measurement.loudness.integrated_lufs + 23.0).abs() <= 0.1);
+
+        let silence = vec![0.0f32; sample_rate as usize * 4];
+        assert!(measure(&silence, 2, sample_rate).is_none());
+    }
+
+    #[test]
+    fn test_true_peak_between_samples() {
+        // fs/4 déphasé de 45° : les échantillons plafonnent à -3 dB du vrai pic
+        let sample_rate = 48_000;
+        let samples = stereo_sine(12_000.0, 0.5, 2.0, sample_rate, PI / 4.0);
+        let sample_peak = samples.iter().fold(0.0f32, |peak, s| peak.max(s.abs()));
+        assert!(20.0 * sample_peak.log10() < -8.9);
+
+        let measurement = measure(&samples, 2, sample_rate).unwrap();
+        let expected = 20.0 * 0.5f32.log10();
+        assert!(
+            (measurement.loudness.true_peak_dbtp - expected).abs() <= 0.5,
+            "{:?}",
+            measurement.loudness
+        );
+    }
+
+    #[test]
+    fn test_album_gating_spans_all_tracks() {
+        let sample_rate = 48_000;
+        let loud = measure(
+            &stereo_sine(1000.0, db_to_amplitude(-13.0), 10.0, sample_rate, 0.0),
+            2,
+            sample_rate,
+        )
+        .unwrap();
+        let quiet = measure(
+            &stereo_sine(1000.0, db_to_amplitude(-30.0), 10.0, sample_rate, 0.0),
+            2,
+            sample_rate,
+        )
+        .unwrap();
+
+        // La piste calme est sous la porte relative de l'album
+        let mut blocks = loud.blocks.clone();
+        blocks.extend(&quiet.blocks);
+        let album = integrated_loudness(&blocks).unwrap();
+        assert!((album + 13.0).abs() <= 0.1, "{}", album);
+    }
+
+    #[tokio::test]
+    async fn test_analyze_pending_skips_failures() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+
+        let media_root =
+            std::env::temp_dir().join(format!("loudness-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&media_root).unwrap();
+        fs::write(media_root.join("broken.mp3"), b"not an mp3").unwrap();
+        let config = MediaConfig {
+            media_root: media_root.clone(),
+            image_cache_dir: media_root.join("cache"),
+        };
+
+        db.query(
+            r#"
+            CREATE album:a SET title = 'A', genres = [], langs = [], total_tracks = 1, total_duration = 1m;
+            CREATE song:broken SET title = 'Broken', file_url = 'broken.mp3', duration = 1m, song_index = 1, tempo = 0;
+            CREATE song:single SET title = 'Single', file_url = 'missing.mp3', duration = 1m, song_index = 1, tempo = 0;
+            RELATE album:a->album_contains_song->song:broken;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let report = LoudnessService::analyze_pending(&db, &config)
+            .await
+            .unwrap();
+        assert_eq!(report.analyzed, 0);
+        assert_eq!(report.failed, 2);
+        assert_eq!(report.albums, 0);
+
+        let album_loudness: Option<Loudness> = db
+            .query("SELECT VALUE loudness FROM ONLY album:a")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(album_loudness, None);
+
+        // Les échecs ne sont pas retentés par le batch
+        let report = LoudnessService::analyze_pending(&db, &config)
+            .await
+            .unwrap();
+        assert_eq!(report.analyzed + report.failed, 0);
+
+        let result = LoudnessService::analyze_album_by_id(&db, &config, "a")
+            .await
+            .unwrap();
+        assert_eq!(result.loudness, None);
+        let failures = AudioAnalysisService::list_failures(&db, Some(AnalysisKind::Loudness))
+            .await
+            .unwrap();
+        let broken = failures
+            .iter()
+            .find(|f| f.song.id.to_raw() == "broken")
+            .unwrap();
+        assert_eq!(broken.attempts, 2);
+
+        fs::remove_dir_all(&media_root).unwrap();
+    }
+}
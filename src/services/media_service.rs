@@ -0,0 +1,366 @@
+use std::{
+    env,
+    io::SeekFrom,
+    path::{Component, Path, PathBuf},
+    time::{SystemTime, UNIX_EPOCH},
+};
+
+use axum::{
+    body::Body,
+    http::{header, HeaderMap, StatusCode},
+    response::Response,
+};
+use chrono::{DateTime, Utc};
+use tokio::io::{AsyncReadExt, AsyncSeekExt};
+use tokio_util::io::ReaderStream;
+
+use crate::{Error, Result};
+
+#[derive(Clone, Debug)]
+pub struct MediaConfig {
+    pub media_root: PathBuf,
+    /// Resized covers, safe to wipe.
+    pub image_cache_dir: PathBuf,
+}
+
+impl MediaConfig {
+    pub fn from_env() -> Self {
+        Self {
+            media_root: PathBuf::from(env::var("MEDIA_ROOT").unwrap_or_else(|_| "./media".to_string())),
+            image_cache_dir: PathBuf::from(
+                env::var("IMAGE_CACHE_DIR").unwrap_or_else(|_| "./cache/images".to_string()),
+            ),
+        }
+    }
+
+    /// Path of a file inside the media root, as stored in `Song.file_url`
+    /// (always `/`-separated, without a leading slash).
+    pub fn relative_path(&self, path: &Path) -> Option<String> {
+        let relative = path.strip_prefix(&self.media_root).ok()?;
+        let parts: Vec<String> = relative
+            .components()
+            .map(|c| match c {
+                Component::Normal(part) => part.to_str().map(str::to_string),
+                _ => None,
+            })
+            .collect::<Option<_>>()?;
+
+        if parts.is_empty() {
+            None
+        } else {
+            Some(parts.join("/"))
+        }
+    }
+
+    /// Resolves a `Song.file_url` inside the media root. Remote URLs and paths
+    /// escaping the root (`..`) are rejected.
+    pub fn resolve(&self, file_url: &str) -> Option<PathBuf> {
+        if file_url.contains("://") {
+            return None;
+        }
+
+        let mut path = self.media_root.clone();
+        for component in Path::new(file_url.trim_start_matches('/')).components() {
+            match component {
+                Component::Normal(part) => path.push(part),
+                Component::CurDir => {}
+                _ => return None,
+            }
+        }
+
+        if path == self.media_root {
+            None
+        } else {
+            Some(path)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq, Eq)]
+enum ByteRange {
+    Full,
+    Partial { start: u64, end: u64 },
+    Unsatisfiable,
+}
+
+pub struct MediaService;
+
+impl MediaService {
+    pub const AUDIO_EXTENSIONS: [&'static str; 6] = ["mp3", "flac", "m4a", "mp4", "ogg", "oga"];
+
+    pub fn is_audio_file(path: &Path) -> bool {
+        path.extension()
+            .and_then(|ext| ext.to_str())
+            .is_some_and(|ext| {
+                Self::AUDIO_EXTENSIONS
+                    .iter()
+                    .any(|known| ext.eq_ignore_ascii_case(known))
+            })
+    }
+
+    pub fn content_type(path: &Path) -> &'static str {
+        let ext = path
+            .extension()
+            .and_then(|ext| ext.to_str())
+            .map(str::to_ascii_lowercase)
+            .unwrap_or_default();
+
+        match ext.as_str() {
+            "mp3" => "audio/mpeg",
+            "flac" => "audio/flac",
+            "m4a" | "mp4" => "audio/mp4",
+            "ogg" | "oga" => "audio/ogg",
+            _ => "application/octet-stream",
+        }
+    }
+
+    /// Serves a file with `Range` (single range), `ETag`/`Last-Modified` and
+    /// conditional request support.
+    pub async fn serve_file(path: &Path, headers: &HeaderMap) -> Result<Response> {
+        let mut file = tokio::fs::File::open(path).await?;
+        let metadata = file.metadata().await?;
+        let len = metadata.len();
+        let modified = metadata.modified().unwrap_or(UNIX_EPOCH);
+
+        let etag = entity_tag(len, modified);
+        let last_modified = http_date(modified);
+
+        let mut builder = Response::builder()
+            .header(header::ACCEPT_RANGES, "bytes")
+            .header(header::ETAG, &etag)
+            .header(header::LAST_MODIFIED, &last_modified);
+
+        if is_not_modified(headers, &etag, modified) {
+            return builder
+                .status(StatusCode::NOT_MODIFIED)
+                .body(Body::empty())
+                .map_err(response_error);
+        }
+
+        // If-Range : une plage n'est servie que si la ressource n'a pas changé
+        let range_allowed = headers
+            .get(header::IF_RANGE)
+            .and_then(|v| v.to_str().ok())
+            .is_none_or(|if_range| if_range == etag || if_range == last_modified);
+
+        let range = match headers.get(header::RANGE).and_then(|v| v.to_str().ok()) {
+            Some(range) if range_allowed => parse_range(range, len),
+            _ => ByteRange::Full,
+        };
+
+        builder = builder.header(header::CONTENT_TYPE, Self::content_type(path));
+
+        let (status, start, end) = match range {
+            ByteRange::Full => (StatusCode::OK, 0, len.saturating_sub(1)),
+            ByteRange::Partial { start, end } => {
+                builder = builder.header(
+                    header::CONTENT_RANGE,
+                    format!("bytes {}-{}/{}", start, end, len),
+                );
+                (StatusCode::PARTIAL_CONTENT, start, end)
+            }
+            ByteRange::Unsatisfiable => {
+                return builder
+                    .status(StatusCode::RANGE_NOT_SATISFIABLE)
+                    .header(header::CONTENT_RANGE, format!("bytes */{}", len))
+                    .body(Body::empty())
+                    .map_err(response_error);
+            }
+        };
+
+        let content_length = if len == 0 { 0 } else { end - start + 1 };
+        file.seek(SeekFrom::Start(start)).await?;
+        let body = Body::from_stream(ReaderStream::new(file.take(content_length)));
+
+        builder
+            .status(status)
+            .header(header::CONTENT_LENGTH, content_length)
+            .body(body)
+            .map_err(response_error)
+    }
+}
+
+fn parse_range(value: &str, len: u64) -> ByteRange {
+    let Some(spec) = value.trim().strip_prefix("bytes=") else {
+        return ByteRange::Full;
+    };
+    // Les requêtes multi-plages sont servies en entier
+    if spec.contains(',') {
+        return ByteRange::Full;
+    }
+    let Some((start, end)) = spec.trim().split_once('-') else {
+        return ByteRange::Full;
+    };
+
+    let (start, end) = match (start.trim(), end.trim()) {
+        ("", "") => return ByteRange::Full,
+        // bytes=-500 : les 500 derniers octets
+        ("", suffix) => match suffix.parse::<u64>() {
+            Ok(0) => return ByteRange::Unsatisfiable,
+            Ok(suffix) => (len.saturating_sub(suffix), len.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, "") => match start.parse::<u64>() {
+            Ok(start) => (start, len.saturating_sub(1)),
+            Err(_) => return ByteRange::Full,
+        },
+        (start, end) => match (start.parse::<u64>(), end.parse::<u64>()) {
+            (Ok(start), Ok(end)) if start <= end => (start, end.min(len.saturating_sub(1))),
+            _ => return ByteRange::Full,
+        },
+    };
+
+    if len == 0 || start >= len {
+        ByteRange::Unsatisfiable
+    } else {
+        ByteRange::Partial { start, end }
+    }
+}
+
+fn entity_tag(len: u64, modified: SystemTime) -> String {
+    let nanos = modified
+        .duration_since(UNIX_EPOCH)
+        .map(|d| d.as_nanos())
+        .unwrap_or_default();
+    format!("\"{:x}-{:x}\"", len, nanos)
+}
+
+fn http_date(time: SystemTime) -> String {
+    DateTime::<Utc>::from(time)
+        .format("%a, %d %b %Y %H:%M:%S GMT")
+        .to_string()
+}
+
+fn is_not_modified(headers: &HeaderMap, etag: &str, modified: SystemTime) -> bool {
+    // If-None-Match prime sur If-Modified-Since (RFC 9110)
+    if let Some(if_none_match) = headers.get(header::IF_NONE_MATCH).and_then(|v| v.to_str().ok()) {
+        return if_none_match
+            .split(',')
+            .map(|tag| tag.trim().trim_start_matches("W/"))
+            .any(|tag| tag == "*" || tag == etag);
+    }
+
+    let Some(since) = headers
+        .get(header::IF_MODIFIED_SINCE)
+        .and_then(|v| v.to_str().ok())
+        .and_then(|v| DateTime::parse_from_rfc2822(v).ok())
+    else {
+        return false;
+    };
+
+    DateTime::<Utc>::from(modified).timestamp() <= since.timestamp()
+}
+
+fn response_error(err: axum::http::Error) -> Error {
+    Error::DbError(format!("Could not build response: {}", err))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_relative_path() {
+        let config = MediaConfig {
+            media_root: PathBuf::from("/srv/media"),
+            image_cache_dir: PathBuf::from("/srv/cache"),
+        };
+
+        assert_eq!(
+            config.relative_path(Path::new("/srv/media/Artist/Album/01 - Intro.mp3")),
+            Some("Artist/Album/01 - Intro.mp3".to_string())
+        );
+        assert_eq!(config.relative_path(Path::new("/srv/other/file.mp3")), None);
+        assert_eq!(config.relative_path(Path::new("/srv/media")), None);
+    }
+
+    #[test]
+    fn test_resolve() {
+        let config = MediaConfig {
+            media_root: PathBuf::from("/srv/media"),
+            image_cache_dir: PathBuf::from("/srv/cache"),
+        };
+
+        assert_eq!(
+            config.resolve("Artist/01.mp3"),
+            Some(PathBuf::from("/srv/media/Artist/01.mp3"))
+        );
+        assert_eq!(
+            config.resolve("/Artist/./01.mp3"),
+            Some(PathBuf::from("/srv/media/Artist/01.mp3"))
+        );
+        assert_eq!(config.resolve("../etc/passwd"), None);
+        assert_eq!(config.resolve("Artist/../../etc/passwd"), None);
+        assert_eq!(config.resolve("https://cdn.example.com/01.mp3"), None);
+        assert_eq!(config.resolve(""), None);
+    }
+
+    #[test]
+    fn test_parse_range() {
+        assert_eq!(parse_range("bytes=0-99", 1000), ByteRange::Partial { start: 0, end: 99 });
+        assert_eq!(parse_range("bytes=900-", 1000), ByteRange::Partial { start: 900, end: 999 });
+        assert_eq!(parse_range("bytes=-100", 1000), ByteRange::Partial { start: 900, end: 999 });
+        assert_eq!(parse_range("bytes=500-5000", 1000), ByteRange::Partial { start: 500, end: 999 });
+        assert_eq!(parse_range("bytes=1000-", 1000), ByteRange::Unsatisfiable);
+        assert_eq!(parse_range("bytes=0-1,5-6", 1000), ByteRange::Full);
+        assert_eq!(parse_range("items=0-1", 1000), ByteRange::Full);
+        assert_eq!(parse_range("bytes=9-1", 1000), ByteRange::Full);
+    }
+
+    #[tokio::test]
+    async fn test_serve_file_ranges_and_conditionals() {
+        let dir = std::env::temp_dir().join(format!("media-test-{}", uuid::Uuid::new_v4()));
+        std::fs::create_dir_all(&dir).unwrap();
+        let path = dir.join("song.mp3");
+        let data: Vec<u8> = (0..=255u8).cycle().take(1000).collect();
+        std::fs::write(&path, &data).unwrap();
+
+        let response = MediaService::serve_file(&path, &HeaderMap::new()).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+        assert_eq!(response.headers()[header::CONTENT_TYPE], "audio/mpeg");
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "1000");
+        assert_eq!(response.headers()[header::ACCEPT_RANGES], "bytes");
+        let etag = response.headers()[header::ETAG].clone();
+        let last_modified = response.headers()[header::LAST_MODIFIED].clone();
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, "bytes=10-19".parse().unwrap());
+        let response = MediaService::serve_file(&path, &headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::PARTIAL_CONTENT);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes 10-19/1000");
+        assert_eq!(response.headers()[header::CONTENT_LENGTH], "10");
+        let body = axum::body::to_bytes(response.into_body(), usize::MAX).await.unwrap();
+        assert_eq!(&body[..], &data[10..20]);
+
+        // If-Range périmé : fichier complet
+        headers.insert(header::IF_RANGE, "\"stale\"".parse().unwrap());
+        let response = MediaService::serve_file(&path, &headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::OK);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::RANGE, "bytes=5000-".parse().unwrap());
+        let response = MediaService::serve_file(&path, &headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::RANGE_NOT_SATISFIABLE);
+        assert_eq!(response.headers()[header::CONTENT_RANGE], "bytes */1000");
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_NONE_MATCH, etag);
+        let response = MediaService::serve_file(&path, &headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        let mut headers = HeaderMap::new();
+        headers.insert(header::IF_MODIFIED_SINCE, last_modified);
+        let response = MediaService::serve_file(&path, &headers).await.unwrap();
+        assert_eq!(response.status(), StatusCode::NOT_MODIFIED);
+
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_is_audio_file() {
+        assert!(MediaService::is_audio_file(Path::new("a/b.FLAC")));
+        assert!(MediaService::is_audio_file(Path::new("a/b.m4a")));
+        assert!(!MediaService::is_audio_file(Path::new("a/cover.jpg")));
+        assert!(!MediaService::is_audio_file(Path::new("a/README")));
+    }
+}
@@ -0,0 +1,677 @@
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::{HashMap, HashSet},
+};
+
+use chrono::Utc;
+use serde::{Deserialize, Serialize};
+use surrealdb::{engine::any::Any, sql::Thing, Datetime, Surreal};
+
+use crate::{
+    error::{Error, Result},
+    helpers::thing_helpers::{create_playlist_thing, create_user_thing},
+    models::{
+        playlist::{CreatePlaylistRequest, Playlist, SaveMixRequest},
+        search::normalize_genre,
+    },
+    services::{
+        playlist_service::PlaylistService,
+        recommendation_service::{
+            RecommendationService, SongFeatures, FEATURES, MAX_RECOMMENDATION_LIMIT,
+        },
+        search_service::LIKE_WEIGHT,
+    },
+};
+
+/// Mixes generated per user, one per top genre.
+pub const MIX_COUNT: usize = 3;
+
+/// Songs per mix.
+pub const MIX_SIZE: usize = 30;
+
+/// Songs by one artist in a mix; a song counts for each of its artists.
+pub const MAX_SONGS_PER_ARTIST: usize = 3;
+
+/// Most popular songs of the most listened albums of a genre considered for
+/// the discoveries of a mix.
+const GENRE_POOL_SIZE: usize = 300;
+
+/// Artists named in the description of a mix.
+const DESCRIBED_ARTISTS: usize = 3;
+
+#[derive(Debug, Clone, Serialize, PartialEq)]
+pub struct MixReport {
+    pub users: usize,
+    pub mixes: usize,
+    pub failed: usize,
+}
+
+#[derive(Debug, Deserialize)]
+struct Listened {
+    song: Thing,
+    weight: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtistName {
+    id: Thing,
+    name: String,
+}
+
+#[derive(Debug, Serialize)]
+struct MixEntry {
+    song: Thing,
+    added_at: Datetime,
+}
+
+#[derive(Debug, Serialize)]
+struct NewMix {
+    name: String,
+    description: String,
+    songs: Vec<MixEntry>,
+}
+
+/// A genre of the user's songs, normalized, with its most weighted spelling.
+struct TasteGenre {
+    genre: String,
+    display: String,
+    weight: u64,
+}
+
+/// What the user listens to: the weight of each known song (listens, plus
+/// [`LIKE_WEIGHT`] when liked) and the resulting artist and genre affinities.
+struct Taste {
+    songs: Vec<(SongFeatures, u64)>,
+    artists: HashMap<String, u64>,
+    /// Heaviest first.
+    genres: Vec<TasteGenre>,
+}
+
+impl Taste {
+    async fn load(db: &Surreal<Any>, user: &Thing) -> Result<Self> {
+        let sql = "
+            SELECT out AS song, total_listens AS weight FROM user_listens_song WHERE in = $user;
+            SELECT VALUE out FROM user_likes_song WHERE in = $user;
+            SELECT VALUE out FROM user_likes_artist WHERE in = $user;
+        ";
+        let mut res = db.query(sql).bind(("user", user.clone())).await?;
+        let listened: Vec<Listened> = res.take(0)?;
+        let liked_songs: Vec<Thing> = res.take(1)?;
+        let liked_artists: Vec<Thing> = res.take(2)?;
+
+        let mut weights: HashMap<String, (Thing, u64)> = HashMap::new();
+        let liked = liked_songs.into_iter().map(|song| Listened {
+            song,
+            weight: LIKE_WEIGHT as u64,
+        });
+        for Listened { song, weight } in listened.into_iter().chain(liked) {
+            weights.entry(song.to_string()).or_insert((song, 0)).1 += weight;
+        }
+
+        let ids: Vec<Thing> = weights.values().map(|(id, _)| id.clone()).collect();
+        let mut res = db
+            .query(format!("SELECT {FEATURES} FROM $ids;"))
+            .bind(("ids", ids))
+            .bind(("like_weight", LIKE_WEIGHT))
+            .await?;
+        let features: Vec<SongFeatures> = res.take(0)?;
+
+        let mut artists: HashMap<String, u64> = HashMap::new();
+        let mut spellings: HashMap<String, HashMap<String, u64>> = HashMap::new();
+        let mut songs = Vec::with_capacity(features.len());
+        for song in features {
+            let weight = weights
+                .get(&song.id.to_string())
+                .map(|(_, weight)| *weight)
+                .unwrap_or(0);
+            for artist in &song.artists {
+                *artists.entry(artist.to_string()).or_default() += weight;
+            }
+            for spelling in song.genres.iter().collect::<HashSet<_>>() {
+                *spellings
+                    .entry(normalize_genre(spelling))
+                    .or_default()
+                    .entry(spelling.clone())
+                    .or_default() += weight;
+            }
+            songs.push((song, weight));
+        }
+        for artist in liked_artists {
+            *artists.entry(artist.to_string()).or_default() += LIKE_WEIGHT as u64;
+        }
+
+        let mut genres: Vec<TasteGenre> = spellings
+            .into_iter()
+            .filter_map(|(genre, spellings)| {
+                let weight = spellings.values().sum();
+                let (display, _) = spellings
+                    .into_iter()
+                    .max_by(|a, b| a.1.cmp(&b.1).then_with(|| b.0.cmp(&a.0)))?;
+                Some(TasteGenre {
+                    genre,
+                    display,
+                    weight,
+                })
+            })
+            .collect();
+        genres.sort_by(|a, b| b.weight.cmp(&a.weight).then_with(|| a.genre.cmp(&b.genre)));
+
+        Ok(Self {
+            songs,
+            artists,
+            genres,
+        })
+    }
+
+    fn artist_affinity(&self, song: &SongFeatures) -> u64 {
+        song.artists
+            .iter()
+            .filter_map(|artist| self.artists.get(&artist.to_string()))
+            .sum()
+    }
+}
+
+/// Picks songs alternately from `familiar` and `discoveries`, skipping the
+/// songs already `used` and those whose artists already have
+/// [`MAX_SONGS_PER_ARTIST`] songs in the mix.
+fn assemble<'a>(
+    familiar: Vec<&'a SongFeatures>,
+    discoveries: Vec<&'a SongFeatures>,
+    used: &mut HashSet<String>,
+) -> Vec<&'a SongFeatures> {
+    let mut mix = Vec::new();
+    let mut per_artist: HashMap<String, usize> = HashMap::new();
+    let mut familiar = familiar.into_iter();
+    let mut discoveries = discoveries.into_iter();
+    let mut from_familiar = true;
+
+    while mix.len() < MIX_SIZE {
+        // Une source épuisée laisse la place à l'autre
+        let next = if from_familiar {
+            familiar.next().or_else(|| discoveries.next())
+        } else {
+            discoveries.next().or_else(|| familiar.next())
+        };
+        let Some(song) = next else {
+            break;
+        };
+        from_familiar = !from_familiar;
+
+        let id = song.id.to_string();
+        let artists: HashSet<String> = song.artists.iter().map(Thing::to_string).collect();
+        if used.contains(&id)
+            || artists
+                .iter()
+                .any(|artist| per_artist.get(artist).copied().unwrap_or(0) >= MAX_SONGS_PER_ARTIST)
+        {
+            continue;
+        }
+
+        used.insert(id);
+        for artist in artists {
+            *per_artist.entry(artist).or_default() += 1;
+        }
+        mix.push(song);
+    }
+
+    mix
+}
+
+pub struct MixService;
+
+impl MixService {
+    /// Daily mixes of the user, generated on the first call.
+    pub async fn get_user_mixes(db: &Surreal<Any>, user_id: &str) -> Result<Vec<Playlist>> {
+        let mixes = Self::stored_mixes(db, user_id).await?;
+        if !mixes.is_empty() {
+            return Ok(mixes);
+        }
+
+        Self::refresh_user_mixes(db, user_id).await?;
+        Self::stored_mixes(db, user_id).await
+    }
+
+    async fn stored_mixes(db: &Surreal<Any>, user_id: &str) -> Result<Vec<Playlist>> {
+        let sql = "SELECT * FROM playlist WHERE created_by = $user AND is_system = true ORDER BY name ASC;";
+        let mut res = db
+            .query(sql)
+            .bind(("user", create_user_thing(user_id)))
+            .await?;
+        Ok(res.take(0)?)
+    }
+
+    /// Replaces the mixes of the user by up to [`MIX_COUNT`] new ones, one per
+    /// top genre of the songs they listened to or liked. Each mix alternates
+    /// between those songs, most listened first, and discoveries of the
+    /// genre: co-occurrence recommendations, then songs of the user's
+    /// favorite artists, then the most popular ones. A song appears in only
+    /// one mix. Returns the number of mixes.
+    pub async fn refresh_user_mixes(db: &Surreal<Any>, user_id: &str) -> Result<usize> {
+        let user = create_user_thing(user_id);
+        let taste = Taste::load(db, &user).await?;
+        let top_genres: Vec<&TasteGenre> = taste.genres.iter().take(MIX_COUNT).collect();
+
+        let mut pool: Vec<SongFeatures> = Vec::new();
+        let mut recommendation_scores: HashMap<String, f32> = HashMap::new();
+        if !top_genres.is_empty() {
+            let (recommended, _) =
+                RecommendationService::cooccurrence_scores(db, user_id, MAX_RECOMMENDATION_LIMIT)
+                    .await?;
+            let ids: Vec<Thing> = recommended.iter().map(|(id, _)| id.clone()).collect();
+            recommendation_scores = recommended
+                .into_iter()
+                .map(|(id, score)| (id.to_string(), score))
+                .collect();
+
+            // Orthographes brutes des genres retenus, pour filtrer côté base
+            let spellings: Vec<String> = taste
+                .songs
+                .iter()
+                .flat_map(|(song, _)| song.genres.iter())
+                .filter(|spelling| {
+                    let genre = normalize_genre(spelling);
+                    top_genres.iter().any(|top| top.genre == genre)
+                })
+                .cloned()
+                .collect::<HashSet<_>>()
+                .into_iter()
+                .collect();
+
+            let mut res = db
+                .query(format!("SELECT {FEATURES} FROM $ids;"))
+                .bind(("ids", ids))
+                .bind(("like_weight", LIKE_WEIGHT))
+                .await?;
+            pool = res.take(0)?;
+            let listened: Vec<Thing> = taste
+                .songs
+                .iter()
+                .map(|(song, _)| song.id.clone())
+                .collect();
+            let popular = RecommendationService::candidate_features(
+                db,
+                vec![],
+                spellings,
+                None,
+                listened,
+                GENRE_POOL_SIZE * MIX_COUNT,
+            )
+            .await?;
+            let in_pool: HashSet<String> = pool.iter().map(|song| song.id.to_string()).collect();
+            pool.extend(
+                popular
+                    .into_iter()
+                    .filter(|song| !in_pool.contains(&song.id.to_string())),
+            );
+        }
+
+        let known: HashSet<String> = taste
+            .songs
+            .iter()
+            .map(|(song, _)| song.id.to_string())
+            .collect();
+        let mut used: HashSet<String> = HashSet::new();
+        let mut mixes: Vec<(&str, Vec<&SongFeatures>)> = Vec::new();
+        for TasteGenre { genre, display, .. } in top_genres {
+            let mut familiar: Vec<&(SongFeatures, u64)> = taste
+                .songs
+                .iter()
+                .filter(|(song, _)| song.genre_set().contains(genre))
+                .collect();
+            familiar.sort_by(|(a, weight_a), (b, weight_b)| {
+                weight_b
+                    .cmp(weight_a)
+                    .then_with(|| a.id.to_raw().cmp(&b.id.to_raw()))
+            });
+
+            let mut discoveries: Vec<&SongFeatures> = pool
+                .iter()
+                .filter(|song| {
+                    !known.contains(&song.id.to_string()) && song.genre_set().contains(genre)
+                })
+                .collect();
+            discoveries.sort_by(|a, b| {
+                let score = |song: &SongFeatures| {
+                    recommendation_scores
+                        .get(&song.id.to_string())
+                        .copied()
+                        .unwrap_or(0.0)
+                };
+                score(b)
+                    .partial_cmp(&score(a))
+                    .unwrap_or(Ordering::Equal)
+                    .then_with(|| taste.artist_affinity(b).cmp(&taste.artist_affinity(a)))
+                    .then(b.popularity.cmp(&a.popularity))
+                    .then_with(|| a.id.to_raw().cmp(&b.id.to_raw()))
+            });
+
+            let songs = assemble(
+                familiar.into_iter().map(|(song, _)| song).collect(),
+                discoveries,
+                &mut used,
+            );
+            if !songs.is_empty() {
+                mixes.push((display, songs));
+            }
+        }
+
+        let mut artist_ids: Vec<Thing> = mixes
+            .iter()
+            .flat_map(|(_, songs)| songs.iter().flat_map(|song| song.artists.iter().cloned()))
+            .collect();
+        artist_ids.sort_unstable_by_key(Thing::to_string);
+        artist_ids.dedup();
+        let mut res = db
+            .query("SELECT id, name FROM $artists;")
+            .bind(("artists", artist_ids))
+            .await?;
+        let artist_names: HashMap<String, String> = res
+            .take::<Vec<ArtistName>>(0)?
+            .into_iter()
+            .map(|artist| (artist.id.to_string(), artist.name))
+            .collect();
+
+        let now = Utc::now();
+        let new_mixes: Vec<NewMix> = mixes
+            .iter()
+            .enumerate()
+            .map(|(index, (genre, songs))| NewMix {
+                name: format!("Daily Mix {}", index + 1),
+                description: describe(genre, songs, &artist_names),
+                songs: songs
+                    .iter()
+                    .enumerate()
+                    .map(|(position, song)| MixEntry {
+                        song: song.id.clone(),
+                        // L'ordre d'une playlist est celui des dates d'ajout
+                        added_at: Datetime::from(
+                            now + chrono::Duration::milliseconds(position as i64),
+                        ),
+                    })
+                    .collect(),
+            })
+            .collect();
+
+        let sql = "
+            BEGIN TRANSACTION;
+            DELETE playlist WHERE created_by = $user AND is_system = true;
+            FOR $mix IN $mixes {
+                LET $playlist = (CREATE ONLY playlist SET
+                    name = $mix.name,
+                    description = $mix.description,
+                    is_public = false,
+                    is_system = true,
+                    created_by = $user,
+                    created_at = time::now(),
+                    updated_at = time::now(),
+                    songs_count = array::len($mix.songs),
+                    total_duration = duration::from::secs(math::sum(
+                        (SELECT VALUE duration::secs(duration) FROM $mix.songs.song)
+                    )),
+                    total_listens = 0,
+                    total_likes = 0
+                ).id;
+                FOR $entry IN $mix.songs {
+                    RELATE $playlist->playlist_contains_song->($entry.song) SET
+                        added_at = $entry.added_at,
+                        added_by = $user;
+                };
+            };
+            COMMIT TRANSACTION;
+        ";
+        let count = new_mixes.len();
+        db.query(sql)
+            .bind(("user", user))
+            .bind(("mixes", new_mixes))
+            .await?
+            .check()?;
+
+        Ok(count)
+    }
+
+    /// Refreshes the mixes of every user who listened to or liked a song.
+    pub async fn refresh_all(db: &Surreal<Any>) -> Result<MixReport> {
+        let sql = "
+            array::distinct(array::concat(
+                (SELECT VALUE in FROM user_listens_song),
+                (SELECT VALUE in FROM user_likes_song)
+            ));
+        ";
+        let mut res = db.query(sql).await?;
+        let users: Vec<Thing> = res.take(0)?;
+
+        let mut report = MixReport {
+            users: users.len(),
+            mixes: 0,
+            failed: 0,
+        };
+        for user in users {
+            match Self::refresh_user_mixes(db, &user.id.to_raw()).await {
+                Ok(count) => report.mixes += count,
+                Err(e) => {
+                    tracing::warn!("Daily mixes of {} failed: {:?}", user, e);
+                    report.failed += 1;
+                }
+            }
+        }
+
+        Ok(report)
+    }
+
+    /// Copies a mix of the user into a new, private playlist of theirs, which
+    /// is not touched by later refreshes.
+    pub async fn save_mix(
+        db: &Surreal<Any>,
+        user_id: &str,
+        mix_id: &str,
+        request: SaveMixRequest,
+    ) -> Result<Thing> {
+        let sql = "
+            SELECT * FROM playlist WHERE id = $mix AND created_by = $user AND is_system = true;
+            SELECT out, added_at FROM playlist_contains_song WHERE in = $mix ORDER BY added_at ASC;
+        ";
+        let mut res = db
+            .query(sql)
+            .bind(("mix", create_playlist_thing(mix_id)))
+            .bind(("user", create_user_thing(user_id)))
+            .await?;
+        let mix: Option<Playlist> = res.take(0)?;
+        let mix = mix.ok_or_else(|| Error::PlaylistNotFound {
+            id: mix_id.to_string(),
+        })?;
+        let songs: Vec<Thing> = res.take((1, "out"))?;
+
+        let name = request
+            .name
+            .map(|name| name.trim().to_string())
+            .filter(|name| !name.is_empty())
+            .unwrap_or(mix.name);
+        let playlist = PlaylistService::create_playlist(
+            db,
+            user_id,
+            CreatePlaylistRequest {
+                name,
+                cover_url: None,
+                is_public: false,
+            },
+        )
+        .await?;
+
+        let now = Utc::now();
+        let entries: Vec<MixEntry> = songs
+            .into_iter()
+            .enumerate()
+            .map(|(position, song)| MixEntry {
+                song,
+                added_at: Datetime::from(now + chrono::Duration::milliseconds(position as i64)),
+            })
+            .collect();
+        let sql = "
+            FOR $entry IN $entries {
+                RELATE $playlist->playlist_contains_song->($entry.song) SET
+                    added_at = $entry.added_at,
+                    added_by = $user;
+            };
+        ";
+        db.query(sql)
+            .bind(("playlist", playlist.clone()))
+            .bind(("entries", entries))
+            .bind(("user", create_user_thing(user_id)))
+            .await?
+            .check()?;
+        PlaylistService::update_playlist_stats(db, &playlist.id.to_raw()).await?;
+
+        Ok(playlist)
+    }
+}
+
+/// `punk: The Clash, The Jam and Sham 69`, the artists with the most songs in
+/// the mix first.
+fn describe(genre: &str, songs: &[&SongFeatures], names: &HashMap<String, String>) -> String {
+    let mut counts: HashMap<&String, usize> = HashMap::new();
+    for song in songs {
+        for artist in &song.artists {
+            if let Some(name) = names.get(&artist.to_string()) {
+                *counts.entry(name).or_default() += 1;
+            }
+        }
+    }
+    let mut artists: Vec<(&String, usize)> = counts.into_iter().collect();
+    artists.sort_by_key(|(name, count)| (Reverse(*count), name.to_string()));
+    let artists: Vec<&str> = artists
+        .into_iter()
+        .take(DESCRIBED_ARTISTS)
+        .map(|(name, _)| name.as_str())
+        .collect();
+
+    match artists.split_last() {
+        None => genre.to_string(),
+        Some((last, [])) => format!("{}: {}", genre, last),
+        Some((last, rest)) => format!("{}: {} and {}", genre, rest.join(", "), last),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::engine::any::connect;
+
+    #[tokio::test]
+    async fn test_mixes_follow_top_genres_and_can_be_saved() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(
+            r#"
+            CREATE user:u1 SET username = 'u1';
+            CREATE artist:clash SET name = 'The Clash', genres = [], country_code = 'GB', albums_count = 1, songs_count = 5;
+            CREATE artist:jam SET name = 'The Jam', genres = [], country_code = 'GB', albums_count = 1, songs_count = 1;
+            CREATE artist:brel SET name = 'Jacques Brel', genres = [], country_code = 'BE', albums_count = 1, songs_count = 2;
+            CREATE album:london SET title = 'London Calling', genres = ['Punk'], langs = ['en'], total_tracks = 5, total_duration = 15m;
+            CREATE album:setting SET title = 'Setting Sons', genres = ['punk'], langs = ['en'], total_tracks = 1, total_duration = 3m;
+            CREATE album:ballades SET title = 'Ballades', genres = ['Chanson'], langs = ['fr'], total_tracks = 2, total_duration = 6m;
+            FOR $song IN [['c1', 'clash', 'london', 9], ['c2', 'clash', 'london', 8], ['c3', 'clash', 'london', 7],
+                ['c4', 'clash', 'london', 6], ['c5', 'clash', 'london', 5], ['eton', 'jam', 'setting', 0],
+                ['b1', 'brel', 'ballades', 2], ['b2', 'brel', 'ballades', 1]] {
+                CREATE type::thing('song', $song[0]) SET title = $song[0], file_url = 'f.mp3',
+                    duration = 3m, song_index = 1, tempo = 0.0, total_listens = $song[3];
+                RELATE (type::thing('artist', $song[1]))->artist_performs_song->(type::thing('song', $song[0]));
+                RELATE (type::thing('album', $song[2]))->album_contains_song->(type::thing('song', $song[0]));
+                IF $song[3] > 0 {
+                    RELATE user:u1->user_listens_song->(type::thing('song', $song[0]))
+                        SET total_listens = $song[3], last_listened_at = time::now();
+                };
+            };
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let mixes = MixService::get_user_mixes(&db, "u1").await.unwrap();
+        assert_eq!(mixes.len(), 2);
+        assert!(mixes.iter().all(|mix| mix.is_system));
+        assert_eq!(mixes[0].name, "Daily Mix 1");
+        assert_eq!(
+            mixes[0].description.as_deref(),
+            Some("punk: The Clash and The Jam")
+        );
+        assert_eq!(
+            mixes[1].description.as_deref(),
+            Some("chanson: Jacques Brel")
+        );
+
+        // Trois titres des Clash au plus, entrecoupés de la découverte
+        let mut res = db
+            .query("SELECT out, added_at FROM playlist_contains_song WHERE in = $mix ORDER BY added_at ASC;")
+            .bind(("mix", mixes[0].id.clone()))
+            .await
+            .unwrap();
+        let punk: Vec<Thing> = res.take((0, "out")).unwrap();
+        let punk: Vec<String> = punk.iter().map(|song| song.id.to_raw()).collect();
+        assert_eq!(punk, vec!["c1", "eton", "c2", "c3"]);
+        assert_eq!(mixes[0].songs_count, 4);
+
+        // Les mix ne sont ni des playlists de l'utilisateur ni modifiables
+        let own = PlaylistService::get_user_playlists(&db, "u1")
+            .await
+            .unwrap();
+        assert!(own.is_empty());
+        let mix_id = mixes[0].id.as_ref().unwrap().id.to_raw();
+        assert!(matches!(
+            PlaylistService::add_song_to_playlist(&db, "u1", "c5", &mix_id).await,
+            Err(Error::PlaylistNotFound { .. })
+        ));
+        assert!(matches!(
+            PlaylistService::remove_song_from_playlist(&db, "u1", "c1", &mix_id).await,
+            Err(Error::PlaylistNotFound { .. })
+        ));
+        assert!(matches!(
+            PlaylistService::delete_playlist(&db, "u1", &mix_id).await,
+            Err(Error::PlaylistNotFound { .. })
+        ));
+
+        // Un nouveau calcul remplace les mix existants
+        assert_eq!(MixService::refresh_user_mixes(&db, "u1").await.unwrap(), 2);
+        let mixes = MixService::get_user_mixes(&db, "u1").await.unwrap();
+        assert_eq!(mixes.len(), 2);
+
+        let saved = MixService::save_mix(
+            &db,
+            "u1",
+            &mixes[0].id.as_ref().unwrap().id.to_raw(),
+            SaveMixRequest {
+                name: Some("  My punk  ".to_string()),
+            },
+        )
+        .await
+        .unwrap();
+        let mut res = db
+            .query("SELECT out, added_at FROM playlist_contains_song WHERE in = $playlist ORDER BY added_at ASC;")
+            .bind(("playlist", saved.clone()))
+            .await
+            .unwrap();
+        let copy: Vec<Thing> = res.take((0, "out")).unwrap();
+        let copy: Vec<String> = copy.iter().map(|song| song.id.to_raw()).collect();
+        assert_eq!(copy, punk);
+
+        let own = PlaylistService::get_user_playlists(&db, "u1")
+            .await
+            .unwrap();
+        assert_eq!(own.len(), 1);
+        assert_eq!(own[0].name, "My punk");
+        assert!(!own[0].is_system);
+
+        // Les copies survivent au rafraîchissement
+        MixService::refresh_user_mixes(&db, "u1").await.unwrap();
+        let own = PlaylistService::get_user_playlists(&db, "u1")
+            .await
+            .unwrap();
+        assert_eq!(own.len(), 1);
+
+        assert!(matches!(
+            MixService::save_mix(&db, "u1", &saved.id.to_raw(), SaveMixRequest::default()).await,
+            Err(Error::PlaylistNotFound { .. })
+        ));
+    }
+}
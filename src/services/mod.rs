@@ -6,6 +6,23 @@ pub mod hcaptcha_service;
 pub mod user_service;
 
 pub mod playlist_service;
+pub mod radio_service;
+pub mod recommendation_service;
+pub mod search_analytics_service;
 pub mod search_service;
+pub mod suggest_service;
 pub mod song_service;
-pub mod badge_service;
\ No newline at end of file
+pub mod badge_service;
+pub mod chart_service;
+pub mod import_service;
+pub mod media_service;
+pub mod mix_service;
+pub mod scan_service;
+pub mod audio_analysis_service;
+pub mod loudness_service;
+pub mod fingerprint_service;
+pub mod tempo_service;
+pub mod waveform_service;
+pub mod wrapped_service;
+pub mod color_service;
+pub mod image_service;
\ No newline at end of file
@@ -40,7 +40,7 @@ impl PlaylistService {
         let playlist_thing = create_playlist_thing(playlist_id);
 
         let mut playlists: Vec<Playlist> = db
-            .query("SELECT * FROM playlist WHERE id = $playlist AND created_by = $user")
+            .query("SELECT * FROM playlist WHERE id = $playlist AND created_by = $user AND is_system != true")
             .bind(("playlist", playlist_thing))
             .bind(("user", user_thing))
             .await
@@ -241,7 +241,7 @@ impl PlaylistService {
                 r#"
             SELECT *
             FROM playlist
-            WHERE created_by = $user
+            WHERE created_by = $user AND is_system != true
             ORDER BY created_at DESC
         "#,
             )
@@ -272,6 +272,7 @@ impl PlaylistService {
                         out.file_url as file_url,
                         out.song_index as song_index,
                         out.tempo as tempo,
+                        out.loudness as loudness,
                         out.total_listens as total_listens,
                         out.total_user_listens as total_user_listens,
                         out.total_likes as total_likes,
@@ -314,7 +315,7 @@ impl PlaylistService {
 
         // Vérifier que la playlist appartient à l'utilisateur
         let playlist_check: Option<Playlist> = db
-            .query("SELECT * FROM playlist WHERE id = $playlist AND created_by = $user")
+            .query("SELECT * FROM playlist WHERE id = $playlist AND created_by = $user AND is_system != true")
             .bind(("playlist", playlist_thing.clone()))
             .bind(("user", user_thing))
             .await?
@@ -350,7 +351,7 @@ impl PlaylistService {
 
         // Vérifier que la playlist appartient à l'utilisateur
         let playlist_check: Option<Playlist> = db
-            .query("SELECT * FROM playlist WHERE id = $playlist AND created_by = $user")
+            .query("SELECT * FROM playlist WHERE id = $playlist AND created_by = $user AND is_system != true")
             .bind(("playlist", playlist_thing.clone()))
             .bind(("user", user_thing))
             .await?
@@ -372,7 +373,7 @@ impl PlaylistService {
     }
 
     /// Met à jour les statistiques d'une playlist
-    async fn update_playlist_stats(db: &Surreal<Any>, playlist_id: &str) -> Result<(), Error> {
+    pub(crate) async fn update_playlist_stats(db: &Surreal<Any>, playlist_id: &str) -> Result<(), Error> {
         let playlist_thing = create_playlist_thing(playlist_id);
 
         // Compter le nombre de chansons dans la playlist
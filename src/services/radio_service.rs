@@ -0,0 +1,468 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+};
+
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
+
+use crate::{
+    error::{Error, Result},
+    helpers::thing_helpers::{
+        create_album_thing, create_artist_thing, create_song_thing, create_user_thing,
+    },
+    models::radio::{
+        RadioBatch, RadioFeedbackKind, RadioFeedbackRequest, RadioQuery, RadioSession,
+        DEFAULT_RADIO_BATCH_SIZE,
+    },
+    services::{
+        recommendation_service::{
+            RecommendationService, SongFeatures, FEATURES, MAX_CANDIDATES, TEMPO_WINDOW,
+        },
+        search_service::LIKE_WEIGHT,
+    },
+};
+
+/// Maximum number of songs returned by a call.
+pub const MAX_RADIO_BATCH_SIZE: u32 = 50;
+
+/// Songs of an album or artist seed anchoring the radio, most popular first.
+const MAX_SEED_SONGS: u32 = 50;
+
+/// Played songs remembered by a session; older ones may be played again.
+const MAX_PLAYED: usize = 1000;
+
+/// Songs by one artist in a batch; a song counts for each of its artists.
+const MAX_SONGS_PER_ARTIST: usize = 2;
+
+/// Share of the similarity to the closest skipped song taken off a score.
+const SKIP_PENALTY: f32 = 0.5;
+
+/// Idle time after which a session expires, as a SurrealQL duration.
+const SESSION_TTL: &str = "1d";
+
+pub struct RadioService;
+
+impl RadioService {
+    /// Next songs of the radio of `query.token`, or of a new radio seeded from
+    /// `query.seed`. Songs are ranked by their similarity to the closest
+    /// anchor, the songs of the seed and those liked during the session,
+    /// minus [`SKIP_PENALTY`] times their similarity to the closest skipped
+    /// song. Once the related songs are exhausted, the most popular ones keep
+    /// the radio going; once every song was played, the older half of them
+    /// may be played again.
+    pub async fn get_next_batch(
+        db: &Surreal<Any>,
+        user_id: &str,
+        query: &RadioQuery,
+    ) -> Result<RadioBatch> {
+        let mut session = match (&query.seed, &query.token) {
+            (Some(seed), None) => Self::start(db, user_id, seed).await?,
+            (None, Some(token)) => Self::session(db, user_id, token).await?,
+            _ => {
+                return Err(Error::InvalidInput {
+                    reason: "Expected either a seed or a token".to_string(),
+                })
+            }
+        };
+        let limit = query
+            .limit
+            .unwrap_or(DEFAULT_RADIO_BATCH_SIZE)
+            .clamp(1, MAX_RADIO_BATCH_SIZE) as usize;
+
+        let mut batch = Self::pick(db, &session, limit).await?;
+        if batch.len() < limit && !session.played.is_empty() {
+            // Tout le catalogue a été joué : la plus ancienne moitié revient
+            let recycled = session.played.len().div_ceil(2);
+            session.played.drain(..recycled);
+            let kept = session.played.len();
+            session.played.extend(batch.iter().cloned());
+            let refill = Self::pick(db, &session, limit - batch.len()).await?;
+            session.played.truncate(kept);
+            batch.extend(refill);
+        }
+
+        let mut played = session.played;
+        played.extend(batch.iter().cloned());
+        let overflow = played.len().saturating_sub(MAX_PLAYED);
+        played.drain(..overflow);
+        db.query("UPDATE $radio SET played = $played, updated_at = time::now();")
+            .bind(("radio", session.id.clone()))
+            .bind(("played", played))
+            .await?
+            .check()?;
+
+        Ok(RadioBatch {
+            token: session.id.id.to_raw(),
+            seed: session.seed,
+            songs: RecommendationService::songs_by_ids(db, batch).await?,
+        })
+    }
+
+    /// Records a skip or a like of a song the radio played, which steers its
+    /// next batches. A like cancels an earlier skip of the song and the other
+    /// way round.
+    pub async fn give_feedback(
+        db: &Surreal<Any>,
+        user_id: &str,
+        request: RadioFeedbackRequest,
+    ) -> Result<()> {
+        let session = Self::session(db, user_id, &request.token).await?;
+        let song = create_song_thing(&request.song_id);
+        if !session.played.contains(&song) {
+            return Err(Error::InvalidInput {
+                reason: format!("Song '{}' was not played by this radio", request.song_id),
+            });
+        }
+
+        let (mut skipped, mut liked) = (session.skipped, session.liked);
+        skipped.retain(|other| other != &song);
+        liked.retain(|other| other != &song);
+        match request.feedback {
+            RadioFeedbackKind::Skip => skipped.push(song),
+            RadioFeedbackKind::Like => liked.push(song),
+        }
+
+        db.query("UPDATE $radio SET skipped = $skipped, liked = $liked, updated_at = time::now();")
+            .bind(("radio", session.id))
+            .bind(("skipped", skipped))
+            .bind(("liked", liked))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    /// Creates the session of a radio seeded from `song:<id>`, `album:<id>` or
+    /// `artist:<id>`, and drops the expired ones.
+    async fn start(db: &Surreal<Any>, user_id: &str, seed: &str) -> Result<RadioSession> {
+        let (seed, songs, not_found) = match seed.split_once(':') {
+            Some(("song", id)) if !id.is_empty() => (
+                create_song_thing(id),
+                "[$seed]",
+                Error::SongNotFound { id: id.to_string() },
+            ),
+            Some(("album", id)) if !id.is_empty() => (
+                create_album_thing(id),
+                "$seed->album_contains_song->song",
+                Error::AlbumNotFound { id: id.to_string() },
+            ),
+            Some(("artist", id)) if !id.is_empty() => (
+                create_artist_thing(id),
+                "$seed->artist_performs_song->song",
+                Error::ArtistNotFound { id: id.to_string() },
+            ),
+            _ => {
+                return Err(Error::InvalidInput {
+                    reason: format!(
+                        "Invalid seed '{}', expected song:<id>, album:<id> or artist:<id>",
+                        seed
+                    ),
+                })
+            }
+        };
+
+        let sql = format!(
+            "
+            SELECT VALUE id FROM $seed;
+            SELECT VALUE id FROM (
+                SELECT id, (total_listens OR 0) + $like_weight * (total_likes OR 0) AS popularity
+                FROM {songs} ORDER BY popularity DESC, id ASC LIMIT $max_seed_songs
+            );
+            "
+        );
+        let mut res = db
+            .query(sql)
+            .bind(("seed", seed.clone()))
+            .bind(("like_weight", LIKE_WEIGHT))
+            .bind(("max_seed_songs", MAX_SEED_SONGS))
+            .await?;
+        let found: Vec<Thing> = res.take(0)?;
+        if found.is_empty() {
+            return Err(not_found);
+        }
+        let seed_songs: Vec<Thing> = res.take(1)?;
+
+        let sql = format!(
+            "
+            DELETE radio_session WHERE updated_at < time::now() - {SESSION_TTL};
+            CREATE ONLY radio_session SET
+                user = $user,
+                seed = $seed,
+                seed_songs = $seed_songs,
+                played = [],
+                skipped = [],
+                liked = [],
+                created_at = time::now(),
+                updated_at = time::now();
+            "
+        );
+        let mut res = db
+            .query(sql)
+            .bind(("user", create_user_thing(user_id)))
+            .bind(("seed", seed))
+            .bind(("seed_songs", seed_songs))
+            .await?;
+        let session: Option<RadioSession> = res.take(1)?;
+
+        session.ok_or_else(|| Error::DbError("Radio session was not created".to_string()))
+    }
+
+    /// Running session of `token`, if it belongs to the user.
+    async fn session(db: &Surreal<Any>, user_id: &str, token: &str) -> Result<RadioSession> {
+        let sql = format!(
+            "SELECT * FROM $radio WHERE user = $user AND updated_at > time::now() - {SESSION_TTL};"
+        );
+        let mut res = db
+            .query(sql)
+            .bind((
+                "radio",
+                Thing::from(("radio_session".to_string(), token.to_string())),
+            ))
+            .bind(("user", create_user_thing(user_id)))
+            .await?;
+        let session: Option<RadioSession> = res.take(0)?;
+
+        session.ok_or_else(|| Error::RadioSessionNotFound {
+            token: token.to_string(),
+        })
+    }
+
+    /// Up to `limit` songs the session did not play, best first.
+    async fn pick(db: &Surreal<Any>, session: &RadioSession, limit: usize) -> Result<Vec<Thing>> {
+        let mut anchors = session.seed_songs.clone();
+        anchors.extend(session.liked.iter().cloned());
+
+        let mut res = db
+            .query(format!(
+                "SELECT {FEATURES} FROM $anchors; SELECT {FEATURES} FROM $skipped;"
+            ))
+            .bind(("anchors", anchors))
+            .bind(("skipped", session.skipped.clone()))
+            .bind(("like_weight", LIKE_WEIGHT))
+            .await?;
+        let anchors: Vec<SongFeatures> = res.take(0)?;
+        let skipped: Vec<SongFeatures> = res.take(1)?;
+
+        let mut artists: Vec<Thing> = anchors
+            .iter()
+            .flat_map(|song| song.artists.iter().cloned())
+            .collect();
+        artists.sort_unstable_by_key(Thing::to_string);
+        artists.dedup();
+        let genres: Vec<String> = anchors
+            .iter()
+            .flat_map(|song| song.genres.iter().cloned())
+            .collect::<HashSet<_>>()
+            .into_iter()
+            .collect();
+        let tempos: Vec<f32> = anchors
+            .iter()
+            .map(|song| song.tempo)
+            .filter(|tempo| *tempo > 0.0)
+            .collect();
+        let tempo = (!tempos.is_empty()).then(|| {
+            (
+                tempos.iter().copied().fold(f32::MAX, f32::min) - TEMPO_WINDOW,
+                tempos.iter().copied().fold(f32::MIN, f32::max) + TEMPO_WINDOW,
+            )
+        });
+
+        let candidates = RecommendationService::candidate_features(
+            db,
+            artists,
+            genres,
+            tempo,
+            session.played.clone(),
+            MAX_CANDIDATES,
+        )
+        .await?;
+
+        let closest = |songs: &[SongFeatures], candidate: &SongFeatures| {
+            songs
+                .iter()
+                .map(|song| song.similarity(candidate))
+                .fold(0.0, f32::max)
+        };
+        let mut ranked: Vec<(f32, SongFeatures)> = candidates
+            .into_iter()
+            .map(|candidate| {
+                let score =
+                    closest(&anchors, &candidate) - SKIP_PENALTY * closest(&skipped, &candidate);
+                (score, candidate)
+            })
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+        ranked.sort_by(|(score_a, a), (score_b, b)| {
+            score_b
+                .partial_cmp(score_a)
+                .unwrap_or(Ordering::Equal)
+                .then(b.popularity.cmp(&a.popularity))
+                .then_with(|| a.id.to_raw().cmp(&b.id.to_raw()))
+        });
+
+        let mut batch: Vec<Thing> = Vec::with_capacity(limit);
+        let mut per_artist: HashMap<String, usize> = HashMap::new();
+        for (_, song) in ranked {
+            if batch.len() == limit {
+                break;
+            }
+            let artists: HashSet<String> = song.artists.iter().map(Thing::to_string).collect();
+            if artists
+                .iter()
+                .any(|artist| per_artist.get(artist).copied().unwrap_or(0) >= MAX_SONGS_PER_ARTIST)
+            {
+                continue;
+            }
+            for artist in artists {
+                *per_artist.entry(artist).or_default() += 1;
+            }
+            batch.push(song.id);
+        }
+
+        if batch.len() < limit {
+            // Les titres liés sont épuisés, les plus écoutés prennent le relais
+            let mut excluded = session.played.clone();
+            excluded.extend(batch.iter().cloned());
+            let sql = "
+                SELECT VALUE id FROM (
+                    SELECT id, (total_listens OR 0) + $like_weight * (total_likes OR 0) AS popularity
+                    FROM song WHERE id NOTINSIDE $excluded
+                    ORDER BY popularity DESC, id ASC LIMIT $limit
+                );
+            ";
+            let mut res = db
+                .query(sql)
+                .bind(("excluded", excluded))
+                .bind(("like_weight", LIKE_WEIGHT))
+                .bind(("limit", (limit - batch.len()) as u32))
+                .await?;
+            let popular: Vec<Thing> = res.take(0)?;
+            batch.extend(popular);
+        }
+
+        Ok(batch)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::engine::any::connect;
+
+    fn ids(batch: &RadioBatch) -> Vec<String> {
+        batch
+            .songs
+            .iter()
+            .map(|song| song.id.as_ref().unwrap().id.to_raw())
+            .collect()
+    }
+
+    async fn next(db: &Surreal<Any>, token: &str, limit: u32) -> RadioBatch {
+        let query = RadioQuery {
+            seed: None,
+            token: Some(token.to_string()),
+            limit: Some(limit),
+        };
+        RadioService::get_next_batch(db, "u1", &query)
+            .await
+            .unwrap()
+    }
+
+    #[tokio::test]
+    async fn test_radio_continues_without_repeats_and_follows_feedback() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(
+            r#"
+            CREATE artist:clash SET name = 'The Clash', genres = [], country_code = 'GB', albums_count = 1, songs_count = 3;
+            CREATE artist:jam SET name = 'The Jam', genres = [], country_code = 'GB', albums_count = 1, songs_count = 1;
+            CREATE artist:abba SET name = 'ABBA', genres = [], country_code = 'SE', albums_count = 1, songs_count = 1;
+            CREATE artist:brel SET name = 'Jacques Brel', genres = [], country_code = 'BE', albums_count = 1, songs_count = 1;
+            CREATE album:london SET title = 'London Calling', genres = ['Punk'], langs = ['en'], total_tracks = 3, total_duration = 9m;
+            CREATE album:setting SET title = 'Setting Sons', genres = ['punk'], langs = ['en'], total_tracks = 1, total_duration = 3m;
+            CREATE album:arrival SET title = 'Arrival', genres = ['Pop'], langs = ['en'], total_tracks = 1, total_duration = 3m;
+            CREATE album:ballades SET title = 'Ballades', genres = ['Chanson'], langs = ['fr'], total_tracks = 1, total_duration = 3m;
+            FOR $song IN [['c1', 'clash', 'london', 30], ['c2', 'clash', 'london', 20], ['c3', 'clash', 'london', 10],
+                ['j1', 'jam', 'setting', 5], ['p1', 'abba', 'arrival', 1], ['b1', 'brel', 'ballades', 0]] {
+                CREATE type::thing('song', $song[0]) SET title = $song[0], file_url = 'f.mp3', duration = 3m,
+                    song_index = 1, tempo = IF $song[1] = 'brel' { 90.0 } ELSE { 150.0 }, total_listens = $song[3];
+                RELATE (type::thing('artist', $song[1]))->artist_performs_song->(type::thing('song', $song[0]));
+                RELATE (type::thing('album', $song[2]))->album_contains_song->(type::thing('song', $song[0]));
+            };
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        // Deux titres des Clash par lot au plus, puis les plus proches
+        let query = RadioQuery {
+            seed: Some("artist:clash".to_string()),
+            token: None,
+            limit: Some(3),
+        };
+        let batch = RadioService::get_next_batch(&db, "u1", &query)
+            .await
+            .unwrap();
+        assert_eq!(ids(&batch), vec!["c1", "c2", "j1"]);
+        assert_eq!(batch.seed.to_string(), "artist:clash");
+
+        // Sans titre lié restant, les plus écoutés prennent le relais
+        let token = batch.token.clone();
+        assert_eq!(ids(&next(&db, &token, 3).await), vec!["c3", "p1", "b1"]);
+
+        // Tout a été joué : les plus anciens titres reviennent
+        assert_eq!(ids(&next(&db, &token, 3).await), vec!["c1", "c2", "j1"]);
+        assert_eq!(ids(&next(&db, &token, 2).await), vec!["c3", "p1"]);
+
+        // Passer un titre des Clash éloigne des suivants
+        let query = RadioQuery {
+            seed: Some("song:j1".to_string()),
+            token: None,
+            limit: Some(2),
+        };
+        let batch = RadioService::get_next_batch(&db, "u1", &query)
+            .await
+            .unwrap();
+        assert_eq!(ids(&batch), vec!["j1", "c1"]);
+        let feedback = |song_id: &str, feedback| RadioFeedbackRequest {
+            token: batch.token.clone(),
+            song_id: song_id.to_string(),
+            feedback,
+        };
+        RadioService::give_feedback(&db, "u1", feedback("c1", RadioFeedbackKind::Skip))
+            .await
+            .unwrap();
+        assert_eq!(ids(&next(&db, &batch.token, 1).await), vec!["p1"]);
+
+        // Un « j'aime » annule le passage
+        RadioService::give_feedback(&db, "u1", feedback("c1", RadioFeedbackKind::Like))
+            .await
+            .unwrap();
+        assert_eq!(ids(&next(&db, &batch.token, 1).await), vec!["c2"]);
+
+        assert!(matches!(
+            RadioService::give_feedback(&db, "u1", feedback("b1", RadioFeedbackKind::Like)).await,
+            Err(Error::InvalidInput { .. })
+        ));
+        let query = RadioQuery {
+            seed: None,
+            token: Some(batch.token.clone()),
+            limit: None,
+        };
+        assert!(matches!(
+            RadioService::get_next_batch(&db, "u2", &query).await,
+            Err(Error::RadioSessionNotFound { .. })
+        ));
+        let query = RadioQuery {
+            seed: Some("album:missing".to_string()),
+            token: None,
+            limit: None,
+        };
+        assert!(matches!(
+            RadioService::get_next_batch(&db, "u1", &query).await,
+            Err(Error::AlbumNotFound { .. })
+        ));
+    }
+}
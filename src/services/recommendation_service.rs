@@ -0,0 +1,850 @@
+use std::{
+    cmp::Ordering,
+    collections::{HashMap, HashSet},
+    hash::Hash,
+};
+
+use serde::{Deserialize, Serialize};
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
+
+use crate::{
+    error::{Error, Result},
+    helpers::thing_helpers::{create_song_thing, create_user_thing},
+    models::{
+        album::AlbumWithArtists,
+        recommendation::{CooccurrenceReport, ScoredAlbum, ScoredSong},
+        search::normalize_genre,
+        song::SongWithRelations,
+    },
+    services::search_service::LIKE_WEIGHT,
+};
+
+/// Maximum number of songs or albums returned by a recommendation.
+pub const MAX_RECOMMENDATION_LIMIT: u32 = 50;
+
+/// Tempo gap, in BPM, beyond which two songs are not considered close.
+pub(crate) const TEMPO_WINDOW: f32 = 20.0;
+
+// Poids des critères de similarité, dont la somme vaut 1
+const ARTIST_WEIGHT: f32 = 0.35;
+const GENRE_WEIGHT: f32 = 0.3;
+const TEMPO_WEIGHT: f32 = 0.2;
+const LANG_WEIGHT: f32 = 0.15;
+
+/// Most listened albums sharing a genre whose songs are candidates.
+const MAX_GENRE_ALBUMS: usize = 100;
+
+/// Songs within the tempo window taken as candidates.
+const MAX_TEMPO_SONGS: usize = 200;
+
+/// Candidates compared to the song, most popular first.
+pub(crate) const MAX_CANDIDATES: usize = 500;
+
+/// Most recent listened songs per user fed to the co-occurrence model, which
+/// bounds the number of pairs of the heaviest listeners. Liked songs are all
+/// kept on top of them.
+const MAX_SONGS_PER_USER: usize = 500;
+
+/// Pairs known by fewer users are left out as noise.
+const MIN_SHARED_USERS: u32 = 2;
+
+/// Neighbours kept per song.
+const MAX_NEIGHBOURS: usize = 50;
+
+/// Most recent listens used as seeds of the personal recommendations, on top
+/// of the liked songs.
+const MAX_SEEDS: usize = 200;
+
+pub(crate) const SONG_RELATIONS: &str = "
+    (SELECT * FROM <-artist_performs_song<-artist) AS artists,
+    (SELECT * FROM <-album_contains_song<-album)[0] AS album
+";
+
+pub(crate) const ALBUM_RELATIONS: &str = "<-artist_creates_album<-artist.* AS artists";
+
+/// Songs known by `$user`, listened or liked, and the seeds of their
+/// recommendations.
+const HISTORY: &str = "
+    LET $listened = SELECT out, last_listened_at FROM user_listens_song
+        WHERE in = $user ORDER BY last_listened_at DESC;
+    LET $liked = SELECT VALUE out FROM user_likes_song WHERE in = $user;
+    LET $known = array::union($listened.out, $liked);
+    LET $seeds = array::union(array::slice($listened.out, 0, $max_seeds), $liked);
+";
+
+/// Tempo, performing artists, and genres and languages of the album of a song,
+/// lowercased. Needs `$like_weight` for the popularity.
+pub(crate) const FEATURES: &str = "
+    id,
+    tempo,
+    <-artist_performs_song.in AS artists,
+    array::map(array::flatten(<-album_contains_song<-album.genres), |$genre| string::lowercase($genre)) AS genres,
+    array::map(array::flatten(<-album_contains_song<-album.langs), |$lang| string::lowercase($lang)) AS langs,
+    (total_listens OR 0) + $like_weight * (total_likes OR 0) AS popularity
+";
+
+#[derive(Debug, Deserialize)]
+pub(crate) struct SongFeatures {
+    pub(crate) id: Thing,
+    pub(crate) tempo: f32,
+    pub(crate) artists: Vec<Thing>,
+    pub(crate) genres: Vec<String>,
+    pub(crate) langs: Vec<String>,
+    pub(crate) popularity: u64,
+}
+
+impl SongFeatures {
+    /// Album genres, normalized as in the search filters.
+    pub(crate) fn genre_set(&self) -> HashSet<String> {
+        self.genres
+            .iter()
+            .map(|genre| normalize_genre(genre))
+            .collect()
+    }
+
+    /// Weighted sum of the shared artists, genres and languages, each as a
+    /// Jaccard index, and of the tempo proximity.
+    pub(crate) fn similarity(&self, other: &SongFeatures) -> f32 {
+        let tempo = if self.tempo > 0.0 && other.tempo > 0.0 {
+            (1.0 - (self.tempo - other.tempo).abs() / TEMPO_WINDOW).max(0.0)
+        } else {
+            0.0
+        };
+
+        ARTIST_WEIGHT * jaccard(&self.artists, &other.artists)
+            + GENRE_WEIGHT * jaccard_sets(&self.genre_set(), &other.genre_set())
+            + TEMPO_WEIGHT * tempo
+            + LANG_WEIGHT * jaccard(&self.langs, &other.langs)
+    }
+}
+
+#[derive(Debug, Deserialize)]
+struct Interaction {
+    user: Thing,
+    song: Thing,
+}
+
+#[derive(Debug, Serialize)]
+struct CooccurrenceRow {
+    r#in: Thing,
+    out: Thing,
+    score: f64,
+    shared_users: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct Neighbour {
+    item: Thing,
+    score: f64,
+}
+
+fn jaccard<T: Eq + Hash + Clone>(a: &[T], b: &[T]) -> f32 {
+    let a: HashSet<T> = a.iter().cloned().collect();
+    let b: HashSet<T> = b.iter().cloned().collect();
+    jaccard_sets(&a, &b)
+}
+
+fn jaccard_sets<T: Eq + Hash>(a: &HashSet<T>, b: &HashSet<T>) -> f32 {
+    let union = a.union(b).count();
+    if union == 0 {
+        return 0.0;
+    }
+    a.intersection(b).count() as f32 / union as f32
+}
+
+pub struct RecommendationService;
+
+impl RecommendationService {
+    /// Songs closest to `song_id`, best first. Candidates share an artist or
+    /// an album genre with the song, or have a tempo within [`TEMPO_WINDOW`]
+    /// of it (see [`Self::candidate_features`]); the language only refines
+    /// their score. Equal scores are ranked by popularity.
+    pub async fn get_similar_songs(
+        db: &Surreal<Any>,
+        song_id: &str,
+        limit: u32,
+    ) -> Result<Vec<ScoredSong>> {
+        let song_thing = create_song_thing(song_id);
+
+        let mut res = db
+            .query(format!("SELECT {FEATURES} FROM $song;"))
+            .bind(("song", song_thing.clone()))
+            .bind(("like_weight", LIKE_WEIGHT))
+            .await?;
+        let seed: Option<SongFeatures> = res.take(0)?;
+        let seed = seed.ok_or_else(|| Error::SongNotFound {
+            id: song_id.to_string(),
+        })?;
+
+        let tempo =
+            (seed.tempo > 0.0).then_some((seed.tempo - TEMPO_WINDOW, seed.tempo + TEMPO_WINDOW));
+        let candidates = Self::candidate_features(
+            db,
+            seed.artists.clone(),
+            seed.genres.clone(),
+            tempo,
+            vec![song_thing],
+            MAX_CANDIDATES,
+        )
+        .await?;
+
+        let mut ranked: Vec<(f32, SongFeatures)> = candidates
+            .into_iter()
+            .map(|candidate| (seed.similarity(&candidate), candidate))
+            .filter(|(score, _)| *score > 0.0)
+            .collect();
+        ranked.sort_by(|(score_a, a), (score_b, b)| {
+            score_b
+                .partial_cmp(score_a)
+                .unwrap_or(Ordering::Equal)
+                .then(b.popularity.cmp(&a.popularity))
+                .then_with(|| a.id.to_raw().cmp(&b.id.to_raw()))
+        });
+        ranked.truncate(limit.clamp(1, MAX_RECOMMENDATION_LIMIT) as usize);
+
+        let scores: Vec<(Thing, f32)> = ranked
+            .into_iter()
+            .map(|(score, candidate)| (candidate.id, score))
+            .collect();
+        let ids = scores.iter().map(|(id, _)| id.clone()).collect();
+
+        let songs = Self::songs_by_ids(db, ids).await?;
+
+        Ok(with_scores(songs, &scores, |song| song.id.as_ref())
+            .into_iter()
+            .map(|(song, score)| ScoredSong { song, score })
+            .collect())
+    }
+
+    /// Features of up to `limit` songs outside `exclude`, most popular first,
+    /// taken from bounded lookups rather than a scan of every song: the songs
+    /// of `artists`, those of the [`MAX_GENRE_ALBUMS`] most listened albums
+    /// with one of `genres` (lowercased), and [`MAX_TEMPO_SONGS`] songs whose
+    /// tempo lies in the `tempo` range.
+    pub(crate) async fn candidate_features(
+        db: &Surreal<Any>,
+        artists: Vec<Thing>,
+        genres: Vec<String>,
+        tempo: Option<(f32, f32)>,
+        exclude: Vec<Thing>,
+        limit: usize,
+    ) -> Result<Vec<SongFeatures>> {
+        // Sans tempo, un intervalle vide n'admet aucun morceau
+        let (min_tempo, max_tempo) = tempo.unwrap_or((1.0, 0.0));
+
+        let sql = format!(
+            "
+            LET $by_artist = array::flatten(SELECT VALUE ->artist_performs_song.out FROM $artists);
+            LET $albums = SELECT VALUE id FROM (
+                SELECT id, total_listens FROM album
+                    WHERE array::map(genres, |$genre| string::lowercase($genre)) CONTAINSANY $genres
+                    ORDER BY total_listens DESC LIMIT $max_genre_albums
+            );
+            LET $by_genre = array::flatten(SELECT VALUE ->album_contains_song.out FROM $albums);
+            LET $by_tempo = SELECT VALUE id FROM song
+                WHERE tempo > 0 AND tempo >= $min_tempo AND tempo <= $max_tempo
+                LIMIT $max_tempo_songs;
+            LET $ids = array::complement(array::union(array::union($by_artist, $by_genre), $by_tempo), $exclude);
+            LET $kept = SELECT VALUE id FROM (
+                SELECT id, (total_listens OR 0) + $like_weight * (total_likes OR 0) AS popularity
+                FROM $ids ORDER BY popularity DESC, id ASC LIMIT $limit
+            );
+            SELECT {FEATURES} FROM $kept;
+            "
+        );
+        let mut res = db
+            .query(sql)
+            .bind(("artists", artists))
+            .bind(("genres", genres))
+            .bind(("min_tempo", min_tempo))
+            .bind(("max_tempo", max_tempo))
+            .bind(("exclude", exclude))
+            .bind(("like_weight", LIKE_WEIGHT))
+            .bind(("max_genre_albums", MAX_GENRE_ALBUMS))
+            .bind(("max_tempo_songs", MAX_TEMPO_SONGS))
+            .bind(("limit", limit))
+            .await?;
+
+        Ok(res.take(6)?)
+    }
+
+    /// Rebuilds `song_cooccurrence` from the listens and likes of every user:
+    /// two songs are neighbours when at least [`MIN_SHARED_USERS`] users know
+    /// both, scored by cosine similarity. The table is replaced in a single
+    /// transaction.
+    pub async fn rebuild_cooccurrence(db: &Surreal<Any>) -> Result<CooccurrenceReport> {
+        let sql = "
+            SELECT in AS user, out AS song, last_listened_at FROM user_listens_song
+                ORDER BY last_listened_at DESC;
+            SELECT in AS user, out AS song FROM user_likes_song;
+        ";
+        let mut res = db.query(sql).await?;
+        let listens: Vec<Interaction> = res.take(0)?;
+        let likes: Vec<Interaction> = res.take(1)?;
+
+        // Clés en texte : un `Thing` n'est pas une clé de hachage sûre
+        let mut songs: Vec<Thing> = Vec::new();
+        let mut song_indexes: HashMap<String, usize> = HashMap::new();
+        let mut histories: HashMap<String, Vec<usize>> = HashMap::new();
+        let mut listened: HashMap<String, usize> = HashMap::new();
+        // Les « j'aime », signal le plus fort, passent avant le plafond des écoutes
+        let likes = likes.into_iter().map(|like| (like, false));
+        for (Interaction { user, song }, is_listen) in
+            likes.chain(listens.into_iter().map(|listen| (listen, true)))
+        {
+            let user = user.to_string();
+            if is_listen {
+                let count = listened.entry(user.clone()).or_default();
+                if *count >= MAX_SONGS_PER_USER {
+                    continue;
+                }
+                *count += 1;
+            }
+            let index = *song_indexes.entry(song.to_string()).or_insert_with(|| {
+                songs.push(song);
+                songs.len() - 1
+            });
+            histories.entry(user).or_default().push(index);
+        }
+
+        let mut listeners = vec![0u32; songs.len()];
+        let mut shared: HashMap<(usize, usize), u32> = HashMap::new();
+        let mut users = 0;
+        for history in histories.values_mut() {
+            // Un morceau écouté et aimé ne compte qu'une fois
+            history.sort_unstable();
+            history.dedup();
+            if history.len() >= 2 {
+                users += 1;
+            }
+            for (position, &a) in history.iter().enumerate() {
+                listeners[a] += 1;
+                for &b in &history[position + 1..] {
+                    *shared.entry((a, b)).or_default() += 1;
+                }
+            }
+        }
+
+        let mut neighbours: Vec<Vec<(f64, usize, u32)>> = vec![Vec::new(); songs.len()];
+        for ((a, b), count) in shared {
+            if count < MIN_SHARED_USERS {
+                continue;
+            }
+            let score = count as f64 / (listeners[a] as f64 * listeners[b] as f64).sqrt();
+            neighbours[a].push((score, b, count));
+            neighbours[b].push((score, a, count));
+        }
+
+        let mut rows = Vec::new();
+        for (a, list) in neighbours.iter_mut().enumerate() {
+            list.sort_by(|x, y| {
+                y.0.partial_cmp(&x.0)
+                    .unwrap_or(Ordering::Equal)
+                    .then(x.1.cmp(&y.1))
+            });
+            list.truncate(MAX_NEIGHBOURS);
+            rows.extend(
+                list.iter()
+                    .map(|&(score, b, shared_users)| CooccurrenceRow {
+                        r#in: songs[a].clone(),
+                        out: songs[b].clone(),
+                        score,
+                        shared_users,
+                    }),
+            );
+        }
+
+        let report = CooccurrenceReport {
+            users,
+            songs: neighbours.iter().filter(|list| !list.is_empty()).count(),
+            neighbours: rows.len(),
+        };
+
+        // Les lecteurs voient l'ancienne table ou la nouvelle, jamais un mélange
+        let sql = "
+            BEGIN TRANSACTION;
+            DELETE song_cooccurrence;
+            FOR $row IN $rows {
+                RELATE ($row.in)->song_cooccurrence->($row.out) SET
+                    score = $row.score,
+                    shared_users = $row.shared_users,
+                    computed_at = time::now();
+            };
+            COMMIT TRANSACTION;
+        ";
+        db.query(sql).bind(("rows", rows)).await?.check()?;
+
+        Ok(report)
+    }
+
+    /// Songs the user does not know yet, ranked by their summed co-occurrence
+    /// with the user's liked and recently listened songs. The list is filled
+    /// up with the most popular unknown songs, scored 0, which is all a user
+    /// without history gets.
+    pub async fn recommend_songs(
+        db: &Surreal<Any>,
+        user_id: &str,
+        limit: u32,
+    ) -> Result<Vec<ScoredSong>> {
+        let limit = limit.clamp(1, MAX_RECOMMENDATION_LIMIT);
+
+        let (scores, mut exclude) = Self::cooccurrence_scores(db, user_id, limit).await?;
+        let ids: Vec<Thing> = scores.iter().map(|(id, _)| id.clone()).collect();
+        exclude.extend(ids.iter().cloned());
+
+        let songs = Self::songs_by_ids(db, ids).await?;
+        let mut recommended: Vec<ScoredSong> =
+            with_scores(songs, &scores, |song| song.id.as_ref())
+                .into_iter()
+                .map(|(song, score)| ScoredSong { song, score })
+                .collect();
+
+        let missing = limit as usize - recommended.len();
+        if missing > 0 {
+            let sql = format!(
+                "
+                SELECT *, {SONG_RELATIONS},
+                    (total_listens OR 0) + $like_weight * (total_likes OR 0) AS popularity
+                FROM song WHERE id NOTINSIDE $exclude
+                ORDER BY popularity DESC, id ASC LIMIT $missing;
+                "
+            );
+            let mut res = db
+                .query(sql)
+                .bind(("exclude", exclude))
+                .bind(("like_weight", LIKE_WEIGHT))
+                .bind(("missing", missing))
+                .await?;
+            let popular: Vec<SongWithRelations> = res.take(0)?;
+            recommended.extend(
+                popular
+                    .into_iter()
+                    .map(|song| ScoredSong { song, score: 0.0 }),
+            );
+        }
+
+        Ok(recommended)
+    }
+
+    /// Up to `limit` songs unknown to the user, with their summed
+    /// co-occurrence with the user's seeds, best first, and the songs the user
+    /// knows.
+    pub(crate) async fn cooccurrence_scores(
+        db: &Surreal<Any>,
+        user_id: &str,
+        limit: u32,
+    ) -> Result<(Vec<(Thing, f32)>, Vec<Thing>)> {
+        let sql = format!(
+            "
+            {HISTORY}
+            SELECT * FROM (
+                SELECT out AS item, math::sum(score) AS score FROM song_cooccurrence
+                    WHERE in INSIDE $seeds AND out NOTINSIDE $known
+                    GROUP BY item
+            ) ORDER BY score DESC, item ASC LIMIT $limit;
+            $known;
+            "
+        );
+        let mut res = db
+            .query(sql)
+            .bind(("user", create_user_thing(user_id)))
+            .bind(("max_seeds", MAX_SEEDS))
+            .bind(("limit", limit))
+            .await?;
+        let neighbours: Vec<Neighbour> = res.take(4)?;
+        let known: Vec<Thing> = res.take(5)?;
+
+        Ok((
+            neighbours
+                .into_iter()
+                .map(|neighbour| (neighbour.item, neighbour.score as f32))
+                .collect(),
+            known,
+        ))
+    }
+
+    /// Albums the user does not know yet, none of whose songs were listened or
+    /// liked, ranked like [`Self::recommend_songs`] by summing the scores of
+    /// their songs, then filled up with the most popular unknown albums.
+    pub async fn recommend_albums(
+        db: &Surreal<Any>,
+        user_id: &str,
+        limit: u32,
+    ) -> Result<Vec<ScoredAlbum>> {
+        let limit = limit.clamp(1, MAX_RECOMMENDATION_LIMIT);
+
+        let sql = format!(
+            "
+            {HISTORY}
+            LET $known_albums = array::union(
+                array::flatten((SELECT VALUE <-album_contains_song.in FROM $known)),
+                array::union(
+                    (SELECT VALUE out FROM user_likes_album WHERE in = $user),
+                    (SELECT VALUE out FROM user_listens_album WHERE in = $user)
+                )
+            );
+            SELECT * FROM (
+                SELECT item, math::sum(score) AS score FROM (
+                    SELECT (out<-album_contains_song<-album)[0] AS item, score FROM song_cooccurrence
+                        WHERE in INSIDE $seeds AND out NOTINSIDE $known
+                ) WHERE item != NONE AND item NOTINSIDE $known_albums
+                GROUP BY item
+            ) ORDER BY score DESC, item ASC LIMIT $limit;
+            $known_albums;
+            "
+        );
+        let mut res = db
+            .query(sql)
+            .bind(("user", create_user_thing(user_id)))
+            .bind(("max_seeds", MAX_SEEDS))
+            .bind(("limit", limit))
+            .await?;
+        let neighbours: Vec<Neighbour> = res.take(5)?;
+        let mut exclude: Vec<Thing> = res.take(6)?;
+
+        let scores: Vec<(Thing, f32)> = neighbours
+            .into_iter()
+            .map(|neighbour| (neighbour.item, neighbour.score as f32))
+            .collect();
+        let ids: Vec<Thing> = scores.iter().map(|(id, _)| id.clone()).collect();
+        exclude.extend(ids.iter().cloned());
+
+        let mut res = db
+            .query(format!("SELECT *, {ALBUM_RELATIONS} FROM $ids;"))
+            .bind(("ids", ids))
+            .await?;
+        let albums: Vec<AlbumWithArtists> = res.take(0)?;
+        let mut recommended: Vec<ScoredAlbum> =
+            with_scores(albums, &scores, |album| album.id.as_ref())
+                .into_iter()
+                .map(|(album, score)| ScoredAlbum { album, score })
+                .collect();
+
+        let missing = limit as usize - recommended.len();
+        if missing > 0 {
+            let sql = format!(
+                "
+                SELECT *, {ALBUM_RELATIONS},
+                    (total_listens OR 0) + $like_weight * (total_likes OR 0) AS popularity
+                FROM album WHERE id NOTINSIDE $exclude
+                ORDER BY popularity DESC, id ASC LIMIT $missing;
+                "
+            );
+            let mut res = db
+                .query(sql)
+                .bind(("exclude", exclude))
+                .bind(("like_weight", LIKE_WEIGHT))
+                .bind(("missing", missing))
+                .await?;
+            let popular: Vec<AlbumWithArtists> = res.take(0)?;
+            recommended.extend(
+                popular
+                    .into_iter()
+                    .map(|album| ScoredAlbum { album, score: 0.0 }),
+            );
+        }
+
+        Ok(recommended)
+    }
+
+    /// Songs with their artists and album, in the order of `ids`. Missing songs
+    /// are left out, so pair the result with anything else by id.
+    pub(crate) async fn songs_by_ids(
+        db: &Surreal<Any>,
+        ids: Vec<Thing>,
+    ) -> Result<Vec<SongWithRelations>> {
+        // Une sélection sur un tableau d'enregistrements conserve son ordre
+        let mut res = db
+            .query(format!("SELECT *, {SONG_RELATIONS} FROM $ids;"))
+            .bind(("ids", ids))
+            .await?;
+        Ok(res.take(0)?)
+    }
+}
+
+/// Pairs each record with the score of its id. Records fetched by id skip
+/// the missing ones, so their positions do not match `scores`.
+fn with_scores<T>(
+    items: Vec<T>,
+    scores: &[(Thing, f32)],
+    id: impl Fn(&T) -> Option<&Thing>,
+) -> Vec<(T, f32)> {
+    let scores: HashMap<String, f32> = scores
+        .iter()
+        .map(|(thing, score)| (thing.to_string(), *score))
+        .collect();
+    items
+        .into_iter()
+        .filter_map(|item| {
+            let score = *scores.get(&id(&item)?.to_string())?;
+            Some((item, score))
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::engine::any::connect;
+
+    #[tokio::test]
+    async fn test_similar_songs_are_ranked_by_shared_features() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(
+            r#"
+            CREATE artist:clash SET name = 'The Clash', genres = [], country_code = 'GB', albums_count = 1, songs_count = 1;
+            CREATE artist:jam SET name = 'The Jam', genres = [], country_code = 'GB', albums_count = 1, songs_count = 1;
+            CREATE album:london SET title = 'London Calling', genres = ['Punk', 'Post-Punk'], langs = ['en'], total_tracks = 2, total_duration = 6m;
+            CREATE album:setting SET title = 'Setting Sons', genres = ['post punk'], langs = ['EN'], total_tracks = 2, total_duration = 6m;
+            CREATE album:tube SET title = 'Ballades', genres = ['chanson'], langs = ['fr'], total_tracks = 2, total_duration = 6m;
+            CREATE song:calling SET title = 'London Calling', file_url = 'a.mp3', duration = 3m, song_index = 1, tempo = 134.0;
+            CREATE song:clampdown SET title = 'Clampdown', file_url = 'b.mp3', duration = 3m, song_index = 2, tempo = 170.0;
+            CREATE song:eton SET title = 'Eton Rifles', file_url = 'c.mp3', duration = 3m, song_index = 1, tempo = 136.0;
+            CREATE song:ballade SET title = 'Ballade', file_url = 'd.mp3', duration = 3m, song_index = 1, tempo = 140.0;
+            CREATE song:slow SET title = 'Slow', file_url = 'e.mp3', duration = 3m, song_index = 2, tempo = 60.0;
+            RELATE artist:clash->artist_performs_song->song:calling;
+            RELATE artist:clash->artist_performs_song->song:clampdown;
+            RELATE artist:jam->artist_performs_song->song:eton;
+            RELATE album:london->album_contains_song->song:calling;
+            RELATE album:london->album_contains_song->song:clampdown;
+            RELATE album:setting->album_contains_song->song:eton;
+            RELATE album:tube->album_contains_song->song:ballade;
+            RELATE album:tube->album_contains_song->song:slow;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let similar = RecommendationService::get_similar_songs(&db, "calling", 10)
+            .await
+            .unwrap();
+        let titles: Vec<&str> = similar.iter().map(|s| s.song.title.as_str()).collect();
+
+        // Même artiste et même album, puis genre et langue partagés au tempo
+        // proche, puis le seul tempo ; « Slow » n'a rien en commun
+        assert_eq!(titles, vec!["Clampdown", "Eton Rifles", "Ballade"]);
+        assert!(similar.windows(2).all(|w| w[0].score >= w[1].score));
+        assert!((similar[0].score - 0.8).abs() < 1e-5);
+        assert_eq!(
+            similar[1]
+                .song
+                .album
+                .as_ref()
+                .map(|album| album.title.as_str()),
+            Some("Setting Sons")
+        );
+        assert_eq!(similar[1].song.artists.as_ref().map(Vec::len), Some(1));
+
+        let similar = RecommendationService::get_similar_songs(&db, "calling", 1)
+            .await
+            .unwrap();
+        assert_eq!(similar.len(), 1);
+
+        assert!(matches!(
+            RecommendationService::get_similar_songs(&db, "missing", 10).await,
+            Err(Error::SongNotFound { .. })
+        ));
+    }
+
+    #[tokio::test]
+    async fn test_candidates_come_from_artists_genres_and_tempo() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(
+            r#"
+            CREATE artist:clash SET name = 'The Clash', genres = [], country_code = 'GB', albums_count = 1, songs_count = 1;
+            CREATE album:london SET title = 'London Calling', genres = ['Punk'], langs = ['en'], total_tracks = 1, total_duration = 3m;
+            CREATE album:tube SET title = 'Ballades', genres = ['chanson'], langs = ['fr'], total_tracks = 3, total_duration = 9m;
+            CREATE song:calling SET title = 'London Calling', file_url = 'a.mp3', duration = 3m, song_index = 1, tempo = 0.0;
+            CREATE song:single SET title = 'Single', file_url = 'b.mp3', duration = 3m, song_index = 1, tempo = 0.0;
+            CREATE song:fast SET title = 'Fast', file_url = 'c.mp3', duration = 3m, song_index = 1, tempo = 130.0, total_listens = 5;
+            CREATE song:slow SET title = 'Slow', file_url = 'd.mp3', duration = 3m, song_index = 2, tempo = 60.0;
+            CREATE song:quiet SET title = 'Quiet', file_url = 'e.mp3', duration = 3m, song_index = 3, tempo = 0.0;
+            RELATE artist:clash->artist_performs_song->song:calling;
+            RELATE artist:clash->artist_performs_song->song:single;
+            RELATE album:london->album_contains_song->song:calling;
+            RELATE album:tube->album_contains_song->song:fast;
+            RELATE album:tube->album_contains_song->song:slow;
+            RELATE album:tube->album_contains_song->song:quiet;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let ids = |songs: Vec<SongFeatures>| -> Vec<String> {
+            songs.into_iter().map(|song| song.id.id.to_raw()).collect()
+        };
+
+        let candidates = RecommendationService::candidate_features(
+            &db,
+            vec![Thing::from(("artist", "clash"))],
+            vec![],
+            Some((120.0, 140.0)),
+            vec![create_song_thing("calling")],
+            MAX_CANDIDATES,
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(candidates), vec!["fast", "single"]);
+
+        // Les genres sont comparés en minuscules, les plus populaires d'abord
+        let candidates = RecommendationService::candidate_features(
+            &db,
+            vec![],
+            vec!["chanson".to_string(), "punk".to_string()],
+            None,
+            vec![],
+            2,
+        )
+        .await
+        .unwrap();
+        assert_eq!(ids(candidates), vec!["fast", "calling"]);
+    }
+
+    #[tokio::test]
+    async fn test_cooccurrence_recommendations_exclude_known_songs() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(include_str!("../../database_recommendations.surql"))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        db.query(
+            r#"
+            FOR $album IN ['x', 'y', 'z'] {
+                CREATE type::thing('album', $album) SET title = $album, genres = [], langs = [],
+                    total_tracks = 2, total_duration = 6m, total_listens = 0;
+            };
+            FOR $song IN [['a', 'x', 5], ['b', 'x', 4], ['c', 'y', 50], ['d', 'z', 10], ['e', 'z', 0]] {
+                CREATE type::thing('song', $song[0]) SET title = $song[0], file_url = 'f.mp3',
+                    duration = 3m, song_index = 1, tempo = 0.0, total_listens = $song[2];
+                RELATE (type::thing('album', $song[1]))->album_contains_song->(type::thing('song', $song[0]));
+            };
+            UPDATE album:z SET total_listens = 10;
+            FOR $listen IN [['u1', 'a'], ['u1', 'b'], ['u1', 'c'], ['u2', 'a'], ['u2', 'b'], ['u2', 'd'],
+                ['u3', 'a'], ['u3', 'c'], ['u4', 'b'], ['u6', 'c']] {
+                RELATE (type::thing('user', $listen[0]))->user_listens_song->(type::thing('song', $listen[1]))
+                    SET total_listens = 1, last_listened_at = time::now();
+            };
+            RELATE user:u3->user_likes_song->song:e;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        // Seules les paires a-b et a-c sont partagées par deux utilisateurs
+        let report = RecommendationService::rebuild_cooccurrence(&db)
+            .await
+            .unwrap();
+        assert_eq!(
+            report,
+            CooccurrenceReport {
+                users: 3,
+                songs: 3,
+                neighbours: 4,
+            }
+        );
+        let report = RecommendationService::rebuild_cooccurrence(&db)
+            .await
+            .unwrap();
+        assert_eq!(report.neighbours, 4);
+        let mut res = db
+            .query("SELECT count() FROM song_cooccurrence GROUP ALL;")
+            .await
+            .unwrap();
+        let stored: Option<u64> = res.take((0, "count")).unwrap();
+        assert_eq!(stored, Some(4));
+
+        let titles = |songs: &[ScoredSong]| {
+            songs
+                .iter()
+                .map(|song| song.song.title.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Un voisin supprimé depuis le calcul ne décale pas les scores
+        db.query(
+            "RELATE song:b->song_cooccurrence->song:ghost SET score = 0.9, shared_users = 2;",
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        // a vient de l'historique, puis les plus écoutés parmi les inconnus
+        let songs = RecommendationService::recommend_songs(&db, "u4", 3)
+            .await
+            .unwrap();
+        assert_eq!(titles(&songs), vec!["a", "c", "d"]);
+        // 2 utilisateurs en commun sur 3 auditeurs de a comme de b
+        assert!((songs[0].score - 2.0 / 3.0).abs() < 1e-5);
+        assert_eq!(songs[1].score, 0.0);
+        assert_eq!(songs[0].song.album.as_ref().unwrap().title, "x");
+
+        let songs = RecommendationService::recommend_songs(&db, "u5", 3)
+            .await
+            .unwrap();
+        assert_eq!(titles(&songs), vec!["c", "d", "a"]);
+        assert!(songs.iter().all(|song| song.score == 0.0));
+
+        let albums = RecommendationService::recommend_albums(&db, "u6", 2)
+            .await
+            .unwrap();
+        let album_titles: Vec<&str> = albums.iter().map(|a| a.album.title.as_str()).collect();
+        assert_eq!(album_titles, vec!["x", "z"]);
+        assert!(albums[0].score > 0.0 && albums[1].score == 0.0);
+
+        // u4 connaît déjà l'album x
+        let albums = RecommendationService::recommend_albums(&db, "u4", 5)
+            .await
+            .unwrap();
+        let album_titles: Vec<&str> = albums.iter().map(|a| a.album.title.as_str()).collect();
+        assert_eq!(album_titles, vec!["z", "y"]);
+    }
+
+    #[tokio::test]
+    async fn test_cooccurrence_keeps_likes_of_heavy_listeners() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(include_str!("../../database_recommendations.surql"))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        db.query(
+            r#"
+            FOR $index IN 0..$max_songs {
+                RELATE user:heavy->user_listens_song->(type::thing('song', 'f' + <string> $index))
+                    SET total_listens = 1, last_listened_at = time::now();
+            };
+            FOR $user IN [user:heavy, user:u2] {
+                RELATE $user->user_likes_song->song:x;
+                RELATE $user->user_likes_song->song:y;
+            };
+            "#,
+        )
+        .bind(("max_songs", MAX_SONGS_PER_USER))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        RecommendationService::rebuild_cooccurrence(&db)
+            .await
+            .unwrap();
+        let mut res = db
+            .query("SELECT VALUE out FROM song_cooccurrence WHERE in = song:x;")
+            .await
+            .unwrap();
+        let neighbours: Vec<Thing> = res.take(0).unwrap();
+        assert_eq!(neighbours, vec![create_song_thing("y")]);
+    }
+}
@@ -0,0 +1,925 @@
+use std::{
+    collections::{HashMap, HashSet},
+    fs::File,
+    io,
+    path::{Path, PathBuf},
+    sync::{Arc, Mutex},
+    time::UNIX_EPOCH,
+};
+
+use chrono::Utc;
+use serde::Deserialize;
+use sha2::{Digest, Sha256};
+use surrealdb::{
+    engine::any::Any,
+    sql::{Duration, Id, Thing},
+    Surreal,
+};
+use symphonia::core::{
+    formats::FormatOptions,
+    io::MediaSourceStream,
+    meta::{MetadataOptions, StandardTagKey, Tag},
+    probe::Hint,
+    units::TimeBase,
+};
+use walkdir::WalkDir;
+
+use crate::{
+    error::{Error, Result},
+    models::{
+        album::CreateAlbumRequest,
+        artist::CreateArtistRequest,
+        scan::{ScanFileError, ScanReport, ScanStatus},
+    },
+    services::{
+        album_service::AlbumService,
+        artist_service::ArtistService,
+        import_service::ImportService,
+        media_service::{MediaConfig, MediaService},
+        suggest_service::SuggestIndex,
+    },
+};
+
+const UNKNOWN_ARTIST: &str = "Unknown Artist";
+
+/// Tags read from an audio file (ID3v2, FLAC/Vorbis comments or MP4 atoms).
+#[derive(Debug, Clone, Default)]
+pub struct AudioTags {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub album_artist: Option<String>,
+    pub album: Option<String>,
+    pub track_number: Option<u16>,
+    pub year: Option<u16>,
+    pub genre: Option<String>,
+    pub bpm: Option<f32>,
+    pub duration_secs: u64,
+}
+
+impl AudioTags {
+    fn from_tags(tags: &[Tag]) -> Self {
+        let mut audio = Self::default();
+
+        for tag in tags {
+            let Some(key) = tag.std_key else { continue };
+            let value = tag.value.to_string().trim().to_string();
+            if value.is_empty() {
+                continue;
+            }
+
+            // Le premier tag rencontré l'emporte (ID3v2 avant les tags du conteneur)
+            match key {
+                StandardTagKey::TrackTitle => {
+                    audio.title.get_or_insert(value);
+                }
+                StandardTagKey::Artist => {
+                    audio.artist.get_or_insert(value);
+                }
+                StandardTagKey::AlbumArtist => {
+                    audio.album_artist.get_or_insert(value);
+                }
+                StandardTagKey::Album => {
+                    audio.album.get_or_insert(value);
+                }
+                StandardTagKey::Genre => {
+                    audio.genre.get_or_insert(value);
+                }
+                StandardTagKey::TrackNumber if audio.track_number.is_none() => {
+                    audio.track_number = leading_number(&value);
+                }
+                StandardTagKey::Date | StandardTagKey::ReleaseDate | StandardTagKey::OriginalDate
+                    if audio.year.is_none() =>
+                {
+                    audio.year = leading_number(&value).filter(|year| *year >= 1000);
+                }
+                StandardTagKey::Bpm if audio.bpm.is_none() => {
+                    audio.bpm = value.parse::<f32>().ok().filter(|bpm| *bpm > 0.0);
+                }
+                _ => {}
+            }
+        }
+
+        audio
+    }
+}
+
+/// A file found on disk, `file_url` is relative to the media root.
+#[derive(Debug, Clone)]
+pub struct ScannedFile {
+    pub file_url: String,
+    pub mtime: i64,
+    pub checksum: String,
+    pub tags: AudioTags,
+}
+
+#[derive(Debug, Clone)]
+struct DiskFile {
+    path: PathBuf,
+    file_url: String,
+    mtime: i64,
+}
+
+#[derive(Debug, Deserialize)]
+struct KnownFile {
+    id: Thing,
+    file_url: String,
+    file_mtime: Option<i64>,
+    file_checksum: Option<String>,
+}
+
+#[derive(Debug, Default)]
+struct Touched {
+    albums: HashSet<Thing>,
+    artists: HashSet<Thing>,
+}
+
+/// Media scan run in the background for the admin API, one at a time, so a
+/// large library does not hold an HTTP request open.
+#[derive(Clone, Default)]
+pub struct ScanTask {
+    status: Arc<Mutex<ScanStatus>>,
+}
+
+impl ScanTask {
+    pub fn status(&self) -> ScanStatus {
+        self.status.lock().unwrap_or_else(|e| e.into_inner()).clone()
+    }
+
+    /// Starts a scan unless one is already running, and returns its status.
+    /// The suggestion index is invalidated once the scan is over.
+    pub fn start(
+        &self,
+        db: Surreal<Any>,
+        config: MediaConfig,
+        suggest_index: SuggestIndex,
+    ) -> ScanStatus {
+        let mut status = self.status.lock().unwrap_or_else(|e| e.into_inner());
+        if status.running {
+            return status.clone();
+        }
+        *status = ScanStatus {
+            running: true,
+            started_at: Some(Utc::now()),
+            ..ScanStatus::default()
+        };
+
+        let task = self.clone();
+        tokio::spawn(async move {
+            let result = ScanService::scan(&db, &config).await;
+            suggest_index.invalidate();
+
+            let mut status = task.status.lock().unwrap_or_else(|e| e.into_inner());
+            status.running = false;
+            status.finished_at = Some(Utc::now());
+            match result {
+                Ok(report) => status.report = Some(report),
+                Err(err) => {
+                    tracing::error!("Media scan failed: {:?}", err);
+                    status.error = Some(err.to_string());
+                }
+            }
+        });
+
+        status.clone()
+    }
+}
+
+pub struct ScanService;
+
+impl ScanService {
+    /// Walks the media root and creates or updates songs, albums and artists
+    /// from the audio tags.
+    ///
+    /// Files whose mtime did not change are skipped without being read. When
+    /// the mtime changed but the checksum did not, only the mtime is updated.
+    /// A new path with the checksum of a vanished file is treated as a move.
+    /// Missing files are reported, their songs are kept.
+    pub async fn scan(db: &Surreal<Any>, config: &MediaConfig) -> Result<ScanReport> {
+        if !config.media_root.is_dir() {
+            return Err(Error::InvalidInput {
+                reason: format!("Media root {} is not a directory", config.media_root.display()),
+            });
+        }
+
+        let mut report = ScanReport::default();
+
+        let walk_config = config.clone();
+        let (disk_files, walk_errors) =
+            tokio::task::spawn_blocking(move || list_audio_files(&walk_config))
+                .await
+                .map_err(|e| Error::DbError(format!("Scan task failed: {}", e)))?;
+        report.errors.extend(walk_errors);
+
+        let known_files: Vec<KnownFile> = db
+            .query("SELECT id, file_url, file_mtime, file_checksum FROM song")
+            .await?
+            .take(0)?;
+
+        let on_disk: HashSet<&str> = disk_files.iter().map(|f| f.file_url.as_str()).collect();
+        let known_by_url: HashMap<&str, &KnownFile> = known_files
+            .iter()
+            .map(|k| (k.file_url.as_str(), k))
+            .collect();
+        let mut vanished_by_checksum: HashMap<&str, &KnownFile> = known_files
+            .iter()
+            .filter(|k| !on_disk.contains(k.file_url.as_str()))
+            .filter_map(|k| k.file_checksum.as_deref().map(|checksum| (checksum, k)))
+            .collect();
+
+        let mut moved = HashSet::new();
+        let mut touched = Touched::default();
+
+        for disk_file in &disk_files {
+            report.scanned += 1;
+            let known = known_by_url.get(disk_file.file_url.as_str()).copied();
+
+            if let Some(known) = known {
+                if known.file_mtime == Some(disk_file.mtime) && known.file_checksum.is_some() {
+                    report.unchanged += 1;
+                    continue;
+                }
+            }
+
+            let path = disk_file.path.clone();
+            let read = tokio::task::spawn_blocking(move || read_audio_file(&path))
+                .await
+                .map_err(|e| Error::DbError(format!("Scan task failed: {}", e)))?;
+
+            let (checksum, tags) = match read {
+                Ok(read) => read,
+                Err(reason) => {
+                    report.errors.push(ScanFileError {
+                        file: disk_file.file_url.clone(),
+                        reason,
+                    });
+                    continue;
+                }
+            };
+
+            if let Some(known) = known {
+                if known.file_checksum.as_deref() == Some(checksum.as_str()) {
+                    Self::touch_mtime(db, &known.id, disk_file.mtime).await?;
+                    report.unchanged += 1;
+                    continue;
+                }
+            }
+
+            let scanned = ScannedFile {
+                file_url: disk_file.file_url.clone(),
+                mtime: disk_file.mtime,
+                checksum,
+                tags,
+            };
+
+            let existing = match known {
+                Some(known) => Some(known.id.clone()),
+                None => vanished_by_checksum
+                    .remove(scanned.checksum.as_str())
+                    .map(|vanished| {
+                        moved.insert(vanished.file_url.clone());
+                        vanished.id.clone()
+                    }),
+            };
+
+            if existing.is_some() {
+                report.changed_files.push(scanned.file_url.clone());
+            } else {
+                report.new_files.push(scanned.file_url.clone());
+            }
+
+            Self::apply_file(db, existing, &scanned, &mut touched).await?;
+        }
+
+        report.missing_files = known_files
+            .iter()
+            .filter(|k| k.file_checksum.is_some())
+            .filter(|k| !on_disk.contains(k.file_url.as_str()) && !moved.contains(&k.file_url))
+            .map(|k| k.file_url.clone())
+            .collect();
+
+        for album_thing in &touched.albums {
+            AlbumService::refresh_aggregates(db, album_thing).await?;
+        }
+        for artist_thing in &touched.artists {
+            ArtistService::refresh_counts(db, artist_thing).await?;
+        }
+
+        tracing::info!(
+            "Media scan: {} files, {} new, {} changed, {} missing, {} errors",
+            report.scanned,
+            report.new_files.len(),
+            report.changed_files.len(),
+            report.missing_files.len(),
+            report.errors.len()
+        );
+
+        Ok(report)
+    }
+
+    /// Creates or updates the song for a scanned file, along with its album,
+    /// artists and edges. `existing` is the song already bound to this file.
+    async fn apply_file(
+        db: &Surreal<Any>,
+        existing: Option<Thing>,
+        file: &ScannedFile,
+        touched: &mut Touched,
+    ) -> Result<()> {
+        let tags = &file.tags;
+        let title = tags.title.clone().unwrap_or_else(|| file_stem(&file.file_url));
+
+        let album_artist_name = tags
+            .album_artist
+            .clone()
+            .or_else(|| tags.artist.clone())
+            .unwrap_or_else(|| UNKNOWN_ARTIST.to_string());
+        let album_artist = Self::find_or_create_artist(db, &album_artist_name).await?;
+
+        let mut performers = vec![album_artist.clone()];
+        if let Some(track_artist) = &tags.artist {
+            if !track_artist.eq_ignore_ascii_case(&album_artist_name) {
+                performers.push(Self::find_or_create_artist(db, track_artist).await?);
+            }
+        }
+
+        let album = match &tags.album {
+            Some(album_title) => Some(
+                Self::find_or_create_album(db, &album_artist, album_title, tags).await?,
+            ),
+            None => None,
+        };
+
+        let song = match (existing, &album) {
+            (Some(song), _) => Some(song),
+            // Un morceau importé sans fichier est rattaché plutôt que dupliqué
+            (None, Some(album)) => ImportService::find_song(db, album, &title).await?,
+            (None, None) => None,
+        };
+        let is_new = song.is_none();
+        let song = song.unwrap_or_else(|| Thing::from(("song", Id::rand())));
+
+        if !is_new {
+            let previous_albums: Vec<Thing> = db
+                .query("SELECT VALUE in FROM album_contains_song WHERE out = $song")
+                .bind(("song", song.clone()))
+                .await?
+                .take(0)?;
+            touched.albums.extend(previous_albums);
+        }
+
+        let write_query = r#"
+            BEGIN TRANSACTION;
+
+            -- Les analyses de l'ancien contenu ne sont plus valables
+            IF $song.file_checksum != $file_checksum {
+                DELETE song_waveform WHERE song = $song;
+                DELETE song_fingerprint WHERE song = $song;
+                UPDATE $song SET loudness = NONE;
+                UPDATE (SELECT VALUE in FROM album_contains_song WHERE out = $song) SET loudness = NONE;
+            };
+
+            IF $is_new THEN
+                CREATE $song SET
+                    title = $title,
+                    file_url = $file_url,
+                    duration = $duration,
+                    song_index = $song_index,
+                    tempo = $tempo OR 0,
+                    file_mtime = $file_mtime,
+                    file_checksum = $file_checksum,
+                    total_listens = 0,
+                    total_user_listens = 0,
+                    total_likes = 0
+            ELSE
+                UPDATE $song SET
+                    title = $title,
+                    file_url = $file_url,
+                    duration = $duration,
+                    song_index = $song_index,
+                    tempo = $tempo OR tempo,
+                    file_mtime = $file_mtime,
+                    file_checksum = $file_checksum
+            END;
+
+            DELETE album_contains_song WHERE out = $song AND in != $album;
+            IF $album != NONE AND array::len(SELECT id FROM album_contains_song WHERE in = $album AND out = $song) = 0 THEN
+                RELATE $album->album_contains_song->$song
+            END;
+
+            FOR $artist IN $artists {
+                IF array::len(SELECT id FROM artist_performs_song WHERE in = $artist AND out = $song) = 0 THEN
+                    RELATE $artist->artist_performs_song->$song
+                END;
+            };
+
+            COMMIT TRANSACTION;
+        "#;
+
+        db.query(write_query)
+            .bind(("is_new", is_new))
+            .bind(("song", song))
+            .bind(("title", title))
+            .bind(("file_url", file.file_url.clone()))
+            .bind(("duration", Duration::from_secs(tags.duration_secs)))
+            .bind(("song_index", tags.track_number.unwrap_or(0)))
+            .bind(("tempo", tags.bpm))
+            .bind(("file_mtime", file.mtime))
+            .bind(("file_checksum", file.checksum.clone()))
+            .bind(("album", album.clone()))
+            .bind(("artists", performers.clone()))
+            .await?
+            .check()?;
+
+        touched.albums.extend(album);
+        touched.artists.extend(performers);
+
+        Ok(())
+    }
+
+    async fn touch_mtime(db: &Surreal<Any>, song: &Thing, mtime: i64) -> Result<()> {
+        db.query("UPDATE $song SET file_mtime = $file_mtime")
+            .bind(("song", song.clone()))
+            .bind(("file_mtime", mtime))
+            .await?
+            .check()?;
+
+        Ok(())
+    }
+
+    async fn find_or_create_artist(db: &Surreal<Any>, name: &str) -> Result<Thing> {
+        if let Some(artist) = ImportService::find_artist(db, name).await? {
+            return Ok(artist);
+        }
+
+        let artist = ArtistService::create_artist(
+            db,
+            CreateArtistRequest {
+                name: name.to_string(),
+                genres: Vec::new(),
+                country_code: String::new(),
+                artist_image: None,
+            },
+        )
+        .await?;
+
+        artist
+            .id
+            .ok_or(Error::DbError("Could not create artist".into()))
+    }
+
+    async fn find_or_create_album(
+        db: &Surreal<Any>,
+        artist: &Thing,
+        title: &str,
+        tags: &AudioTags,
+    ) -> Result<Thing> {
+        if let Some(album) = ImportService::find_album(db, artist, title).await? {
+            return Ok(album);
+        }
+
+        let album = AlbumService::create_album(
+            db,
+            CreateAlbumRequest {
+                title: title.to_string(),
+                cover_url: None,
+                release_year: tags.year,
+                genres: tags.genre.iter().cloned().collect(),
+                langs: Vec::new(),
+                dominant_color: None,
+                artist_ids: vec![artist.id.to_raw()],
+            },
+        )
+        .await?;
+
+        album
+            .id
+            .ok_or(Error::DbError("Could not create album".into()))
+    }
+}
+
+fn list_audio_files(config: &MediaConfig) -> (Vec<DiskFile>, Vec<ScanFileError>) {
+    let mut files = Vec::new();
+    let mut errors = Vec::new();
+
+    for entry in WalkDir::new(&config.media_root).follow_links(true) {
+        let entry = match entry {
+            Ok(entry) => entry,
+            Err(e) => {
+                errors.push(ScanFileError {
+                    file: e
+                        .path()
+                        .and_then(|p| config.relative_path(p))
+                        .unwrap_or_default(),
+                    reason: e.to_string(),
+                });
+                continue;
+            }
+        };
+
+        if !entry.file_type().is_file() || !MediaService::is_audio_file(entry.path()) {
+            continue;
+        }
+
+        let Some(file_url) = config.relative_path(entry.path()) else {
+            continue;
+        };
+
+        let mtime = entry
+            .metadata()
+            .map_err(io::Error::from)
+            .and_then(|m| m.modified())
+            .map(|modified| {
+                modified
+                    .duration_since(UNIX_EPOCH)
+                    .map(|d| d.as_secs() as i64)
+                    .unwrap_or_default()
+            });
+
+        match mtime {
+            Ok(mtime) => files.push(DiskFile {
+                path: entry.into_path(),
+                file_url,
+                mtime,
+            }),
+            Err(e) => errors.push(ScanFileError {
+                file: file_url,
+                reason: e.to_string(),
+            }),
+        }
+    }
+
+    files.sort_by(|a, b| a.file_url.cmp(&b.file_url));
+    (files, errors)
+}
+
+/// Reads the checksum and tags of a file. Errors are returned as text so they
+/// can be reported per file without stopping the scan.
+fn read_audio_file(path: &Path) -> std::result::Result<(String, AudioTags), String> {
+    let checksum = file_checksum(path).map_err(|e| e.to_string())?;
+    let tags = read_tags(path)?;
+    Ok((checksum, tags))
+}
+
+fn file_checksum(path: &Path) -> io::Result<String> {
+    let mut file = File::open(path)?;
+    let mut hasher = Sha256::new();
+    io::copy(&mut file, &mut hasher)?;
+    Ok(hex::encode(hasher.finalize()))
+}
+
+fn read_tags(path: &Path) -> std::result::Result<AudioTags, String> {
+    let file = File::open(path).map_err(|e| e.to_string())?;
+    let mss = MediaSourceStream::new(Box::new(file), Default::default());
+
+    let mut hint = Hint::new();
+    if let Some(ext) = path.extension().and_then(|ext| ext.to_str()) {
+        hint.with_extension(ext);
+    }
+
+    let mut probed = symphonia::default::get_probe()
+        .format(&hint, mss, &FormatOptions::default(), &MetadataOptions::default())
+        .map_err(|e| format!("Unsupported audio file: {}", e))?;
+
+    let mut tags: Vec<Tag> = Vec::new();
+    if let Some(metadata) = probed.metadata.get() {
+        if let Some(revision) = metadata.current() {
+            tags.extend(revision.tags().iter().cloned());
+        }
+    }
+    if let Some(revision) = probed.format.metadata().current() {
+        tags.extend(revision.tags().iter().cloned());
+    }
+
+    let mut audio = AudioTags::from_tags(&tags);
+
+    let track = probed
+        .format
+        .default_track()
+        .ok_or_else(|| "No audio track".to_string())?;
+    let track_id = track.id;
+    let params = track.codec_params.clone();
+
+    let time_base = params
+        .time_base
+        .or_else(|| params.sample_rate.map(|rate| TimeBase::new(1, rate)))
+        .ok_or_else(|| "Unknown audio time base".to_string())?;
+
+    let frames = match params.n_frames {
+        Some(frames) => frames,
+        None => {
+            // Pas de durée dans l'en-tête (MP3 sans Xing) : on additionne les paquets
+            let mut frames = 0;
+            while let Ok(packet) = probed.format.next_packet() {
+                if packet.track_id() == track_id {
+                    frames += packet.dur;
+                }
+            }
+            frames
+        }
+    };
+
+    let time = time_base.calc_time(frames);
+    audio.duration_secs = time.seconds + u64::from(time.frac >= 0.5);
+
+    Ok(audio)
+}
+
+fn leading_number(value: &str) -> Option<u16> {
+    let digits: String = value
+        .trim()
+        .chars()
+        .take_while(|c| c.is_ascii_digit())
+        .collect();
+    digits.parse().ok()
+}
+
+fn file_stem(file_url: &str) -> String {
+    Path::new(file_url)
+        .file_stem()
+        .and_then(|stem| stem.to_str())
+        .unwrap_or(file_url)
+        .to_string()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::{album::Album, artist::Artist, loudness::Loudness, song::Song};
+    use std::fs;
+    use surrealdb::engine::any::connect;
+
+    async fn setup_db() -> Surreal<Any> {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db
+    }
+
+    fn setup_media_root() -> MediaConfig {
+        let media_root = std::env::temp_dir().join(format!("scan-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&media_root).unwrap();
+        MediaConfig {
+            image_cache_dir: media_root.join("cache"),
+            media_root,
+        }
+    }
+
+    fn id3_text_frame(id: &str, text: &str) -> Vec<u8> {
+        let mut frame = id.as_bytes().to_vec();
+        frame.extend(((text.len() + 1) as u32).to_be_bytes());
+        frame.extend([0, 0, 0]);
+        frame.extend(text.as_bytes());
+        frame
+    }
+
+    /// MP3 minimal : tag ID3v2.3 puis des trames MPEG-1 Layer III silencieuses
+    /// (128 kbps, 44.1 kHz, 1152 échantillons par trame).
+    fn write_mp3(path: &Path, tags: &[(&str, &str)], frames: usize) {
+        let body: Vec<u8> = tags
+            .iter()
+            .flat_map(|(id, text)| id3_text_frame(id, text))
+            .collect();
+        let size = body.len() as u32;
+        let mut data = b"ID3\x03\x00\x00".to_vec();
+        data.extend([
+            ((size >> 21) & 0x7f) as u8,
+            ((size >> 14) & 0x7f) as u8,
+            ((size >> 7) & 0x7f) as u8,
+            (size & 0x7f) as u8,
+        ]);
+        data.extend(body);
+
+        for _ in 0..frames {
+            let mut frame = vec![0u8; 417];
+            frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+            data.extend(frame);
+        }
+
+        fs::create_dir_all(path.parent().unwrap()).unwrap();
+        fs::write(path, data).unwrap();
+    }
+
+    fn set_mtime(path: &Path, secs: u64) {
+        let file = File::options().write(true).open(path).unwrap();
+        file.set_modified(UNIX_EPOCH + std::time::Duration::from_secs(secs))
+            .unwrap();
+    }
+
+    const TAGS: [(&str, &str); 6] = [
+        ("TIT2", "Intro"),
+        ("TPE1", "Scan Artist"),
+        ("TALB", "Scan Album"),
+        ("TRCK", "3/12"),
+        ("TBPM", "128"),
+        ("TYER", "2019"),
+    ];
+
+    #[tokio::test]
+    async fn test_scan_creates_records_from_tags() {
+        let db = setup_db().await;
+        let config = setup_media_root();
+        write_mp3(&config.media_root.join("Scan Artist/Scan Album/03.mp3"), &TAGS, 115);
+        fs::write(config.media_root.join("Scan Artist/Scan Album/cover.jpg"), b"jpg").unwrap();
+
+        let report = ScanService::scan(&db, &config).await.unwrap();
+
+        assert_eq!(report.scanned, 1);
+        assert_eq!(report.new_files, vec!["Scan Artist/Scan Album/03.mp3".to_string()]);
+        assert!(report.errors.is_empty());
+
+        let songs: Vec<Song> = db.select("song").await.unwrap();
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].title, "Intro");
+        assert_eq!(songs[0].file_url, "Scan Artist/Scan Album/03.mp3");
+        assert_eq!(songs[0].song_index, 3);
+        assert_eq!(songs[0].tempo, 128.0);
+        // 115 trames * 1152 / 44100 ≈ 3 s
+        assert_eq!(songs[0].duration, Duration::from_secs(3));
+
+        let albums: Vec<Album> = db.select("album").await.unwrap();
+        assert_eq!(albums.len(), 1);
+        assert_eq!(albums[0].title, "Scan Album");
+        assert_eq!(albums[0].release_year, Some(2019));
+        assert_eq!(albums[0].total_tracks, 1);
+
+        let artists: Vec<Artist> = db.select("artist").await.unwrap();
+        assert_eq!(artists.len(), 1);
+        assert_eq!(artists[0].albums_count, 1);
+        assert_eq!(artists[0].songs_count, 1);
+
+        fs::remove_dir_all(&config.media_root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scan_task_runs_in_the_background() {
+        let db = setup_db().await;
+        let config = setup_media_root();
+        write_mp3(&config.media_root.join("a/01.mp3"), &TAGS, 40);
+
+        let task = ScanTask::default();
+        assert!(!task.status().running);
+
+        let status = task.start(db.clone(), config.clone(), SuggestIndex::default());
+        assert!(status.running);
+        assert!(status.started_at.is_some());
+        // Une seconde demande renvoie le passage en cours
+        let again = task.start(db.clone(), config.clone(), SuggestIndex::default());
+        assert_eq!(again.started_at, status.started_at);
+
+        while task.status().running {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let status = task.status();
+        assert!(status.finished_at.is_some());
+        assert!(status.error.is_none());
+        assert_eq!(status.report.map(|report| report.new_files.len()), Some(1));
+
+        let missing = MediaConfig {
+            media_root: config.media_root.join("missing"),
+            ..config
+        };
+        task.start(db, missing, SuggestIndex::default());
+        while task.status().running {
+            tokio::time::sleep(std::time::Duration::from_millis(10)).await;
+        }
+        let status = task.status();
+        assert!(status.report.is_none());
+        assert!(status.error.is_some());
+    }
+
+    #[tokio::test]
+    async fn test_rescan_is_incremental() {
+        let db = setup_db().await;
+        let config = setup_media_root();
+        let path = config.media_root.join("a/01.mp3");
+        write_mp3(&path, &TAGS, 40);
+        set_mtime(&path, 1_700_000_000);
+
+        ScanService::scan(&db, &config).await.unwrap();
+
+        // Même mtime : le fichier n'est pas relu
+        let report = ScanService::scan(&db, &config).await.unwrap();
+        assert_eq!(report.unchanged, 1);
+        assert!(report.new_files.is_empty() && report.changed_files.is_empty());
+
+        // mtime modifié, contenu identique
+        set_mtime(&path, 1_700_000_100);
+        let report = ScanService::scan(&db, &config).await.unwrap();
+        assert_eq!(report.unchanged, 1);
+        assert!(report.changed_files.is_empty());
+
+        let waveform_count = |db: Surreal<Any>| async move {
+            let count: Option<usize> = db
+                .query("RETURN count(SELECT id FROM song_waveform)")
+                .await
+                .unwrap()
+                .take(0)
+                .unwrap();
+            count.unwrap_or(0)
+        };
+        db.query(
+            r#"
+            CREATE song_waveform SET song = (SELECT VALUE id FROM ONLY song LIMIT 1);
+            CREATE song_fingerprint SET song = (SELECT VALUE id FROM ONLY song LIMIT 1), fingerprint = [1, 2, 3];
+            UPDATE song, album SET loudness = { integrated_lufs: -9.0, true_peak_dbtp: 0.5, gain_db: -9.0 };
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        // Contenu modifié : les analyses de l'ancien fichier sont supprimées
+        let mut retagged = TAGS;
+        retagged[0] = ("TIT2", "Intro (Remastered)");
+        write_mp3(&path, &retagged, 40);
+        set_mtime(&path, 1_700_000_200);
+        let report = ScanService::scan(&db, &config).await.unwrap();
+        assert_eq!(report.changed_files, vec!["a/01.mp3".to_string()]);
+
+        let songs: Vec<Song> = db.select("song").await.unwrap();
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].title, "Intro (Remastered)");
+        assert!(songs[0].loudness.is_none());
+        assert_eq!(waveform_count(db.clone()).await, 0);
+        let fingerprints: Option<usize> = db
+            .query("RETURN count(SELECT id FROM song_fingerprint)")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(fingerprints, Some(0));
+        let album_loudness: Vec<Option<Loudness>> =
+            db.query("SELECT VALUE loudness FROM album").await.unwrap().take(0).unwrap();
+        assert_eq!(album_loudness, vec![None]);
+
+        db.query("CREATE song_waveform SET song = (SELECT VALUE id FROM ONLY song LIMIT 1)")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        // Fichier déplacé : même morceau, rien de manquant
+        fs::create_dir_all(config.media_root.join("b")).unwrap();
+        fs::rename(&path, config.media_root.join("b/01.mp3")).unwrap();
+        let report = ScanService::scan(&db, &config).await.unwrap();
+        assert_eq!(report.changed_files, vec!["b/01.mp3".to_string()]);
+        assert!(report.missing_files.is_empty());
+
+        let songs: Vec<Song> = db.select("song").await.unwrap();
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].file_url, "b/01.mp3");
+        assert_eq!(waveform_count(db.clone()).await, 1);
+
+        // Fichier supprimé : signalé, le morceau est conservé
+        fs::remove_file(config.media_root.join("b/01.mp3")).unwrap();
+        let report = ScanService::scan(&db, &config).await.unwrap();
+        assert_eq!(report.missing_files, vec!["b/01.mp3".to_string()]);
+
+        let songs: Vec<Song> = db.select("song").await.unwrap();
+        assert_eq!(songs.len(), 1);
+
+        fs::remove_dir_all(&config.media_root).unwrap();
+    }
+
+    #[tokio::test]
+    async fn test_scan_attaches_imported_song() {
+        let db = setup_db().await;
+        let config = setup_media_root();
+
+        let manifest = serde_json::json!({
+            "artists": [{
+                "name": "Scan Artist",
+                "country_code": "FR",
+                "albums": [{
+                    "title": "Scan Album",
+                    "songs": [{ "title": "Intro", "file_url": "https://cdn.example.com/intro.mp3", "duration_secs": 1, "song_index": 3 }]
+                }]
+            }]
+        })
+        .to_string();
+        ImportService::import(
+            &db,
+            &manifest,
+            crate::services::import_service::ImportFormat::Json,
+            false,
+        )
+        .await
+        .unwrap();
+
+        write_mp3(&config.media_root.join("intro.mp3"), &TAGS, 40);
+        let report = ScanService::scan(&db, &config).await.unwrap();
+        assert_eq!(report.new_files.len(), 1);
+
+        let songs: Vec<Song> = db.select("song").await.unwrap();
+        assert_eq!(songs.len(), 1);
+        assert_eq!(songs[0].file_url, "intro.mp3");
+
+        fs::remove_dir_all(&config.media_root).unwrap();
+    }
+
+    #[test]
+    fn test_leading_number() {
+        assert_eq!(leading_number("3/12"), Some(3));
+        assert_eq!(leading_number("2019-05-01"), Some(2019));
+        assert_eq!(leading_number("n/a"), None);
+    }
+}
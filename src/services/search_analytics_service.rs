@@ -0,0 +1,433 @@
+use serde::Deserialize;
+use surrealdb::{engine::any::Any, sql::Duration, sql::Thing, Surreal};
+
+use crate::{
+    error::{Error, Result},
+    helpers::thing_helpers::thing_to_string,
+    models::search_analytics::{ClickThroughReport, ClicksByType, TopSearchQuery, ZeroResultQuery},
+    services::search_service::SearchResult,
+};
+
+const SEARCH_EVENT_TABLE: &str = "search_event";
+const MAX_REPORT_LIMIT: u32 = 100;
+
+#[derive(Debug, Deserialize)]
+struct ClickSummary {
+    searches: u64,
+    clicked_searches: u64,
+    zero_result_searches: u64,
+    authenticated_searches: u64,
+    corrected_searches: u64,
+}
+
+#[derive(Debug, Deserialize)]
+struct TopQueryRow {
+    query: String,
+    searches: u64,
+    clicked_searches: u64,
+    avg_results: f64,
+}
+
+pub struct SearchAnalyticsService;
+
+impl SearchAnalyticsService {
+    /// Stores a search event and returns its id, to be sent back with a click.
+    pub async fn record(
+        db: &Surreal<Any>,
+        term: &str,
+        result: &SearchResult,
+        authenticated: bool,
+    ) -> Result<String> {
+        let album_count = result
+            .albums
+            .as_ref()
+            .map(|page| page.pagination.total_items);
+        let song_count = result
+            .songs
+            .as_ref()
+            .map(|page| page.pagination.total_items);
+        let artist_count = result
+            .artists
+            .as_ref()
+            .map(|page| page.pagination.total_items);
+        let playlist_count = result
+            .playlists
+            .as_ref()
+            .map(|page| page.pagination.total_items);
+        let user_count = result
+            .users
+            .as_ref()
+            .map(|page| page.pagination.total_items);
+        let total_count: u64 = [
+            album_count,
+            song_count,
+            artist_count,
+            playlist_count,
+            user_count,
+        ]
+        .into_iter()
+        .flatten()
+        .sum();
+
+        let sql = "
+            CREATE search_event SET
+                query = $query,
+                album_count = $album_count,
+                song_count = $song_count,
+                artist_count = $artist_count,
+                playlist_count = $playlist_count,
+                user_count = $user_count,
+                total_count = $total_count,
+                corrected = $corrected,
+                authenticated = $authenticated
+            RETURN id;
+        ";
+
+        let mut res = db
+            .query(sql)
+            .bind(("query", normalize_search_term(term)))
+            .bind(("album_count", album_count))
+            .bind(("song_count", song_count))
+            .bind(("artist_count", artist_count))
+            .bind(("playlist_count", playlist_count))
+            .bind(("user_count", user_count))
+            .bind(("total_count", total_count))
+            .bind(("corrected", result.did_you_mean.is_some()))
+            .bind(("authenticated", authenticated))
+            .await?;
+
+        let id: Option<Thing> = res.take((0, "id"))?;
+        id.map(|id| thing_to_string(&id))
+            .ok_or_else(|| Error::DbError("Search event was not created".to_string()))
+    }
+
+    /// Records that `result_id` was opened from the search `search_id`. Only
+    /// the first opened result is kept; later ones are counted.
+    pub async fn record_click(db: &Surreal<Any>, search_id: &str, result_id: &str) -> Result<()> {
+        let event = Thing::from((
+            SEARCH_EVENT_TABLE.to_string(),
+            search_id
+                .strip_prefix("search_event:")
+                .unwrap_or(search_id)
+                .to_string(),
+        ));
+        let result = parse_result_id(result_id)?;
+
+        let sql = "
+            UPDATE $event SET
+                click_count += 1,
+                clicked_result = clicked_result OR $result,
+                clicked_at = clicked_at OR time::now()
+            RETURN id;
+        ";
+        let mut res = db
+            .query(sql)
+            .bind(("event", event))
+            .bind(("result", result))
+            .await?;
+
+        let updated: Option<Thing> = res.take((0, "id"))?;
+        match updated {
+            Some(_) => Ok(()),
+            None => Err(Error::SearchEventNotFound {
+                id: search_id.to_string(),
+            }),
+        }
+    }
+
+    /// Most frequent queries of the last `days` days.
+    pub async fn top_queries(
+        db: &Surreal<Any>,
+        days: u32,
+        limit: u32,
+    ) -> Result<Vec<TopSearchQuery>> {
+        // Le tri porte sur les agrégats : il doit suivre le regroupement
+        let sql = "
+            SELECT * FROM (
+                SELECT
+                    query,
+                    count() AS searches,
+                    count(clicked_at != NONE) AS clicked_searches,
+                    math::mean(total_count) AS avg_results
+                FROM search_event
+                WHERE searched_at > time::now() - $period
+                GROUP BY query
+            )
+            ORDER BY searches DESC, query ASC
+            LIMIT $limit;
+        ";
+
+        let mut res = db
+            .query(sql)
+            .bind(("period", period(days)))
+            .bind(("limit", limit.clamp(1, MAX_REPORT_LIMIT)))
+            .await?;
+        let rows: Vec<TopQueryRow> = res.take(0)?;
+
+        Ok(rows
+            .into_iter()
+            .map(|row| TopSearchQuery {
+                click_through_rate: rate(row.clicked_searches, row.searches),
+                query: row.query,
+                searches: row.searches,
+                clicked_searches: row.clicked_searches,
+                avg_results: row.avg_results,
+            })
+            .collect())
+    }
+
+    /// Most frequent queries of the last `days` days that found nothing,
+    /// even after correction.
+    pub async fn zero_result_queries(
+        db: &Surreal<Any>,
+        days: u32,
+        limit: u32,
+    ) -> Result<Vec<ZeroResultQuery>> {
+        let sql = "
+            SELECT * FROM (
+                SELECT
+                    query,
+                    count() AS searches,
+                    time::max(searched_at) AS last_searched_at
+                FROM search_event
+                WHERE searched_at > time::now() - $period AND total_count = 0
+                GROUP BY query
+            )
+            ORDER BY searches DESC, query ASC
+            LIMIT $limit;
+        ";
+
+        let mut res = db
+            .query(sql)
+            .bind(("period", period(days)))
+            .bind(("limit", limit.clamp(1, MAX_REPORT_LIMIT)))
+            .await?;
+        let queries: Vec<ZeroResultQuery> = res.take(0)?;
+        Ok(queries)
+    }
+
+    /// Share of the searches of the last `days` days after which a result
+    /// was opened, with the type of the opened results.
+    pub async fn click_through(db: &Surreal<Any>, days: u32) -> Result<ClickThroughReport> {
+        let sql = "
+            SELECT
+                count() AS searches,
+                count(clicked_at != NONE) AS clicked_searches,
+                count(total_count = 0) AS zero_result_searches,
+                count(authenticated = true) AS authenticated_searches,
+                count(corrected = true) AS corrected_searches
+            FROM search_event
+            WHERE searched_at > time::now() - $period
+            GROUP ALL;
+            SELECT record::tb(clicked_result) AS result_type, count() AS clicks
+            FROM search_event
+            WHERE searched_at > time::now() - $period AND clicked_result != NONE
+            GROUP BY result_type;
+        ";
+
+        let mut res = db.query(sql).bind(("period", period(days))).await?;
+        let summary: Option<ClickSummary> = res.take(0)?;
+        let mut clicks_by_type: Vec<ClicksByType> = res.take(1)?;
+        clicks_by_type.sort_by(|a, b| {
+            b.clicks
+                .cmp(&a.clicks)
+                .then_with(|| a.result_type.cmp(&b.result_type))
+        });
+
+        let summary = summary.unwrap_or(ClickSummary {
+            searches: 0,
+            clicked_searches: 0,
+            zero_result_searches: 0,
+            authenticated_searches: 0,
+            corrected_searches: 0,
+        });
+
+        Ok(ClickThroughReport {
+            days,
+            searches: summary.searches,
+            clicked_searches: summary.clicked_searches,
+            click_through_rate: rate(summary.clicked_searches, summary.searches),
+            zero_result_searches: summary.zero_result_searches,
+            authenticated_searches: summary.authenticated_searches,
+            corrected_searches: summary.corrected_searches,
+            clicks_by_type,
+        })
+    }
+}
+
+/// Lowercased with whitespace collapsed, so that the same query typed
+/// differently is reported once. Structured fields are kept as typed.
+fn normalize_search_term(term: &str) -> String {
+    term.split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+        .to_lowercase()
+}
+
+/// `table:id` of a searchable record.
+fn parse_result_id(result_id: &str) -> Result<Thing> {
+    match result_id.split_once(':') {
+        Some((table @ ("album" | "song" | "artist" | "playlist" | "user"), id))
+            if !id.is_empty() =>
+        {
+            Ok(Thing::from((table.to_string(), id.to_string())))
+        }
+        _ => Err(Error::InvalidInput {
+            reason: format!(
+                "Invalid result id '{}', expected album:…, song:…, artist:…, playlist:… or user:…",
+                result_id
+            ),
+        }),
+    }
+}
+
+fn period(days: u32) -> Duration {
+    Duration::from_secs(u64::from(days) * 86_400)
+}
+
+fn rate(part: u64, total: u64) -> f64 {
+    if total == 0 {
+        0.0
+    } else {
+        part as f64 / total as f64
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::pagination::{PaginatedResponse, PaginationInfo};
+    use surrealdb::engine::any::connect;
+
+    async fn setup_db() -> Surreal<Any> {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(include_str!("../../database_search_analytics.surql"))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        db
+    }
+
+    fn page<T>(total_items: u64) -> Option<PaginatedResponse<T>> {
+        Some(PaginatedResponse {
+            data: Vec::new(),
+            pagination: PaginationInfo {
+                current_page: 1,
+                total_pages: 1,
+                total_items,
+                page_size: 20,
+                has_next_page: false,
+                has_previous_page: false,
+            },
+        })
+    }
+
+    fn result(songs: u64, albums: u64) -> SearchResult {
+        SearchResult {
+            songs: page(songs),
+            albums: page(albums),
+            ..Default::default()
+        }
+    }
+
+    #[tokio::test]
+    async fn test_reports_top_queries_zero_results_and_clicks() {
+        let db = setup_db().await;
+
+        let first = SearchAnalyticsService::record(&db, "London  Calling", &result(2, 1), true)
+            .await
+            .unwrap();
+        assert!(first.starts_with("search_event:"));
+        SearchAnalyticsService::record(&db, "london calling", &result(2, 1), false)
+            .await
+            .unwrap();
+        SearchAnalyticsService::record(&db, "zzz", &result(0, 0), false)
+            .await
+            .unwrap();
+        SearchAnalyticsService::record(&db, "zzz", &result(0, 0), true)
+            .await
+            .unwrap();
+        SearchAnalyticsService::record(&db, "clash", &result(1, 0), false)
+            .await
+            .unwrap();
+        // Hors de la période du rapport, et supprimé par la rétention au prochain CREATE
+        db.query(
+            "CREATE search_event SET query = 'old', total_count = 0,
+                searched_at = time::now() - 100d;",
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        SearchAnalyticsService::record_click(&db, &first, "song:calling")
+            .await
+            .unwrap();
+        SearchAnalyticsService::record_click(&db, &first, "album:london")
+            .await
+            .unwrap();
+
+        let top = SearchAnalyticsService::top_queries(&db, 30, 2)
+            .await
+            .unwrap();
+        assert_eq!(top.len(), 2);
+        assert_eq!(top[0].query, "london calling");
+        assert_eq!(top[0].searches, 2);
+        assert_eq!(top[0].clicked_searches, 1);
+        assert_eq!(top[0].click_through_rate, 0.5);
+        assert_eq!(top[0].avg_results, 3.0);
+        assert_eq!(top[1].query, "zzz");
+
+        let zero = SearchAnalyticsService::zero_result_queries(&db, 30, 20)
+            .await
+            .unwrap();
+        assert_eq!(zero.len(), 1);
+        assert_eq!((zero[0].query.as_str(), zero[0].searches), ("zzz", 2));
+
+        let report = SearchAnalyticsService::click_through(&db, 30)
+            .await
+            .unwrap();
+        assert_eq!(report.searches, 5);
+        assert_eq!(report.clicked_searches, 1);
+        assert_eq!(report.click_through_rate, 0.2);
+        assert_eq!(report.zero_result_searches, 2);
+        assert_eq!(report.authenticated_searches, 2);
+        // Seul le premier résultat ouvert est retenu
+        assert_eq!(
+            report.clicks_by_type,
+            vec![ClicksByType {
+                result_type: "song".to_string(),
+                clicks: 1
+            }]
+        );
+
+        SearchAnalyticsService::record(&db, "after", &result(1, 0), false)
+            .await
+            .unwrap();
+        let mut res = db
+            .query("SELECT VALUE query FROM search_event WHERE query = 'old';")
+            .await
+            .unwrap();
+        let old: Vec<String> = res.take(0).unwrap();
+        assert!(old.is_empty());
+    }
+
+    #[tokio::test]
+    async fn test_click_validation() {
+        let db = setup_db().await;
+        let id = SearchAnalyticsService::record(&db, "clash", &result(1, 0), false)
+            .await
+            .unwrap();
+
+        assert!(matches!(
+            SearchAnalyticsService::record_click(&db, &id, "label:stiff").await,
+            Err(Error::InvalidInput { .. })
+        ));
+        assert!(matches!(
+            SearchAnalyticsService::record_click(&db, "search_event:missing", "song:a").await,
+            Err(Error::SearchEventNotFound { .. })
+        ));
+    }
+}
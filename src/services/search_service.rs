@@ -1,66 +1,1086 @@
-use crate::error::Result;
-use serde::{Deserialize, Serialize};
+use crate::{error::Result, helpers::search_helpers::parse_search_term};
+use serde::{de::DeserializeOwned, Deserialize, Serialize};
+use std::{cmp::Reverse, collections::HashMap};
 use surrealdb::{engine::any::Any, Surreal};
 
-use crate::models::{album::AlbumWithArtists, artist::Artist, song::SongWithRelations};
+use crate::services::suggest_service::{fold_words, SuggestIndex};
 
-#[derive(Debug, Clone, Serialize, Deserialize)]
+use crate::models::{
+    album::AlbumWithArtists,
+    artist::Artist,
+    database_helpers::CountResult,
+    pagination::{PaginatedResponse, PaginationInfo},
+    playlist::PlaylistWithOwner,
+    search::{SearchOptions, SearchType},
+    song::SongWithRelations,
+    user::UserProfile,
+};
+
+// Bonus de popularité ajouté au score BM25 : 0.25 * ln(1 + écoutes + 5 * likes),
+// soit ~1.7 pour 1000 écoutes. Départage les titres proches sans écraser la pertinence
+const POPULARITY_WEIGHT: f32 = 0.25;
+pub(crate) const LIKE_WEIGHT: u32 = 5;
+
+// Filtres d'album, réutilisés sur les albums des morceaux et des artistes
+// (genres d'album libres, genres d'artiste stockés sous leur nom de variante)
+const GENRE_FILTER: &str = "(array::len($genres) = 0 OR genres.map(|$genre|
+        string::replace(string::replace(string::replace(string::lowercase($genre), '_', ''), '-', ''), ' ', '')
+    ) CONTAINSANY $genres)";
+const RELEASE_FILTER: &str =
+    "(array::len($langs) = 0 OR langs.map(|$lang| string::lowercase($lang)) CONTAINSANY $langs)
+    AND ($year_from = NONE OR release_year >= $year_from)
+    AND ($year_to = NONE OR release_year <= $year_to)";
+// Un tempo nul signifie « pas encore analysé »
+const TEMPO_FILTER: &str = "($filter_tempo = false OR (tempo > 0
+    AND ($tempo_min = NONE OR tempo >= $tempo_min)
+    AND ($tempo_max = NONE OR tempo <= $tempo_max)))";
+
+// Champs de `UserProfile` : jamais le mot de passe
+const USER_PROFILE_FIELDS: &str = "id, username, created_at, listen_count, total_listening_time,
+    favorite_count, listening_streak, badges, level, experience_points, role";
+
+/// A full-text query on one table: `select` lists the returned fields,
+/// `popularity` the expression behind the popularity bonus and `filter` the
+/// conditions added to the match on `field`.
+struct RankedQuery<'a> {
+    table: &'a str,
+    field: &'a str,
+    select: &'a str,
+    popularity: &'a str,
+    filter: &'a str,
+}
+
+/// A search result with its ranking score and a highlight snippet: the
+/// matched title, HTML-escaped, with the matching terms wrapped in `<mark>`.
+#[derive(Debug, Clone, Serialize)]
+pub struct SearchHit<T> {
+    #[serde(flatten)]
+    pub item: T,
+    pub score: f32,
+    pub highlight: String,
+}
+
+/// One page of hits per requested type; types that were not requested are
+/// omitted.
+#[derive(Debug, Clone, Default, Serialize)]
 pub struct SearchResult {
-    pub albums: Vec<AlbumWithArtists>,
-    pub artists: Vec<Artist>,
-    pub songs: Vec<SongWithRelations>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub albums: Option<PaginatedResponse<SearchHit<AlbumWithArtists>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub artists: Option<PaginatedResponse<SearchHit<Artist>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub songs: Option<PaginatedResponse<SearchHit<SongWithRelations>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub playlists: Option<PaginatedResponse<SearchHit<PlaylistWithOwner>>>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub users: Option<PaginatedResponse<SearchHit<UserProfile>>>,
+    /// Corrected query, set when the original one matched nothing and the
+    /// results are those of the correction.
+    pub did_you_mean: Option<String>,
+    /// Search event to report an opened result to, on the first page only.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub search_id: Option<String>,
+}
+
+impl SearchResult {
+    fn is_empty(&self) -> bool {
+        let total = |page: Option<u64>| page.unwrap_or(0);
+        total(self.albums.as_ref().map(|page| page.pagination.total_items)) == 0
+            && total(
+                self.artists
+                    .as_ref()
+                    .map(|page| page.pagination.total_items),
+            ) == 0
+            && total(self.songs.as_ref().map(|page| page.pagination.total_items)) == 0
+            && total(
+                self.playlists
+                    .as_ref()
+                    .map(|page| page.pagination.total_items),
+            ) == 0
+            && total(self.users.as_ref().map(|page| page.pagination.total_items)) == 0
+    }
+}
+
+/// Character range of a matched term, as returned by `search::offsets`.
+#[derive(Debug, Deserialize)]
+struct Offset {
+    s: usize,
+    e: usize,
 }
 
 pub struct SearchService;
 
 impl SearchService {
+    /// Accent- and punctuation-insensitive search of the requested types, one
+    /// page each. Fields of a structured term (`artist:"X" year:1990..1995`)
+    /// are added to the filters of `options`. A query without any hit is
+    /// retried once with its misspelled words corrected.
     pub async fn search_albums_songs_artists(
         db: &Surreal<Any>,
+        suggest_index: &SuggestIndex,
         term: &str,
+        options: &SearchOptions,
+    ) -> Result<SearchResult> {
+        let mut options = options.clone();
+        let text = parse_search_term(term, &mut options)?;
+        let query = normalize_query(&text);
+        if query.is_empty() && !options.has_filters() {
+            return Ok(SearchResult::default());
+        }
+
+        let result = Self::search_all(db, &query, &options).await?;
+        if !result.is_empty() || query.is_empty() {
+            return Ok(result);
+        }
+
+        let vocabulary = suggest_index.vocabulary(db).await?;
+        if let Some(corrected) = correct_query(&vocabulary, &query) {
+            let mut corrected_result = Self::search_all(db, &corrected, &options).await?;
+            if !corrected_result.is_empty() {
+                corrected_result.did_you_mean = Some(corrected);
+                return Ok(corrected_result);
+            }
+        }
+
+        Ok(result)
+    }
+
+    async fn search_all(
+        db: &Surreal<Any>,
+        query: &str,
+        options: &SearchOptions,
     ) -> Result<SearchResult> {
-        let albums: Vec<AlbumWithArtists> = Self::search_albums(db, term).await?;
-        let songs: Vec<SongWithRelations> = Self::search_songs(db, term).await?;
-        let artists: Vec<Artist> = Self::search_artists(db, term).await?;
-
-        Ok(SearchResult {
-            albums,
-            artists,
-            songs,
+        let mut result = SearchResult::default();
+
+        if options.includes(SearchType::Album) {
+            result.albums = Some(Self::search_albums(db, query, options).await?);
+        }
+        if options.includes(SearchType::Song) {
+            result.songs = Some(Self::search_songs(db, query, options).await?);
+        }
+        if options.includes(SearchType::Artist) {
+            result.artists = Some(Self::search_artists(db, query, options).await?);
+        }
+
+        // Les filtres de catalogue ne s'appliquent pas aux playlists ni aux utilisateurs
+        if options.has_filters() {
+            return Ok(result);
+        }
+        if options.includes(SearchType::Playlist) {
+            result.playlists = Some(Self::search_playlists(db, query, options).await?);
+        }
+        if options.includes(SearchType::User) {
+            result.users = Some(Self::search_users(db, query, options).await?);
+        }
+
+        Ok(result)
+    }
+
+    async fn search_albums(
+        db: &Surreal<Any>,
+        term: &str,
+        options: &SearchOptions,
+    ) -> Result<PaginatedResponse<SearchHit<AlbumWithArtists>>> {
+        let filter = format!(
+            "{GENRE_FILTER} AND {RELEASE_FILTER}
+                AND ($filter_tempo = false
+                    OR array::len(->album_contains_song->(song WHERE {TEMPO_FILTER})) > 0)
+                AND ($artist = NONE
+                    OR string::lowercase(string::join('|', <-artist_creates_album<-artist.name)) CONTAINS $artist)
+                AND ($country_code = NONE OR <-artist_creates_album<-artist.country_code CONTAINS $country_code)"
+        );
+        let query = RankedQuery {
+            table: "album",
+            field: "title",
+            select: "*, <-artist_creates_album<-artist.* AS artists",
+            popularity: "(total_listens OR 0) + $like_weight * (total_likes OR 0)",
+            filter: &filter,
+        };
+
+        Self::ranked(db, &query, term, options, |album: &AlbumWithArtists| {
+            &album.title
+        })
+        .await
+    }
+
+    async fn search_songs(
+        db: &Surreal<Any>,
+        term: &str,
+        options: &SearchOptions,
+    ) -> Result<PaginatedResponse<SearchHit<SongWithRelations>>> {
+        let filter = format!(
+            "($filter_albums = false
+                    OR array::len(<-album_contains_song<-(album WHERE {GENRE_FILTER} AND {RELEASE_FILTER})) > 0)
+                AND {TEMPO_FILTER}
+                AND ($artist = NONE
+                    OR string::lowercase(string::join('|', <-artist_performs_song<-artist.name)) CONTAINS $artist)
+                AND ($country_code = NONE OR <-artist_performs_song<-artist.country_code CONTAINS $country_code)"
+        );
+        let query = RankedQuery {
+            table: "song",
+            field: "title",
+            select: "*,
+                (SELECT * FROM <-artist_performs_song<-artist) AS artists,
+                (SELECT * FROM <-album_contains_song<-album)[0] AS album",
+            popularity: "(total_listens OR 0) + $like_weight * (total_likes OR 0)",
+            filter: &filter,
+        };
+
+        Self::ranked(db, &query, term, options, |song: &SongWithRelations| {
+            &song.title
+        })
+        .await
+    }
+
+    async fn search_artists(
+        db: &Surreal<Any>,
+        term: &str,
+        options: &SearchOptions,
+    ) -> Result<PaginatedResponse<SearchHit<Artist>>> {
+        let filter = format!(
+            "{GENRE_FILTER}
+                AND ($filter_releases = false
+                    OR array::len(->artist_creates_album->(album WHERE {RELEASE_FILTER})) > 0)
+                AND ($filter_tempo = false
+                    OR array::len(->artist_performs_song->(song WHERE {TEMPO_FILTER})) > 0)
+                AND ($artist = NONE OR string::lowercase(name) CONTAINS $artist)
+                AND ($country_code = NONE OR country_code = $country_code)"
+        );
+        let query = RankedQuery {
+            table: "artist",
+            field: "name",
+            select: "*",
+            popularity: "$like_weight * (total_likes OR 0)",
+            filter: &filter,
+        };
+
+        Self::ranked(db, &query, term, options, |artist: &Artist| &artist.name).await
+    }
+
+    /// Public playlists only: a private playlist is never matched.
+    async fn search_playlists(
+        db: &Surreal<Any>,
+        term: &str,
+        options: &SearchOptions,
+    ) -> Result<PaginatedResponse<SearchHit<PlaylistWithOwner>>> {
+        let select = format!("*, created_by.{{{USER_PROFILE_FIELDS}}} AS created_by");
+        let query = RankedQuery {
+            table: "playlist",
+            field: "name",
+            select: &select,
+            popularity: "(total_listens OR 0) + $like_weight * (total_likes OR 0)",
+            filter: "is_public = true",
+        };
+
+        Self::ranked(db, &query, term, options, |playlist: &PlaylistWithOwner| {
+            &playlist.name
+        })
+        .await
+    }
+
+    async fn search_users(
+        db: &Surreal<Any>,
+        term: &str,
+        options: &SearchOptions,
+    ) -> Result<PaginatedResponse<SearchHit<UserProfile>>> {
+        let query = RankedQuery {
+            table: "user",
+            field: "username",
+            select: USER_PROFILE_FIELDS,
+            popularity: "(listen_count OR 0)",
+            filter: "true",
+        };
+
+        Self::ranked(db, &query, term, options, |user: &UserProfile| {
+            &user.username
+        })
+        .await
+    }
+
+    /// Runs `query` for the requested page and pairs each item with its score
+    /// and highlight. Without full-text terms, every row passing the filters
+    /// matches and the score is the popularity bonus alone.
+    async fn ranked<T, F>(
+        db: &Surreal<Any>,
+        query: &RankedQuery<'_>,
+        term: &str,
+        options: &SearchOptions,
+        text: F,
+    ) -> Result<PaginatedResponse<SearchHit<T>>>
+    where
+        T: DeserializeOwned,
+        F: Fn(&T) -> &str,
+    {
+        let RankedQuery {
+            table,
+            field,
+            select,
+            popularity,
+            filter,
+        } = query;
+        let (matches, relevance, offsets) = if term.is_empty() {
+            ("true".to_string(), "0", "NONE")
+        } else {
+            (
+                format!("{field} @1@ $term"),
+                "search::score(1)",
+                "search::offsets(1)",
+            )
+        };
+
+        let sql = format!(
+            "SELECT {select},
+                    {relevance} + $popularity_weight * math::ln(1 + {popularity}) AS score,
+                    {offsets} AS offsets
+                FROM {table}
+                WHERE {matches} AND {filter}
+                ORDER BY score DESC, {field} ASC
+                START $start LIMIT $limit;
+            SELECT count() AS total FROM {table} WHERE {matches} AND {filter} GROUP ALL;"
+        );
+
+        let mut res = db
+            .query(sql)
+            .bind(("term", term.to_string()))
+            .bind(("start", (options.page - 1) * options.page_size))
+            .bind(("limit", options.page_size))
+            .bind(("popularity_weight", POPULARITY_WEIGHT))
+            .bind(("like_weight", LIKE_WEIGHT))
+            .bind(("genres", options.genres.clone()))
+            .bind(("langs", options.langs.clone()))
+            .bind(("year_from", options.year_from))
+            .bind(("year_to", options.year_to))
+            .bind(("country_code", options.country_code.clone()))
+            .bind(("filter_albums", options.filters_albums()))
+            .bind(("filter_releases", options.filters_releases()))
+            .bind(("filter_tempo", options.filters_tempo()))
+            .bind(("tempo_min", options.tempo_min))
+            .bind(("tempo_max", options.tempo_max))
+            .bind(("artist", options.artist.clone()))
+            .await?;
+
+        // Les métadonnées sont extraites avant les éléments : un `#[serde(flatten)]`
+        // ne sait pas désérialiser les `Thing`
+        let scores: Vec<f32> = res.take((0, "score"))?;
+        let offsets: Vec<Option<HashMap<String, Vec<Offset>>>> = res.take((0, "offsets"))?;
+        let items: Vec<T> = res.take(0)?;
+        let count: Option<CountResult> = res.take(1)?;
+
+        let data = items
+            .into_iter()
+            .zip(scores)
+            .zip(offsets)
+            .map(|((item, score), offsets)| {
+                let ranges = offsets
+                    .and_then(|mut offsets| offsets.remove("0"))
+                    .unwrap_or_default();
+                let highlight = highlight(text(&item), &ranges);
+                SearchHit {
+                    item,
+                    score,
+                    highlight,
+                }
+            })
+            .collect();
+
+        let total_items = count.map(|count| count.total).unwrap_or(0);
+        let total_pages = total_items.div_ceil(u64::from(options.page_size)) as u32;
+
+        Ok(PaginatedResponse {
+            data,
+            pagination: PaginationInfo {
+                current_page: options.page,
+                total_pages,
+                total_items,
+                page_size: options.page_size,
+                has_next_page: options.page < total_pages,
+                has_previous_page: options.page > 1,
+            },
+        })
+    }
+}
+
+/// Turns punctuation (apostrophes, dashes, brackets...) into spaces, so
+/// `l’amour` finds `L'Amour` and `(live)` finds `Live`. Case and accents are
+/// folded by the `music_search` analyzer.
+fn normalize_query(term: &str) -> String {
+    term.chars()
+        .map(|c| if c.is_alphanumeric() { c } else { ' ' })
+        .collect::<String>()
+        .split_whitespace()
+        .collect::<Vec<_>>()
+        .join(" ")
+}
+
+/// Replaces each query word missing from `vocabulary` by the closest word of
+/// it (by edit distance, then frequency). `None` when nothing could be
+/// corrected.
+fn correct_query(vocabulary: &HashMap<String, u32>, query: &str) -> Option<String> {
+    let mut changed = false;
+    let corrected: Vec<String> = fold_words(query)
+        .into_iter()
+        .map(|word| {
+            if vocabulary.contains_key(&word) {
+                return word;
+            }
+            match closest_word(&word, vocabulary) {
+                Some(closest) => {
+                    changed = true;
+                    closest.to_string()
+                }
+                None => word,
+            }
+        })
+        .collect();
+
+    changed.then(|| corrected.join(" "))
+}
+
+/// Edits allowed when correcting a word: none for short words, where a single
+/// edit already lands on unrelated words.
+fn max_edits(len: usize) -> usize {
+    match len {
+        0..=3 => 0,
+        4..=7 => 1,
+        _ => 2,
+    }
+}
+
+fn closest_word<'a>(word: &str, vocabulary: &'a HashMap<String, u32>) -> Option<&'a str> {
+    let chars: Vec<char> = word.chars().collect();
+    let max = max_edits(chars.len());
+    if max == 0 {
+        return None;
+    }
+
+    vocabulary
+        .iter()
+        .filter(|(candidate, _)| candidate.chars().count().abs_diff(chars.len()) <= max)
+        .filter_map(|(candidate, &count)| {
+            let candidate_chars: Vec<char> = candidate.chars().collect();
+            let distance = edit_distance(&chars, &candidate_chars);
+            (distance <= max).then_some((distance, Reverse(count), candidate.as_str()))
         })
+        .min()
+        .map(|(_, _, candidate)| candidate)
+}
+
+/// Optimal string alignment distance: insertions, deletions, substitutions
+/// and swaps of two adjacent characters count as one edit.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut before: Vec<usize> = vec![0; b.len() + 1];
+    let mut previous: Vec<usize> = (0..=b.len()).collect();
+    let mut current: Vec<usize> = vec![0; b.len() + 1];
+
+    for i in 1..=a.len() {
+        current[0] = i;
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            current[j] = (previous[j] + 1)
+                .min(current[j - 1] + 1)
+                .min(previous[j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                current[j] = current[j].min(before[j - 2] + 1);
+            }
+        }
+        std::mem::swap(&mut before, &mut previous);
+        std::mem::swap(&mut previous, &mut current);
+    }
+
+    previous[b.len()]
+}
+
+/// HTML-escapes `text` and wraps the given character ranges in `<mark>`.
+fn highlight(text: &str, ranges: &[Offset]) -> String {
+    let mut html = String::with_capacity(text.len() + ranges.len() * 13);
+
+    for (i, c) in text.chars().enumerate() {
+        if ranges.iter().any(|range| range.s == i && range.e > i) {
+            html.push_str("<mark>");
+        }
+        match c {
+            '&' => html.push_str("&amp;"),
+            '<' => html.push_str("&lt;"),
+            '>' => html.push_str("&gt;"),
+            '"' => html.push_str("&quot;"),
+            '\'' => html.push_str("&#39;"),
+            c => html.push(c),
+        }
+        if ranges.iter().any(|range| range.e == i + 1 && range.s <= i) {
+            html.push_str("</mark>");
+        }
+    }
+
+    html
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::models::search::SearchQuery;
+    use surrealdb::engine::any::connect;
+
+    async fn setup_db() -> Surreal<Any> {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(include_str!("../../database_search.surql"))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        db
+    }
+
+    fn hits<T>(page: &Option<PaginatedResponse<T>>) -> &[T] {
+        page.as_ref()
+            .map(|page| page.data.as_slice())
+            .unwrap_or_default()
+    }
+
+    #[test]
+    fn test_highlight_escapes_and_marks_ranges() {
+        let ranges = [Offset { s: 0, e: 5 }, Offset { s: 8, e: 17 }];
+        assert_eq!(
+            highlight("Clash & Éléphants <live>", &ranges),
+            "<mark>Clash</mark> &amp; <mark>Éléphants</mark> &lt;live&gt;"
+        );
+        assert_eq!(highlight("Rock'n'roll", &[]), "Rock&#39;n&#39;roll");
+    }
+
+    #[test]
+    fn test_normalize_query_and_edit_distance() {
+        assert_eq!(normalize_query("  L’Amour - (Live)  "), "L Amour Live");
+        assert_eq!(normalize_query("Rock'n'Roll!"), "Rock n Roll");
+        assert_eq!(normalize_query("?!"), "");
+
+        let distance = |a: &str, b: &str| {
+            edit_distance(
+                &a.chars().collect::<Vec<_>>(),
+                &b.chars().collect::<Vec<_>>(),
+            )
+        };
+        assert_eq!(distance("beatles", "beatles"), 0);
+        assert_eq!(distance("beatels", "beatles"), 1);
+        assert_eq!(distance("kitten", "sitting"), 3);
+        assert_eq!(distance("", "abc"), 3);
+    }
+
+    #[tokio::test]
+    async fn test_search_folds_accents_and_suggests_corrections() {
+        let db = setup_db().await;
+
+        db.query(
+            r#"
+            CREATE artist:beatles SET name = 'The Beatles', genres = [], country_code = 'GB',
+                albums_count = 0, songs_count = 0;
+            CREATE song:chanson SET title = "Chânson d'Été", file_url = 'a.mp3', duration = 3m,
+                song_index = 1, tempo = 0.0;
+            CREATE song:amour SET title = "L'Amour (Live)", file_url = 'b.mp3', duration = 3m,
+                song_index = 2, tempo = 0.0;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let result = SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "chanson ete",
+            &SearchOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(hits(&result.songs).len(), 1);
+        assert_eq!(
+            hits(&result.songs)[0].highlight,
+            "<mark>Chânson</mark> d&#39;<mark>Été</mark>"
+        );
+        assert_eq!(result.did_you_mean, None);
+
+        let result = SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "l’amour (live)",
+            &SearchOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(hits(&result.songs).len(), 1);
+        assert_eq!(hits(&result.songs)[0].item.title, "L'Amour (Live)");
+
+        let result = SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "the beatels",
+            &SearchOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.did_you_mean.as_deref(), Some("the beatles"));
+        assert_eq!(hits(&result.artists).len(), 1);
+        assert_eq!(hits(&result.artists)[0].item.name, "The Beatles");
+
+        let result = SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "zzzzzzzz",
+            &SearchOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(result.is_empty());
+        assert_eq!(result.did_you_mean, None);
+    }
+
+    #[tokio::test]
+    async fn test_corrections_come_from_the_cached_vocabulary() {
+        let db = setup_db().await;
+        db.query(
+            r#"
+            CREATE artist:beatles SET name = 'The Beatles', genres = [], country_code = 'GB',
+                albums_count = 0, songs_count = 0;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let index = SuggestIndex::default();
+        let did_you_mean = |term: &'static str| {
+            let (db, index) = (&db, &index);
+            async move {
+                SearchService::search_albums_songs_artists(
+                    db,
+                    index,
+                    term,
+                    &SearchOptions::default(),
+                )
+                .await
+                .unwrap()
+                .did_you_mean
+            }
+        };
+        assert_eq!(
+            did_you_mean("the beatels").await.as_deref(),
+            Some("the beatles")
+        );
+
+        // Le vocabulaire n'est relu qu'après invalidation
+        db.query(
+            r#"
+            CREATE artist:kinks SET name = 'The Kinks', genres = [], country_code = 'GB',
+                albums_count = 0, songs_count = 0;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+        assert_eq!(did_you_mean("the kinsk").await, None);
+        index.invalidate();
+        assert_eq!(
+            did_you_mean("the kinsk").await.as_deref(),
+            Some("the kinks")
+        );
     }
 
-    async fn search_albums(db: &Surreal<Any>, term: &str) -> Result<Vec<AlbumWithArtists>> {
-        let sql = "
-            SELECT *, <-artist_creates_album<-artist.* AS artists
-            FROM album
-            WHERE string::lowercase(title) CONTAINS string::lowercase($term);
-        ";
+    #[tokio::test]
+    async fn test_search_ranks_by_relevance_and_popularity() {
+        let db = setup_db().await;
 
-        let mut res = db.query(sql).bind(("term", term.to_string())).await?;
-        let albums: Vec<AlbumWithArtists> = res.take(0)?;
-        Ok(albums)
+        db.query(
+            r#"
+            CREATE artist:clash SET name = 'The Clash', genres = [], country_code = 'GB',
+                albums_count = 1, songs_count = 3, total_likes = 40;
+            CREATE artist:other SET name = 'Other Band', genres = [], country_code = 'FR',
+                albums_count = 0, songs_count = 0;
+            CREATE album:london SET title = 'London Calling', genres = [], langs = [],
+                total_tracks = 2, total_duration = 6m, total_listens = 10;
+            CREATE song:calling SET title = 'London Calling', file_url = 'a.mp3', duration = 3m,
+                song_index = 1, tempo = 0.0, total_listens = 5;
+            CREATE song:calling_demo SET title = 'London Calling (Demo)', file_url = 'b.mp3',
+                duration = 3m, song_index = 2, tempo = 0.0, total_listens = 2000, total_likes = 50;
+            CREATE song:train SET title = 'Train in Vain', file_url = 'c.mp3', duration = 3m,
+                song_index = 3, tempo = 0.0;
+            RELATE artist:clash->artist_creates_album->album:london;
+            RELATE album:london->album_contains_song->song:calling;
+            RELATE artist:clash->artist_performs_song->song:calling;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let result = SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "LONDON calling",
+            &SearchOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(hits(&result.albums).len(), 1);
+        assert_eq!(hits(&result.albums)[0].item.artists[0].name, "The Clash");
+        assert_eq!(
+            hits(&result.albums)[0].highlight,
+            "<mark>London</mark> <mark>Calling</mark>"
+        );
+
+        // Les deux titres correspondent : la démo, bien plus écoutée, passe devant
+        let titles: Vec<&str> = hits(&result.songs)
+            .iter()
+            .map(|hit| hit.item.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["London Calling (Demo)", "London Calling"]);
+        assert!(hits(&result.songs)[0].score > hits(&result.songs)[1].score);
+        assert_eq!(
+            hits(&result.songs)[0].highlight,
+            "<mark>London</mark> <mark>Calling</mark> (Demo)"
+        );
+        assert_eq!(
+            hits(&result.songs)[1].item.album.as_ref().unwrap().title,
+            "London Calling"
+        );
+        assert!(hits(&result.artists).is_empty());
+
+        let result = SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "clash",
+            &SearchOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(hits(&result.artists).len(), 1);
+        assert_eq!(hits(&result.artists)[0].highlight, "The <mark>Clash</mark>");
+        assert!(hits(&result.albums).is_empty() && hits(&result.songs).is_empty());
+
+        // Un mot entier est requis, plus de sous-chaîne : le mot incomplet est corrigé
+        let result = SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "ondon",
+            &SearchOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert_eq!(result.did_you_mean.as_deref(), Some("london"));
+        assert_eq!(hits(&result.albums).len(), 1);
+    }
+
+    #[tokio::test]
+    async fn test_search_paginates_per_type_and_filters() {
+        let db = setup_db().await;
+
+        db.query(
+            r#"
+            CREATE artist:sham SET name = 'Sham Rock', genres = ['Oi'], country_code = 'GB',
+                albums_count = 1, songs_count = 3;
+            CREATE artist:fr SET name = 'Rock Français', genres = ['ChantMilitaire'],
+                country_code = 'FR', albums_count = 1, songs_count = 1;
+            CREATE album:oi SET title = 'Rock Anthems', genres = ['Oi'], langs = ['EN'],
+                release_year = 1978, total_tracks = 3, total_duration = 9m;
+            CREATE album:fr SET title = 'Rock en France', genres = ['Chant militaire'],
+                langs = ['fr'], release_year = 1994, total_tracks = 1, total_duration = 3m;
+            CREATE song:one SET title = 'Rock One', file_url = 'a.mp3', duration = 3m,
+                song_index = 1, tempo = 0.0, total_listens = 30;
+            CREATE song:two SET title = 'Rock Two', file_url = 'b.mp3', duration = 3m,
+                song_index = 2, tempo = 0.0, total_listens = 20;
+            CREATE song:three SET title = 'Rock Three', file_url = 'c.mp3', duration = 3m,
+                song_index = 3, tempo = 0.0, total_listens = 10;
+            CREATE song:quatre SET title = 'Rock Quatre', file_url = 'd.mp3', duration = 3m,
+                song_index = 1, tempo = 0.0;
+            RELATE artist:sham->artist_creates_album->album:oi;
+            RELATE artist:fr->artist_creates_album->album:fr;
+            RELATE album:oi->album_contains_song->[song:one, song:two, song:three];
+            RELATE album:fr->album_contains_song->song:quatre;
+            RELATE artist:sham->artist_performs_song->[song:one, song:two, song:three];
+            RELATE artist:fr->artist_performs_song->song:quatre;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let search = |options: SearchOptions| {
+            let db = db.clone();
+            async move {
+                SearchService::search_albums_songs_artists(
+                    &db,
+                    &SuggestIndex::default(),
+                    "rock",
+                    &options,
+                )
+                .await
+                .unwrap()
+            }
+        };
+        let query = SearchQuery {
+            term: "rock".to_string(),
+            types: Some("artists, albums".to_string()),
+            genres: Some("CHANT_MILITAIRE".to_string()),
+            country_code: Some(" fr ".to_string()),
+            ..Default::default()
+        };
+
+        // Seuls les types demandés sont renvoyés, page par page
+        let result = search(SearchOptions {
+            types: vec![SearchType::Song],
+            page: 2,
+            page_size: 2,
+            ..Default::default()
+        })
+        .await;
+        assert!(result.albums.is_none() && result.artists.is_none());
+        let songs = result.songs.unwrap();
+        assert_eq!(songs.pagination.total_items, 4);
+        assert_eq!(songs.pagination.total_pages, 2);
+        assert!(!songs.pagination.has_next_page && songs.pagination.has_previous_page);
+        let titles: Vec<&str> = songs
+            .data
+            .iter()
+            .map(|hit| hit.item.title.as_str())
+            .collect();
+        assert_eq!(titles, vec!["Rock Three", "Rock Quatre"]);
+
+        let result = search(SearchOptions {
+            genres: vec!["oi".to_string()],
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(hits(&result.albums).len(), 1);
+        assert_eq!(hits(&result.albums)[0].item.title, "Rock Anthems");
+        assert_eq!(hits(&result.songs).len(), 3);
+        assert_eq!(hits(&result.artists).len(), 1);
+        assert_eq!(hits(&result.artists)[0].item.name, "Sham Rock");
+
+        // Le genre d'artiste est comparé sans séparateurs ni casse
+        let result = search(SearchOptions::from_query(&query).unwrap()).await;
+        assert_eq!(hits(&result.artists)[0].item.name, "Rock Français");
+        assert_eq!(hits(&result.albums)[0].item.title, "Rock en France");
+
+        let result = search(SearchOptions {
+            langs: vec!["fr".to_string()],
+            year_from: Some(1990),
+            year_to: Some(1999),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(hits(&result.albums).len(), 1);
+        assert_eq!(hits(&result.songs).len(), 1);
+        assert_eq!(hits(&result.songs)[0].item.title, "Rock Quatre");
+        assert_eq!(hits(&result.artists)[0].item.name, "Rock Français");
+
+        let result = search(SearchOptions {
+            country_code: Some("GB".to_string()),
+            ..Default::default()
+        })
+        .await;
+        assert_eq!(hits(&result.albums)[0].item.title, "Rock Anthems");
+        assert_eq!(hits(&result.songs).len(), 3);
+        assert_eq!(hits(&result.artists).len(), 1);
+
+        let result = search(SearchOptions {
+            year_to: Some(1970),
+            ..Default::default()
+        })
+        .await;
+        assert!(result.is_empty());
+
+        let options = SearchOptions::from_query(&query).unwrap();
+        assert_eq!(options.types, vec![SearchType::Artist, SearchType::Album]);
+        assert_eq!(options.genres, vec!["chantmilitaire"]);
+        assert_eq!(options.country_code.as_deref(), Some("FR"));
+        assert!(SearchOptions::from_query(&SearchQuery {
+            types: Some("podcast".to_string()),
+            ..Default::default()
+        })
+        .is_err());
+        assert!(SearchOptions::from_query(&SearchQuery {
+            year_from: Some(2000),
+            year_to: Some(1990),
+            ..Default::default()
+        })
+        .is_err());
     }
 
-    async fn search_songs(db: &Surreal<Any>, term: &str) -> Result<Vec<SongWithRelations>> {
-        let sql = "SELECT *,
-            (SELECT * FROM <-artist_performs_song<-artist) AS artists,
-            (SELECT * FROM <-album_contains_song<-album)[0] AS album
-        FROM song
-        WHERE string::lowercase(title) CONTAINS string::lowercase($term);";
+    #[tokio::test]
+    async fn test_search_structured_query() {
+        let db = setup_db().await;
+
+        db.query(
+            r#"
+            CREATE artist:clash SET name = 'The Clash', genres = ['Oi'], country_code = 'GB',
+                albums_count = 1, songs_count = 2;
+            CREATE artist:jam SET name = 'The Jam', genres = ['Oi'], country_code = 'GB',
+                albums_count = 1, songs_count = 1;
+            CREATE album:london SET title = 'London Calling', genres = ['Oi'], langs = ['en'],
+                release_year = 1979, total_tracks = 2, total_duration = 6m;
+            CREATE album:sound SET title = 'Sound Affects', genres = ['Oi'], langs = ['en'],
+                release_year = 1980, total_tracks = 1, total_duration = 3m;
+            CREATE song:calling SET title = 'London Calling', file_url = 'a.mp3', duration = 3m,
+                song_index = 1, tempo = 134.0;
+            CREATE song:guns SET title = 'Guns of Brixton', file_url = 'b.mp3', duration = 3m,
+                song_index = 2, tempo = 0.0;
+            CREATE song:start SET title = 'Start!', file_url = 'c.mp3', duration = 3m,
+                song_index = 1, tempo = 150.0;
+            RELATE artist:clash->artist_creates_album->album:london;
+            RELATE artist:jam->artist_creates_album->album:sound;
+            RELATE album:london->album_contains_song->[song:calling, song:guns];
+            RELATE album:sound->album_contains_song->song:start;
+            RELATE artist:clash->artist_performs_song->[song:calling, song:guns];
+            RELATE artist:jam->artist_performs_song->song:start;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let search = |term: &'static str| {
+            let db = db.clone();
+            async move {
+                SearchService::search_albums_songs_artists(
+                    &db,
+                    &SuggestIndex::default(),
+                    term,
+                    &SearchOptions::default(),
+                )
+                .await
+            }
+        };
+        let titles = |hits: &[SearchHit<SongWithRelations>]| {
+            hits.iter()
+                .map(|hit| hit.item.title.clone())
+                .collect::<Vec<_>>()
+        };
+
+        // Des champs seuls, sans texte : tout le catalogue filtré
+        let result = search(r#"artist:"the clash" year:1975..1979"#)
+            .await
+            .unwrap();
+        assert_eq!(hits(&result.albums).len(), 1);
+        assert_eq!(
+            titles(hits(&result.songs)),
+            vec!["Guns of Brixton", "London Calling"]
+        );
+        assert_eq!(hits(&result.artists)[0].item.name, "The Clash");
+        assert_eq!(
+            hits(&result.songs)[1].highlight,
+            "London Calling",
+            "no term, nothing to highlight"
+        );
+
+        // Les morceaux sans tempo mesuré sont exclus
+        let result = search("tempo:>130 type:songs").await.unwrap();
+        assert!(result.albums.is_none() && result.artists.is_none());
+        assert_eq!(
+            titles(hits(&result.songs)),
+            vec!["London Calling", "Start!"]
+        );
 
-        let mut res = db.query(sql).bind(("term", term.to_string())).await?;
-        let songs: Vec<SongWithRelations> = res.take(0)?;
-        Ok(songs)
+        let result = search("tempo:140.. genre:OI lang:EN").await.unwrap();
+        assert_eq!(titles(hits(&result.songs)), vec!["Start!"]);
+        assert_eq!(hits(&result.albums)[0].item.title, "Sound Affects");
+        assert_eq!(hits(&result.artists)[0].item.name, "The Jam");
+
+        // Texte et champs combinés
+        let result = search("calling artist:clash").await.unwrap();
+        assert_eq!(titles(hits(&result.songs)), vec!["London Calling"]);
+        assert_eq!(
+            hits(&result.songs)[0].highlight,
+            "London <mark>Calling</mark>"
+        );
+        assert!(search("calling artist:jam").await.unwrap().is_empty());
+
+        assert!(matches!(
+            search("label:cbs").await,
+            Err(crate::Error::InvalidInput { .. })
+        ));
     }
 
-    async fn search_artists(db: &Surreal<Any>, term: &str) -> Result<Vec<Artist>> {
-        let sql = "
-            SELECT *
-            FROM artist
-            WHERE string::lowercase(name) CONTAINS string::lowercase($term);
-        ";
-        let mut res = db.query(sql).bind(("term", term.to_string())).await?;
-        let artists: Vec<Artist> = res.take(0)?;
-        Ok(artists)
+    #[tokio::test]
+    async fn test_search_public_playlists_and_users() {
+        let db = setup_db().await;
+
+        db.query(
+            r#"
+            CREATE user:alice SET username = 'alice_punk', password = 'hash', created_at = time::now(),
+                listen_count = 10, total_listening_time = 0, favorite_count = 0,
+                listening_streak = 0, badges = [], level = 0, experience_points = 0, role = 'user';
+            CREATE user:bob SET username = 'bob', password = 'hash', created_at = time::now(),
+                listen_count = 0, total_listening_time = 0, favorite_count = 0,
+                listening_streak = 0, badges = [], level = 0, experience_points = 0, role = 'user';
+            CREATE playlist:public SET name = 'Punk Classics', is_public = true,
+                created_by = user:alice, created_at = time::now(), updated_at = time::now(),
+                songs_count = 0, total_duration = 0s, total_listens = 0, total_likes = 0;
+            CREATE playlist:private SET name = 'Punk Secrets', is_public = false,
+                created_by = user:bob, created_at = time::now(), updated_at = time::now(),
+                songs_count = 0, total_duration = 0s, total_listens = 0, total_likes = 0;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let result = SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "punk",
+            &SearchOptions::default(),
+        )
+        .await
+        .unwrap();
+
+        // La playlist privée n'apparaît jamais
+        let playlists = result.playlists.as_ref().unwrap();
+        assert_eq!(playlists.pagination.total_items, 1);
+        assert_eq!(playlists.data[0].item.name, "Punk Classics");
+        assert_eq!(playlists.data[0].highlight, "<mark>Punk</mark> Classics");
+        assert_eq!(playlists.data[0].item.created_by.username, "alice_punk");
+
+        assert_eq!(hits(&result.users).len(), 1);
+        assert_eq!(hits(&result.users)[0].item.username, "alice_punk");
+        let json = serde_json::to_string(&result).unwrap();
+        assert!(!json.contains("password") && !json.contains("hash"));
+
+        assert!(SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "secrets",
+            &SearchOptions::default()
+        )
+        .await
+        .unwrap()
+        .is_empty());
+
+        // Seuls les types demandés, et aucun avec des filtres de catalogue
+        let result = SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "bob type:users",
+            &SearchOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(result.playlists.is_none() && result.songs.is_none());
+        assert_eq!(hits(&result.users)[0].item.username, "bob");
+
+        let result = SearchService::search_albums_songs_artists(
+            &db,
+            &SuggestIndex::default(),
+            "punk genre:oi",
+            &SearchOptions::default(),
+        )
+        .await
+        .unwrap();
+        assert!(result.playlists.is_none() && result.users.is_none());
     }
 }
@@ -1,15 +1,23 @@
-use crate::error::Result;
-use crate::helpers::thing_helpers::{create_song_thing, create_user_thing};
+use crate::error::{Error, Result};
+use crate::helpers::album_helpers::album_exists;
+use crate::helpers::artist_helpers::artist_exists;
+use crate::helpers::song_helpers::song_exists;
+use crate::helpers::thing_helpers::{
+    create_album_thing, create_artist_thing, create_song_thing, create_user_thing,
+};
 use crate::models::album::AlbumWithRelations;
 use crate::models::database_helpers::CountResult;
 use crate::models::pagination::{PaginatedResponse, PaginationInfo, PaginationQuery};
-use crate::models::song::{Song, SongWithRelations};
+use crate::models::song::{CreateSongRequest, Song, SongWithRelations, UpdateSongRequest};
+use crate::services::album_service::AlbumService;
+use crate::services::artist_service::ArtistService;
 use crate::services::badge_service::{BadgeService, BadgeUnlockResult};
+use crate::services::playlist_service::PlaylistService;
 use serde::{Deserialize, Serialize};
 use serde_json::{json, Value};
 use surrealdb::{
     engine::any::Any,
-    sql::{Duration, Thing},
+    sql::{Duration, Id, Thing},
     Surreal,
 };
 
@@ -122,9 +130,17 @@ impl SongService {
             badge_result = Some(BadgeService::check_badges_after_listen(db, user_thing).await?);
         }
 
-        db.query("UPDATE $song_id SET total_listens = (total_listens OR 0) + 1")
-            .bind(("song_id", song_thing))
-            .await?;
+        // Le journal des écoutes, y compris anonymes, alimente les classements
+        db.query(
+            "
+            UPDATE $song_id SET total_listens = (total_listens OR 0) + 1;
+            CREATE listen_event SET song = $song_id, user = $user_id, listened_at = time::now();
+            ",
+        )
+        .bind(("song_id", song_thing))
+        .bind(("user_id", user_id.map(create_user_thing)))
+        .await?
+        .check()?;
 
         Ok(ListenResult {
             success: true,
@@ -202,6 +218,7 @@ impl SongService {
                     duration: res.out.duration,
                     song_index: res.out.song_index,
                     tempo: res.out.tempo,
+                    loudness: res.out.loudness,
                     total_listens: res.out.total_listens,
                     total_user_listens: res.out.total_user_listens,
                     total_likes: res.out.total_likes,
@@ -288,6 +305,7 @@ impl SongService {
                     "duration": s.duration.to_string(),
                     "song_index": s.song_index,
                     "tempo": s.tempo,
+                    "loudness": s.loudness,
                     "total_listens": s.total_listens,
                     "total_user_listens": s.total_user_listens,
                     "total_likes": s.total_likes,
@@ -350,6 +368,7 @@ impl SongService {
                     "duration": row.song.duration.to_string(),
                     "song_index": row.song.song_index,
                     "tempo": row.song.tempo,
+                    "loudness": row.song.loudness,
                     "total_listens": row.song.total_listens,
                     "total_user_listens": row.song.total_user_listens,
                     "total_likes": row.song.total_likes,
@@ -379,6 +398,434 @@ impl SongService {
 
         Ok(album)
     }
+
+    pub async fn create_song(db: &Surreal<Any>, payload: CreateSongRequest) -> Result<Song> {
+        let title = payload.title.trim().to_string();
+        if title.is_empty() {
+            return Err(Error::InvalidInput {
+                reason: "Song title cannot be empty".to_string(),
+            });
+        }
+        if payload.file_url.trim().is_empty() {
+            return Err(Error::InvalidInput {
+                reason: "Song file_url cannot be empty".to_string(),
+            });
+        }
+
+        let album_thing = match &payload.album_id {
+            Some(album_id) => {
+                if !album_exists(db, album_id).await? {
+                    return Err(Error::AlbumNotFound {
+                        id: album_id.to_string(),
+                    });
+                }
+                Some(create_album_thing(album_id))
+            }
+            None => None,
+        };
+
+        let mut artist_things = Vec::with_capacity(payload.artist_ids.len());
+        for artist_id in &payload.artist_ids {
+            if !artist_exists(db, artist_id).await? {
+                return Err(Error::ArtistNotFound {
+                    id: artist_id.to_string(),
+                });
+            }
+            artist_things.push(create_artist_thing(artist_id));
+        }
+
+        let song_thing = Thing::from(("song", Id::rand()));
+
+        let create_query = r#"
+            BEGIN TRANSACTION;
+
+            CREATE $song SET
+                title = $title,
+                file_url = $file_url,
+                duration = $duration,
+                song_index = $song_index,
+                tempo = $tempo,
+                total_listens = 0,
+                total_user_listens = 0,
+                total_likes = 0;
+
+            IF $album != NONE THEN
+                RELATE $album->album_contains_song->$song
+            END;
+
+            FOR $artist IN $artists {
+                RELATE $artist->artist_performs_song->$song;
+            };
+
+            COMMIT TRANSACTION;
+        "#;
+
+        db.query(create_query)
+            .bind(("song", song_thing.clone()))
+            .bind(("title", title))
+            .bind(("file_url", payload.file_url))
+            .bind(("duration", Duration::from_secs(payload.duration_secs)))
+            .bind(("song_index", payload.song_index))
+            .bind(("tempo", payload.tempo))
+            .bind(("album", album_thing.clone()))
+            .bind(("artists", artist_things.clone()))
+            .await?
+            .check()?;
+
+        if let Some(album_thing) = &album_thing {
+            AlbumService::refresh_aggregates(db, album_thing).await?;
+        }
+        for artist_thing in &artist_things {
+            ArtistService::refresh_counts(db, artist_thing).await?;
+        }
+
+        Self::get_song_by_id(db, &song_thing.id.to_raw())
+            .await?
+            .ok_or(Error::DbError("Could not create song".into()))
+    }
+
+    pub async fn update_song(
+        db: &Surreal<Any>,
+        song_id: &str,
+        payload: UpdateSongRequest,
+    ) -> Result<Song> {
+        if !song_exists(db, song_id).await? {
+            return Err(Error::SongNotFound {
+                id: song_id.to_string(),
+            });
+        }
+
+        let mut patch = serde_json::Map::new();
+        if let Some(title) = payload.title {
+            if title.trim().is_empty() {
+                return Err(Error::InvalidInput {
+                    reason: "Song title cannot be empty".to_string(),
+                });
+            }
+            patch.insert("title".into(), json!(title.trim()));
+        }
+        if let Some(file_url) = payload.file_url {
+            patch.insert("file_url".into(), json!(file_url));
+        }
+        if let Some(song_index) = payload.song_index {
+            patch.insert("song_index".into(), json!(song_index));
+        }
+        if let Some(tempo) = payload.tempo {
+            patch.insert("tempo".into(), json!(tempo));
+        }
+
+        let song_thing = create_song_thing(song_id);
+
+        db.query("UPDATE $song MERGE $patch")
+            .bind(("song", song_thing.clone()))
+            .bind(("patch", Value::Object(patch)))
+            .await?
+            .check()?;
+
+        // The duration must stay a SurrealDB duration, it can't go through the JSON patch
+        if let Some(duration_secs) = payload.duration_secs {
+            db.query("UPDATE $song SET duration = $duration")
+                .bind(("song", song_thing.clone()))
+                .bind(("duration", Duration::from_secs(duration_secs)))
+                .await?
+                .check()?;
+
+            Self::refresh_song_albums(db, &song_thing).await?;
+            Self::refresh_song_playlists(db, &song_thing).await?;
+        }
+
+        Self::get_song_by_id(db, song_id)
+            .await?
+            .ok_or(Error::SongNotFound {
+                id: song_id.to_string(),
+            })
+    }
+
+    /// Deletes a song along with its album, artist, playlist, like and listen edges.
+    pub async fn delete_song(db: &Surreal<Any>, song_id: &str) -> Result<()> {
+        if !song_exists(db, song_id).await? {
+            return Err(Error::SongNotFound {
+                id: song_id.to_string(),
+            });
+        }
+
+        let song_thing = create_song_thing(song_id);
+
+        let delete_query = r#"
+            LET $albums = (SELECT VALUE in FROM album_contains_song WHERE out = $song);
+            LET $artists = (SELECT VALUE in FROM artist_performs_song WHERE out = $song);
+
+            BEGIN TRANSACTION;
+            DELETE album_contains_song WHERE out = $song;
+            DELETE artist_performs_song WHERE out = $song;
+            DELETE playlist_contains_song WHERE out = $song;
+            DELETE user_likes_song WHERE out = $song;
+            DELETE user_listens_song WHERE out = $song;
+            DELETE analysis_failure WHERE song = $song;
+            DELETE song_waveform WHERE song = $song;
+            DELETE song_fingerprint WHERE song = $song;
+            DELETE $song;
+            COMMIT TRANSACTION;
+
+            RETURN { albums: $albums, artists: $artists };
+        "#;
+
+        #[derive(Deserialize)]
+        struct Linked {
+            albums: Vec<Thing>,
+            artists: Vec<Thing>,
+        }
+
+        let mut response = db
+            .query(delete_query)
+            .bind(("song", song_thing))
+            .await?;
+
+        let linked: Option<Linked> = response.take(response.num_statements() - 1)?;
+        if let Some(linked) = linked {
+            for album_thing in &linked.albums {
+                AlbumService::refresh_aggregates(db, album_thing).await?;
+            }
+            for artist_thing in &linked.artists {
+                ArtistService::refresh_counts(db, artist_thing).await?;
+            }
+        }
+
+        Ok(())
+    }
+
+    /// Merges duplicate songs into `song_id`: listens are summed per user,
+    /// likes, playlist entries, albums and artists are carried over, then the
+    /// duplicates are deleted.
+    pub async fn merge_songs(
+        db: &Surreal<Any>,
+        song_id: &str,
+        duplicate_ids: &[String],
+    ) -> Result<Song> {
+        if !song_exists(db, song_id).await? {
+            return Err(Error::SongNotFound {
+                id: song_id.to_string(),
+            });
+        }
+
+        let survivor = create_song_thing(song_id);
+        let mut duplicates: Vec<Thing> = Vec::new();
+        for duplicate_id in duplicate_ids {
+            let duplicate = create_song_thing(duplicate_id);
+            if duplicate == survivor {
+                return Err(Error::InvalidInput {
+                    reason: "A song cannot be merged into itself".to_string(),
+                });
+            }
+            if !song_exists(db, duplicate_id).await? {
+                return Err(Error::SongNotFound {
+                    id: duplicate_id.to_string(),
+                });
+            }
+            if !duplicates.contains(&duplicate) {
+                duplicates.push(duplicate);
+            }
+        }
+        if duplicates.is_empty() {
+            return Err(Error::InvalidInput {
+                reason: "No duplicate to merge".to_string(),
+            });
+        }
+
+        let merge_query = r#"
+            LET $songs = array::concat([$survivor], $duplicates);
+            LET $albums = array::distinct(SELECT VALUE in FROM album_contains_song WHERE out INSIDE $songs);
+            LET $artists = array::distinct(SELECT VALUE in FROM artist_performs_song WHERE out INSIDE $songs);
+
+            BEGIN TRANSACTION;
+
+            -- Écoutes : une seule relation par utilisateur, les compteurs sont additionnés
+            FOR $listen IN (SELECT * FROM user_listens_song WHERE out INSIDE $duplicates) {
+                LET $user = $listen.in;
+                LET $existing = (SELECT * FROM user_listens_song WHERE in = $user AND out = $survivor)[0];
+                IF $existing {
+                    UPDATE $existing.id SET
+                        total_listens = (total_listens OR 0) + ($listen.total_listens OR 0),
+                        total_duration = (total_duration OR 0s) + ($listen.total_duration OR 0s),
+                        recent_dates = array::slice(
+                            array::sort::desc(array::concat(recent_dates OR [], $listen.recent_dates OR [])),
+                            0,
+                            30
+                        ),
+                        first_listened_at = IF $listen.first_listened_at < first_listened_at
+                            THEN $listen.first_listened_at ELSE first_listened_at END,
+                        last_listened_at = IF $listen.last_listened_at > last_listened_at
+                            THEN $listen.last_listened_at ELSE last_listened_at END;
+                } ELSE {
+                    RELATE $user->user_listens_song->$survivor SET
+                        total_listens = $listen.total_listens OR 0,
+                        total_duration = $listen.total_duration OR 0s,
+                        recent_dates = $listen.recent_dates OR [],
+                        first_listened_at = $listen.first_listened_at OR time::now(),
+                        last_listened_at = $listen.last_listened_at OR time::now();
+                };
+            };
+
+            FOR $like IN (SELECT * FROM user_likes_song WHERE out INSIDE $duplicates) {
+                LET $user = $like.in;
+                IF array::len(SELECT id FROM user_likes_song WHERE in = $user AND out = $survivor) = 0 {
+                    RELATE $user->user_likes_song->$survivor SET
+                        created_at = $like.created_at OR time::now(),
+                        sort_order = $like.sort_order OR 0,
+                        last_accessed = $like.last_accessed;
+                };
+            };
+
+            FOR $entry IN (SELECT * FROM playlist_contains_song WHERE out INSIDE $duplicates) {
+                LET $playlist = $entry.in;
+                IF array::len(SELECT id FROM playlist_contains_song WHERE in = $playlist AND out = $survivor) = 0 {
+                    RELATE $playlist->playlist_contains_song->$survivor SET
+                        added_at = $entry.added_at OR time::now(),
+                        added_by = $entry.added_by;
+                };
+            };
+
+            FOR $album IN $albums {
+                IF array::len(SELECT id FROM album_contains_song WHERE in = $album AND out = $survivor) = 0 {
+                    RELATE $album->album_contains_song->$survivor;
+                };
+            };
+
+            FOR $artist IN $artists {
+                IF array::len(SELECT id FROM artist_performs_song WHERE in = $artist AND out = $survivor) = 0 {
+                    RELATE $artist->artist_performs_song->$survivor;
+                };
+            };
+
+            LET $listens = math::sum(SELECT VALUE total_listens OR 0 FROM $duplicates);
+
+            -- Le journal des écoutes reste attribué au titre conservé
+            UPDATE listen_event SET song = $survivor WHERE song INSIDE $duplicates;
+
+            DELETE album_contains_song WHERE out INSIDE $duplicates;
+            DELETE artist_performs_song WHERE out INSIDE $duplicates;
+            DELETE playlist_contains_song WHERE out INSIDE $duplicates;
+            DELETE user_likes_song WHERE out INSIDE $duplicates;
+            DELETE user_listens_song WHERE out INSIDE $duplicates;
+            DELETE analysis_failure WHERE song INSIDE $duplicates;
+            DELETE song_waveform WHERE song INSIDE $duplicates;
+            DELETE song_fingerprint WHERE song INSIDE $duplicates;
+            DELETE $duplicates;
+
+            UPDATE $survivor SET total_listens = (total_listens OR 0) + $listens;
+
+            COMMIT TRANSACTION;
+
+            -- Les relations créées ci-dessus ne sont comptées qu'une fois validées
+            UPDATE $survivor SET
+                total_user_listens = array::len(SELECT id FROM user_listens_song WHERE out = $survivor),
+                total_likes = array::len(SELECT id FROM user_likes_song WHERE out = $survivor);
+
+            RETURN { albums: $albums, artists: $artists };
+        "#;
+
+        #[derive(Deserialize)]
+        struct Linked {
+            albums: Vec<Thing>,
+            artists: Vec<Thing>,
+        }
+
+        let mut response = db
+            .query(merge_query)
+            .bind(("survivor", survivor))
+            .bind(("duplicates", duplicates))
+            .await?
+            .check()?;
+
+        let linked: Option<Linked> = response.take(response.num_statements() - 1)?;
+        if let Some(linked) = linked {
+            for album_thing in &linked.albums {
+                AlbumService::refresh_aggregates(db, album_thing).await?;
+            }
+            for artist_thing in &linked.artists {
+                ArtistService::refresh_counts(db, artist_thing).await?;
+            }
+        }
+
+        Self::get_song_by_id(db, song_id)
+            .await?
+            .ok_or(Error::SongNotFound {
+                id: song_id.to_string(),
+            })
+    }
+
+    pub async fn link_artist(db: &Surreal<Any>, song_id: &str, artist_id: &str) -> Result<()> {
+        if !song_exists(db, song_id).await? {
+            return Err(Error::SongNotFound {
+                id: song_id.to_string(),
+            });
+        }
+        if !artist_exists(db, artist_id).await? {
+            return Err(Error::ArtistNotFound {
+                id: artist_id.to_string(),
+            });
+        }
+
+        let artist_thing = create_artist_thing(artist_id);
+
+        // Linking twice is a no-op
+        let link_query = r#"
+            IF array::len(SELECT id FROM artist_performs_song WHERE in = $artist AND out = $song) = 0 THEN
+                RELATE $artist->artist_performs_song->$song
+            END;
+        "#;
+
+        db.query(link_query)
+            .bind(("artist", artist_thing.clone()))
+            .bind(("song", create_song_thing(song_id)))
+            .await?
+            .check()?;
+
+        ArtistService::refresh_counts(db, &artist_thing).await
+    }
+
+    pub async fn unlink_artist(db: &Surreal<Any>, song_id: &str, artist_id: &str) -> Result<()> {
+        let artist_thing = create_artist_thing(artist_id);
+
+        db.query("DELETE artist_performs_song WHERE in = $artist AND out = $song")
+            .bind(("artist", artist_thing.clone()))
+            .bind(("song", create_song_thing(song_id)))
+            .await?
+            .check()?;
+
+        ArtistService::refresh_counts(db, &artist_thing).await
+    }
+
+    /// Recomputes the aggregates of every album containing the song.
+    async fn refresh_song_albums(db: &Surreal<Any>, song_thing: &Thing) -> Result<()> {
+        let albums: Vec<Thing> = db
+            .query("SELECT VALUE in FROM album_contains_song WHERE out = $song")
+            .bind(("song", song_thing.clone()))
+            .await?
+            .take(0)?;
+
+        for album_thing in &albums {
+            AlbumService::refresh_aggregates(db, album_thing).await?;
+        }
+
+        Ok(())
+    }
+
+    /// Recomputes the song count and total duration of every playlist
+    /// containing the song.
+    async fn refresh_song_playlists(db: &Surreal<Any>, song_thing: &Thing) -> Result<()> {
+        let playlists: Vec<Thing> = db
+            .query("RETURN array::distinct(SELECT VALUE in FROM playlist_contains_song WHERE out = $song)")
+            .bind(("song", song_thing.clone()))
+            .await?
+            .take(0)?;
+
+        for playlist_thing in &playlists {
+            PlaylistService::update_playlist_stats(db, &playlist_thing.id.to_raw()).await?;
+        }
+
+        Ok(())
+    }
 }
 
 #[cfg(test)]
@@ -388,7 +835,7 @@ mod tests {
     use crate::models::artist::Artist;
     use crate::models::music_genre::MusicGenre;
     use crate::models::song::Song;
-    use crate::models::user::UserRecord;
+    use crate::models::user::{Role, UserRecord};
     use surrealdb::engine::any::connect;
     use surrealdb::{sql::Duration, Datetime};
 
@@ -411,6 +858,7 @@ mod tests {
             badges: Vec::new(),
             level: 0,
             experience_points: 0,
+            role: Role::User,
         };
 
         let created_user: UserRecord = db
@@ -431,6 +879,7 @@ mod tests {
             duration: Duration::new(180, 0),
             song_index: 1,
             tempo: 120.0,
+            loudness: None,
             total_listens: 0,
             total_user_listens: 0,
             total_likes: 0,
@@ -451,10 +900,12 @@ mod tests {
             id: Some(crate::helpers::thing_helpers::create_album_thing(id)),
             title: title.to_string(),
             cover_url: Some(format!("/covers/{}.jpg", id)),
+            cover_variants: None,
             release_year: Some(2024),
             genres: vec!["Rock".to_string()],
             langs: vec!["en".to_string()],
             dominant_color: Some("#FF0000".to_string()),
+            loudness: None,
             total_tracks: 0,
             total_duration: Duration::new(0, 0),
             total_listens: 0,
@@ -479,6 +930,7 @@ mod tests {
             genres: vec![MusicGenre::Rac],
             country_code: "US".to_string(),
             artist_image: Some(format!("/artists/{}.jpg", id)),
+            image_variants: None,
             albums_count: 0,
             songs_count: 0,
             total_likes: 0,
@@ -966,6 +1418,7 @@ mod tests {
             duration: Duration::new(0, 0), // 0 seconds
             song_index: 1,
             tempo: 120.0,
+            loudness: None,
             total_listens: 0,
             total_user_listens: 0,
             total_likes: 0,
@@ -1039,6 +1492,7 @@ mod tests {
             badges: Vec::new(),
             level: 9999,
             experience_points: 999_999_999,
+            role: Role::User,
         };
 
         let user: UserRecord = db
@@ -1183,4 +1637,293 @@ mod tests {
             .unwrap();
         assert_eq!(song[0].total_listens, 7); // 5 + 2
     }
+
+    #[tokio::test]
+    async fn test_create_song_with_album_and_artists() {
+        let db = setup_db().await;
+        let album_id = create_test_album(&db, "album1", "Test Album").await;
+        let artist_id = create_test_artist(&db, "artist1", "Test Artist").await;
+
+        let song = SongService::create_song(
+            &db,
+            CreateSongRequest {
+                title: "New Song".to_string(),
+                file_url: "/songs/new.mp3".to_string(),
+                duration_secs: 200,
+                song_index: 1,
+                tempo: 0.0,
+                album_id: Some(album_id.clone()),
+                artist_ids: vec![artist_id.clone()],
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(song.title, "New Song");
+        assert_eq!(song.duration, Duration::new(200, 0));
+
+        let song_id = song.id.unwrap().id.to_raw();
+        let album = SongService::get_album_from_song(&db, &song_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(album.total_tracks, 1);
+        assert_eq!(album.total_duration, Duration::new(200, 0));
+
+        let artist: Option<Artist> = db
+            .query("SELECT * FROM $artist")
+            .bind(("artist", create_artist_thing(&artist_id)))
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(artist.unwrap().songs_count, 1);
+    }
+
+    #[tokio::test]
+    async fn test_update_song_duration_refreshes_album() {
+        let db = setup_db().await;
+        let album_id = create_test_album(&db, "album1", "Test Album").await;
+        let song_id = create_test_song(&db, "song1", "Test Song").await;
+        AlbumService::add_song(&db, &album_id, &song_id).await.unwrap();
+
+        let song = SongService::update_song(
+            &db,
+            &song_id,
+            UpdateSongRequest {
+                title: Some("Renamed".to_string()),
+                duration_secs: Some(300),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        assert_eq!(song.title, "Renamed");
+        assert_eq!(song.duration, Duration::new(300, 0));
+
+        let album = SongService::get_album_from_song(&db, &song_id)
+            .await
+            .unwrap()
+            .unwrap();
+        assert_eq!(album.total_duration, Duration::new(300, 0));
+    }
+
+    #[tokio::test]
+    async fn test_update_song_duration_refreshes_playlists() {
+        let db = setup_db().await;
+        let user_id = create_test_user(&db, "user1").await;
+        let song_id = create_test_song(&db, "song1", "Test Song").await;
+        let other_id = create_test_song(&db, "song2", "Other Song").await;
+        db.query(
+            r#"
+            CREATE playlist:p1 SET name = 'Mix', total_duration = 0s;
+            RELATE playlist:p1->playlist_contains_song->$song SET added_by = $user;
+            RELATE playlist:p1->playlist_contains_song->$other SET added_by = $user;
+            "#,
+        )
+        .bind(("user", create_user_thing(&user_id)))
+        .bind(("song", create_song_thing(&song_id)))
+        .bind(("other", create_song_thing(&other_id)))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let other = SongService::get_song_by_id(&db, &other_id).await.unwrap().unwrap();
+
+        SongService::update_song(
+            &db,
+            &song_id,
+            UpdateSongRequest {
+                duration_secs: Some(300),
+                ..Default::default()
+            },
+        )
+        .await
+        .unwrap();
+
+        let total_duration: Option<Duration> = db
+            .query("SELECT VALUE total_duration FROM ONLY playlist:p1")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(
+            total_duration,
+            Some(Duration::new(300 + other.duration.as_secs(), 0))
+        );
+    }
+
+    #[tokio::test]
+    async fn test_delete_song_refreshes_aggregates() {
+        let db = setup_db().await;
+        let album_id = create_test_album(&db, "album1", "Test Album").await;
+        let artist_id = create_test_artist(&db, "artist1", "Test Artist").await;
+        let song_id = create_test_song(&db, "song1", "Test Song").await;
+        AlbumService::add_song(&db, &album_id, &song_id).await.unwrap();
+        SongService::link_artist(&db, &song_id, &artist_id).await.unwrap();
+
+        SongService::delete_song(&db, &song_id).await.unwrap();
+
+        assert!(SongService::get_song_by_id(&db, &song_id).await.unwrap().is_none());
+
+        let album = AlbumService::get_album(&db, &album_id).await.unwrap().unwrap();
+        assert_eq!(album.total_tracks, 0);
+        assert_eq!(album.songs.len(), 0);
+
+        let artist: Option<Artist> = db
+            .query("SELECT * FROM $artist")
+            .bind(("artist", create_artist_thing(&artist_id)))
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(artist.unwrap().songs_count, 0);
+    }
+
+    #[tokio::test]
+    async fn test_merge_songs_moves_listens_likes_and_playlists() {
+        let db = setup_db().await;
+        let album_id = create_test_album(&db, "album1", "Best Of").await;
+        let artist_id = create_test_artist(&db, "artist1", "Test Artist").await;
+        let user1 = create_test_user(&db, "user1").await;
+        let user2 = create_test_user(&db, "user2").await;
+        let survivor = create_test_song(&db, "song1", "Song").await;
+        let duplicate = create_test_song(&db, "song2", "Song (Remaster)").await;
+        let other_duplicate = create_test_song(&db, "song3", "Song (Live)").await;
+        AlbumService::add_song(&db, &album_id, &duplicate).await.unwrap();
+        SongService::link_artist(&db, &duplicate, &artist_id).await.unwrap();
+
+        let duration = Duration::new(180, 0);
+        SongService::listen_to_song(&db, &survivor, Some(&user1), duration)
+            .await
+            .unwrap();
+        for _ in 0..2 {
+            SongService::listen_to_song(&db, &duplicate, Some(&user1), duration)
+                .await
+                .unwrap();
+        }
+        SongService::listen_to_song(&db, &duplicate, Some(&user2), duration)
+            .await
+            .unwrap();
+        SongService::listen_to_song(&db, &other_duplicate, Some(&user2), duration)
+            .await
+            .unwrap();
+
+        // Le même utilisateur et la même playlist sur les deux doublons
+        db.query(
+            r#"
+            CREATE playlist:p1 SET name = 'Mix';
+            RELATE $user->user_likes_song->$duplicate SET sort_order = 3;
+            RELATE $user->user_likes_song->$other SET sort_order = 3;
+            RELATE playlist:p1->playlist_contains_song->$duplicate SET added_by = $user;
+            RELATE playlist:p1->playlist_contains_song->$other SET added_by = $user;
+            "#,
+        )
+        .bind(("user", create_user_thing(&user2)))
+        .bind(("duplicate", create_song_thing(&duplicate)))
+        .bind(("other", create_song_thing(&other_duplicate)))
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let song =
+            SongService::merge_songs(&db, &survivor, &[duplicate.clone(), other_duplicate.clone()])
+                .await
+                .unwrap();
+        assert_eq!(song.total_listens, 5);
+        assert_eq!(song.total_user_listens, 2);
+        assert_eq!(song.total_likes, 1);
+        assert!(SongService::get_song_by_id(&db, &duplicate).await.unwrap().is_none());
+
+        #[derive(Deserialize)]
+        struct Listen {
+            total_listens: u32,
+            recent_dates: Vec<surrealdb::Datetime>,
+        }
+        let listen: Option<Listen> = db
+            .query("SELECT total_listens, recent_dates FROM user_listens_song WHERE in = $user AND out = $song")
+            .bind(("user", create_user_thing(&user1)))
+            .bind(("song", create_song_thing(&survivor)))
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        let listen = listen.unwrap();
+        assert_eq!(listen.total_listens, 3);
+        assert_eq!(listen.recent_dates.len(), 3);
+        assert!(listen.recent_dates.windows(2).all(|dates| dates[0] >= dates[1]));
+
+        let edges: Option<usize> = db
+            .query(
+                r#"
+                RETURN count(SELECT id FROM user_listens_song WHERE out = $duplicate)
+                    + count(SELECT id FROM user_likes_song WHERE out = $song AND sort_order = 3)
+                    + count(SELECT id FROM playlist_contains_song WHERE out = $song);
+                "#,
+            )
+            .bind(("duplicate", create_song_thing(&duplicate)))
+            .bind(("song", create_song_thing(&survivor)))
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(edges, Some(2));
+
+        let events: Vec<Thing> = db
+            .query("SELECT VALUE song FROM listen_event;")
+            .await
+            .unwrap()
+            .take(0)
+            .unwrap();
+        assert_eq!(events.len(), 5);
+        assert!(events.iter().all(|song| *song == create_song_thing(&survivor)));
+
+        let album = AlbumService::get_album(&db, &album_id).await.unwrap().unwrap();
+        assert_eq!(album.total_tracks, 1);
+        assert_eq!(album.songs[0].id, Some(create_song_thing(&survivor)));
+
+        let result =
+            SongService::merge_songs(&db, &survivor, std::slice::from_ref(&survivor)).await;
+        assert!(matches!(result, Err(Error::InvalidInput { .. })));
+        let result = SongService::merge_songs(&db, &survivor, &[]).await;
+        assert!(matches!(result, Err(Error::InvalidInput { .. })));
+        let result = SongService::merge_songs(&db, &survivor, &[duplicate]).await;
+        assert!(matches!(result, Err(Error::SongNotFound { .. })));
+    }
+
+    #[tokio::test]
+    async fn test_merge_songs_fails_when_the_transaction_is_cancelled() {
+        let db = setup_db().await;
+        let user = create_test_user(&db, "user1").await;
+        let survivor = create_test_song(&db, "song1", "Song").await;
+        let duplicate = create_test_song(&db, "song2", "Song (Remaster)").await;
+        SongService::listen_to_song(&db, &duplicate, Some(&user), Duration::new(180, 0))
+            .await
+            .unwrap();
+
+        // La suppression des doublons échoue au milieu de la transaction
+        db.query(
+            r#"DEFINE EVENT refuse_delete ON song WHEN $event = "DELETE" THEN { THROW "refused" };"#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let result =
+            SongService::merge_songs(&db, &survivor, std::slice::from_ref(&duplicate)).await;
+        assert!(result.is_err());
+        assert!(SongService::get_song_by_id(&db, &duplicate).await.unwrap().is_some());
+
+        let mut res = db
+            .query("SELECT count() FROM user_listens_song WHERE out = $song GROUP ALL;")
+            .bind(("song", create_song_thing(&duplicate)))
+            .await
+            .unwrap();
+        let listens: Option<u64> = res.take((0, "count")).unwrap();
+        assert_eq!(listens, Some(1));
+    }
 }
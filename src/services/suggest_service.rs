@@ -0,0 +1,361 @@
+use std::{
+    cmp::Reverse,
+    collections::HashMap,
+    sync::{
+        atomic::{AtomicBool, Ordering},
+        Arc, RwLock,
+    },
+};
+
+use deunicode::deunicode;
+use serde::Deserialize;
+use surrealdb::{engine::any::Any, sql::Thing, Surreal};
+
+use crate::{
+    error::Result,
+    models::search::{SuggestResult, Suggestion},
+    services::search_service::LIKE_WEIGHT,
+};
+
+/// Maximum number of suggestions returned per entity type.
+pub const SUGGEST_LIMIT: usize = 5;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+enum Kind {
+    Album,
+    Artist,
+    Song,
+}
+
+#[derive(Debug, Deserialize)]
+struct Row {
+    id: Thing,
+    title: String,
+    popularity: u64,
+}
+
+#[derive(Debug)]
+struct Entry {
+    kind: Kind,
+    suggestion: Suggestion,
+    words: Vec<String>,
+    popularity: u64,
+}
+
+/// Folded words of every title, sorted so that all the words starting with a
+/// prefix form a contiguous range.
+#[derive(Debug, Default)]
+struct PrefixIndex {
+    words: Vec<(String, usize)>,
+    entries: Vec<Entry>,
+    /// Every folded word of the titles, public playlist names and usernames,
+    /// with its number of occurrences.
+    vocabulary: Arc<HashMap<String, u32>>,
+}
+
+impl PrefixIndex {
+    async fn load(db: &Surreal<Any>) -> Result<Self> {
+        let sql = "
+            SELECT id, title, (total_listens OR 0) + $like_weight * (total_likes OR 0) AS popularity
+                FROM album;
+            SELECT id, name AS title, $like_weight * (total_likes OR 0) AS popularity FROM artist;
+            SELECT id, title, (total_listens OR 0) + $like_weight * (total_likes OR 0) AS popularity
+                FROM song;
+            SELECT VALUE name FROM playlist WHERE is_public = true;
+            SELECT VALUE username FROM user;
+        ";
+        let mut res = db.query(sql).bind(("like_weight", LIKE_WEIGHT)).await?;
+
+        let mut index = PrefixIndex::default();
+        for (statement, kind) in [Kind::Album, Kind::Artist, Kind::Song]
+            .into_iter()
+            .enumerate()
+        {
+            let rows: Vec<Row> = res.take(statement)?;
+            for row in rows {
+                index.insert(kind, row);
+            }
+        }
+        index.words.sort_unstable();
+
+        let mut vocabulary: HashMap<String, u32> = HashMap::new();
+        let names: Vec<String> = res.take(3)?;
+        let usernames: Vec<String> = res.take(4)?;
+        let words = index.words.iter().map(|(word, _)| word.clone());
+        for word in words.chain(
+            names
+                .iter()
+                .chain(&usernames)
+                .flat_map(|name| fold_words(name)),
+        ) {
+            *vocabulary.entry(word).or_default() += 1;
+        }
+        index.vocabulary = Arc::new(vocabulary);
+
+        Ok(index)
+    }
+
+    fn insert(&mut self, kind: Kind, row: Row) {
+        let position = self.entries.len();
+        let words = fold_words(&row.title);
+        self.words
+            .extend(words.iter().map(|word| (word.clone(), position)));
+        self.entries.push(Entry {
+            kind,
+            suggestion: Suggestion {
+                id: row.id,
+                title: row.title,
+            },
+            words,
+            popularity: row.popularity,
+        });
+    }
+
+    /// Entries having, for each query word, a word starting with it. Titles
+    /// starting with the query come first, then the most popular ones.
+    fn lookup(&self, query: &str) -> SuggestResult {
+        let query_words = fold_words(query);
+        let Some(last) = query_words.last() else {
+            return SuggestResult::default();
+        };
+
+        // Le dernier mot, en cours de saisie, délimite les candidats
+        let start = self.words.partition_point(|(word, _)| word < last);
+        let mut positions: Vec<usize> = self.words[start..]
+            .iter()
+            .take_while(|(word, _)| word.starts_with(last.as_str()))
+            .map(|(_, position)| *position)
+            .collect();
+        positions.sort_unstable();
+        positions.dedup();
+
+        let mut matches: Vec<&Entry> = positions
+            .into_iter()
+            .map(|position| &self.entries[position])
+            .filter(|entry| {
+                query_words.iter().all(|query_word| {
+                    entry
+                        .words
+                        .iter()
+                        .any(|word| word.starts_with(query_word.as_str()))
+                })
+            })
+            .collect();
+        matches.sort_by_key(|entry| {
+            let leading = entry
+                .words
+                .iter()
+                .zip(&query_words)
+                .all(|(word, query_word)| word.starts_with(query_word.as_str()));
+            (
+                Reverse(leading),
+                Reverse(entry.popularity),
+                entry.suggestion.title.len(),
+            )
+        });
+
+        let mut result = SuggestResult::default();
+        for entry in matches {
+            let list = match entry.kind {
+                Kind::Album => &mut result.albums,
+                Kind::Artist => &mut result.artists,
+                Kind::Song => &mut result.songs,
+            };
+            if list.len() < SUGGEST_LIMIT {
+                list.push(entry.suggestion.clone());
+            }
+        }
+
+        result
+    }
+}
+
+/// In-memory prefix index of album titles, artist names and song titles, and
+/// vocabulary of the searchable names, shared by the request handlers. It is
+/// rebuilt on the next lookup after [`SuggestIndex::invalidate`], and
+/// periodically by the `suggest` job.
+#[derive(Clone)]
+pub struct SuggestIndex {
+    index: Arc<RwLock<Arc<PrefixIndex>>>,
+    stale: Arc<AtomicBool>,
+}
+
+impl Default for SuggestIndex {
+    fn default() -> Self {
+        Self {
+            index: Arc::default(),
+            // Construit à la première requête
+            stale: Arc::new(AtomicBool::new(true)),
+        }
+    }
+}
+
+impl SuggestIndex {
+    /// Marks the index as outdated after a catalog change.
+    pub fn invalidate(&self) {
+        self.stale.store(true, Ordering::Release);
+    }
+
+    /// Reloads every title from the database and returns the number of
+    /// indexed entries.
+    pub async fn rebuild(&self, db: &Surreal<Any>) -> Result<usize> {
+        // Un changement pendant le chargement marquera de nouveau l'index
+        self.stale.store(false, Ordering::Release);
+
+        match PrefixIndex::load(db).await {
+            Ok(index) => {
+                let count = index.entries.len();
+                *self.index.write().unwrap_or_else(|e| e.into_inner()) = Arc::new(index);
+                Ok(count)
+            }
+            Err(e) => {
+                self.stale.store(true, Ordering::Release);
+                Err(e)
+            }
+        }
+    }
+
+    /// Top album, artist and song suggestions for the words typed so far, the
+    /// last one being a prefix. Accents, case and punctuation are ignored.
+    pub async fn suggest(&self, db: &Surreal<Any>, query: &str) -> Result<SuggestResult> {
+        Ok(self.current(db).await?.lookup(query))
+    }
+
+    /// Folded words of the album titles, artist names, song titles, public
+    /// playlist names and usernames, with their number of occurrences.
+    pub async fn vocabulary(&self, db: &Surreal<Any>) -> Result<Arc<HashMap<String, u32>>> {
+        Ok(self.current(db).await?.vocabulary.clone())
+    }
+
+    async fn current(&self, db: &Surreal<Any>) -> Result<Arc<PrefixIndex>> {
+        if self.stale.load(Ordering::Acquire) {
+            self.rebuild(db).await?;
+        }
+
+        Ok(self.index.read().unwrap_or_else(|e| e.into_inner()).clone())
+    }
+}
+
+/// Lowercased words without accents, split on anything but letters and digits.
+/// Accents are folded by `deunicode`, like the `ascii` filter of the
+/// `music_search` analyzer, so suggestions and search agree on a query.
+pub(crate) fn fold_words(text: &str) -> Vec<String> {
+    deunicode(&text.to_lowercase())
+        .to_lowercase()
+        .split(|c: char| !c.is_alphanumeric())
+        .filter(|word| !word.is_empty())
+        .map(str::to_string)
+        .collect()
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::engine::any::connect;
+
+    #[test]
+    fn test_fold_words() {
+        assert_eq!(fold_words("Chânson d'Été"), vec!["chanson", "d", "ete"]);
+        assert_eq!(fold_words("  Œuvre—Live! "), vec!["oeuvre", "live"]);
+        assert!(fold_words("?!").is_empty());
+        assert_eq!(fold_words("Şarkı Söyleğ"), vec!["sarki", "soyleg"]);
+    }
+
+    #[tokio::test]
+    async fn test_fold_words_matches_the_search_analyzer() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(include_str!("../../database_search.surql"))
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+
+        for title in [
+            "Chânson d'Été",
+            "Şarkı Söyle",
+            "Œuvre—Live!",
+            "Straße Ørsted Łódź",
+            "Ἀθῆναι",
+        ] {
+            let mut res = db
+                .query("RETURN search::analyze('music_search', $title);")
+                .bind(("title", title))
+                .await
+                .unwrap();
+            let analyzed: Vec<String> = res.take(0).unwrap();
+            let analyzed: Vec<String> = analyzed
+                .into_iter()
+                .filter(|term| term.chars().all(char::is_alphanumeric))
+                .collect();
+            assert_eq!(fold_words(title), analyzed, "{}", title);
+        }
+    }
+
+    #[tokio::test]
+    async fn test_suggest_matches_prefixes_and_follows_catalog_changes() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        db.query(
+            r#"
+            CREATE artist:clash SET name = 'The Clash', total_likes = 3;
+            CREATE album:london SET title = 'London Calling', total_listens = 10;
+            CREATE song:calling SET title = 'London Calling', total_listens = 5;
+            CREATE song:demo SET title = 'London Calling (Demo)', total_listens = 500;
+            CREATE song:clampdown SET title = 'Clampdown', total_listens = 1;
+            CREATE song:out SET title = 'Calling Out';
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let index = SuggestIndex::default();
+        let titles = |suggestions: &[Suggestion]| {
+            suggestions
+                .iter()
+                .map(|suggestion| suggestion.title.clone())
+                .collect::<Vec<_>>()
+        };
+
+        let result = index.suggest(&db, "lon").await.unwrap();
+        assert_eq!(titles(&result.albums), vec!["London Calling"]);
+        assert_eq!(
+            titles(&result.songs),
+            vec!["London Calling (Demo)", "London Calling"]
+        );
+        assert!(result.artists.is_empty());
+
+        let result = index.suggest(&db, "cla").await.unwrap();
+        assert_eq!(titles(&result.artists), vec!["The Clash"]);
+        assert_eq!(titles(&result.songs), vec!["Clampdown"]);
+
+        // Les titres commençant par la saisie passent devant les plus écoutés
+        let result = index.suggest(&db, "call").await.unwrap();
+        assert_eq!(
+            titles(&result.songs),
+            vec!["Calling Out", "London Calling (Demo)", "London Calling"]
+        );
+
+        let result = index.suggest(&db, "london CALLING (d").await.unwrap();
+        assert_eq!(titles(&result.songs), vec!["London Calling (Demo)"]);
+        assert!(result.albums.is_empty());
+        assert_eq!(
+            result.songs[0].id,
+            Thing::from(("song".to_string(), "demo".to_string()))
+        );
+
+        assert!(index.suggest(&db, " - ").await.unwrap().songs.is_empty());
+
+        // L'index n'est rechargé qu'après invalidation
+        db.query("CREATE song:londres SET title = 'Londres';")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        assert_eq!(index.suggest(&db, "lond").await.unwrap().songs.len(), 2);
+        index.invalidate();
+        assert_eq!(index.suggest(&db, "lond").await.unwrap().songs.len(), 3);
+    }
+}
@@ -0,0 +1,328 @@
+use std::f32::consts::PI;
+
+use rustfft::{num_complex::Complex, FftPlanner};
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    error::{Error, Result},
+    helpers::thing_helpers::thing_to_string,
+    models::{
+        analysis::{AnalysisKind, AnalysisReport},
+        song::Song,
+    },
+    services::{
+        audio_analysis_service::{AudioAnalysisService, SongFile},
+        media_service::MediaConfig,
+        song_service::SongService,
+    },
+};
+
+// Le signal est ramené vers 11 kHz : le tempo se lit dans les attaques, pas dans les aigus
+const ANALYSIS_RATE: u32 = 11_025;
+const FRAME_SIZE: usize = 1024;
+const HOP_SIZE: usize = 128;
+
+const MIN_BPM: f32 = 60.0;
+const MAX_BPM: f32 = 200.0;
+// Centre et largeur (en octaves) de la préférence de tempo perçue
+const PREFERRED_BPM: f32 = 120.0;
+const PREFERENCE_OCTAVES: f32 = 1.0;
+
+const MIN_ANALYSIS_SECS: f64 = 5.0;
+const MAX_ANALYSIS_SECS: f64 = 120.0;
+
+const BATCH_SIZE: u32 = 50;
+
+pub struct TempoService;
+
+impl TempoService {
+    /// Estimates the tempo of a mono signal: spectral-flux onset envelope, then
+    /// the autocorrelation lag with the strongest periodicity. `None` when the
+    /// signal is too short or has no rhythmic content.
+    pub fn detect_bpm(samples: &[f32], sample_rate: u32) -> Option<f32> {
+        let factor = (sample_rate / ANALYSIS_RATE).max(1) as usize;
+        let signal: Vec<f32> = samples
+            .chunks(factor)
+            .map(|chunk| chunk.iter().sum::<f32>() / chunk.len() as f32)
+            .collect();
+        let frames_per_sec = sample_rate as f32 / factor as f32 / HOP_SIZE as f32;
+
+        let envelope = onset_envelope(&signal);
+        if (envelope.len() as f32) < frames_per_sec * MIN_ANALYSIS_SECS as f32 {
+            return None;
+        }
+
+        let min_lag = (60.0 * frames_per_sec / MAX_BPM).floor() as usize;
+        let max_lag = ((60.0 * frames_per_sec / MIN_BPM).ceil() as usize).min(envelope.len() / 2);
+        if min_lag < 1 || max_lag <= min_lag + 1 {
+            return None;
+        }
+
+        let acf: Vec<f32> = (0..=max_lag + 1)
+            .map(|lag| autocorrelation(&envelope, lag))
+            .collect();
+        if acf[0] <= f32::EPSILON {
+            return None;
+        }
+
+        let (best_lag, _) = (min_lag..=max_lag)
+            .map(|lag| {
+                let bpm = 60.0 * frames_per_sec / lag as f32;
+                let octaves = (bpm / PREFERRED_BPM).log2() / PREFERENCE_OCTAVES;
+                (lag, acf[lag] * (-0.5 * octaves * octaves).exp())
+            })
+            .max_by(|a, b| a.1.total_cmp(&b.1))?;
+
+        // Une périodicité faible devant l'énergie du signal : pas de pulsation
+        if acf[best_lag] < 0.1 * acf[0] {
+            return None;
+        }
+
+        // Interpolation parabolique autour du pic
+        let (prev, peak, next) = (acf[best_lag - 1], acf[best_lag], acf[best_lag + 1]);
+        let denominator = prev - 2.0 * peak + next;
+        let offset = if denominator.abs() > f32::EPSILON {
+            (0.5 * (prev - next) / denominator).clamp(-0.5, 0.5)
+        } else {
+            0.0
+        };
+
+        let bpm = 60.0 * frames_per_sec / (best_lag as f32 + offset);
+        Some((bpm * 10.0).round() / 10.0)
+    }
+
+    fn analyze_file(path: &std::path::Path) -> std::result::Result<f32, String> {
+        let audio = AudioAnalysisService::decode_mono(path, Some(MAX_ANALYSIS_SECS))?;
+        if audio.duration_secs() < MIN_ANALYSIS_SECS {
+            return Err("Audio too short to estimate a tempo".to_string());
+        }
+        Self::detect_bpm(&audio.samples, audio.sample_rate)
+            .ok_or_else(|| "No rhythmic content detected".to_string())
+    }
+
+    async fn analyze(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        song: &SongFile,
+    ) -> Result<std::result::Result<f32, String>> {
+        let outcome =
+            AudioAnalysisService::run(db, config, song, AnalysisKind::Tempo, Self::analyze_file)
+                .await?;
+
+        if let Ok(tempo) = outcome {
+            db.query("UPDATE $song SET tempo = $tempo")
+                .bind(("song", song.id.clone()))
+                .bind(("tempo", tempo))
+                .await?
+                .check()?;
+        }
+
+        Ok(outcome)
+    }
+
+    /// On-demand analysis of one song, whatever its current tempo.
+    pub async fn analyze_song(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        song_id: &str,
+    ) -> Result<Song> {
+        let song = AudioAnalysisService::get_song_file(db, song_id).await?;
+
+        if let Err(reason) = Self::analyze(db, config, &song).await? {
+            return Err(Error::AnalysisFailed {
+                song_id: thing_to_string(&song.id),
+                reason,
+            });
+        }
+
+        SongService::get_song_by_id(db, song_id).await?.ok_or(Error::SongNotFound {
+            id: song_id.to_string(),
+        })
+    }
+
+    /// Analyzes every song with `tempo = 0`. Songs that already failed are
+    /// skipped until they are analyzed on demand.
+    pub async fn analyze_pending(db: &Surreal<Any>, config: &MediaConfig) -> Result<AnalysisReport> {
+        let mut report = AnalysisReport::default();
+
+        loop {
+            let songs = AudioAnalysisService::pending_songs(db, AnalysisKind::Tempo, BATCH_SIZE).await?;
+
+            for song in &songs {
+                match Self::analyze(db, config, song).await? {
+                    Ok(_) => report.analyzed += 1,
+                    Err(_) => report.failed += 1,
+                }
+            }
+
+            if (songs.len() as u32) < BATCH_SIZE {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Positive spectral flux of log-compressed magnitudes, minus its local mean.
+fn onset_envelope(signal: &[f32]) -> Vec<f32> {
+    if signal.len() < FRAME_SIZE {
+        return Vec::new();
+    }
+
+    let fft = FftPlanner::<f32>::new().plan_fft_forward(FRAME_SIZE);
+    let window: Vec<f32> = (0..FRAME_SIZE)
+        .map(|i| 0.5 - 0.5 * (2.0 * PI * i as f32 / FRAME_SIZE as f32).cos())
+        .collect();
+
+    let bins = FRAME_SIZE / 2;
+    let mut previous = vec![0.0f32; bins];
+    let mut buffer = vec![Complex::new(0.0, 0.0); FRAME_SIZE];
+    let mut flux = Vec::with_capacity((signal.len() - FRAME_SIZE) / HOP_SIZE + 1);
+
+    for start in (0..=signal.len() - FRAME_SIZE).step_by(HOP_SIZE) {
+        for (i, value) in buffer.iter_mut().enumerate() {
+            *value = Complex::new(signal[start + i] * window[i], 0.0);
+        }
+        fft.process(&mut buffer);
+
+        let mut sum = 0.0;
+        for (bin, previous) in previous.iter_mut().enumerate() {
+            let magnitude = (1.0 + 100.0 * buffer[bin].norm()).ln();
+            sum += (magnitude - *previous).max(0.0);
+            *previous = magnitude;
+        }
+        flux.push(sum);
+    }
+    // La première trame part de zéro
+    if let Some(first) = flux.first_mut() {
+        *first = 0.0;
+    }
+
+    // Moyenne glissante sur ~0.25 s pour ne garder que les attaques
+    let half_window = 10;
+    (0..flux.len())
+        .map(|i| {
+            let from = i.saturating_sub(half_window);
+            let to = (i + half_window + 1).min(flux.len());
+            let mean = flux[from..to].iter().sum::<f32>() / (to - from) as f32;
+            (flux[i] - mean).max(0.0)
+        })
+        .collect()
+}
+
+fn autocorrelation(envelope: &[f32], lag: usize) -> f32 {
+    if lag >= envelope.len() {
+        return 0.0;
+    }
+    let sum: f32 = envelope
+        .iter()
+        .zip(&envelope[lag..])
+        .map(|(a, b)| a * b)
+        .sum();
+    sum / (envelope.len() - lag) as f32
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+    use surrealdb::engine::any::connect;
+
+    const SAMPLE_RATE: u32 = 22_050;
+
+    /// Percussive clicks (decaying 1 kHz bursts) at a fixed tempo.
+    fn click_track(bpm: f32, secs: f32) -> Vec<f32> {
+        let len = (secs * SAMPLE_RATE as f32) as usize;
+        let beat = 60.0 / bpm * SAMPLE_RATE as f32;
+        let burst = (0.03 * SAMPLE_RATE as f32) as usize;
+
+        let mut samples = vec![0.0f32; len];
+        let mut position = 0.0;
+        while (position as usize) < len {
+            let start = position as usize;
+            for i in 0..burst.min(len - start) {
+                let t = i as f32 / SAMPLE_RATE as f32;
+                samples[start + i] += (2.0 * PI * 1000.0 * t).sin() * (-t * 150.0).exp();
+            }
+            position += beat;
+        }
+        samples
+    }
+
+    #[test]
+    fn test_detect_bpm_on_click_tracks() {
+        for expected in [72.0, 95.0, 120.0, 128.0, 174.0] {
+            let bpm = TempoService::detect_bpm(&click_track(expected, 30.0), SAMPLE_RATE).unwrap();
+            assert!(
+                (bpm - expected).abs() <= 1.5,
+                "expected ~{} BPM, got {}",
+                expected,
+                bpm
+            );
+        }
+    }
+
+    #[test]
+    fn test_detect_bpm_rejects_silence_and_short_signals() {
+        let silence = vec![0.0f32; SAMPLE_RATE as usize * 20];
+        assert_eq!(TempoService::detect_bpm(&silence, SAMPLE_RATE), None);
+
+        let short = click_track(120.0, 2.0);
+        assert_eq!(TempoService::detect_bpm(&short, SAMPLE_RATE), None);
+    }
+
+    #[tokio::test]
+    async fn test_failures_are_recorded_and_skipped() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+
+        let media_root = std::env::temp_dir().join(format!("tempo-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&media_root).unwrap();
+        fs::write(media_root.join("broken.mp3"), b"not an mp3").unwrap();
+        let config = MediaConfig {
+            media_root: media_root.clone(),
+            image_cache_dir: media_root.join("cache"),
+        };
+
+        db.query(
+            r#"
+            CREATE song:broken SET title = 'Broken', file_url = 'broken.mp3', tempo = 0;
+            CREATE song:missing SET title = 'Missing', file_url = 'missing.mp3', tempo = 0;
+            CREATE song:done SET title = 'Done', file_url = 'done.mp3', tempo = 128;
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let report = TempoService::analyze_pending(&db, &config).await.unwrap();
+        assert_eq!(report.analyzed, 0);
+        assert_eq!(report.failed, 2);
+
+        let failures = AudioAnalysisService::list_failures(&db, Some(AnalysisKind::Tempo))
+            .await
+            .unwrap();
+        assert_eq!(failures.len(), 2);
+        assert!(failures
+            .iter()
+            .any(|f| f.song.id.to_raw() == "missing" && f.reason.contains("not found")));
+
+        // Les échecs ne sont pas retentés par le batch
+        let report = TempoService::analyze_pending(&db, &config).await.unwrap();
+        assert_eq!(report.analyzed + report.failed, 0);
+
+        // Mais le sont à la demande
+        let result = TempoService::analyze_song(&db, &config, "broken").await;
+        assert!(matches!(result, Err(Error::AnalysisFailed { .. })));
+        let failures = AudioAnalysisService::list_failures(&db, None).await.unwrap();
+        let broken = failures.iter().find(|f| f.song.id.to_raw() == "broken").unwrap();
+        assert_eq!(broken.attempts, 2);
+
+        let result = TempoService::analyze_song(&db, &config, "unknown").await;
+        assert!(matches!(result, Err(Error::SongNotFound { .. })));
+
+        fs::remove_dir_all(&media_root).unwrap();
+    }
+}
@@ -12,7 +12,7 @@ impl UserService {
     pub async fn get_user_profile(db: &Surreal<Any>, user_id: &str) -> Result<UserProfile, Error> {
         let user_thing = create_user_thing(user_id);
 
-        let sql_query = "SELECT badges, created_at, experience_points, favorite_count, id, level, listen_count, listening_streak, total_listening_time, username FROM user WHERE id = $user_id;";
+        let sql_query = "SELECT badges, created_at, experience_points, favorite_count, id, level, listen_count, listening_streak, role, total_listening_time, username FROM user WHERE id = $user_id;";
 
         let mut response = db
             .query(sql_query)
@@ -0,0 +1,292 @@
+use std::path::Path;
+
+use surrealdb::{engine::any::Any, Surreal};
+
+use crate::{
+    error::{Error, Result},
+    helpers::thing_helpers::{create_song_thing, thing_to_string},
+    models::{
+        analysis::{AnalysisKind, AnalysisReport},
+        waveform::{Waveform, WaveformPeaks},
+    },
+    services::{
+        audio_analysis_service::{AudioAnalysisService, SongFile},
+        media_service::MediaConfig,
+    },
+};
+
+/// Number of points of each stored resolution, from a thumbnail to a
+/// full-width scrubber. A song shorter than a resolution gets fewer points.
+pub const WAVEFORM_RESOLUTIONS: [u32; 3] = [128, 512, 2048];
+
+// Les pics sont d'abord réduits par blocs fixes pendant le décodage : un
+// morceau d'une heure tient en quelques Mo au lieu de centaines
+const BLOCK_SIZE: usize = 64;
+
+const BATCH_SIZE: u32 = 50;
+
+/// Min/max of consecutive blocks of `BLOCK_SIZE` samples.
+#[derive(Debug, Default)]
+struct BlockPeaks {
+    blocks: Vec<(f32, f32)>,
+    current: Option<(f32, f32)>,
+    filled: usize,
+    samples: u64,
+}
+
+impl BlockPeaks {
+    fn push(&mut self, chunk: &[f32]) {
+        for &sample in chunk {
+            let (min, max) = self.current.unwrap_or((sample, sample));
+            self.current = Some((min.min(sample), max.max(sample)));
+            self.filled += 1;
+
+            if self.filled == BLOCK_SIZE {
+                self.blocks.extend(self.current.take());
+                self.filled = 0;
+            }
+        }
+        self.samples += chunk.len() as u64;
+    }
+
+    fn finish(mut self, sample_rate: u32) -> Waveform {
+        self.blocks.extend(self.current.take());
+
+        Waveform {
+            duration_secs: self.samples as f64 / f64::from(sample_rate),
+            sample_rate,
+            resolutions: WAVEFORM_RESOLUTIONS
+                .iter()
+                .map(|&points| summarize(&self.blocks, points))
+                .collect(),
+        }
+    }
+}
+
+pub struct WaveformService;
+
+impl WaveformService {
+    fn analyze_file(path: &Path) -> std::result::Result<Waveform, String> {
+        let mut peaks = BlockPeaks::default();
+        let sample_rate = AudioAnalysisService::decode_each(path, None, |chunk, _| peaks.push(chunk))?;
+        Ok(peaks.finish(sample_rate))
+    }
+
+    async fn analyze(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        song: &SongFile,
+    ) -> Result<std::result::Result<Waveform, String>> {
+        let outcome =
+            AudioAnalysisService::run(db, config, song, AnalysisKind::Waveform, Self::analyze_file)
+                .await?;
+
+        if let Ok(waveform) = &outcome {
+            let query = r#"
+                UPSERT type::thing('song_waveform', record::id($song)) SET
+                    song = $song,
+                    duration_secs = $waveform.duration_secs,
+                    sample_rate = $waveform.sample_rate,
+                    resolutions = $waveform.resolutions,
+                    computed_at = time::now();
+            "#;
+
+            db.query(query)
+                .bind(("song", song.id.clone()))
+                .bind(("waveform", waveform.clone()))
+                .await?
+                .check()?;
+        }
+
+        Ok(outcome)
+    }
+
+    pub async fn get_waveform(db: &Surreal<Any>, song_id: &str) -> Result<Waveform> {
+        let waveform: Option<Waveform> = db
+            .query(
+                "SELECT duration_secs, sample_rate, resolutions FROM song_waveform
+                 WHERE song = $song LIMIT 1",
+            )
+            .bind(("song", create_song_thing(song_id)))
+            .await?
+            .take(0)?;
+
+        waveform.ok_or(Error::WaveformNotFound {
+            song_id: song_id.to_string(),
+        })
+    }
+
+    /// On-demand computation for one song, replacing its stored peaks.
+    pub async fn analyze_song(
+        db: &Surreal<Any>,
+        config: &MediaConfig,
+        song_id: &str,
+    ) -> Result<Waveform> {
+        let song = AudioAnalysisService::get_song_file(db, song_id).await?;
+
+        Self::analyze(db, config, &song)
+            .await?
+            .map_err(|reason| Error::AnalysisFailed {
+                song_id: thing_to_string(&song.id),
+                reason,
+            })
+    }
+
+    /// Computes the peaks of every song without a waveform. Songs that
+    /// already failed are skipped until they are analyzed on demand.
+    pub async fn analyze_pending(db: &Surreal<Any>, config: &MediaConfig) -> Result<AnalysisReport> {
+        let mut report = AnalysisReport::default();
+
+        loop {
+            let songs =
+                AudioAnalysisService::pending_songs(db, AnalysisKind::Waveform, BATCH_SIZE).await?;
+
+            for song in &songs {
+                match Self::analyze(db, config, song).await? {
+                    Ok(_) => report.analyzed += 1,
+                    Err(_) => report.failed += 1,
+                }
+            }
+
+            if (songs.len() as u32) < BATCH_SIZE {
+                break;
+            }
+        }
+
+        Ok(report)
+    }
+}
+
+/// Merges the blocks into at most `points` buckets of (nearly) equal width.
+fn summarize(blocks: &[(f32, f32)], points: u32) -> WaveformPeaks {
+    let count = blocks.len().min(points as usize);
+    let mut min = Vec::with_capacity(count);
+    let mut max = Vec::with_capacity(count);
+
+    for bucket in 0..count {
+        let from = bucket * blocks.len() / count;
+        let to = (bucket + 1) * blocks.len() / count;
+        let (low, high) = blocks[from..to]
+            .iter()
+            .fold((f32::MAX, f32::MIN), |(low, high), &(block_min, block_max)| {
+                (low.min(block_min), high.max(block_max))
+            });
+        min.push(quantize(low));
+        max.push(quantize(high));
+    }
+
+    WaveformPeaks {
+        points: count as u32,
+        min,
+        max,
+    }
+}
+
+fn quantize(amplitude: f32) -> i8 {
+    (amplitude.clamp(-1.0, 1.0) * 127.0).round() as i8
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::{f32::consts::PI, fs};
+    use surrealdb::engine::any::connect;
+
+    fn compute(samples: &[f32], sample_rate: u32) -> Waveform {
+        let mut peaks = BlockPeaks::default();
+        peaks.push(samples);
+        peaks.finish(sample_rate)
+    }
+
+    #[test]
+    fn test_compute_peaks() {
+        let sample_rate = 8_000;
+        // 4 s : sinusoïde d'amplitude 0.5, puis silence
+        let samples: Vec<f32> = (0..sample_rate * 4)
+            .map(|i| {
+                if i < sample_rate * 2 {
+                    0.5 * (2.0 * PI * 440.0 * i as f32 / sample_rate as f32).sin()
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+
+        let waveform = compute(&samples, sample_rate);
+        assert_eq!(waveform.sample_rate, sample_rate);
+        assert!((waveform.duration_secs - 4.0).abs() < 1e-9);
+
+        // 32000 échantillons = 500 blocs : la plus grande résolution est tronquée
+        let points: Vec<u32> = waveform.resolutions.iter().map(|peaks| peaks.points).collect();
+        assert_eq!(points, vec![128, 500, 500]);
+
+        for peaks in &waveform.resolutions {
+            assert_eq!(peaks.min.len(), peaks.points as usize);
+            assert_eq!(peaks.max.len(), peaks.points as usize);
+
+            let half = peaks.points as usize / 2;
+            assert!(peaks.max[..half - 1].iter().all(|&max| (62..=64).contains(&max)));
+            assert!(peaks.min[..half - 1].iter().all(|&min| (-64..=-62).contains(&min)));
+            assert!(peaks.max[half + 1..].iter().all(|&max| max == 0));
+            assert!(peaks.min[half + 1..].iter().all(|&min| min == 0));
+        }
+
+        assert_eq!(waveform.resolution(Some(100)).unwrap().points, 128);
+        assert_eq!(waveform.resolution(Some(129)).unwrap().points, 500);
+        assert_eq!(waveform.resolution(None).unwrap().points, 500);
+
+        let empty = compute(&[], sample_rate);
+        assert!(empty.resolutions.iter().all(|peaks| peaks.points == 0));
+    }
+
+    #[tokio::test]
+    async fn test_analyze_pending_stores_waveforms() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+
+        let media_root = std::env::temp_dir().join(format!("waveform-test-{}", uuid::Uuid::new_v4()));
+        fs::create_dir_all(&media_root).unwrap();
+        // Trames MPEG-1 Layer III silencieuses (128 kbps, 44.1 kHz)
+        let mut mp3 = Vec::new();
+        for _ in 0..50 {
+            let mut frame = vec![0u8; 417];
+            frame[..4].copy_from_slice(&[0xFF, 0xFB, 0x90, 0x00]);
+            mp3.extend(frame);
+        }
+        fs::write(media_root.join("silent.mp3"), mp3).unwrap();
+        let config = MediaConfig {
+            media_root: media_root.clone(),
+            image_cache_dir: media_root.join("cache"),
+        };
+
+        db.query(
+            r#"
+            CREATE song:silent SET title = 'Silent', file_url = 'silent.mp3';
+            CREATE song:missing SET title = 'Missing', file_url = 'missing.mp3';
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let report = WaveformService::analyze_pending(&db, &config).await.unwrap();
+        assert_eq!(report.analyzed, 1);
+        assert_eq!(report.failed, 1);
+
+        let waveform = WaveformService::get_waveform(&db, "silent").await.unwrap();
+        assert_eq!(waveform.sample_rate, 44_100);
+        assert_eq!(waveform.resolutions.len(), WAVEFORM_RESOLUTIONS.len());
+        assert_eq!(waveform.resolutions[0].points, 128);
+        assert!(waveform.resolutions[0].max.iter().all(|&max| max == 0));
+
+        let result = WaveformService::get_waveform(&db, "missing").await;
+        assert!(matches!(result, Err(Error::WaveformNotFound { .. })));
+
+        // Rien à refaire au passage suivant
+        let report = WaveformService::analyze_pending(&db, &config).await.unwrap();
+        assert_eq!(report.analyzed + report.failed, 0);
+
+        fs::remove_dir_all(&media_root).unwrap();
+    }
+}
@@ -0,0 +1,593 @@
+use std::collections::{BTreeSet, HashMap};
+
+use chrono::{Datelike, NaiveDate, TimeZone, Utc};
+use serde::{de::DeserializeOwned, Deserialize};
+use surrealdb::{engine::any::Any, sql::Thing, Datetime, Surreal};
+
+use crate::{
+    error::{Error, Result},
+    helpers::thing_helpers::create_user_thing,
+    models::{
+        album::AlbumWithArtists,
+        artist::Artist,
+        search::normalize_genre,
+        song::SongWithRelations,
+        user::BadgeEnum,
+        wrapped::{
+            WrappedAlbum, WrappedArtist, WrappedFirstSong, WrappedGenre, WrappedMonth,
+            WrappedReport, WrappedSong, WrappedStreak,
+        },
+    },
+    services::recommendation_service::{RecommendationService, ALBUM_RELATIONS},
+};
+
+/// Songs, artists, albums and genres listed in a report.
+pub const WRAPPED_TOP_SIZE: usize = 5;
+
+#[derive(Debug, Deserialize)]
+struct ListenRow {
+    song: Thing,
+    listened_at: Datetime,
+    day: String,
+    month: u32,
+}
+
+#[derive(Debug, Deserialize)]
+struct SongInfo {
+    id: Thing,
+    title: String,
+    seconds: u64,
+    artists: Vec<Thing>,
+    album: Option<Thing>,
+    genres: Vec<String>,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArtistName {
+    id: Thing,
+    name: String,
+}
+
+#[derive(Debug, Default, Deserialize)]
+struct Lifetime {
+    #[serde(default)]
+    listen_count: u64,
+    /// In seconds.
+    #[serde(default)]
+    total_listening_time: u64,
+}
+
+/// Listens and listening time of a song, artist, album or genre.
+#[derive(Debug, Default)]
+struct Tally {
+    listens: u64,
+    seconds: u64,
+}
+
+/// Totals of a month with its songs and artists.
+#[derive(Debug, Default)]
+struct MonthTally {
+    totals: Tally,
+    songs: HashMap<String, Tally>,
+    artists: HashMap<String, Tally>,
+}
+
+/// Keys of `tallies`, most listened first, equal counts ordered by key.
+fn ranked(tallies: &HashMap<String, Tally>) -> Vec<(&String, &Tally)> {
+    let mut ranked: Vec<(&String, &Tally)> = tallies.iter().collect();
+    ranked.sort_by(|(key_a, a), (key_b, b)| b.listens.cmp(&a.listens).then(key_a.cmp(key_b)));
+    ranked
+}
+
+/// Longest run of consecutive days, the earliest of equal runs.
+fn longest_streak(days: &BTreeSet<NaiveDate>) -> Option<WrappedStreak> {
+    let mut best: Option<WrappedStreak> = None;
+    let mut current: Option<WrappedStreak> = None;
+    for &day in days {
+        current = match current {
+            Some(streak) if streak.to.succ_opt() == Some(day) => Some(WrappedStreak {
+                days: streak.days + 1,
+                to: day,
+                ..streak
+            }),
+            _ => Some(WrappedStreak {
+                days: 1,
+                from: day,
+                to: day,
+            }),
+        };
+        if best.as_ref().map(|best| best.days) < current.as_ref().map(|streak| streak.days) {
+            best = current.clone();
+        }
+    }
+    best
+}
+
+pub struct WrappedService;
+
+impl WrappedService {
+    /// Year in review of the user. A past year is computed once and then
+    /// read from the cache; the current one is computed on each call.
+    pub async fn get_wrapped(db: &Surreal<Any>, user_id: &str, year: i32) -> Result<WrappedReport> {
+        let current_year = Utc::now().year();
+        if !(1970..=current_year).contains(&year) {
+            return Err(Error::InvalidInput {
+                reason: format!("Year must be between 1970 and {}", current_year),
+            });
+        }
+        let user = create_user_thing(user_id);
+        let complete = year < current_year;
+
+        if complete {
+            let mut res = db
+                .query(
+                    "SELECT VALUE report FROM wrapped_report WHERE user = $user AND year = $year;",
+                )
+                .bind(("user", user.clone()))
+                .bind(("year", year))
+                .await?;
+            let cached: Option<WrappedReport> = res.take(0)?;
+            if let Some(report) = cached {
+                return Ok(report);
+            }
+        }
+
+        let mut report = Self::compute(db, &user, year).await?;
+        report.complete = complete;
+
+        if complete {
+            let sql = "
+                BEGIN TRANSACTION;
+                DELETE wrapped_report WHERE user = $user AND year = $year;
+                CREATE wrapped_report SET
+                    user = $user,
+                    year = $year,
+                    report = $report,
+                    computed_at = time::now();
+                COMMIT TRANSACTION;
+            ";
+            db.query(sql)
+                .bind(("user", user))
+                .bind(("year", year))
+                .bind(("report", report.clone()))
+                .await?
+                .check()?;
+        }
+
+        Ok(report)
+    }
+
+    /// Builds the report from the song events of the listen log. Album
+    /// listens are left out, an album counts the listens of its songs.
+    async fn compute(db: &Surreal<Any>, user: &Thing, year: i32) -> Result<WrappedReport> {
+        let start = Utc
+            .with_ymd_and_hms(year, 1, 1, 0, 0, 0)
+            .single()
+            .ok_or_else(|| Error::InvalidInput {
+                reason: format!("Invalid year {}", year),
+            })?;
+        let end = Utc
+            .with_ymd_and_hms(year + 1, 1, 1, 0, 0, 0)
+            .single()
+            .ok_or_else(|| Error::InvalidInput {
+                reason: format!("Invalid year {}", year),
+            })?;
+
+        let sql = "
+            SELECT
+                song,
+                listened_at,
+                time::format(listened_at, '%Y-%m-%d') AS day,
+                time::month(listened_at) AS month
+            FROM listen_event
+            WHERE user = $user AND song != NONE AND listened_at >= $start AND listened_at < $end
+            ORDER BY listened_at ASC;
+            SELECT badge, awarded_at FROM badge_award
+                WHERE user = $user AND awarded_at >= $start AND awarded_at < $end
+                ORDER BY awarded_at ASC;
+            SELECT listen_count, total_listening_time FROM $user;
+        ";
+        let mut res = db
+            .query(sql)
+            .bind(("user", user.clone()))
+            .bind(("start", Datetime::from(start)))
+            .bind(("end", Datetime::from(end)))
+            .await?;
+        let listens: Vec<ListenRow> = res.take(0)?;
+        let badges: Vec<BadgeEnum> = res.take((1, "badge"))?;
+        let lifetime: Option<Lifetime> = res.take(2)?;
+        let lifetime = lifetime.unwrap_or_default();
+
+        let mut song_ids: Vec<Thing> = listens.iter().map(|listen| listen.song.clone()).collect();
+        song_ids.sort_unstable_by_key(Thing::to_string);
+        song_ids.dedup();
+        let sql = "
+            SELECT
+                id,
+                title,
+                duration::secs(duration) AS seconds,
+                <-artist_performs_song.in AS artists,
+                (<-album_contains_song.in)[0] AS album,
+                array::flatten(<-album_contains_song<-album.genres) AS genres
+            FROM $songs;
+        ";
+        let mut res = db.query(sql).bind(("songs", song_ids)).await?;
+        let songs: HashMap<String, SongInfo> = res
+            .take::<Vec<SongInfo>>(0)?
+            .into_iter()
+            .map(|song| (song.id.to_string(), song))
+            .collect();
+
+        let mut totals = Tally::default();
+        let mut by_song: HashMap<String, Tally> = HashMap::new();
+        let mut by_artist: HashMap<String, Tally> = HashMap::new();
+        let mut by_album: HashMap<String, Tally> = HashMap::new();
+        let mut by_genre: HashMap<String, Tally> = HashMap::new();
+        let mut spellings: HashMap<String, HashMap<String, u64>> = HashMap::new();
+        let mut by_month: Vec<MonthTally> = (1..=12).map(|_| Default::default()).collect();
+        let mut days: BTreeSet<NaiveDate> = BTreeSet::new();
+
+        // Un titre supprimé depuis compte toujours dans les totaux
+        for listen in &listens {
+            let song = songs.get(&listen.song.to_string());
+            let seconds = song.map(|song| song.seconds).unwrap_or(0);
+            let count = |tallies: &mut HashMap<String, Tally>, key: String| {
+                let tally = tallies.entry(key).or_default();
+                tally.listens += 1;
+                tally.seconds += seconds;
+            };
+
+            totals.listens += 1;
+            totals.seconds += seconds;
+            if let Ok(day) = NaiveDate::parse_from_str(&listen.day, "%Y-%m-%d") {
+                days.insert(day);
+            }
+            let Some(song) = song else {
+                continue;
+            };
+
+            count(&mut by_song, song.id.to_string());
+            for artist in &song.artists {
+                count(&mut by_artist, artist.to_string());
+            }
+            if let Some(album) = &song.album {
+                count(&mut by_album, album.to_string());
+            }
+            let mut genres: HashMap<String, &String> = HashMap::new();
+            for spelling in &song.genres {
+                genres.entry(normalize_genre(spelling)).or_insert(spelling);
+            }
+            for (genre, spelling) in genres {
+                *spellings
+                    .entry(genre.clone())
+                    .or_default()
+                    .entry(spelling.clone())
+                    .or_default() += 1;
+                count(&mut by_genre, genre);
+            }
+
+            if let Some(month) = by_month.get_mut(listen.month.saturating_sub(1) as usize) {
+                month.totals.listens += 1;
+                month.totals.seconds += seconds;
+                count(&mut month.songs, song.id.to_string());
+                for artist in &song.artists {
+                    count(&mut month.artists, artist.to_string());
+                }
+            }
+        }
+
+        let mut artist_ids: Vec<Thing> = songs
+            .values()
+            .flat_map(|song| song.artists.iter().cloned())
+            .collect();
+        artist_ids.sort_unstable_by_key(Thing::to_string);
+        artist_ids.dedup();
+        let mut res = db
+            .query("SELECT id, name FROM $artists;")
+            .bind(("artists", artist_ids))
+            .await?;
+        let artist_names: HashMap<String, String> = res
+            .take::<Vec<ArtistName>>(0)?
+            .into_iter()
+            .map(|artist| (artist.id.to_string(), artist.name))
+            .collect();
+
+        let things: HashMap<String, Thing> = songs
+            .values()
+            .flat_map(|song| {
+                std::iter::once(&song.id)
+                    .chain(&song.artists)
+                    .chain(&song.album)
+            })
+            .map(|thing| (thing.to_string(), thing.clone()))
+            .collect();
+        let top_ids = |tallies: &HashMap<String, Tally>| -> Vec<Thing> {
+            ranked(tallies)
+                .into_iter()
+                .take(WRAPPED_TOP_SIZE)
+                .filter_map(|(key, _)| things.get(key).cloned())
+                .collect()
+        };
+        let mut song_ids = top_ids(&by_song);
+        song_ids.extend(listens.first().map(|listen| listen.song.clone()));
+        let mut song_details: HashMap<String, SongWithRelations> =
+            RecommendationService::songs_by_ids(db, song_ids)
+                .await?
+                .into_iter()
+                .filter_map(|song| Some((song.id.as_ref()?.to_string(), song)))
+                .collect();
+        let mut artist_details: HashMap<String, Artist> =
+            Self::by_id(db, "*", top_ids(&by_artist), |artist: &Artist| {
+                artist.id.as_ref().map(Thing::to_string)
+            })
+            .await?;
+        let mut album_details: HashMap<String, AlbumWithArtists> = Self::by_id(
+            db,
+            &format!("*, {ALBUM_RELATIONS}"),
+            top_ids(&by_album),
+            |album: &AlbumWithArtists| album.id.as_ref().map(Thing::to_string),
+        )
+        .await?;
+
+        let first_song = listens.first().and_then(|listen| {
+            let song = song_details.get(&listen.song.to_string())?.clone();
+            Some(WrappedFirstSong {
+                listened_at: listen.listened_at.clone(),
+                song,
+            })
+        });
+        let top_songs = ranked(&by_song)
+            .into_iter()
+            .take(WRAPPED_TOP_SIZE)
+            .filter_map(|(key, tally)| {
+                Some(WrappedSong {
+                    song: song_details.remove(key)?,
+                    listens: tally.listens,
+                })
+            })
+            .collect();
+        let top_artists = ranked(&by_artist)
+            .into_iter()
+            .take(WRAPPED_TOP_SIZE)
+            .filter_map(|(key, tally)| {
+                Some(WrappedArtist {
+                    artist: artist_details.remove(key)?,
+                    listens: tally.listens,
+                    minutes: tally.seconds / 60,
+                })
+            })
+            .collect();
+        let top_albums = ranked(&by_album)
+            .into_iter()
+            .take(WRAPPED_TOP_SIZE)
+            .filter_map(|(key, tally)| {
+                Some(WrappedAlbum {
+                    album: album_details.remove(key)?,
+                    listens: tally.listens,
+                })
+            })
+            .collect();
+        let top_genres = ranked(&by_genre)
+            .into_iter()
+            .take(WRAPPED_TOP_SIZE)
+            .map(|(genre, tally)| WrappedGenre {
+                genre: spellings
+                    .get(genre)
+                    .and_then(|spellings| {
+                        spellings
+                            .iter()
+                            .max_by(|a, b| a.1.cmp(b.1).then_with(|| b.0.cmp(a.0)))
+                    })
+                    .map(|(spelling, _)| spelling.clone())
+                    .unwrap_or_else(|| genre.clone()),
+                listens: tally.listens,
+            })
+            .collect();
+        let months = by_month
+            .iter()
+            .enumerate()
+            .map(|(index, month)| WrappedMonth {
+                month: index as u32 + 1,
+                listens: month.totals.listens,
+                minutes: month.totals.seconds / 60,
+                top_song: ranked(&month.songs)
+                    .first()
+                    .and_then(|(key, _)| songs.get(*key))
+                    .map(|song| song.title.clone()),
+                top_artist: ranked(&month.artists)
+                    .first()
+                    .and_then(|(key, _)| artist_names.get(*key))
+                    .cloned(),
+            })
+            .collect();
+
+        Ok(WrappedReport {
+            year,
+            complete: false,
+            listens: totals.listens,
+            minutes: totals.seconds / 60,
+            top_songs,
+            top_artists,
+            top_albums,
+            top_genres,
+            longest_streak: longest_streak(&days),
+            badges,
+            first_song,
+            months,
+            lifetime_listens: lifetime.listen_count,
+            lifetime_minutes: lifetime.total_listening_time / 60,
+            computed_at: Datetime::from(Utc::now()),
+        })
+    }
+
+    /// Records of `ids` selected with `projection`, by id.
+    async fn by_id<T: DeserializeOwned>(
+        db: &Surreal<Any>,
+        projection: &str,
+        ids: Vec<Thing>,
+        id: impl Fn(&T) -> Option<String>,
+    ) -> Result<HashMap<String, T>> {
+        let mut res = db
+            .query(format!("SELECT {projection} FROM $ids;"))
+            .bind(("ids", ids))
+            .await?;
+        Ok(res
+            .take::<Vec<T>>(0)?
+            .into_iter()
+            .filter_map(|item| Some((id(&item)?, item)))
+            .collect())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use surrealdb::engine::any::connect;
+
+    #[tokio::test]
+    async fn test_wrapped_recaps_a_year_and_caches_it_once_over() {
+        let db = connect("mem://").await.unwrap();
+        db.use_ns("test").use_db("test").await.unwrap();
+        for migration in [
+            include_str!("../../database_charts.surql"),
+            include_str!("../../database_wrapped.surql"),
+        ] {
+            db.query(migration).await.unwrap().check().unwrap();
+        }
+        db.query(
+            r#"
+            CREATE user:u1 SET username = 'u1', listen_count = 40, total_listening_time = 7200;
+            CREATE artist:clash SET name = 'The Clash', genres = [], country_code = 'GB', albums_count = 1, songs_count = 2;
+            CREATE artist:jam SET name = 'The Jam', genres = [], country_code = 'GB', albums_count = 1, songs_count = 1;
+            CREATE album:london SET title = 'London Calling', genres = ['Punk'], langs = [], total_tracks = 2, total_duration = 6m;
+            CREATE album:setting SET title = 'Setting Sons', genres = ['punk'], langs = [], total_tracks = 1, total_duration = 4m;
+            RELATE artist:clash->artist_creates_album->album:london;
+            RELATE artist:jam->artist_creates_album->album:setting;
+            FOR $song IN [['c1', 'London Calling', 'clash', 'london', 3m], ['c2', 'Clampdown', 'clash', 'london', 3m],
+                ['j1', 'Eton Rifles', 'jam', 'setting', 4m]] {
+                CREATE type::thing('song', $song[0]) SET title = $song[1], file_url = 'f.mp3', duration = $song[4],
+                    song_index = 1, tempo = 0.0;
+                RELATE (type::thing('artist', $song[2]))->artist_performs_song->(type::thing('song', $song[0]));
+                RELATE (type::thing('album', $song[3]))->album_contains_song->(type::thing('song', $song[0]));
+            };
+            FOR $listen IN [['c1', d'2025-01-01T10:00:00Z'], ['c1', d'2025-01-02T10:00:00Z'],
+                ['c2', d'2025-01-03T23:59:00Z'], ['j1', d'2025-03-10T08:00:00Z'], ['c1', d'2025-03-11T08:00:00Z'],
+                ['c2', d'2024-12-31T23:00:00Z']] {
+                CREATE listen_event SET song = type::thing('song', $listen[0]), user = user:u1, listened_at = $listen[1];
+            };
+            CREATE listen_event SET song = song:j1, listened_at = d'2025-06-01T08:00:00Z';
+            CREATE listen_event SET album = album:london, user = user:u1, listened_at = d'2025-06-01T08:00:00Z';
+            CREATE badge_award SET user = user:u1, badge = 'listen_10_hours', awarded_at = d'2025-05-01T00:00:00Z';
+            CREATE badge_award SET user = user:u1, badge = 'favorite_10_song', awarded_at = d'2024-05-01T00:00:00Z';
+            "#,
+        )
+        .await
+        .unwrap()
+        .check()
+        .unwrap();
+
+        let report = WrappedService::get_wrapped(&db, "u1", 2025).await.unwrap();
+        assert!(report.complete);
+        assert_eq!((report.listens, report.minutes), (5, 16));
+        assert_eq!(
+            report
+                .top_songs
+                .iter()
+                .map(|entry| (entry.song.title.as_str(), entry.listens))
+                .collect::<Vec<_>>(),
+            vec![("London Calling", 3), ("Clampdown", 1), ("Eton Rifles", 1)]
+        );
+        assert_eq!(
+            report
+                .top_artists
+                .iter()
+                .map(|entry| (entry.artist.name.as_str(), entry.listens, entry.minutes))
+                .collect::<Vec<_>>(),
+            vec![("The Clash", 4, 12), ("The Jam", 1, 4)]
+        );
+        assert_eq!(
+            report
+                .top_albums
+                .iter()
+                .map(|entry| (entry.album.title.as_str(), entry.listens))
+                .collect::<Vec<_>>(),
+            vec![("London Calling", 4), ("Setting Sons", 1)]
+        );
+        assert_eq!(report.top_albums[0].album.artists[0].name, "The Clash");
+        assert_eq!(
+            report.top_genres,
+            vec![WrappedGenre {
+                genre: "Punk".to_string(),
+                listens: 5,
+            }]
+        );
+        assert_eq!(
+            report.longest_streak,
+            Some(WrappedStreak {
+                days: 3,
+                from: NaiveDate::from_ymd_opt(2025, 1, 1).unwrap(),
+                to: NaiveDate::from_ymd_opt(2025, 1, 3).unwrap(),
+            })
+        );
+        assert_eq!(report.badges, vec![BadgeEnum::Listen10Hours]);
+        assert_eq!(
+            report
+                .first_song
+                .as_ref()
+                .map(|first| first.song.title.as_str()),
+            Some("London Calling")
+        );
+        assert_eq!(report.months.len(), 12);
+        assert_eq!(
+            report.months[0],
+            WrappedMonth {
+                month: 1,
+                listens: 3,
+                minutes: 9,
+                top_song: Some("London Calling".to_string()),
+                top_artist: Some("The Clash".to_string()),
+            }
+        );
+        assert_eq!(
+            (
+                report.months[1].listens,
+                report.months[1].top_song.as_deref()
+            ),
+            (0, None)
+        );
+        assert_eq!((report.months[2].listens, report.months[2].minutes), (2, 7));
+        assert_eq!(
+            (report.lifetime_listens, report.lifetime_minutes),
+            (40, 120)
+        );
+
+        // Une année terminée est servie depuis le cache
+        db.query("CREATE listen_event SET song = song:j1, user = user:u1, listened_at = d'2025-12-01T08:00:00Z';")
+            .await
+            .unwrap()
+            .check()
+            .unwrap();
+        let cached = WrappedService::get_wrapped(&db, "u1", 2025).await.unwrap();
+        assert_eq!(cached.listens, 5);
+        assert_eq!(cached.top_songs[0].song.title, "London Calling");
+        assert_eq!(cached.longest_streak, report.longest_streak);
+
+        let current_year = Utc::now().year();
+        let current = WrappedService::get_wrapped(&db, "u1", current_year)
+            .await
+            .unwrap();
+        assert!(!current.complete);
+        assert_eq!(current.listens, 0);
+        let mut res = db
+            .query("SELECT count() FROM wrapped_report GROUP ALL;")
+            .await
+            .unwrap();
+        let stored: Option<u64> = res.take((0, "count")).unwrap();
+        assert_eq!(stored, Some(1));
+
+        assert!(matches!(
+            WrappedService::get_wrapped(&db, "u1", current_year + 1).await,
+            Err(Error::InvalidInput { .. })
+        ));
+    }
+}